@@ -22,7 +22,191 @@ class PyO3Breakpoint(gdb.Breakpoint):
             return True
 
 
-gdb.register_window_type("inspect_embassy_window", gdb_backend.GdbTui)
+class BreakpointKindParameter(gdb.Parameter):
+    """The kind of breakpoints inspect-embassy sets at the end of the executor's poll function.
+
+    Use software breakpoints on cores with only a few hardware breakpoints. Only used when the
+    window gets opened."""
+
+    set_doc = "Set the kind of breakpoints used by inspect-embassy."
+    show_doc = "Show the kind of breakpoints used by inspect-embassy."
+
+    def __init__(self):
+        super().__init__(
+            "inspect-embassy-breakpoints",
+            gdb.COMMAND_BREAKPOINTS,
+            gdb.PARAM_ENUM,
+            ["hardware", "software"],
+        )
+        self.value = "hardware"
+
+
+class ColorsParameter(gdb.Parameter):
+    """The colors the terminal can show, use 16 to replace the other colors by the nearest of the
+    16 ANSI colors. Only used when the window gets opened."""
+
+    set_doc = "Set the colors inspect-embassy uses."
+    show_doc = "Show the colors inspect-embassy uses."
+
+    def __init__(self):
+        super().__init__(
+            "inspect-embassy-colors",
+            gdb.COMMAND_TUI,
+            gdb.PARAM_ENUM,
+            ["full", "16"],
+        )
+        self.value = "full"
+
+
+class ThemeParameter(gdb.Parameter):
+    """The colors the pages are drawn with, use light for terminals with a light background. Only
+    used when the window gets opened."""
+
+    set_doc = "Set the color theme inspect-embassy uses."
+    show_doc = "Show the color theme inspect-embassy uses."
+
+    def __init__(self):
+        super().__init__(
+            "inspect-embassy-theme",
+            gdb.COMMAND_TUI,
+            gdb.PARAM_ENUM,
+            ["dark", "light"],
+        )
+        self.value = "dark"
+
+
+# The opened windows together with their gdb.TuiWindow, to find the one to send commands to
+windows = []
+
+
+def _live_windows():
+    """The windows that are still open, forgetting the closed ones."""
+    windows[:] = [(tui_window, window) for (tui_window, window) in windows if tui_window.is_valid()]
+    return [window for _, window in windows]
+
+
+class StuckAfterParameter(gdb.Parameter):
+    """After how many stops at which other tasks got polled a task that stayed in the same state
+    is marked as stuck, 0 to never mark tasks. Also changes the open windows."""
+
+    set_doc = "Set after how many unchanged stops inspect-embassy marks a task as stuck."
+    show_doc = "Show after how many unchanged stops inspect-embassy marks a task as stuck."
+
+    def __init__(self):
+        super().__init__(
+            "inspect-embassy-stuck-after",
+            gdb.COMMAND_TUI,
+            gdb.PARAM_ZUINTEGER,
+        )
+        self.value = 50
+
+    def get_set_string(self):
+        for window in _live_windows():
+            window.set_stuck_after(self.value)
+        return ""
+
+
+def create_window(tui_window):
+    window = gdb_backend.GdbTui(tui_window)
+    windows.append((tui_window, window))
+    return window
+
+
+class InspectFutureCommand(gdb.Command):
+    """Show a future that is not in a task pool in the inspect-embassy window.
+
+Usage: inspect-embassy-future ADDRESS TYPE
+ADDRESS is an expression for the address of the future, TYPE the path of its type or of the async
+fn returning it."""
+
+    def __init__(self):
+        super().__init__("inspect-embassy-future", gdb.COMMAND_DATA)
+
+    def invoke(self, argument, from_tty):
+        args = gdb.string_to_argv(argument)
+        if len(args) != 2:
+            raise gdb.GdbError("Usage: inspect-embassy-future ADDRESS TYPE")
+        address = int(gdb.parse_and_eval(args[0]))
+
+        live_windows = _live_windows()
+        if not live_windows:
+            raise gdb.GdbError("Open the inspect_embassy layout first")
+        live_windows[-1].inspect_future(address, args[1])
+
+
+class KeyCommand(gdb.Command):
+    """Move the selection in the inspect-embassy window without the mouse.
+
+Usage: inspect-embassy-key KEY
+KEY is up or down to move the selection, enter to open or toggle the selected row, back to go
+back to the previous page and escape to stop typing."""
+
+    def __init__(self):
+        super().__init__("inspect-embassy-key", gdb.COMMAND_TUI)
+
+    def invoke(self, argument, from_tty):
+        args = gdb.string_to_argv(argument)
+        if len(args) != 1:
+            raise gdb.GdbError("Usage: inspect-embassy-key up|down|enter|back|escape")
+
+        live_windows = _live_windows()
+        if not live_windows:
+            raise gdb.GdbError("Open the inspect_embassy layout first")
+        try:
+            live_windows[-1].key(args[0])
+        except ValueError as e:
+            raise gdb.GdbError(str(e))
+
+
+class TypeCommand(gdb.Command):
+    """Type text into the inspect-embassy window followed by enter.
+
+Usage: inspect-embassy-type TEXT
+Used for the bytes to write after clicking "write new bytes" below a watched member, and for the
+filter of the main menu after typing "/"."""
+
+    def __init__(self):
+        super().__init__("inspect-embassy-type", gdb.COMMAND_TUI)
+
+    def invoke(self, argument, from_tty):
+        live_windows = _live_windows()
+        if not live_windows:
+            raise gdb.GdbError("Open the inspect_embassy layout first")
+        live_windows[-1].type_text(argument)
+
+
+class ExportCommand(gdb.Command):
+    """Write the state of all tasks at the last stop to a file as JSON.
+
+Usage: inspect-embassy-export FILE
+Includes the source location of every await point and the raw bytes of every member."""
+
+    def __init__(self):
+        super().__init__("inspect-embassy-export", gdb.COMMAND_DATA, gdb.COMPLETE_FILENAME)
+
+    def invoke(self, argument, from_tty):
+        args = gdb.string_to_argv(argument)
+        if len(args) != 1:
+            raise gdb.GdbError("Usage: inspect-embassy-export FILE")
+
+        live_windows = _live_windows()
+        if not live_windows:
+            raise gdb.GdbError("Open the inspect_embassy layout first")
+        try:
+            live_windows[-1].export_json(args[0])
+        except OSError as e:
+            raise gdb.GdbError(str(e))
+
+
+BreakpointKindParameter()
+ColorsParameter()
+ThemeParameter()
+StuckAfterParameter()
+ExportCommand()
+InspectFutureCommand()
+KeyCommand()
+TypeCommand()
+gdb.register_window_type("inspect_embassy_window", create_window)
 gdb.execute("tui new-layout inspect_embassy inspect_embassy_window 1 status 0 cmd 1")
 print("inspect-embassy loaded")
 
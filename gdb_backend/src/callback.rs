@@ -7,7 +7,7 @@ use pyo3::{
     types::{PyBytes, PyDict},
 };
 
-use inspect_embassy::{Callback, Type};
+use inspect_embassy::{BreakpointKind, Callback, Type};
 
 pub(crate) struct GdbCallback<'a, 'py> {
     py: Python<'py>,
@@ -59,10 +59,16 @@ impl<'a, 'py> GdbCallback<'a, 'py> {
                 .gdb_gdb_type(&inner)?
                 .call_method0(intern!(py, "reference"))
                 .ok(),
-            Type::Base(name) => self
+            Type::Base(name) | Type::Enum { name, .. } => self
                 .gdb
                 .call_method1(intern!(py, "lookup_type"), (name,))
                 .ok(),
+            // GDB does not find most tuples by name, those are formatted as builtin types before
+            // ever getting here.
+            Type::Tuple(_) => self
+                .gdb
+                .call_method1(intern!(py, "lookup_type"), (ty.to_string(),))
+                .ok(),
         }
     }
 }
@@ -87,11 +93,14 @@ impl<'a, 'py> Callback for GdbCallback<'a, 'py> {
             }))
     }
 
-    fn set_breakpoint(&mut self, addr: u64) -> Result<u64> {
+    fn set_breakpoint(&mut self, addr: u64, kind: BreakpointKind) -> Result<u64> {
         let py = self.py;
 
         let breakpoint = self.main.getattr(intern!(py, "PyO3Breakpoint"))?;
-        let breakpoint_type = self.gdb.getattr(intern!(py, "BP_HARDWARE_BREAKPOINT"))?;
+        let breakpoint_type = match kind {
+            BreakpointKind::Hardware => self.gdb.getattr(intern!(py, "BP_HARDWARE_BREAKPOINT"))?,
+            BreakpointKind::Software => self.gdb.getattr(intern!(py, "BP_BREAKPOINT"))?,
+        };
 
         let kwargs = PyDict::new(py);
         kwargs.set_item(intern!(py, "internal"), true)?;
@@ -104,6 +113,15 @@ impl<'a, 'py> Callback for GdbCallback<'a, 'py> {
         Ok(id)
     }
 
+    fn clear_breakpoint(&mut self, id: u64) -> Result<()> {
+        let py = self.py;
+
+        if let Some(breakpoint) = self.breakpoint_reg.remove(&id) {
+            breakpoint.call_method0(py, intern!(py, "delete"))?;
+        }
+        Ok(())
+    }
+
     fn resume(&mut self) -> Result<()> {
         let py = self.py;
 
@@ -138,6 +156,16 @@ impl<'a, 'py> Callback for GdbCallback<'a, 'py> {
         Ok(bytes)
     }
 
+    fn write_memory(&mut self, addr: u64, bytes: &[u8]) -> Result<()> {
+        let py = self.py;
+
+        self.gdb
+            .call_method0(intern!(py, "selected_inferior"))?
+            .call_method1(intern!(py, "write_memory"), (addr, PyBytes::new(py, bytes)))?;
+
+        Ok(())
+    }
+
     fn try_format_value(&mut self, bytes: &[u8], ty: &Type) -> Option<String> {
         let py = self.py;
 
@@ -154,4 +182,16 @@ impl<'a, 'py> Callback for GdbCallback<'a, 'py> {
             .ok()?;
         value.extract().ok()
     }
+
+    fn read_pc(&mut self) -> Result<Option<u64>> {
+        let py = self.py;
+
+        let pc = self
+            .gdb
+            .call_method0(intern!(py, "selected_frame"))?
+            .call_method0(intern!(py, "pc"))?
+            .extract()?;
+
+        Ok(Some(pc))
+    }
 }
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 
 use pyo3::{intern, prelude::*};
 
-use inspect_embassy::{Click, EmbassyInspector, Event};
+use inspect_embassy::{Click, Config, EmbassyInspector, Event, KeyCode};
 
 use callback::GdbCallback;
 use ratatui_backend::GdbRatatuiBackend;
@@ -22,6 +22,8 @@ pub struct GdbTui {
     inspector: EmbassyInspector<GdbRatatuiBackend>,
 
     breakpoint_reg: HashMap<u64, PyObject>,
+    /// The `stop_event` method connected to `gdb.events.stop`, disconnected again on close.
+    stop_event_handler: Option<PyObject>,
 }
 
 #[pymethods]
@@ -32,8 +34,48 @@ impl GdbTui {
 
         let mut breakpoint_reg = HashMap::new();
 
+        let gdb = py.import(intern!(py, "gdb"))?;
+        let breakpoint_kind = gdb
+            .call_method1(
+                intern!(py, "parameter"),
+                (intern!(py, "inspect-embassy-breakpoints"),),
+            )?
+            .extract::<String>()?
+            .parse()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let color_palette = gdb
+            .call_method1(
+                intern!(py, "parameter"),
+                (intern!(py, "inspect-embassy-colors"),),
+            )?
+            .extract::<String>()?
+            .parse()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let theme = gdb
+            .call_method1(
+                intern!(py, "parameter"),
+                (intern!(py, "inspect-embassy-theme"),),
+            )?
+            .extract::<String>()?
+            .parse()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let stuck_after = gdb
+            .call_method1(
+                intern!(py, "parameter"),
+                (intern!(py, "inspect-embassy-stuck-after"),),
+            )?
+            .extract::<usize>()?;
+        let config = Config {
+            breakpoint_kind,
+            color_palette,
+            theme,
+            stuck_after_stops: (stuck_after != 0).then_some(stuck_after),
+            debug_data_cache: Config::default_debug_data_cache(),
+            ..Config::default()
+        };
+
         let mut callback = GdbCallback::new(py, &mut breakpoint_reg)?;
-        let mut inspector = EmbassyInspector::new(ratatui_backend, &mut callback)?;
+        let mut inspector = EmbassyInspector::with_config(ratatui_backend, &mut callback, config)?;
         inspector.handle_event(Event::Redraw, &mut callback)?;
 
         let s = Bound::new(
@@ -41,21 +83,35 @@ impl GdbTui {
             Self {
                 inspector,
                 breakpoint_reg,
+                stop_event_handler: None,
             },
         )?;
         let stop_event_handler = s.getattr(intern!(py, "stop_event"))?;
 
-        let gdb = py.import(intern!(py, "gdb"))?;
         gdb.getattr(intern!(py, "events"))?
             .getattr(intern!(py, "stop"))?
-            .call_method1(intern!(py, "connect"), (stop_event_handler,))?;
+            .call_method1(intern!(py, "connect"), (&stop_event_handler,))?;
+        s.borrow_mut().stop_event_handler = Some(stop_event_handler.unbind());
 
         Ok(s.unbind())
     }
 
     /// When the TUI window is closed, the gdb.TuiWindow object will be put into an invalid state. At this time, GDB will call close method on the window object.
     /// After this method is called, GDB will discard any references it holds on this window object, and will no longer call methods on this object.
-    fn close(&self) {}
+    fn close(&mut self, py: Python) -> PyResult<()> {
+        // Stops after closing are no longer handled by the inspector.
+        if let Some(stop_event_handler) = self.stop_event_handler.take() {
+            py.import(intern!(py, "gdb"))?
+                .getattr(intern!(py, "events"))?
+                .getattr(intern!(py, "stop"))?
+                .call_method1(intern!(py, "disconnect"), (stop_event_handler,))?;
+        }
+
+        // Opening the window again sets new breakpoints.
+        let mut callback = GdbCallback::new(py, &mut self.breakpoint_reg)?;
+        self.inspector.shutdown(&mut callback)?;
+        Ok(())
+    }
 
     /// In some situations, a TUI window can change size. For example, this can happen if the user resizes the terminal, or changes the layout. When this happens, GDB will call the render method on the window object.
     /// If your window is intended to update in response to changes in the inferior, you will probably also want to register event listeners and send output to the gdb.TuiWindow.
@@ -110,6 +166,59 @@ impl GdbTui {
         }
         Ok(())
     }
+
+    /// Send a key to move the selection without the mouse, gdb keeps the keys pressed in the TUI
+    /// for itself. Called by the `inspect-embassy-key` command.
+    fn key(&mut self, key: &str, py: Python) -> PyResult<()> {
+        let key = match key {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "enter" => KeyCode::Enter,
+            "back" => KeyCode::Backspace,
+            "escape" => KeyCode::Esc,
+            other => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown key `{other}`, expected up, down, enter, back or escape"
+                )));
+            }
+        };
+        self.send_event(Event::Key(key), py)
+    }
+
+    /// Type `text` followed by enter, like the filter of the main menu or the bytes to write to a
+    /// member. Called by the `inspect-embassy-type` command.
+    fn type_text(&mut self, text: &str, py: Python) -> PyResult<()> {
+        for c in text.chars() {
+            self.send_event(Event::Key(KeyCode::Char(c)), py)?;
+        }
+        self.send_event(Event::Key(KeyCode::Enter), py)
+    }
+
+    /// Write the state of all tasks at the last stop to the file at `path` as JSON. Called by the
+    /// `inspect-embassy-export` command.
+    fn export_json(&mut self, path: &str, py: Python) -> PyResult<()> {
+        let mut callback = GdbCallback::new(py, &mut self.breakpoint_reg)?;
+        let json = self.inspector.export_json(&mut callback);
+        std::fs::write(path, json.to_string())?;
+        Ok(())
+    }
+
+    /// Mark tasks as stuck after this many unchanged stops, 0 to never mark them. Called when the
+    /// `inspect-embassy-stuck-after` parameter is set.
+    fn set_stuck_after(&mut self, stops: usize, py: Python) -> PyResult<()> {
+        self.inspector
+            .set_stuck_after_stops((stops != 0).then_some(stops));
+        self.send_event(Event::Redraw, py)
+    }
+
+    /// Show the future of the type at `type_path` stored at `address`, for futures that are not in
+    /// a task pool. Called by the `inspect-embassy-future` command.
+    fn inspect_future(&mut self, address: u64, type_path: &str, py: Python) -> PyResult<()> {
+        let mut callback = GdbCallback::new(py, &mut self.breakpoint_reg)?;
+        self.inspector
+            .inspect_future(address, type_path, &mut callback)?;
+        Ok(())
+    }
 }
 
 impl GdbTui {
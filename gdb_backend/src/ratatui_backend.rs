@@ -55,6 +55,10 @@ pub struct GdbRatatuiBackend {
     // Using 2 vectors to more easaly support resizing.
     buffer: Vec<Vec<ratatui::buffer::Cell>>,
     cursor_pos: ratatui::layout::Position,
+
+    // The contents of the last write, every write replaces the full window so writing the same
+    // contents again can be skipped. Scrolling quickly otherwise floods gdb with writes.
+    last_written: String,
 }
 impl GdbRatatuiBackend {
     pub(crate) fn new(tui_window: PyObject, py: Python) -> PyResult<Self> {
@@ -64,6 +68,7 @@ impl GdbRatatuiBackend {
             tui_window,
             buffer: Vec::new(),
             cursor_pos: ratatui::layout::Position::ORIGIN,
+            last_written: String::new(),
         })
     }
 }
@@ -118,6 +123,8 @@ impl ratatui::backend::Backend for GdbRatatuiBackend {
         for row in &mut self.buffer {
             row.fill(Cell::EMPTY);
         }
+        // Clearing is done when the window got invalid, so it needs to be written again.
+        self.last_written.clear();
         Ok(())
     }
 
@@ -182,7 +189,12 @@ impl ratatui::backend::Backend for GdbRatatuiBackend {
             }
         }
 
+        if s == self.last_written {
+            return Ok(());
+        }
+
         Python::with_gil(|py| self.tui_window.write(&s, py)).map_err(py_error_to_io_error)?;
+        self.last_written = s;
 
         Ok(())
     }
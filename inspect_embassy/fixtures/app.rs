@@ -0,0 +1,131 @@
+//! An embassy app on the thread mode executor, without the `nightly` feature. Tasks are expanded
+//! like the task macro does, see `stubs/`.
+#![no_std]
+#![no_main]
+
+use core::{
+    future::Future,
+    hint::black_box,
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+    task::{Context, Poll},
+};
+
+use embassy_executor::{
+    Spawner,
+    arch::thread::Executor,
+    raw::{SpawnToken, TaskPoolHolder, task_pool_align, task_pool_new, task_pool_size},
+};
+use futures_util::future::FutureExt;
+
+static EXECUTOR: Executor = Executor::new();
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// Pending once, like waiting for an interrupt.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+#[inline(never)]
+async fn tick() -> u32 {
+    yield_now().await;
+    TICKS.fetch_add(1, Ordering::Relaxed)
+}
+
+async fn __collect_task() {
+    let mut samples = arrayvec::ArrayVec::<u32, 4>::new();
+    let mut small = tinyvec::ArrayVec::<[u16; 4]>::new();
+    loop {
+        let tick = tick().await;
+        samples.push(tick);
+        small.push(tick as u16);
+        black_box((&samples, &small));
+        if samples.len() == 4 {
+            return;
+        }
+    }
+}
+
+fn collect() -> SpawnToken {
+    const POOL_SIZE: usize = 1;
+    static POOL: TaskPoolHolder<
+        { task_pool_size::<_, _, _, POOL_SIZE>(__collect_task) },
+        { task_pool_align::<_, _, _, POOL_SIZE>(__collect_task) },
+    > = unsafe { core::mem::transmute(task_pool_new::<_, _, _, POOL_SIZE>(__collect_task)) };
+    unsafe { POOL.get::<_, _, _, POOL_SIZE>(__collect_task) }.spawn(__collect_task)
+}
+
+async fn __fused_task() {
+    let mut fused = core::pin::pin!(tick().fuse());
+    fused.as_mut().await;
+    black_box(&fused);
+    yield_now().await;
+}
+
+fn fused() -> SpawnToken {
+    const POOL_SIZE: usize = 1;
+    static POOL: TaskPoolHolder<
+        { task_pool_size::<_, _, _, POOL_SIZE>(__fused_task) },
+        { task_pool_align::<_, _, _, POOL_SIZE>(__fused_task) },
+    > = unsafe { core::mem::transmute(task_pool_new::<_, _, _, POOL_SIZE>(__fused_task)) };
+    unsafe { POOL.get::<_, _, _, POOL_SIZE>(__fused_task) }.spawn(__fused_task)
+}
+
+async fn __worker_task(id: u32) {
+    loop {
+        let tick = tick().await;
+        black_box((id, tick));
+    }
+}
+
+fn worker(id: u32) -> SpawnToken {
+    const POOL_SIZE: usize = 2;
+    static POOL: TaskPoolHolder<
+        { task_pool_size::<_, _, _, POOL_SIZE>(__worker_task) },
+        { task_pool_align::<_, _, _, POOL_SIZE>(__worker_task) },
+    > = unsafe { core::mem::transmute(task_pool_new::<_, _, _, POOL_SIZE>(__worker_task)) };
+    unsafe { POOL.get::<_, _, _, POOL_SIZE>(__worker_task) }.spawn(move || __worker_task(id))
+}
+
+fn init(spawner: Spawner) {
+    spawner.spawn(collect());
+    spawner.spawn(fused());
+    spawner.spawn(worker(0));
+    spawner.spawn(worker(1));
+}
+
+fn stop() -> bool {
+    TICKS.load(Ordering::Relaxed) > 16
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _start() -> ! {
+    EXECUTOR.run(init, stop);
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+/// Referenced by the prebuilt `core`, nothing unwinds with `panic=abort`.
+#[unsafe(no_mangle)]
+extern "C" fn rust_eh_personality() {}
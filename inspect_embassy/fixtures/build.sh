@@ -0,0 +1,30 @@
+#!/bin/sh
+# Builds the object files of the fixtures, see README.md.
+set -eu
+cd "$(dirname "$0")"
+
+out=$(mktemp -d)
+trap 'rm -rf "$out"' EXIT
+
+llvm-mc -triple x86_64-unknown-linux-gnu -filetype=obj handwritten.s -o "$out/handwritten.o"
+ld -e 0 -o handwritten.elf "$out/handwritten.o"
+
+# The stubs are built for the host, without std. LTO keeps the debug data of `core` small.
+flags="--edition 2024 -C panic=abort -g -C opt-level=1 --remap-path-prefix $(pwd)=fixtures"
+link="-C lto=fat -C relocation-model=static -C link-arg=-nostartfiles -C link-arg=-nostdlib
+  -C link-arg=-static"
+mkdir "$out/stable" "$out/nightly" "$out/inline_poll"
+for stub in embassy_executor static_cell arrayvec tinyvec futures_util; do
+    rustc +stable $flags --crate-type rlib "stubs/$stub.rs" --out-dir "$out/stable"
+    rustc +nightly $flags --crate-type rlib "stubs/$stub.rs" --out-dir "$out/nightly"
+done
+rustc +stable $flags --cfg inline_poll --crate-type rlib stubs/embassy_executor.rs \
+    --out-dir "$out/inline_poll"
+
+rustc +stable $flags $link -L "$out/stable" --extern embassy_executor --extern arrayvec \
+    --extern tinyvec --extern futures_util app.rs -o app.elf
+rustc +stable $flags $link \
+    --extern embassy_executor="$out/inline_poll/libembassy_executor.rlib" multiprio.rs \
+    -o multiprio.elf
+rustc +nightly $flags $link -L "$out/nightly" --extern embassy_executor --extern static_cell \
+    nightly.rs -o nightly.elf
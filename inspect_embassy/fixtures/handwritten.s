@@ -0,0 +1,353 @@
+# Debug data with shapes rustc does not emit for the test programs, but that other compiler
+# versions or optimizations can produce. Written by hand in the layout rustc uses for async fns,
+# without any code. See README.md for how to build it.
+
+	.section	.debug_abbrev,"",@progbits
+	# 1: compile unit
+	.byte	1, 0x11, 1
+	.byte	0x25, 0x08		# DW_AT_producer, DW_FORM_string
+	.byte	0x13, 0x05		# DW_AT_language, DW_FORM_data2
+	.byte	0x03, 0x08		# DW_AT_name, DW_FORM_string
+	.byte	0, 0
+	# 2: namespace
+	.byte	2, 0x39, 1
+	.byte	0x03, 0x08		# DW_AT_name
+	.byte	0, 0
+	# 3: struct
+	.byte	3, 0x13, 1
+	.byte	0x03, 0x08		# DW_AT_name
+	.byte	0x0b, 0x0b		# DW_AT_byte_size, DW_FORM_data1
+	.byte	0x88, 0x01, 0x0f	# DW_AT_alignment, DW_FORM_udata
+	.byte	0, 0
+	# 4: variant part
+	.byte	4, 0x33, 1
+	.byte	0x15, 0x13		# DW_AT_discr, DW_FORM_ref4
+	.byte	0, 0
+	# 5: artificial member, the discriminant
+	.byte	5, 0x0d, 0
+	.byte	0x03, 0x08		# DW_AT_name
+	.byte	0x49, 0x13		# DW_AT_type, DW_FORM_ref4
+	.byte	0x88, 0x01, 0x0f	# DW_AT_alignment
+	.byte	0x38, 0x0b		# DW_AT_data_member_location, DW_FORM_data1
+	.byte	0x34, 0x19		# DW_AT_artificial, DW_FORM_flag_present
+	.byte	0, 0
+	# 6: variant with a discriminant value
+	.byte	6, 0x19, 1
+	.byte	0x16, 0x0b		# DW_AT_discr_value, DW_FORM_data1
+	.byte	0, 0
+	# 7: member
+	.byte	7, 0x0d, 0
+	.byte	0x03, 0x08		# DW_AT_name
+	.byte	0x49, 0x13		# DW_AT_type
+	.byte	0x88, 0x01, 0x0f	# DW_AT_alignment
+	.byte	0x38, 0x0b		# DW_AT_data_member_location
+	.byte	0, 0
+	# 8: base type
+	.byte	8, 0x24, 0
+	.byte	0x03, 0x08		# DW_AT_name
+	.byte	0x3e, 0x0b		# DW_AT_encoding, DW_FORM_data1
+	.byte	0x0b, 0x0b		# DW_AT_byte_size
+	.byte	0, 0
+	# 9: struct that is only declared, without a size
+	.byte	9, 0x13, 0
+	.byte	0x03, 0x08		# DW_AT_name
+	.byte	0x3c, 0x19		# DW_AT_declaration, DW_FORM_flag_present
+	.byte	0, 0
+	# 10: struct without a size
+	.byte	10, 0x13, 1
+	.byte	0x03, 0x08		# DW_AT_name
+	.byte	0x88, 0x01, 0x0f	# DW_AT_alignment
+	.byte	0, 0
+	# 11: variant without a discriminant value, used for every other value
+	.byte	11, 0x19, 1
+	.byte	0, 0
+	.byte	0
+
+	.section	.debug_info,"",@progbits
+.Lcu_begin:
+	.long	.Lcu_end - .Lcu_version	# unit_length
+.Lcu_version:
+	.short	4			# version
+	.long	0			# debug_abbrev_offset
+	.byte	8			# address_size
+
+	.byte	1			# DW_TAG_compile_unit
+	.asciz	"handwritten"
+	.short	0x1c			# DW_LANG_Rust
+	.asciz	"fixtures/handwritten.rs"
+
+.Lu8:
+	.byte	8
+	.asciz	"u8"
+	.byte	0x07			# DW_ATE_unsigned
+	.byte	1
+.Lu32:
+	.byte	8
+	.asciz	"u32"
+	.byte	0x07			# DW_ATE_unsigned
+	.byte	4
+
+	.byte	2			# DW_TAG_namespace
+	.asciz	"fixture"
+
+.Lopaque:
+	.byte	9			# DW_TAG_structure_type
+	.asciz	"Opaque"
+
+	# fixture::missing_size::{async_fn_env#0}
+	#
+	# The `opaque` member of `Suspend0` has a type without a size, so the member has no size.
+	.byte	2			# DW_TAG_namespace
+	.asciz	"missing_size"
+
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"{async_fn_env#0}"
+	.byte	16
+	.uleb128 4
+
+	.byte	4			# DW_TAG_variant_part
+	.long	.Lmissing_size_state - .Lcu_begin
+.Lmissing_size_state:
+	.byte	5			# DW_TAG_member
+	.asciz	"__state"
+	.long	.Lu8 - .Lcu_begin
+	.uleb128 1
+	.byte	12
+
+	.byte	6			# DW_TAG_variant
+	.byte	0
+	.byte	7			# DW_TAG_member
+	.asciz	"0"
+	.long	.Lmissing_size_unresumed - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+	.byte	6			# DW_TAG_variant
+	.byte	1
+	.byte	7			# DW_TAG_member
+	.asciz	"1"
+	.long	.Lmissing_size_returned - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+	.byte	6			# DW_TAG_variant
+	.byte	3
+	.byte	7			# DW_TAG_member
+	.asciz	"3"
+	.long	.Lmissing_size_suspend0 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+	.byte	0			# end of the variant part
+
+.Lmissing_size_unresumed:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Unresumed"
+	.byte	16
+	.uleb128 4
+	.byte	7			# DW_TAG_member
+	.asciz	"x"
+	.long	.Lu32 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+.Lmissing_size_returned:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Returned"
+	.byte	16
+	.uleb128 4
+	.byte	0
+
+.Lmissing_size_suspend0:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Suspend0"
+	.byte	16
+	.uleb128 4
+	.byte	7			# DW_TAG_member
+	.asciz	"x"
+	.long	.Lu32 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	7			# DW_TAG_member
+	.asciz	"opaque"
+	.long	.Lopaque - .Lcu_begin
+	.uleb128 1
+	.byte	4
+	.byte	7			# DW_TAG_member
+	.asciz	"__awaitee"
+	.long	.Lu32 - .Lcu_begin
+	.uleb128 4
+	.byte	8
+	.byte	0
+
+	.byte	0			# end of {async_fn_env#0}
+	.byte	0			# end of fixture::missing_size
+
+	# fixture::no_size::{async_fn_env#0}
+	#
+	# The future itself has no size, its state ends last at 9 bytes.
+	.byte	2			# DW_TAG_namespace
+	.asciz	"no_size"
+
+	.byte	10			# DW_TAG_structure_type
+	.asciz	"{async_fn_env#0}"
+	.uleb128 4
+
+	.byte	4			# DW_TAG_variant_part
+	.long	.Lno_size_state - .Lcu_begin
+.Lno_size_state:
+	.byte	5			# DW_TAG_member
+	.asciz	"__state"
+	.long	.Lu8 - .Lcu_begin
+	.uleb128 1
+	.byte	8
+
+	.byte	6			# DW_TAG_variant
+	.byte	0
+	.byte	7			# DW_TAG_member
+	.asciz	"0"
+	.long	.Lno_size_unresumed - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+	.byte	6			# DW_TAG_variant
+	.byte	1
+	.byte	7			# DW_TAG_member
+	.asciz	"1"
+	.long	.Lno_size_returned - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+	.byte	6			# DW_TAG_variant
+	.byte	3
+	.byte	7			# DW_TAG_member
+	.asciz	"3"
+	.long	.Lno_size_suspend0 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+	.byte	0			# end of the variant part
+
+.Lno_size_unresumed:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Unresumed"
+	.byte	12
+	.uleb128 4
+	.byte	7			# DW_TAG_member
+	.asciz	"x"
+	.long	.Lu32 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+.Lno_size_returned:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Returned"
+	.byte	12
+	.uleb128 4
+	.byte	0
+
+.Lno_size_suspend0:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Suspend0"
+	.byte	12
+	.uleb128 4
+	.byte	7			# DW_TAG_member
+	.asciz	"x"
+	.long	.Lu32 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	7			# DW_TAG_member
+	.asciz	"__awaitee"
+	.long	.Lu32 - .Lcu_begin
+	.uleb128 4
+	.byte	4
+	.byte	0
+
+	.byte	0			# end of {async_fn_env#0}
+	.byte	0			# end of fixture::no_size
+
+	# fixture::default_variant::{async_fn_env#0}
+	#
+	# `Suspend0` has no discriminant value, every state value but 0 and 1 is that state.
+	.byte	2			# DW_TAG_namespace
+	.asciz	"default_variant"
+
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"{async_fn_env#0}"
+	.byte	8
+	.uleb128 4
+
+	.byte	4			# DW_TAG_variant_part
+	.long	.Ldefault_variant_state - .Lcu_begin
+.Ldefault_variant_state:
+	.byte	5			# DW_TAG_member
+	.asciz	"__state"
+	.long	.Lu8 - .Lcu_begin
+	.uleb128 1
+	.byte	4
+
+	.byte	6			# DW_TAG_variant
+	.byte	0
+	.byte	7			# DW_TAG_member
+	.asciz	"0"
+	.long	.Ldefault_variant_unresumed - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+	.byte	6			# DW_TAG_variant
+	.byte	1
+	.byte	7			# DW_TAG_member
+	.asciz	"1"
+	.long	.Ldefault_variant_returned - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+	.byte	11			# DW_TAG_variant
+	.byte	7			# DW_TAG_member
+	.asciz	"3"
+	.long	.Ldefault_variant_suspend0 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+	.byte	0			# end of the variant part
+
+.Ldefault_variant_unresumed:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Unresumed"
+	.byte	8
+	.uleb128 4
+	.byte	0
+
+.Ldefault_variant_returned:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Returned"
+	.byte	8
+	.uleb128 4
+	.byte	0
+
+.Ldefault_variant_suspend0:
+	.byte	3			# DW_TAG_structure_type
+	.asciz	"Suspend0"
+	.byte	8
+	.uleb128 4
+	.byte	7			# DW_TAG_member
+	.asciz	"__awaitee"
+	.long	.Lu32 - .Lcu_begin
+	.uleb128 4
+	.byte	0
+	.byte	0
+
+	.byte	0			# end of {async_fn_env#0}
+	.byte	0			# end of fixture::default_variant
+
+	.byte	0			# end of fixture
+	.byte	0			# end of the compile unit
+.Lcu_end:
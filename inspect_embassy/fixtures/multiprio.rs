@@ -0,0 +1,107 @@
+//! An embassy app with a thread mode and an interrupt executor, like the `multiprio` example of
+//! embassy, with the poll function inlined into both. See `app.rs`.
+#![no_std]
+#![no_main]
+
+use core::{
+    future::Future,
+    hint::black_box,
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+    task::{Context, Poll},
+};
+
+use embassy_executor::{
+    Spawner,
+    arch::{interrupt::InterruptExecutor, thread::Executor},
+    raw::{SpawnToken, TaskPoolHolder, task_pool_align, task_pool_new, task_pool_size},
+};
+
+static EXECUTOR_HIGH: InterruptExecutor = InterruptExecutor::new();
+static EXECUTOR_LOW: Executor = Executor::new();
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// Pending once, like waiting for an interrupt.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+async fn tick() -> u32 {
+    YieldNow { yielded: false }.await;
+    TICKS.fetch_add(1, Ordering::Relaxed)
+}
+
+async fn __run_high_task() {
+    loop {
+        black_box(tick().await);
+    }
+}
+
+fn run_high() -> SpawnToken {
+    const POOL_SIZE: usize = 1;
+    static POOL: TaskPoolHolder<
+        { task_pool_size::<_, _, _, POOL_SIZE>(__run_high_task) },
+        { task_pool_align::<_, _, _, POOL_SIZE>(__run_high_task) },
+    > = unsafe { core::mem::transmute(task_pool_new::<_, _, _, POOL_SIZE>(__run_high_task)) };
+    unsafe { POOL.get::<_, _, _, POOL_SIZE>(__run_high_task) }.spawn(__run_high_task)
+}
+
+async fn __run_low_task() {
+    loop {
+        let ticks = [tick().await, tick().await];
+        black_box(ticks);
+    }
+}
+
+fn run_low() -> SpawnToken {
+    const POOL_SIZE: usize = 1;
+    static POOL: TaskPoolHolder<
+        { task_pool_size::<_, _, _, POOL_SIZE>(__run_low_task) },
+        { task_pool_align::<_, _, _, POOL_SIZE>(__run_low_task) },
+    > = unsafe { core::mem::transmute(task_pool_new::<_, _, _, POOL_SIZE>(__run_low_task)) };
+    unsafe { POOL.get::<_, _, _, POOL_SIZE>(__run_low_task) }.spawn(__run_low_task)
+}
+
+/// The interrupt the high priority executor runs on.
+#[unsafe(no_mangle)]
+pub extern "C" fn SWI0_EGU0() {
+    EXECUTOR_HIGH.on_interrupt();
+}
+
+fn init(spawner: Spawner) {
+    spawner.spawn(run_low());
+}
+
+fn stop() -> bool {
+    TICKS.load(Ordering::Relaxed) > 16
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _start() -> ! {
+    EXECUTOR_HIGH.spawner().spawn(run_high());
+    SWI0_EGU0();
+    EXECUTOR_LOW.run(init, stop);
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+/// Referenced by the prebuilt `core`, nothing unwinds with `panic=abort`.
+#[unsafe(no_mangle)]
+extern "C" fn rust_eh_personality() {}
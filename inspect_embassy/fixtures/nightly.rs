@@ -0,0 +1,106 @@
+//! An embassy app built with the `nightly` feature of embassy-executor, which names the future
+//! types of the tasks with `type_alias_impl_trait` so the `POOL` statics are the `TaskPool`s
+//! themselves. It also drives a future stored in a `StaticCell` by hand. See `app.rs`.
+#![no_std]
+#![no_main]
+#![feature(type_alias_impl_trait)]
+
+use core::{
+    future::Future,
+    hint::black_box,
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use embassy_executor::{
+    Spawner,
+    arch::thread::Executor,
+    raw::{SpawnToken, TaskPool},
+};
+use static_cell::StaticCell;
+
+static EXECUTOR: Executor = Executor::new();
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// Pending once, like waiting for an interrupt.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+async fn tick() -> u32 {
+    YieldNow { yielded: false }.await;
+    TICKS.fetch_add(1, Ordering::Relaxed)
+}
+
+async fn __blink_task(period: u32) {
+    loop {
+        let tick = tick().await;
+        black_box(tick % period);
+    }
+}
+
+type BlinkFut = impl Future + 'static;
+
+#[define_opaque(BlinkFut)]
+fn blink(period: u32) -> SpawnToken {
+    const POOL_SIZE: usize = 2;
+    static POOL: TaskPool<BlinkFut, POOL_SIZE> = TaskPool::new();
+    POOL.spawn(move || __blink_task(period))
+}
+
+/// A future driven without the executor.
+async fn manual() {
+    loop {
+        black_box(tick().await);
+    }
+}
+
+type ManualFut = impl Future<Output = ()>;
+
+static MANUAL: StaticCell<ManualFut> = StaticCell::new();
+
+#[define_opaque(ManualFut)]
+fn manual_future() -> ManualFut {
+    manual()
+}
+
+fn init(spawner: Spawner) {
+    spawner.spawn(blink(2));
+    spawner.spawn(blink(3));
+
+    let future = unsafe { Pin::new_unchecked(MANUAL.init(manual_future())) };
+    let _ = future.poll(&mut Context::from_waker(Waker::noop()));
+}
+
+fn stop() -> bool {
+    TICKS.load(Ordering::Relaxed) > 16
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _start() -> ! {
+    EXECUTOR.run(init, stop);
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+/// Referenced by the prebuilt `core`, nothing unwinds with `panic=abort`.
+#[unsafe(no_mangle)]
+extern "C" fn rust_eh_personality() {}
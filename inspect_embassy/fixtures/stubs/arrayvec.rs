@@ -0,0 +1,33 @@
+//! `arrayvec::ArrayVec` with the same layout, see `embassy_executor.rs`.
+#![no_std]
+
+pub mod arrayvec {
+    use core::mem::MaybeUninit;
+
+    pub struct ArrayVec<T, const CAP: usize> {
+        len: u32,
+        xs: [MaybeUninit<T>; CAP],
+    }
+
+    impl<T, const CAP: usize> ArrayVec<T, CAP> {
+        pub const fn new() -> Self {
+            Self {
+                len: 0,
+                xs: [const { MaybeUninit::uninit() }; CAP],
+            }
+        }
+
+        pub fn push(&mut self, element: T) {
+            if let Some(slot) = self.xs.get_mut(self.len as usize) {
+                slot.write(element);
+                self.len += 1;
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.len as usize
+        }
+    }
+}
+
+pub use arrayvec::ArrayVec;
@@ -0,0 +1,330 @@
+//! The parts of embassy-executor the inspector looks for in the debug data, with the same paths,
+//! names and layouts. The task macro is expanded by hand in the fixtures.
+//!
+//! Built with `--cfg inline_poll` the poll function gets inlined into the executors, otherwise
+//! only its closure gets inlined into it.
+#![no_std]
+
+pub mod raw {
+    use core::{
+        cell::UnsafeCell,
+        future::Future,
+        mem::MaybeUninit,
+        pin::Pin,
+        ptr,
+        sync::atomic::{AtomicU8, Ordering},
+        task::{Context, Waker},
+    };
+
+    const STATE_SPAWNED: u8 = 0b01;
+    const STATE_QUEUED: u8 = 0b10;
+
+    pub struct TaskHeader {
+        pub(crate) state: AtomicU8,
+        run_queue_item: UnsafeCell<*mut TaskHeader>,
+        poll_fn: UnsafeCell<Option<unsafe fn(*mut TaskHeader)>>,
+    }
+
+    #[repr(C)]
+    pub struct TaskStorage<F: Future + 'static> {
+        raw: TaskHeader,
+        future: UnsafeCell<MaybeUninit<F>>,
+    }
+
+    unsafe impl<F: Future + 'static> Sync for TaskStorage<F> {}
+
+    impl<F: Future + 'static> TaskStorage<F> {
+        pub const fn new() -> Self {
+            Self {
+                raw: TaskHeader {
+                    state: AtomicU8::new(0),
+                    run_queue_item: UnsafeCell::new(ptr::null_mut()),
+                    poll_fn: UnsafeCell::new(None),
+                },
+                future: UnsafeCell::new(MaybeUninit::uninit()),
+            }
+        }
+
+        unsafe fn poll(p: *mut TaskHeader) {
+            let this = unsafe { &*(p as *const Self) };
+            let future = unsafe { Pin::new_unchecked((*this.future.get()).assume_init_mut()) };
+            let mut cx = Context::from_waker(Waker::noop());
+            if future.poll(&mut cx).is_ready() {
+                unsafe { (*this.future.get()).assume_init_drop() };
+                this.raw.state.store(0, Ordering::Release);
+            }
+        }
+
+        fn spawn(&'static self, future: impl FnOnce() -> F) -> SpawnToken {
+            if self
+                .raw
+                .state
+                .compare_exchange(0, STATE_SPAWNED, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+            {
+                return SpawnToken { raw: None };
+            }
+
+            unsafe {
+                (*self.future.get()).write(future());
+                *self.raw.poll_fn.get() = Some(Self::poll);
+            }
+            SpawnToken {
+                raw: Some(&self.raw as *const TaskHeader as *mut TaskHeader),
+            }
+        }
+    }
+
+    pub struct TaskPool<F: Future + 'static, const N: usize> {
+        pool: [TaskStorage<F>; N],
+    }
+
+    unsafe impl<F: Future + 'static, const N: usize> Sync for TaskPool<F, N> {}
+
+    impl<F: Future + 'static, const N: usize> TaskPool<F, N> {
+        pub const fn new() -> Self {
+            Self {
+                pool: [const { TaskStorage::new() }; N],
+            }
+        }
+
+        pub fn spawn(&'static self, future: impl FnOnce() -> F) -> SpawnToken {
+            for task in &self.pool {
+                if task.raw.state.load(Ordering::Acquire) == 0 {
+                    return task.spawn(future);
+                }
+            }
+            SpawnToken { raw: None }
+        }
+    }
+
+    /// The trick of the stable task macro to store a `TaskPool` of an unnameable future type.
+    pub trait Alignment {
+        type Archetype: Copy;
+    }
+
+    pub struct Align<const N: usize>([<Self as Alignment>::Archetype; 0])
+    where
+        Self: Alignment;
+
+    macro_rules! aligns {
+        ($($n:literal => $archetype:ty),*) => {
+            $(
+                impl Alignment for Align<$n> {
+                    type Archetype = $archetype;
+                }
+            )*
+        };
+    }
+    aligns!(1 => u8, 2 => u16, 4 => u32, 8 => u64, 16 => u128);
+
+    #[repr(C)]
+    pub struct TaskPoolHolder<const SIZE: usize, const ALIGN: usize>
+    where
+        Align<ALIGN>: Alignment,
+    {
+        data: UnsafeCell<[MaybeUninit<u8>; SIZE]>,
+        align: Align<ALIGN>,
+    }
+
+    unsafe impl<const SIZE: usize, const ALIGN: usize> Sync for TaskPoolHolder<SIZE, ALIGN> where
+        Align<ALIGN>: Alignment
+    {
+    }
+
+    impl<const SIZE: usize, const ALIGN: usize> TaskPoolHolder<SIZE, ALIGN>
+    where
+        Align<ALIGN>: Alignment,
+    {
+        /// # Safety
+        ///
+        /// The holder must have been created from a `TaskPool<Fut, POOL_SIZE>`.
+        pub const unsafe fn get<F, Args, Fut, const POOL_SIZE: usize>(
+            &'static self,
+            _: F,
+        ) -> &'static TaskPool<Fut, POOL_SIZE>
+        where
+            F: TaskFn<Args, Fut = Fut>,
+            Fut: Future + 'static,
+        {
+            unsafe { &*(self.data.get() as *const TaskPool<Fut, POOL_SIZE>) }
+        }
+    }
+
+    pub trait TaskFn<Args>: Copy {
+        type Fut: Future + 'static;
+    }
+
+    impl<F, Fut> TaskFn<()> for F
+    where
+        F: Copy + FnOnce() -> Fut,
+        Fut: Future + 'static,
+    {
+        type Fut = Fut;
+    }
+
+    impl<F, Fut, A> TaskFn<(A,)> for F
+    where
+        F: Copy + FnOnce(A) -> Fut,
+        Fut: Future + 'static,
+    {
+        type Fut = Fut;
+    }
+
+    pub const fn task_pool_size<F, Args, Fut, const POOL_SIZE: usize>(_: F) -> usize
+    where
+        F: TaskFn<Args, Fut = Fut>,
+        Fut: Future + 'static,
+    {
+        size_of::<TaskPool<Fut, POOL_SIZE>>()
+    }
+
+    pub const fn task_pool_align<F, Args, Fut, const POOL_SIZE: usize>(_: F) -> usize
+    where
+        F: TaskFn<Args, Fut = Fut>,
+        Fut: Future + 'static,
+    {
+        align_of::<TaskPool<Fut, POOL_SIZE>>()
+    }
+
+    pub const fn task_pool_new<F, Args, Fut, const POOL_SIZE: usize>(
+        _: F,
+    ) -> TaskPool<Fut, POOL_SIZE>
+    where
+        F: TaskFn<Args, Fut = Fut>,
+        Fut: Future + 'static,
+    {
+        TaskPool::new()
+    }
+
+    pub struct SpawnToken {
+        raw: Option<*mut TaskHeader>,
+    }
+
+    struct RunQueue {
+        head: UnsafeCell<*mut TaskHeader>,
+    }
+
+    impl RunQueue {
+        fn enqueue(&self, task: *mut TaskHeader) {
+            unsafe {
+                *(*task).run_queue_item.get() = *self.head.get();
+                *self.head.get() = task;
+            }
+        }
+
+        #[inline(always)]
+        fn dequeue_all(&self, mut on_task: impl FnMut(*mut TaskHeader)) {
+            let mut task = unsafe { ptr::replace(self.head.get(), ptr::null_mut()) };
+            while !task.is_null() {
+                let next = unsafe { *(*task).run_queue_item.get() };
+                on_task(task);
+                task = next;
+            }
+        }
+    }
+
+    pub struct SyncExecutor {
+        run_queue: RunQueue,
+    }
+
+    unsafe impl Sync for SyncExecutor {}
+
+    impl SyncExecutor {
+        pub const fn new() -> Self {
+            Self {
+                run_queue: RunQueue {
+                    head: UnsafeCell::new(ptr::null_mut()),
+                },
+            }
+        }
+
+        pub(crate) fn spawn(&'static self, token: SpawnToken) {
+            if let Some(task) = token.raw {
+                unsafe { (*task).state.fetch_or(STATE_QUEUED, Ordering::AcqRel) };
+                self.run_queue.enqueue(task);
+            }
+        }
+
+        #[cfg_attr(inline_poll, inline(always))]
+        #[cfg_attr(not(inline_poll), inline(never))]
+        pub(crate) fn poll(&'static self) {
+            self.run_queue.dequeue_all(|task| unsafe {
+                (*task).state.fetch_and(!STATE_QUEUED, Ordering::AcqRel);
+                if let Some(poll_fn) = *(*task).poll_fn.get() {
+                    poll_fn(task);
+                }
+            });
+        }
+    }
+}
+
+use raw::{SpawnToken, SyncExecutor};
+
+#[derive(Clone, Copy)]
+pub struct Spawner {
+    executor: &'static SyncExecutor,
+}
+
+impl Spawner {
+    pub fn spawn(&self, token: SpawnToken) {
+        self.executor.spawn(token);
+    }
+}
+
+pub mod arch {
+    pub mod thread {
+        use crate::{Spawner, raw::SyncExecutor};
+
+        pub struct Executor {
+            inner: SyncExecutor,
+        }
+
+        impl Executor {
+            pub const fn new() -> Self {
+                Self {
+                    inner: SyncExecutor::new(),
+                }
+            }
+
+            /// Polls the tasks until `stop` returns true, the real executor never returns.
+            #[inline(never)]
+            pub fn run(&'static self, init: fn(Spawner), stop: fn() -> bool) {
+                init(Spawner {
+                    executor: &self.inner,
+                });
+                while !stop() {
+                    self.inner.poll();
+                }
+            }
+        }
+    }
+
+    pub mod interrupt {
+        use crate::{Spawner, raw::SyncExecutor};
+
+        pub struct InterruptExecutor {
+            executor: SyncExecutor,
+        }
+
+        impl InterruptExecutor {
+            pub const fn new() -> Self {
+                Self {
+                    executor: SyncExecutor::new(),
+                }
+            }
+
+            pub fn spawner(&'static self) -> Spawner {
+                Spawner {
+                    executor: &self.executor,
+                }
+            }
+
+            /// Called from the interrupt handler the executor is started on.
+            #[inline(never)]
+            pub fn on_interrupt(&'static self) {
+                self.executor.poll();
+            }
+        }
+    }
+}
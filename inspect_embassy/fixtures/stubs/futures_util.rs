@@ -0,0 +1,52 @@
+//! `futures_util::future::Fuse` with the same path and layout, see `embassy_executor.rs`.
+#![no_std]
+
+pub mod future {
+    pub mod future {
+        pub mod fuse {
+            use core::{
+                future::Future,
+                pin::Pin,
+                task::{Context, Poll},
+            };
+
+            pub struct Fuse<Fut> {
+                inner: Option<Fut>,
+            }
+
+            impl<Fut: Future> Fuse<Fut> {
+                pub fn new(future: Fut) -> Self {
+                    Self {
+                        inner: Some(future),
+                    }
+                }
+            }
+
+            impl<Fut: Future> Future for Fuse<Fut> {
+                type Output = Fut::Output;
+
+                fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Fut::Output> {
+                    let this = unsafe { self.get_unchecked_mut() };
+                    let Some(inner) = &mut this.inner else {
+                        return Poll::Pending;
+                    };
+                    let output = unsafe { Pin::new_unchecked(inner) }.poll(cx);
+                    if output.is_ready() {
+                        this.inner = None;
+                    }
+                    output
+                }
+            }
+        }
+    }
+
+    pub use future::fuse::Fuse;
+
+    pub trait FutureExt: core::future::Future + Sized {
+        fn fuse(self) -> Fuse<Self> {
+            Fuse::new(self)
+        }
+    }
+
+    impl<F: core::future::Future> FutureExt for F {}
+}
@@ -0,0 +1,31 @@
+//! `static_cell::StaticCell` with the same layout, see `embassy_executor.rs`.
+#![no_std]
+
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+pub struct StaticCell<T> {
+    used: AtomicBool,
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T> Sync for StaticCell<T> {}
+
+impl<T> StaticCell<T> {
+    pub const fn new() -> Self {
+        Self {
+            used: AtomicBool::new(false),
+            val: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    pub fn init(&'static self, val: T) -> &'static mut T {
+        if self.used.swap(true, Ordering::AcqRel) {
+            panic!("StaticCell::init called more than once");
+        }
+        unsafe { (*self.val.get()).write(val) }
+    }
+}
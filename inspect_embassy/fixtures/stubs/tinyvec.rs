@@ -0,0 +1,31 @@
+//! `tinyvec::ArrayVec` with the same layout, see `embassy_executor.rs`.
+#![no_std]
+
+pub mod arrayvec {
+    pub struct ArrayVec<A> {
+        len: u16,
+        data: A,
+    }
+
+    impl<T: Copy + Default, const N: usize> ArrayVec<[T; N]> {
+        pub fn new() -> Self {
+            Self {
+                len: 0,
+                data: [T::default(); N],
+            }
+        }
+
+        pub fn push(&mut self, element: T) {
+            if let Some(slot) = self.data.get_mut(self.len as usize) {
+                *slot = element;
+                self.len += 1;
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.len as usize
+        }
+    }
+}
+
+pub use arrayvec::ArrayVec;
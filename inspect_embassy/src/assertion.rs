@@ -0,0 +1,291 @@
+//! Small expressions checking the state of the tasks, to use the inspector as a gate in CI without
+//! anyone looking at the TUI.
+//!
+//! An assertion selects a task by the path of its pool and its index, and compares the name of the
+//! state its async fn is in or the formatted value of one of its members:
+//!
+//! ```text
+//! pool("app::worker")[0].state == "Suspend3"
+//! pool("app::worker")[1].member("retries") != 0
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::model::{
+    future::FutureValueKind,
+    task_pool::{TaskPoolValue, TaskValue},
+    ty::Type,
+};
+
+/// A parsed assertion, see the [module documentation](self) for the syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    /// The text the assertion was parsed from, to report which one failed.
+    text: String,
+    pool_path: String,
+    task_idx: usize,
+    query: Query,
+    operator: Operator,
+    expected: String,
+}
+
+/// What of the task is compared.
+#[derive(Debug, Clone, PartialEq)]
+enum Query {
+    /// The name of the state of the task's async fn.
+    State,
+    /// The formatted value of the member with this name in the current state.
+    Member(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl FromStr for Assertion {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { rest: text };
+
+        parser.expect("pool")?;
+        parser.expect("(")?;
+        let pool_path = parser.string()?;
+        parser.expect(")")?;
+        parser.expect("[")?;
+        let task_idx = parser.number()?;
+        parser.expect("]")?;
+        parser.expect(".")?;
+        let query = match parser.identifier()? {
+            "state" => Query::State,
+            "member" => {
+                parser.expect("(")?;
+                let name = parser.string()?;
+                parser.expect(")")?;
+                Query::Member(name)
+            }
+            other => return Err(format!("expected `state` or `member`, found `{other}`")),
+        };
+        let operator = match parser.eat("==") {
+            true => Operator::Equal,
+            false if parser.eat("!=") => Operator::NotEqual,
+            false => return Err(parser.error("`==` or `!=`")),
+        };
+        let expected = parser.literal()?;
+
+        parser.skip_whitespace();
+        if !parser.rest.is_empty() {
+            return Err(parser.error("the end of the assertion"));
+        }
+
+        Ok(Self {
+            text: text.trim().to_owned(),
+            pool_path,
+            task_idx,
+            query,
+            operator,
+            expected,
+        })
+    }
+}
+
+impl Assertion {
+    /// Check the assertion against the values of the task pools, `format` formats the bytes of a
+    /// member as they are shown in the TUI.
+    ///
+    /// Returns why the assertion does not hold, also when the task or member does not exist.
+    pub(crate) fn check(
+        &self,
+        values: &[TaskPoolValue],
+        mut format: impl FnMut(&[u8], &Type) -> String,
+    ) -> Result<(), String> {
+        let pool = values
+            .iter()
+            .find(|pool| pool.task_pool.path == self.pool_path)
+            .ok_or_else(|| format!("there is no task pool {}", self.pool_path))?;
+        let task = pool.task_values.get(self.task_idx).ok_or_else(|| {
+            format!(
+                "task pool {} has only {} tasks",
+                self.pool_path,
+                pool.task_values.len()
+            )
+        })?;
+        let TaskValue::Init(future, _) = task else {
+            return Err(format!("task {} is not spawned", self.task_idx));
+        };
+        let FutureValueKind::AsyncFn(async_fn) = &future.kind else {
+            return Err(format!("task {} is not an async fn", self.task_idx));
+        };
+        let state = async_fn.state_value.as_ref().map_err(|(discriminant, _)| {
+            format!("state has invalid discriminant {discriminant}")
+        })?;
+
+        let actual = match &self.query {
+            Query::State => state.state.name.clone(),
+            Query::Member(name) => {
+                let member = state
+                    .members
+                    .iter()
+                    .find(|member| member.member.name == *name)
+                    .ok_or_else(|| format!("state {} has no member {name}", state.state.name))?;
+                format(&member.bytes, &member.member.ty)
+            }
+        };
+
+        let holds = match self.operator {
+            Operator::Equal => actual == self.expected,
+            Operator::NotEqual => actual != self.expected,
+        };
+        match holds {
+            true => Ok(()),
+            false => Err(format!("found {actual:?}")),
+        }
+    }
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.rest.is_empty() {
+            true => format!("expected {expected}, found the end of the assertion"),
+            false => format!("expected {expected}, found `{}`", self.rest),
+        }
+    }
+
+    /// Consume `token` if the rest starts with it.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error(&format!("`{token}`"))),
+        }
+    }
+
+    /// Consume the characters at the start of the rest for which `f` is true.
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let end = self.rest.find(|c| !f(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, String> {
+        match self.take_while(|c| c.is_alphanumeric() || c == '_') {
+            "" => Err(self.error("a name")),
+            identifier => Ok(identifier),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        digits.parse().map_err(|_| self.error("a task index"))
+    }
+
+    /// A string in double quotes, `\"` and `\\` can be used inside it.
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+
+        let mut string = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[idx + 1..];
+                    return Ok(string);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped @ ('"' | '\\'))) => string.push(escaped),
+                    _ => return Err(String::from("only `\\\"` and `\\\\` can be escaped")),
+                },
+                c => string.push(c),
+            }
+        }
+        Err(String::from("unterminated string"))
+    }
+
+    /// The value to compare with, a string or a bare value like a number.
+    fn literal(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        if self.rest.starts_with('"') {
+            return self.string();
+        }
+        match self.take_while(|c| !c.is_whitespace()) {
+            "" => Err(self.error("a value")),
+            value => Ok(value.to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let assertion: Assertion = r#"pool("app::worker")[0].state == "Suspend3""#.parse().unwrap();
+        assert_eq!(assertion.pool_path, "app::worker");
+        assert_eq!(assertion.task_idx, 0);
+        assert_eq!(assertion.query, Query::State);
+        assert_eq!(assertion.operator, Operator::Equal);
+        assert_eq!(assertion.expected, "Suspend3");
+
+        let assertion: Assertion = r#" pool ( "app::worker" ) [12] . member("name") != "a \"b\"" "#
+            .parse()
+            .unwrap();
+        assert_eq!(assertion.task_idx, 12);
+        assert_eq!(assertion.query, Query::Member(String::from("name")));
+        assert_eq!(assertion.operator, Operator::NotEqual);
+        assert_eq!(assertion.expected, "a \"b\"");
+
+        let assertion: Assertion = r#"pool("a")[0].member("retries") == 3"#.parse().unwrap();
+        assert_eq!(assertion.expected, "3");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            r#"pool("a")[0].state"#.parse::<Assertion>(),
+            Err(String::from(
+                "expected `==` or `!=`, found the end of the assertion"
+            ))
+        );
+        assert_eq!(
+            r#"pool("a")[0].value == 1"#.parse::<Assertion>(),
+            Err(String::from("expected `state` or `member`, found `value`"))
+        );
+        assert_eq!(
+            r#"pool("a)[0].state == 1"#.parse::<Assertion>(),
+            Err(String::from("unterminated string"))
+        );
+        assert_eq!(
+            r#"pool("a")[0].state == 1 2"#.parse::<Assertion>(),
+            Err(String::from("expected the end of the assertion, found `2`"))
+        );
+    }
+}
@@ -1,7 +1,58 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 use crate::Type;
 
+/// The kind of breakpoint the inspector would like a backend to set.
+///
+/// Cores only have a few hardware breakpoints, while there is a poll done breakpoint for every
+/// executor and every place the poll function got inlined. Software breakpoints need the code to be
+/// writable, backends that can't set them return an error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakpointKind {
+    #[default]
+    Hardware,
+    Software,
+}
+
+impl std::str::FromStr for BreakpointKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hardware" => Ok(Self::Hardware),
+            "software" => Ok(Self::Software),
+            _ => Err(format!(
+                "unknown breakpoint kind `{s}`, expected hardware or software"
+            )),
+        }
+    }
+}
+
+/// Error a [`Callback`] can return when the connection to the target was lost.
+///
+/// The inspector then shows that the target is disconnected until memory can be read again or
+/// [`Event::Reconnected`](crate::Event::Reconnected) is sent. It can be wrapped with
+/// [`anyhow::Context`] to add details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetDisconnected;
+
+impl std::fmt::Display for TargetDisconnected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "target disconnected")
+    }
+}
+
+impl std::error::Error for TargetDisconnected {}
+
+impl TargetDisconnected {
+    /// Whether the error is caused by a [`TargetDisconnected`].
+    pub fn is_cause_of(error: &anyhow::Error) -> bool {
+        error.chain().any(|e| e.is::<Self>())
+    }
+}
+
 /// Trait with methods an [`EmbassyInspector`](crate::EmbassyInspector) can call.
 ///
 /// A backend should have a single implementation of this trait, providing it when calling methods
@@ -18,20 +69,82 @@ pub trait Callback {
     /// It is valid to just use the address as the id, but some backend allow setting multiple
     /// breakpoints on the same address, these could for example return a hash of the breakpoint
     /// object.
-    fn set_breakpoint(&mut self, addr: u64) -> Result<u64>;
+    ///
+    /// The `kind` is a preference, see [`BreakpointKind`].
+    fn set_breakpoint(&mut self, addr: u64, kind: BreakpointKind) -> Result<u64>;
+
+    /// Remove a breakpoint set by [`set_breakpoint`](Self::set_breakpoint), by its id.
+    ///
+    /// Called from [`EmbassyInspector::shutdown`](crate::EmbassyInspector::shutdown), when the
+    /// inspector quits on [`Event::Quit`](crate::Event::Quit) or when a backend tears it down
+    /// itself, like the probe-rs backend after `--once` or `--assert` and the GDB backend when its
+    /// window is closed. Backends that remove all breakpoints themselves when exiting can keep the
+    /// default implementation.
+    fn clear_breakpoint(&mut self, _id: u64) -> Result<()> {
+        Ok(())
+    }
 
     /// Resume executing code on the target. Do nothing if already executing.
     ///
     /// Take care to implement this in a non blocking way.
     fn resume(&mut self) -> Result<()>;
 
+    /// Halt the target, for [`Event::Halt`](crate::Event::Halt). Do nothing if already halted.
+    ///
+    /// Backends that can't halt the target can keep the default implementation returning an
+    /// error, which is shown to the user.
+    fn halt(&mut self) -> Result<()> {
+        anyhow::bail!("This backend can't halt the target")
+    }
+
     /// Read `len` bytes at `addr` from the target.
+    ///
+    /// Return [`TargetDisconnected`] if the connection to the target was lost.
     fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>>;
 
+    /// Write `bytes` at `addr` to the target, to change the state of a task while it is halted.
+    ///
+    /// Only called when the user edits a member while the target is stopped. Backends that can't
+    /// write memory can keep the default implementation returning an error, which is shown to the
+    /// user.
+    fn write_memory(&mut self, _addr: u64, _bytes: &[u8]) -> Result<()> {
+        anyhow::bail!("This backend can't write memory")
+    }
+
     /// Try to format the given bytes as a type of the given name, this function if allowed to
     /// return `None` if the bytes are invalid or if this backend does not support formatting
     /// values.
     ///
     /// The returned string is allowed to contain ANSI escape codes for coloring.
     fn try_format_value(&mut self, bytes: &[u8], ty: &Type) -> Option<String>;
+
+    /// Read the program counter of the stopped target.
+    ///
+    /// Used to find the task that is currently executing, backends that can't read the program
+    /// counter can keep the default implementation returning `None`.
+    fn read_pc(&mut self) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Called when handling [`Event::Tick`](crate::Event::Tick) and
+    /// [`Event::Redraw`](crate::Event::Redraw), for periodic work like flushing statistics at the
+    /// pace of the inspector.
+    ///
+    /// This must be cheap and must not block, it delays drawing the TUI. Errors are passed back
+    /// from [`handle_event`](crate::EmbassyInspector::handle_event).
+    fn on_idle(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Copy plain text, like a page or the value of a member, to the clipboard, returning a
+    /// message to show to the user.
+    ///
+    /// The default implementation writes it to a file in the temporary directory, as there is
+    /// often no clipboard to reach from a headless debugger.
+    fn copy_to_clipboard(&mut self, text: &str) -> Result<String> {
+        let path = std::env::temp_dir().join("inspect-embassy-page.txt");
+        std::fs::write(&path, text)?;
+        log::info!("Copied text written to {}", path.display());
+        Ok(format!("Written to {}", path.display()))
+    }
 }
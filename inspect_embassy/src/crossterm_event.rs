@@ -0,0 +1,65 @@
+//! Translation of crossterm events for backends drawing to a terminal with crossterm, enabled by
+//! the `crossterm` feature.
+
+use ratatui::{
+    crossterm::event::{self, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind},
+    layout::Position,
+};
+
+use crate::{Click, ClickButton, Event, KeyCode};
+
+/// The [`Event`] to send for a crossterm event, `None` for events the inspector does not use.
+///
+/// `ctrl-c` quits, the other keys are sent as [`Event::Key`] of which `q` also quits, `s` halts and
+/// `c` continues when they are not typed into a filter. The mouse can click, drag with the left
+/// button and scroll.
+pub fn from_crossterm(event: event::Event) -> Option<Event> {
+    let event = match event {
+        event::Event::Key(key_event) => {
+            let ctrl_c = key_event.modifiers.contains(KeyModifiers::CONTROL)
+                && key_event.code == event::KeyCode::Char('c');
+            if ctrl_c {
+                return Some(Event::Quit);
+            }
+            // Some terminals also report releasing and repeating keys.
+            if key_event.kind == KeyEventKind::Release {
+                return None;
+            }
+            Event::Key(match key_event.code {
+                event::KeyCode::Up => KeyCode::Up,
+                event::KeyCode::Down => KeyCode::Down,
+                event::KeyCode::Left => KeyCode::Left,
+                event::KeyCode::Right => KeyCode::Right,
+                event::KeyCode::Enter => KeyCode::Enter,
+                event::KeyCode::Backspace => KeyCode::Backspace,
+                event::KeyCode::Esc => KeyCode::Esc,
+                event::KeyCode::Char(c) => KeyCode::Char(c),
+                _ => return None,
+            })
+        }
+        event::Event::Mouse(mouse_event) => {
+            let pos = Position {
+                x: mouse_event.column,
+                y: mouse_event.row,
+            };
+            match mouse_event.kind {
+                MouseEventKind::Down(button) => Event::Click(Click {
+                    pos,
+                    button: match button {
+                        MouseButton::Left => ClickButton::Left,
+                        MouseButton::Right => ClickButton::Right,
+                        MouseButton::Middle => ClickButton::Middle,
+                    },
+                }),
+                MouseEventKind::Drag(MouseButton::Left) => Event::Drag(pos),
+                MouseEventKind::ScrollDown => Event::ScrollAt { lines: -3, pos },
+                MouseEventKind::ScrollUp => Event::ScrollAt { lines: 3, pos },
+                _ => return None,
+            }
+        }
+        event::Event::Resize(_, _) => Event::Redraw,
+        _ => return None,
+    };
+
+    Some(event)
+}
@@ -0,0 +1,295 @@
+//! Recording and replaying of the [`Event`]s sent to an inspector.
+//!
+//! With [`Config::record_events`] every handled event is written to a file, one JSON object per
+//! line together with the size of the terminal and the time since the start at that moment. Calls
+//! made by the backend that use the callback, like [`EmbassyInspector::snapshot_json`], are written
+//! in between. Replaying those with the transcript of the same session written by a
+//! [`RecordingCallback`](crate::RecordingCallback) in [`replay_session`] draws exactly what was
+//! shown at the end of the session, so a reproduction of a bug in the UI can be turned into a test.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+use ratatui::{backend::TestBackend, buffer::Buffer, layout::Position};
+use serde::{Deserialize, Serialize};
+
+use crate::{Config, EmbassyInspector, Event, ReplayCallback};
+
+/// Serialization of [`Position`], which has no serde implementations without a ratatui feature.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Position")]
+pub(crate) struct PositionDef {
+    x: u16,
+    y: u16,
+}
+
+/// A single handled event.
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedEvent {
+    /// Size of the terminal the event was handled in, clicks only hit the same lines in a
+    /// terminal of the same size.
+    width: u16,
+    height: u16,
+    /// Milliseconds since the inspector was created, the parked times are measured with it.
+    #[serde(default)]
+    elapsed_ms: u64,
+    event: Event,
+}
+
+/// A call of a method of the inspector by the backend other than
+/// [`EmbassyInspector::handle_event`] that uses the callback.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+pub(crate) enum RecordedCall {
+    /// [`EmbassyInspector::snapshot_json`].
+    SnapshotJson,
+}
+
+/// A line of the event log.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum LogLine {
+    Event(RecordedEvent),
+    Call(RecordedCall),
+}
+
+/// Writes the events handled by an inspector to a file, see the [module](self) docs.
+#[derive(Debug)]
+pub(crate) struct EventRecorder {
+    writer: BufWriter<File>,
+}
+
+impl EventRecorder {
+    /// Create or truncate the event log at `path`.
+    pub(crate) fn new(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: &Event, width: u16, height: u16, elapsed: Duration) {
+        self.write(&LogLine::Event(RecordedEvent {
+            width,
+            height,
+            elapsed_ms: elapsed.as_millis() as u64,
+            event: event.clone(),
+        }));
+    }
+
+    pub(crate) fn record_call(&mut self, call: RecordedCall) {
+        self.write(&LogLine::Call(call));
+    }
+
+    fn write(&mut self, line: &LogLine) {
+        let result = serde_json::to_writer(&mut self.writer, line)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| Ok(self.writer.write_all(b"\n")?))
+            // Flushing every event so the log is complete even if the backend crashes.
+            .and_then(|()| Ok(self.writer.flush()?));
+
+        if let Err(e) = result {
+            log::error!("Could not write to the event log: {e}");
+        }
+    }
+}
+
+/// Replay a recorded session, returning the buffer that was drawn after the last event.
+///
+/// `transcript` is the file written by a [`RecordingCallback`](crate::RecordingCallback) and
+/// `events` the one written for [`Config::record_events`] during the same session. The `config`
+/// and the `futures` added with [`EmbassyInspector::inspect_future`] at the start must be the same
+/// as during the recording, otherwise the calls no longer match the transcript. Fails when a call
+/// does not match or when not all recorded calls got replayed.
+pub fn replay_session(
+    transcript: impl AsRef<Path>,
+    events: impl AsRef<Path>,
+    config: Config,
+    futures: &[(u64, String)],
+) -> Result<Buffer> {
+    let lines = read_log(events)?;
+    let (width, height) = lines
+        .iter()
+        .find_map(|line| match line {
+            LogLine::Event(event) => Some((event.width, event.height)),
+            LogLine::Call(_) => None,
+        })
+        .unwrap_or((80, 24));
+
+    let mut callback = ReplayCallback::from_file(transcript)?;
+    let config = Config {
+        record_events: None,
+        ..config
+    };
+    let mut inspector =
+        EmbassyInspector::with_config(TestBackend::new(width, height), &mut callback, config)?;
+    for (address, type_path) in futures {
+        inspector.inspect_future(*address, type_path, &mut callback)?;
+    }
+
+    for line in lines {
+        match line {
+            LogLine::Event(recorded) => {
+                let backend = inspector.terminal.backend_mut();
+                if backend.buffer().area.as_size() != (recorded.width, recorded.height).into() {
+                    backend.resize(recorded.width, recorded.height);
+                }
+                let elapsed = Duration::from_millis(recorded.elapsed_ms);
+                inspector.handle_event_at(recorded.event, elapsed, &mut callback)?;
+            }
+            LogLine::Call(RecordedCall::SnapshotJson) => {
+                inspector.snapshot_json(&mut callback);
+            }
+        }
+    }
+
+    if !callback.is_done() {
+        bail!("Not all recorded calls were replayed, the session went differently");
+    }
+
+    Ok(inspector.terminal.backend().buffer().clone())
+}
+
+fn read_log(path: impl AsRef<Path>) -> Result<Vec<LogLine>> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut lines = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        lines.push(serde_json::from_str(&line)?);
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{BreakpointKind, Callback, Click, ClickButton, KeyCode, RecordingCallback, Type};
+
+    #[test]
+    fn event_json() {
+        let event = RecordedEvent {
+            width: 80,
+            height: 24,
+            elapsed_ms: 1500,
+            event: Event::Click(Click {
+                pos: Position::new(3, 4),
+                button: ClickButton::Left,
+            }),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"width":80,"height":24,"elapsed_ms":1500,"event":{"click":{"pos":{"x":3,"y":4},"button":"left"}}}"#
+        );
+
+        let parsed: RecordedEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.elapsed_ms, 1500);
+        assert!(matches!(
+            parsed.event,
+            Event::Click(Click {
+                pos: Position { x: 3, y: 4 },
+                button: ClickButton::Left
+            })
+        ));
+
+        // Logs from before the time was recorded.
+        let parsed: RecordedEvent =
+            serde_json::from_str(r#"{"width":80,"height":24,"event":"redraw"}"#).unwrap();
+        assert_eq!(parsed.elapsed_ms, 0);
+
+        let parsed: RecordedEvent =
+            serde_json::from_str(r#"{"width":80,"height":24,"event":{"scroll":-3}}"#).unwrap();
+        assert!(matches!(parsed.event, Event::Scroll(-3)));
+
+        let parsed: RecordedEvent = serde_json::from_str(
+            r#"{"width":80,"height":24,"event":{"scroll_at":{"lines":3,"pos":{"x":70,"y":5}}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            parsed.event,
+            Event::ScrollAt {
+                lines: 3,
+                pos: Position { x: 70, y: 5 }
+            }
+        ));
+    }
+
+    #[test]
+    fn call_json() {
+        let json = serde_json::to_string(&LogLine::Call(RecordedCall::SnapshotJson)).unwrap();
+        assert_eq!(json, r#"{"call":"snapshot_json"}"#);
+
+        let parsed: LogLine = serde_json::from_str(&json).unwrap();
+        assert!(matches!(parsed, LogLine::Call(RecordedCall::SnapshotJson)));
+        let parsed: LogLine =
+            serde_json::from_str(r#"{"width":80,"height":24,"event":{"scroll":-3}}"#).unwrap();
+        assert!(matches!(parsed, LogLine::Event(_)));
+    }
+
+    /// Callback for a fake target running the app fixture, with memory counting up from 0.
+    struct FakeTarget;
+
+    impl Callback for FakeTarget {
+        fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+            Ok([crate::model::fixtures::path("app.elf")].into_iter())
+        }
+
+        fn set_breakpoint(&mut self, addr: u64, _kind: BreakpointKind) -> Result<u64> {
+            Ok(addr)
+        }
+
+        fn resume(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
+            Ok((addr..addr + len).map(|b| b as u8).collect())
+        }
+
+        fn try_format_value(&mut self, bytes: &[u8], _ty: &Type) -> Option<String> {
+            Some(format!("{bytes:?}"))
+        }
+    }
+
+    #[test]
+    fn record_and_replay_session() -> Result<()> {
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let transcript = dir.join(format!("inspect-embassy-session-{id}.jsonl"));
+        let events = dir.join(format!("inspect-embassy-events-{id}.jsonl"));
+        let config = Config {
+            record_events: Some(events.clone()),
+            ..Config::default()
+        };
+
+        let mut callback = RecordingCallback::new(FakeTarget, &transcript)?;
+        let mut inspector = EmbassyInspector::with_config(
+            TestBackend::new(100, 30),
+            &mut callback,
+            config.clone(),
+        )?;
+        inspector.handle_event(Event::Stoped, &mut callback)?;
+        inspector.handle_event(Event::Key(KeyCode::Down), &mut callback)?;
+        inspector.snapshot_json(&mut callback);
+        inspector.handle_event(Event::Key(KeyCode::Enter), &mut callback)?;
+        inspector.handle_event(Event::Tick, &mut callback)?;
+        let recorded = inspector.terminal.backend().buffer().clone();
+        drop(inspector);
+        drop(callback);
+
+        let replayed = replay_session(&transcript, &events, config, &[]);
+        std::fs::remove_file(&transcript)?;
+        std::fs::remove_file(&events)?;
+        assert_eq!(replayed?, recorded);
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//! Export of what every task is waiting on as folded stacks, the input of flamegraph tools like
+//! `inferno` and FlameGraph.
+//!
+//! Every time the target stops a sample is added per spawned task, so over a session the
+//! flamegraph shows at which await points the tasks spent their time suspended.
+
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use crate::model::{
+    future::{FutureValue, FutureValueKind},
+    task_pool::{TaskPoolValue, TaskValue},
+};
+
+/// Append a line per active leaf future of every spawned task to the file at `path`.
+pub(crate) fn append_folded_stacks(path: &Path, values: &[TaskPoolValue]) -> std::io::Result<()> {
+    let mut lines = Vec::new();
+    for pool in values {
+        for (task_idx, task) in pool.task_values.iter().enumerate() {
+            if let TaskValue::Init(value, _) = task {
+                let root = frame(&format!("{}[{task_idx}]", pool.task_pool.path));
+                add_stacks(value, &mut vec![root], &mut lines);
+            }
+        }
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.concat().as_bytes())
+}
+
+/// Add the stacks from the frames in `stack` through `value` to each of the futures it is
+/// waiting on at the end, a join waiting on two futures adds two stacks.
+fn add_stacks(value: &FutureValue, stack: &mut Vec<String>, lines: &mut Vec<String>) {
+    let depth = stack.len();
+
+    stack.push(frame(&value.ty.to_string()));
+    if let FutureValueKind::AsyncFn(async_fn) = &value.kind {
+        stack.push(match &async_fn.state_value {
+            Ok(state) => match &state.state.source {
+                Some(source) => frame(&format!("{} at {source}", state.state.name)),
+                None => frame(&state.state.name),
+            },
+            Err((Some(discriminant), _)) => format!("unknown state {discriminant}"),
+            Err((None, _)) => String::from("unreadable state"),
+        });
+    }
+
+    let children = value.children();
+    if children.is_empty() {
+        lines.push(format!("{} 1\n", stack.join(";")));
+    }
+    for (_, child) in children {
+        add_stacks(child, stack, lines);
+    }
+
+    stack.truncate(depth);
+}
+
+/// Frames are separated by `;`, which can also be part of type names like `[u8; 4]`.
+fn frame(name: &str) -> String {
+    name.replace(';', ",")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::model::{future::JoinValue, ty::Type};
+
+    fn unknown(name: &str) -> FutureValue {
+        FutureValue {
+            ty: Type::Base(name.to_owned()),
+            address: 0,
+            kind: FutureValueKind::Unknown(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn stack_per_leaf() {
+        let join = FutureValue {
+            ty: Type::Base("Join<[u8; 4]>".to_owned()),
+            address: 0,
+            kind: FutureValueKind::JoinValue(JoinValue {
+                awaitees: Box::new([unknown("Timer"), unknown("Wait")]),
+            }),
+        };
+
+        let mut lines = Vec::new();
+        add_stacks(&join, &mut vec![String::from("app::run[0]")], &mut lines);
+        assert_eq!(
+            lines,
+            vec![
+                "app::run[0];Join<[u8, 4]>;Timer 1\n",
+                "app::run[0];Join<[u8, 4]>;Wait 1\n",
+            ]
+        );
+    }
+}
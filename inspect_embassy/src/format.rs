@@ -0,0 +1,1190 @@
+//! Formatting of raw values read from the target.
+//!
+//! Values are formatted by the first of these that succeeds:
+//! 1. Builtin formatting for well known types and tuples, see [`BuiltinType`].
+//! 2. Arrays, formatting each of the elements.
+//! 3. Fieldless enums, showing the name of the variant.
+//! 4. The formatter of the backend, see [`Callback::try_format_value`].
+//! 5. The fallback chosen with [`UnformattableValues`], by default a list of the raw bytes.
+//!
+//! Arrays and collections only show their first `max_elements` elements, see
+//! [`Config::max_elements`](crate::Config::max_elements).
+
+use std::collections::HashMap;
+
+use ratatui::{
+    style::Stylize,
+    text::{Line, Span},
+};
+
+use crate::{
+    Callback, UnformattableValues,
+    model::{builtin::BuiltinType, ty::Type},
+};
+
+/// Format a value of the given type.
+pub(crate) fn format_value<C: Callback>(
+    bytes: &[u8],
+    ty: &Type,
+    builtin_types: &HashMap<Type, BuiltinType>,
+    unformattable: UnformattableValues,
+    max_elements: usize,
+    callback: &mut C,
+) -> Line<'static> {
+    if let Some(builtin_type) = builtin_types.get(ty)
+        && let Some(line) = format_builtin(
+            bytes,
+            builtin_type,
+            builtin_types,
+            unformattable,
+            max_elements,
+            callback,
+        )
+    {
+        return line;
+    }
+
+    if let Type::Array { inner, count } = ty
+        && let Some(elements) = array_elements(bytes, *count)
+    {
+        return format_elements(
+            elements,
+            inner,
+            builtin_types,
+            unformattable,
+            max_elements,
+            callback,
+        );
+    }
+
+    if let Some(line) = format_enum(bytes, ty) {
+        return line;
+    }
+
+    if let Some(line) = format_with_backend(bytes, ty, callback) {
+        return line;
+    }
+
+    if let Some(line) = format_pointer(bytes, ty) {
+        return line;
+    }
+
+    match unformattable {
+        UnformattableValues::Bytes => format_bytes(bytes),
+        UnformattableValues::Placeholder => {
+            Line::raw(format!("<{ty}: {} bytes>", bytes.len())).dark_gray()
+        }
+        UnformattableValues::Hidden => Line::default(),
+    }
+}
+
+/// Format the bytes as a list of hex values.
+pub(crate) fn format_bytes(bytes: &[u8]) -> Line<'static> {
+    Line::from_iter([
+        Span::raw("bytes ["),
+        Span::raw(
+            bytes
+                .iter()
+                .map(|b| format!(" {b:0>2x}"))
+                .collect::<String>(),
+        )
+        .blue(),
+        Span::raw(" ]"),
+    ])
+}
+
+/// The bytes as `[2a 00 00 00]`, to show next to a decoded value.
+pub(crate) fn format_raw_bytes(bytes: &[u8]) -> String {
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{b:0>2x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("[{hex}]")
+}
+
+/// Parse bytes typed as hex, like [`format_raw_bytes`] formats them. Spaces and the brackets are
+/// optional.
+pub(crate) fn parse_raw_bytes(text: &str) -> Result<Vec<u8>, String> {
+    let digits = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '[' | ']'))
+        .collect::<Vec<_>>();
+    if digits.len() % 2 != 0 {
+        return Err(String::from("expected two hex digits per byte"));
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = pair.iter().collect::<String>();
+            u8::from_str_radix(&pair, 16).map_err(|_| format!("`{pair}` is not a hex byte"))
+        })
+        .collect()
+}
+
+fn format_with_backend<C: Callback>(
+    bytes: &[u8],
+    ty: &Type,
+    callback: &mut C,
+) -> Option<Line<'static>> {
+    let formatted = callback.try_format_value(bytes, ty)?;
+
+    Some(ansi_to_line(&formatted))
+}
+
+/// Convert a string with ANSI escape codes to a line, falling back to the plain text if the escape
+/// codes can't be parsed.
+fn ansi_to_line(formatted: &str) -> Line<'static> {
+    match ansi_to_tui::IntoText::into_text(&formatted) {
+        Ok(text) => Line::from_iter(text.into_iter().flatten()),
+        Err(e) => {
+            log::debug!("Could not parse escape codes in {formatted:?}: {e}");
+            Line::raw(strip_escapes(formatted))
+        }
+    }
+}
+
+/// Remove all escape sequences (CSI, OSC and two character ones) from the string.
+fn strip_escapes(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: parameters and intermediates until a final byte in `@..=~`
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: until BEL or ST (`ESC \`)
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Split the bytes of an array into its `count` elements.
+fn array_elements(bytes: &[u8], count: u64) -> Option<Vec<&[u8]>> {
+    let count = usize::try_from(count).ok()?;
+    if count == 0 {
+        return bytes.is_empty().then(Vec::new);
+    }
+    if bytes.len() % count != 0 {
+        return None;
+    }
+
+    Some(bytes.chunks_exact(bytes.len() / count).collect())
+}
+
+/// Format a value that is part of another one, like an element of an array. A hidden value is
+/// shown as `…` there, so the surrounding value still reads like `[…, …]` instead of `[, ]`.
+fn format_nested<C: Callback>(
+    bytes: &[u8],
+    ty: &Type,
+    builtin_types: &HashMap<Type, BuiltinType>,
+    unformattable: UnformattableValues,
+    max_elements: usize,
+    callback: &mut C,
+) -> Line<'static> {
+    let line = format_value(
+        bytes,
+        ty,
+        builtin_types,
+        unformattable,
+        max_elements,
+        callback,
+    );
+    match line.spans.is_empty() {
+        true => Line::raw("…").dark_gray(),
+        false => line,
+    }
+}
+
+/// Format the elements as a list like `[1, 2, 3]`, only the first `max_elements` are shown.
+fn format_elements<C: Callback>(
+    elements: Vec<&[u8]>,
+    element_type: &Type,
+    builtin_types: &HashMap<Type, BuiltinType>,
+    unformattable: UnformattableValues,
+    max_elements: usize,
+    callback: &mut C,
+) -> Line<'static> {
+    let mut line = Line::raw("[");
+    for (i, element) in elements.iter().take(max_elements).enumerate() {
+        if i != 0 {
+            line.push_span(", ");
+        }
+        line.extend(format_nested(
+            element,
+            element_type,
+            builtin_types,
+            unformattable,
+            max_elements,
+            callback,
+        ));
+    }
+    if elements.len() > max_elements {
+        if max_elements != 0 {
+            line.push_span(", ");
+        }
+        line.push_span(Span::raw(format!("… +{} more", elements.len() - max_elements)).dark_gray());
+    }
+    line.push_span("]");
+    line
+}
+
+fn format_builtin<C: Callback>(
+    bytes: &[u8],
+    builtin_type: &BuiltinType,
+    builtin_types: &HashMap<Type, BuiltinType>,
+    unformattable: UnformattableValues,
+    max_elements: usize,
+    callback: &mut C,
+) -> Option<Line<'static>> {
+    match builtin_type {
+        BuiltinType::Transparent { value } => Some(format_value(
+            value.bytes(bytes)?,
+            &value.ty,
+            builtin_types,
+            unformattable,
+            max_elements,
+            callback,
+        )),
+        BuiltinType::Cell { value } => {
+            let inner = format_nested(
+                value.bytes(bytes)?,
+                &value.ty,
+                builtin_types,
+                unformattable,
+                max_elements,
+                callback,
+            );
+
+            let mut line = Line::raw("Cell(");
+            line.extend(inner);
+            line.push_span(")");
+            Some(line)
+        }
+        BuiltinType::RefCell { borrow, value } => {
+            let borrow_state = match decode_signed(borrow.bytes(bytes)?)? {
+                0 => String::from("unborrowed"),
+                1 => String::from("1 shared borrow"),
+                n if n > 0 => format!("{n} shared borrows"),
+                _ => String::from("mutably borrowed"),
+            };
+            let inner = format_nested(
+                value.bytes(bytes)?,
+                &value.ty,
+                builtin_types,
+                unformattable,
+                max_elements,
+                callback,
+            );
+
+            let mut line = Line::raw("RefCell(");
+            line.extend(inner);
+            line.push_span(Span::raw(format!(", {borrow_state}")).gray());
+            line.push_span(")");
+            Some(line)
+        }
+        BuiltinType::Scaled { base, scale } => {
+            let integer = decode_integer(bytes, base)?;
+            Some(Line::raw((integer as f64 / scale).to_string()))
+        }
+        BuiltinType::EmbassyTicks {
+            ticks,
+            tick_hz,
+            instant,
+        } => {
+            let ticks = decode_unsigned(ticks.bytes(bytes)?)?;
+            let formatted = if ticks == u64::MAX as u128 {
+                String::from("MAX")
+            } else if *tick_hz == 0 {
+                return None;
+            } else {
+                format_nanos(ticks * 1_000_000_000 / *tick_hz as u128)
+            };
+
+            match instant {
+                true => Some(Line::raw(format!("{formatted} after boot"))),
+                false => Some(Line::raw(formatted)),
+            }
+        }
+        BuiltinType::CoreDuration { secs, nanos } => {
+            let secs = decode_unsigned(secs.bytes(bytes)?)?;
+            let nanos = decode_unsigned(nanos.bytes(bytes)?)?;
+            Some(Line::raw(format_nanos(secs * 1_000_000_000 + nanos)))
+        }
+        BuiltinType::InlineVec { len, buffer } => {
+            let len = decode_unsigned(len.bytes(bytes)?)?;
+            let elements = buffer.elements(bytes, len.try_into().ok()?)?;
+
+            Some(format_elements(
+                elements,
+                &buffer.element_type,
+                builtin_types,
+                unformattable,
+                max_elements,
+                callback,
+            ))
+        }
+        BuiltinType::HeaplessString { vec } => {
+            let Some(BuiltinType::InlineVec { len, buffer }) = builtin_types.get(&vec.ty) else {
+                return None;
+            };
+            let bytes = vec.bytes(bytes)?;
+            let len = decode_unsigned(len.bytes(bytes)?)?;
+            let elements = buffer.elements(bytes, len.try_into().ok()?)?;
+
+            let string = elements.concat();
+            Some(Line::raw(format!("{:?}", String::from_utf8_lossy(&string))))
+        }
+        BuiltinType::WakerRegistration { vtable } => match decode_unsigned(vtable.bytes(bytes)?)? {
+            0 => Some(Line::raw("no waker").gray()),
+            _ => Some(Line::raw("waker registered").blue()),
+        },
+        BuiltinType::Atomic { value } => {
+            let mut line = format_nested(
+                value.bytes(bytes)?,
+                &value.ty,
+                builtin_types,
+                unformattable,
+                max_elements,
+                callback,
+            );
+            line.push_span(Span::raw(" (atomic)").dark_gray());
+            Some(line)
+        }
+        BuiltinType::Tuple { elements } => {
+            let mut line = Line::raw("(");
+            for (i, element) in elements.iter().enumerate() {
+                if i != 0 {
+                    line.push_span(", ");
+                }
+                line.extend(format_nested(
+                    element.bytes(bytes)?,
+                    &element.ty,
+                    builtin_types,
+                    unformattable,
+                    max_elements,
+                    callback,
+                ));
+            }
+            if elements.len() == 1 {
+                line.push_span(",");
+            }
+            line.push_span(")");
+            Some(line)
+        }
+    }
+}
+
+/// Format a number of nanoseconds in the largest unit that keeps it at least 1, like `1.5s`.
+fn format_nanos(nanos: u128) -> String {
+    let (unit, nanos_per_unit) = match nanos {
+        1_000_000_000.. => ("s", 1_000_000_000),
+        1_000_000.. => ("ms", 1_000_000),
+        1_000.. => ("µs", 1_000),
+        _ => return format!("{nanos}ns"),
+    };
+
+    let whole = nanos / nanos_per_unit;
+    // Up to 3 decimals, without trailing zeros
+    let fraction = nanos % nanos_per_unit * 1000 / nanos_per_unit;
+    if fraction == 0 {
+        format!("{whole}{unit}")
+    } else {
+        let fraction = format!("{fraction:03}");
+        format!("{whole}.{}{unit}", fraction.trim_end_matches('0'))
+    }
+}
+
+/// Decode a little endian unsigned integer of at most 16 bytes.
+pub(crate) fn decode_unsigned(bytes: &[u8]) -> Option<u128> {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return None;
+    }
+
+    let mut buf = [0; 16];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    Some(u128::from_le_bytes(buf))
+}
+
+/// Decode a little endian signed integer of at most 16 bytes.
+pub(crate) fn decode_signed(bytes: &[u8]) -> Option<i128> {
+    let unsigned = decode_unsigned(bytes)?;
+    let shift = 128 - bytes.len() as u32 * 8;
+    Some(((unsigned << shift) as i128) >> shift)
+}
+
+/// Decode the leading bytes as the given primitive integer type, `usize` and `isize` use all bytes.
+fn decode_integer(bytes: &[u8], ty: &Type) -> Option<i128> {
+    let Type::Base(name) = ty else {
+        return None;
+    };
+
+    let size = match name.as_str() {
+        "u8" | "i8" => 1,
+        "u16" | "i16" => 2,
+        "u32" | "i32" => 4,
+        "u64" | "i64" => 8,
+        "usize" | "isize" => bytes.len(),
+        _ => return None,
+    };
+    let bytes = bytes.get(..size)?;
+
+    if name.starts_with('i') {
+        decode_signed(bytes)
+    } else {
+        decode_unsigned(bytes).map(|i| i as i128)
+    }
+}
+
+/// Format a fieldless enum as the name of its variant, or as the discriminant when no variant has
+/// it, which happens when the memory is corrupted or not initialized yet.
+fn format_enum(bytes: &[u8], ty: &Type) -> Option<Line<'static>> {
+    let Type::Enum { variants, .. } = ty else {
+        return None;
+    };
+
+    let discriminant = decode_unsigned(bytes.get(..bytes.len().min(8))?)? as u64;
+    // Negative discriminants are sign extended in the debug data.
+    let mask = u64::MAX >> (64 - 8 * bytes.len().min(8) as u32);
+    match variants
+        .iter()
+        .find(|(value, _)| value & mask == discriminant)
+    {
+        Some((_, name)) => Some(Line::raw(name.clone())),
+        None => Some(Line::from_iter([
+            Span::raw(discriminant.to_string()),
+            Span::raw(" (no such variant)").dark_gray(),
+        ])),
+    }
+}
+
+/// Format a thin pointer or reference as the address it points to. The size of the pointer comes
+/// from the debug data, so this works for targets with any pointer width.
+fn format_pointer(bytes: &[u8], ty: &Type) -> Option<Line<'static>> {
+    if !matches!(ty, Type::Pointer(_) | Type::Refrence(_)) || ![2, 4, 8].contains(&bytes.len()) {
+        return None;
+    }
+
+    Some(Line::raw(format!("{:#x}", decode_unsigned(bytes)?)))
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::model::builtin::{ArrayField, Field};
+
+    /// Callback formatting only integers and booleans, like the formatter of a backend without
+    /// pretty printers.
+    struct PrimitiveFormatting;
+
+    impl Callback for PrimitiveFormatting {
+        fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+            Ok(std::iter::empty())
+        }
+
+        fn set_breakpoint(&mut self, addr: u64, _kind: crate::BreakpointKind) -> Result<u64> {
+            Ok(addr)
+        }
+
+        fn resume(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_memory(&mut self, _addr: u64, len: u64) -> Result<Vec<u8>> {
+            Ok(vec![0; len as usize])
+        }
+
+        fn try_format_value(&mut self, bytes: &[u8], ty: &Type) -> Option<String> {
+            match ty {
+                Type::Base(name) if name == "bool" => Some((*bytes.first()? != 0).to_string()),
+                _ => Some(decode_integer(bytes, ty)?.to_string()),
+            }
+        }
+    }
+
+    fn format(bytes: &[u8], ty: &Type, builtin_types: &HashMap<Type, BuiltinType>) -> String {
+        format_value(
+            bytes,
+            ty,
+            builtin_types,
+            UnformattableValues::Bytes,
+            64,
+            &mut PrimitiveFormatting,
+        )
+        .to_string()
+    }
+
+    fn base(name: &str) -> Type {
+        Type::Base(name.to_owned())
+    }
+
+    #[test]
+    fn raw_bytes() {
+        assert_eq!(format_raw_bytes(&[0x2a, 0, 0, 0xff]), "[2a 00 00 ff]");
+        assert_eq!(format_raw_bytes(&[]), "[]");
+    }
+
+    #[test]
+    fn enums() {
+        let builtin_types = HashMap::new();
+        let mode = Type::Enum {
+            name: "app::Mode".to_owned(),
+            variants: vec![
+                (0, "Idle".to_owned()),
+                (1, "Fast".to_owned()),
+                (-1i64 as u64, "Off".to_owned()),
+            ],
+        };
+
+        assert_eq!(format(&[1], &mode, &builtin_types), "Fast");
+        assert_eq!(format(&[0xff], &mode, &builtin_types), "Off");
+        assert_eq!(format(&[0, 0], &mode, &builtin_types), "Idle");
+        assert_eq!(format(&[7], &mode, &builtin_types), "7 (no such variant)");
+    }
+
+    #[test]
+    fn pointers() {
+        let builtin_types = HashMap::new();
+        let pointer = Type::Pointer(Box::new(base("u8")));
+        let reference = Type::Refrence(Box::new(base("u8")));
+
+        assert_eq!(
+            format(&[0x00, 0x10, 0x00, 0x20], &pointer, &builtin_types),
+            "0x20001000"
+        );
+        assert_eq!(
+            format(
+                &[0x00, 0x10, 0x00, 0x80, 0x3f, 0x00, 0x00, 0x00],
+                &reference,
+                &builtin_types
+            ),
+            "0x3f80001000"
+        );
+        // Not a thin pointer.
+        assert_eq!(
+            format(&[0; 3], &pointer, &builtin_types),
+            "bytes [ 00 00 00 ]"
+        );
+    }
+
+    #[test]
+    fn bytes_fallback() {
+        let builtin_types = HashMap::new();
+
+        assert_eq!(
+            format(&[1, 2], &base("u32"), &builtin_types),
+            "bytes [ 01 02 ]"
+        );
+        assert_eq!(
+            format(&[0xab], &base("SomeStruct"), &builtin_types),
+            "bytes [ ab ]"
+        );
+    }
+
+    #[test]
+    fn unformattable_fallbacks() {
+        let builtin_types = HashMap::new();
+        let format = |unformattable| {
+            format_value(
+                &[1, 2, 3],
+                &base("app::Opaque"),
+                &builtin_types,
+                unformattable,
+                64,
+                &mut PrimitiveFormatting,
+            )
+            .to_string()
+        };
+
+        assert_eq!(format(UnformattableValues::Bytes), "bytes [ 01 02 03 ]");
+        assert_eq!(
+            format(UnformattableValues::Placeholder),
+            "<app::Opaque: 3 bytes>"
+        );
+        assert_eq!(format(UnformattableValues::Hidden), "");
+
+        // Hidden elements still take their place in a collection.
+        let array = Type::Array {
+            inner: Box::new(base("app::Opaque")),
+            count: 2,
+        };
+        let formatted = format_value(
+            &[1, 2],
+            &array,
+            &builtin_types,
+            UnformattableValues::Hidden,
+            64,
+            &mut PrimitiveFormatting,
+        );
+        assert_eq!(formatted.to_string(), "[…, …]");
+    }
+
+    #[test]
+    fn strip_unsupported_escapes() {
+        assert_eq!(
+            strip_escapes(
+                "\x1b[34m5\x1b[m \x1b]8;;file:///main.rs\x1b\\main\x1b]8;;\x07 \x1b7done"
+            ),
+            "5 main done"
+        );
+        assert_eq!(strip_escapes("no escapes"), "no escapes");
+        // A truncated sequence at the end is dropped
+        assert_eq!(strip_escapes("value\x1b[3"), "value");
+    }
+
+    #[test]
+    fn atomics() {
+        let mut builtin_types = HashMap::new();
+        for (atomic, inner, size) in [("AtomicU8", "u8", 1), ("AtomicI32", "i32", 4)] {
+            builtin_types.insert(
+                base(&format!("core::cell::UnsafeCell<{inner}>")),
+                BuiltinType::Transparent {
+                    value: Field {
+                        offset: 0,
+                        size,
+                        ty: base(inner),
+                    },
+                },
+            );
+            builtin_types.insert(
+                base(&format!("core::sync::atomic::{atomic}")),
+                BuiltinType::Atomic {
+                    value: Field {
+                        offset: 0,
+                        size,
+                        ty: base(&format!("core::cell::UnsafeCell<{inner}>")),
+                    },
+                },
+            );
+        }
+        builtin_types.insert(
+            base("core::sync::atomic::AtomicU64"),
+            BuiltinType::Atomic {
+                value: Field {
+                    offset: 0,
+                    size: 8,
+                    ty: base("u64"),
+                },
+            },
+        );
+        builtin_types.insert(
+            base("core::sync::atomic::AtomicBool"),
+            BuiltinType::Atomic {
+                value: Field {
+                    offset: 0,
+                    size: 1,
+                    ty: base("bool"),
+                },
+            },
+        );
+
+        assert_eq!(
+            format(
+                &[200],
+                &base("core::sync::atomic::AtomicU8"),
+                &builtin_types
+            ),
+            "200 (atomic)"
+        );
+        assert_eq!(
+            format(
+                &[0xfb, 0xff, 0xff, 0xff],
+                &base("core::sync::atomic::AtomicI32"),
+                &builtin_types
+            ),
+            "-5 (atomic)"
+        );
+        assert_eq!(
+            format(
+                &[0, 0, 0, 0, 1, 0, 0, 0],
+                &base("core::sync::atomic::AtomicU64"),
+                &builtin_types
+            ),
+            "4294967296 (atomic)"
+        );
+        assert_eq!(
+            format(
+                &[1],
+                &base("core::sync::atomic::AtomicBool"),
+                &builtin_types
+            ),
+            "true (atomic)"
+        );
+    }
+
+    #[test]
+    fn cells() {
+        let mut builtin_types = HashMap::new();
+        builtin_types.insert(
+            base("core::cell::UnsafeCell<u16>"),
+            BuiltinType::Transparent {
+                value: Field {
+                    offset: 0,
+                    size: 2,
+                    ty: base("u16"),
+                },
+            },
+        );
+        builtin_types.insert(
+            base("core::cell::Cell<u16>"),
+            BuiltinType::Cell {
+                value: Field {
+                    offset: 0,
+                    size: 2,
+                    ty: base("core::cell::UnsafeCell<u16>"),
+                },
+            },
+        );
+        builtin_types.insert(
+            base("core::cell::RefCell<u16>"),
+            BuiltinType::RefCell {
+                borrow: Field {
+                    offset: 0,
+                    size: 4,
+                    ty: base("core::cell::Cell<isize>"),
+                },
+                value: Field {
+                    offset: 4,
+                    size: 2,
+                    ty: base("core::cell::UnsafeCell<u16>"),
+                },
+            },
+        );
+
+        assert_eq!(
+            format(&[7, 0], &base("core::cell::Cell<u16>"), &builtin_types),
+            "Cell(7)"
+        );
+        assert_eq!(
+            format(
+                &[0, 0, 0, 0, 3, 0],
+                &base("core::cell::RefCell<u16>"),
+                &builtin_types
+            ),
+            "RefCell(3, unborrowed)"
+        );
+        assert_eq!(
+            format(
+                &[0xff, 0xff, 0xff, 0xff, 3, 0],
+                &base("core::cell::RefCell<u16>"),
+                &builtin_types
+            ),
+            "RefCell(3, mutably borrowed)"
+        );
+        // Missing bytes for the inner value make it fall back to the raw bytes.
+        assert_eq!(
+            format(&[7], &base("core::cell::Cell<u16>"), &builtin_types),
+            "bytes [ 07 ]"
+        );
+    }
+
+    #[test]
+    fn durations() {
+        let ticks = Field {
+            offset: 0,
+            size: 8,
+            ty: base("u64"),
+        };
+        let mut builtin_types = HashMap::new();
+        builtin_types.insert(
+            base("embassy_time::duration::Duration"),
+            BuiltinType::EmbassyTicks {
+                ticks: ticks.clone(),
+                tick_hz: 1_000_000,
+                instant: false,
+            },
+        );
+        builtin_types.insert(
+            base("embassy_time::instant::Instant"),
+            BuiltinType::EmbassyTicks {
+                ticks,
+                tick_hz: 32_768,
+                instant: true,
+            },
+        );
+        builtin_types.insert(
+            base("core::time::Duration"),
+            BuiltinType::CoreDuration {
+                secs: Field {
+                    offset: 0,
+                    size: 8,
+                    ty: base("u64"),
+                },
+                nanos: Field {
+                    offset: 8,
+                    size: 4,
+                    ty: base("core::time::Nanoseconds"),
+                },
+            },
+        );
+
+        let embassy_duration = |ticks: u64| {
+            format(
+                &ticks.to_le_bytes(),
+                &base("embassy_time::duration::Duration"),
+                &builtin_types,
+            )
+        };
+        assert_eq!(embassy_duration(1_500_000), "1.5s");
+        assert_eq!(embassy_duration(250_000), "250ms");
+        assert_eq!(embassy_duration(1_250), "1.25ms");
+        assert_eq!(embassy_duration(7), "7µs");
+        assert_eq!(embassy_duration(0), "0ns");
+        assert_eq!(embassy_duration(u64::MAX), "MAX");
+
+        assert_eq!(
+            format(
+                &(3 * 32_768u64).to_le_bytes(),
+                &base("embassy_time::instant::Instant"),
+                &builtin_types
+            ),
+            "3s after boot"
+        );
+
+        let mut bytes = 2u64.to_le_bytes().to_vec();
+        bytes.extend(5_000_000u32.to_le_bytes());
+        bytes.extend([0; 4]); // padding
+        assert_eq!(
+            format(&bytes, &base("core::time::Duration"), &builtin_types),
+            "2.005s"
+        );
+    }
+
+    #[test]
+    fn heapless() {
+        let mut builtin_types = HashMap::new();
+        builtin_types.insert(
+            base("heapless::vec::Vec<u16, 4>"),
+            BuiltinType::InlineVec {
+                len: Field {
+                    offset: 0,
+                    size: 4,
+                    ty: base("usize"),
+                },
+                buffer: ArrayField {
+                    offset: 4,
+                    element_size: 2,
+                    element_type: base("u16"),
+                    count: 4,
+                },
+            },
+        );
+        builtin_types.insert(
+            base("heapless::vec::Vec<u8, 8>"),
+            BuiltinType::InlineVec {
+                len: Field {
+                    offset: 0,
+                    size: 4,
+                    ty: base("usize"),
+                },
+                buffer: ArrayField {
+                    offset: 4,
+                    element_size: 1,
+                    element_type: base("u8"),
+                    count: 8,
+                },
+            },
+        );
+        builtin_types.insert(
+            base("heapless::string::String<8>"),
+            BuiltinType::HeaplessString {
+                vec: Field {
+                    offset: 0,
+                    size: 12,
+                    ty: base("heapless::vec::Vec<u8, 8>"),
+                },
+            },
+        );
+
+        // Only the first `len` elements are shown, the rest is uninitialized.
+        let vec = [2, 0, 0, 0, 7, 0, 9, 0, 0xaa, 0xaa, 0xaa, 0xaa];
+        assert_eq!(
+            format(&vec, &base("heapless::vec::Vec<u16, 4>"), &builtin_types),
+            "[7, 9]"
+        );
+        let empty = [0, 0, 0, 0, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa];
+        assert_eq!(
+            format(&empty, &base("heapless::vec::Vec<u16, 4>"), &builtin_types),
+            "[]"
+        );
+        // A length larger than the capacity means the bytes are not a valid Vec.
+        let invalid = [5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            format(
+                &invalid,
+                &base("heapless::vec::Vec<u16, 4>"),
+                &builtin_types
+            ),
+            "bytes [ 05 00 00 00 00 00 00 00 00 00 00 00 ]"
+        );
+
+        let string = [5, 0, 0, 0, b'h', b'e', b'l', b'l', b'o', 0xaa, 0xaa, 0xaa];
+        assert_eq!(
+            format(
+                &string,
+                &base("heapless::string::String<8>"),
+                &builtin_types
+            ),
+            "\"hello\""
+        );
+    }
+
+    #[test]
+    fn inline_vecs() {
+        let mut builtin_types = HashMap::new();
+        // The length can be after the elements and of any integer type.
+        builtin_types.insert(
+            base("arrayvec::arrayvec::ArrayVec<u8, 4>"),
+            BuiltinType::InlineVec {
+                len: Field {
+                    offset: 4,
+                    size: 4,
+                    ty: base("u32"),
+                },
+                buffer: ArrayField {
+                    offset: 0,
+                    element_size: 1,
+                    element_type: base("u8"),
+                    count: 4,
+                },
+            },
+        );
+        builtin_types.insert(
+            base("tinyvec::arrayvec::ArrayVec<[u16; 2]>"),
+            BuiltinType::InlineVec {
+                len: Field {
+                    offset: 0,
+                    size: 2,
+                    ty: base("u16"),
+                },
+                buffer: ArrayField {
+                    offset: 2,
+                    element_size: 2,
+                    element_type: base("u16"),
+                    count: 2,
+                },
+            },
+        );
+
+        let arrayvec = [1, 2, 3, 0xaa, 3, 0, 0, 0];
+        assert_eq!(
+            format(
+                &arrayvec,
+                &base("arrayvec::arrayvec::ArrayVec<u8, 4>"),
+                &builtin_types
+            ),
+            "[1, 2, 3]"
+        );
+        let tinyvec = [1, 0, 7, 0, 0, 0];
+        assert_eq!(
+            format(
+                &tinyvec,
+                &base("tinyvec::arrayvec::ArrayVec<[u16; 2]>"),
+                &builtin_types
+            ),
+            "[7]"
+        );
+    }
+
+    #[test]
+    fn inline_vecs_from_fixture() {
+        let builtin_types = crate::model::fixtures::debug_data("app.elf").builtin_types;
+
+        // `xs: [MaybeUninit<u32>; 4]` at 0 and `len: u32` at 0x10.
+        let mut arrayvec = [0; 20];
+        arrayvec[..4].copy_from_slice(&7u32.to_le_bytes());
+        arrayvec[4..8].copy_from_slice(&9u32.to_le_bytes());
+        arrayvec[8..12].copy_from_slice(&0xaau32.to_le_bytes());
+        arrayvec[16..].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            format(
+                &arrayvec,
+                &base("arrayvec::arrayvec::ArrayVec<u32, 4>"),
+                &builtin_types
+            ),
+            "[7, 9]"
+        );
+
+        // `data: [u16; 4]` at 0 and `len: u16` at 8.
+        let tinyvec = [1, 0, 2, 0, 3, 0, 4, 0, 3, 0];
+        assert_eq!(
+            format(
+                &tinyvec,
+                &base("tinyvec::arrayvec::ArrayVec<[u16; 4]>"),
+                &builtin_types
+            ),
+            "[1, 2, 3]"
+        );
+    }
+
+    #[test]
+    fn fixed_point() {
+        let mut builtin_types = HashMap::new();
+        builtin_types.insert(
+            base("fixed::Q16_16"),
+            BuiltinType::Scaled {
+                base: base("i32"),
+                scale: 65536.0,
+            },
+        );
+
+        let q16_16 =
+            |value: i32| format(&value.to_le_bytes(), &base("fixed::Q16_16"), &builtin_types);
+
+        assert_eq!(q16_16(0x0001_8000), "1.5");
+        assert_eq!(q16_16(-0x0002_4000), "-2.25");
+        assert_eq!(q16_16(0), "0");
+        // Too few bytes for the base type fall back to the raw bytes.
+        assert_eq!(
+            format(&[0, 0], &base("fixed::Q16_16"), &builtin_types),
+            "bytes [ 00 00 ]"
+        );
+    }
+
+    #[test]
+    fn waker_registration() {
+        let mut builtin_types = HashMap::new();
+        builtin_types.insert(
+            base("embassy_sync::waitqueue::waker_registration::WakerRegistration"),
+            BuiltinType::WakerRegistration {
+                vtable: Field {
+                    offset: 4,
+                    size: 4,
+                    ty: base("&core::task::wake::RawWakerVTable"),
+                },
+            },
+        );
+        let format = |bytes: &[u8]| {
+            format(
+                bytes,
+                &base("embassy_sync::waitqueue::waker_registration::WakerRegistration"),
+                &builtin_types,
+            )
+        };
+
+        assert_eq!(
+            format(&[0x00, 0x10, 0x00, 0x20, 0x40, 0x3f, 0x00, 0x00]),
+            "waker registered"
+        );
+        // Only the vtable decides, the data pointer can contain anything without a waker.
+        assert_eq!(format(&[0x00, 0x10, 0x00, 0x20, 0, 0, 0, 0]), "no waker");
+    }
+
+    #[test]
+    fn arrays() {
+        let builtin_types = HashMap::new();
+        let array = |count| Type::Array {
+            inner: Box::new(base("u16")),
+            count,
+        };
+        let format_capped = |bytes: &[u8], ty: &Type, max_elements| {
+            format_value(
+                bytes,
+                ty,
+                &builtin_types,
+                UnformattableValues::Bytes,
+                max_elements,
+                &mut PrimitiveFormatting,
+            )
+            .to_string()
+        };
+
+        let bytes = [1, 0, 2, 0, 3, 0];
+        assert_eq!(format_capped(&bytes, &array(3), 64), "[1, 2, 3]");
+        assert_eq!(format_capped(&bytes, &array(3), 2), "[1, 2, … +1 more]");
+        assert_eq!(format_capped(&bytes, &array(3), 0), "[… +3 more]");
+        assert_eq!(format_capped(&[], &array(0), 64), "[]");
+        // Bytes that can't be split into the elements fall back to the raw bytes.
+        assert_eq!(
+            format_capped(&[1, 0, 2], &array(2), 64),
+            "bytes [ 01 00 02 ]"
+        );
+    }
+
+    #[test]
+    fn tuples() {
+        let mut builtin_types = HashMap::new();
+        let pair = Type::Tuple(vec![base("u16"), base("bool")]);
+        // Rustc moved the `bool` in front of the `u16`.
+        builtin_types.insert(
+            pair.clone(),
+            BuiltinType::Tuple {
+                elements: vec![
+                    Field {
+                        offset: 2,
+                        size: 2,
+                        ty: base("u16"),
+                    },
+                    Field {
+                        offset: 0,
+                        size: 1,
+                        ty: base("bool"),
+                    },
+                ],
+            },
+        );
+        let single = Type::Tuple(vec![base("u8")]);
+        builtin_types.insert(
+            single.clone(),
+            BuiltinType::Tuple {
+                elements: vec![Field {
+                    offset: 0,
+                    size: 1,
+                    ty: base("u8"),
+                }],
+            },
+        );
+
+        assert_eq!(pair.to_string(), "(u16, bool)");
+        assert_eq!(
+            format(&[1, 0, 0x34, 0x12], &pair, &builtin_types),
+            "(4660, true)"
+        );
+        assert_eq!(single.to_string(), "(u8,)");
+        assert_eq!(format(&[7], &single, &builtin_types), "(7,)");
+        assert_eq!(Type::Tuple(Vec::new()).to_string(), "()");
+        // Too few bytes for the elements falls back to the raw bytes.
+        assert_eq!(format(&[1, 0], &pair, &builtin_types), "bytes [ 01 00 ]");
+    }
+
+    #[test]
+    fn parse_typed_bytes() {
+        assert_eq!(parse_raw_bytes("0a 00 ff"), Ok(vec![0x0a, 0x00, 0xff]));
+        assert_eq!(
+            parse_raw_bytes(&format_raw_bytes(&[1, 2, 3])),
+            Ok(vec![1, 2, 3])
+        );
+        assert_eq!(parse_raw_bytes("0A00"), Ok(vec![0x0a, 0x00]));
+        assert_eq!(parse_raw_bytes(""), Ok(vec![]));
+
+        assert!(parse_raw_bytes("0a 0").is_err());
+        assert!(parse_raw_bytes("zz").is_err());
+    }
+}
@@ -10,29 +10,72 @@
 //! Backend should create a [`EmbassyInspector`] before starting its own an event loop.
 //! Events should then be sent to via [`EmbassyInspector::handle_event`]. See the [`Callback`] trait
 //! for what operations you will have to be able to implement.
+//!
+//! Backends drawing to a terminal with crossterm can enable the `crossterm` feature and use
+//! `from_crossterm` to turn the terminal events into [`Event`]s.
 
+mod assertion;
 mod callback;
+#[cfg(feature = "crossterm")]
+mod crossterm_event;
+mod event_log;
+mod folded;
+mod format;
+mod logger;
 mod model;
+mod palette;
+mod parked;
+mod snapshot;
+mod source_files;
+mod stuck;
+mod theme;
+mod transcript;
 mod ui;
+mod watch;
 
-use std::collections::HashMap;
-
-use anyhow::{Result, anyhow};
-use ratatui::{
-    Terminal,
-    layout::Position,
-    style::Stylize,
-    text::{Line, Span},
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
-use model::{DebugData, task_pool::TaskPoolValue};
-use ui::{UiDrawCtx, UiState};
+use anyhow::{Result, anyhow, bail};
+use ratatui::{Terminal, layout::Position, text::Line};
+use serde::{Deserialize, Serialize};
 
-pub use crate::callback::Callback;
-pub use model::ty::Type;
+use event_log::{EventRecorder, RecordedCall};
+use model::{
+    ExecutorKind,
+    builtin::BuiltinType,
+    future::{FutureValue, FutureValueKind},
+    task_pool::{TaskPoolValue, TaskValue},
+};
+use parked::ParkedTimes;
+use snapshot::Snapshot;
+use source_files::SourceFiles;
+use stuck::StuckTasks;
+use ui::{UiDrawCtx, UiEvent, UiState};
+use watch::Watch;
+
+pub use crate::assertion::Assertion;
+pub use crate::callback::{BreakpointKind, Callback, TargetDisconnected};
+#[cfg(feature = "crossterm")]
+pub use crate::crossterm_event::from_crossterm;
+pub use crate::event_log::replay_session;
+pub use crate::logger::init_tui_logger;
+pub use crate::palette::ColorPalette;
+pub use crate::snapshot::SnapshotCallback;
+pub use crate::theme::Theme;
+pub use crate::transcript::{RecordingCallback, ReplayCallback};
+pub use model::{DebugData, ty::Type};
+/// The `ratatui-image` version used for [`EmbassyInspector::set_image_picker`].
+#[cfg(feature = "task-graph-image")]
+pub use ratatui_image;
 
 /// The mouse button that was used for a click.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ClickButton {
     Left,
     Middle,
@@ -40,25 +83,62 @@ pub enum ClickButton {
 }
 
 /// A single mouse click.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Click {
+    #[serde(with = "event_log::PositionDef")]
     pub pos: Position,
     pub button: ClickButton,
 }
 
+/// A key pressed in the TUI, to navigate without a mouse.
+///
+/// Up and down move the selection on the current page, enter activates the selected row like a
+/// click and left or backspace go back. On the main menu `/` starts typing a filter for the task
+/// pools. When they are not typed into a filter `q` is handled like [`Event::Quit`], `s` like
+/// [`Event::Halt`] and `c` like [`Event::Continue`].
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyCode {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Esc,
+    Char(char),
+}
+
 /// External event to be send to an [`EmbassyInspector`].
-#[derive(Debug)]
+///
+/// Events can be serialized to record and replay them, see [`Config::record_events`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Event {
     /// Window was resized or made invalid for a different reason.
     ///
     /// This event will fore the TUI the be redrawn.
     Redraw,
+    /// Sent periodically by the backend while nothing else happens, calls [`Callback::on_idle`]
+    /// and redraws to keep times like how long futures are parked up to date.
+    Tick,
     /// The user clicked on the TUI.
     Click(Click),
+    /// The user moved the mouse while holding down the left button, used to resize panes.
+    Drag(#[serde(with = "event_log::PositionDef")] Position),
     /// The user scrolled in the TUI.
     ///
     /// A negative number indicates scrolling down, the magnitude is the amount of lines to scroll.
     Scroll(i32),
+    /// Like [`Event::Scroll`], with the position of the mouse to scroll the pane below it, like the
+    /// details pane of a task.
+    ScrollAt {
+        lines: i32,
+        #[serde(with = "event_log::PositionDef")]
+        pos: Position,
+    },
+    /// The user pressed a key in the TUI, see [`KeyCode`].
+    Key(KeyCode),
 
     /// The breakpoint with the given id was hit. See [`Callback`] for how the id's work.
     ///
@@ -68,6 +148,265 @@ pub enum Event {
     ///
     /// **The target should be readable when this event is triggered.**
     Stoped,
+
+    /// The connection to the target was lost, the last read values stay visible.
+    ///
+    /// A target held by the inspector is not held anymore and "Run to next state" is cancelled.
+    Disconnected,
+    /// The connection to the target was restored after a [`Event::Disconnected`].
+    ///
+    /// The poll breakpoints will be set again. **The target should be readable when this event is
+    /// triggered.**
+    Reconnected,
+
+    /// The user wants to halt the target, for example by pressing `s`.
+    ///
+    /// The target is halted with [`Callback::halt`] and held, [`Event::Breakpoint`]s are ignored
+    /// until [`Event::Continue`]. Does nothing while the target is already held.
+    Halt,
+    /// The user wants the held target to run again, for example by pressing `c`. Does nothing
+    /// while the target is not held, either after [`Event::Halt`] or after the task of "Run to
+    /// next state" changed state.
+    Continue,
+
+    /// The user wants to quit, for example by pressing `q`.
+    ///
+    /// The inspector is [shut down](EmbassyInspector::shutdown), after which
+    /// [`EmbassyInspector::quit_requested`] returns `true` and the backend should stop its event
+    /// loop. No other events should be sent after this one.
+    Quit,
+}
+
+/// What to show for values that can't be formatted in any other way.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum UnformattableValues {
+    /// A hex dump of the raw bytes.
+    #[default]
+    Bytes,
+    /// The type name and the size, like `<app::Opaque: 12 bytes>`.
+    Placeholder,
+    /// Nothing, only the type is shown.
+    Hidden,
+}
+
+impl std::str::FromStr for UnformattableValues {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bytes" => Ok(Self::Bytes),
+            "placeholder" => Ok(Self::Placeholder),
+            "hidden" => Ok(Self::Hidden),
+            _ => Err(format!(
+                "unknown value `{s}`, expected one of bytes, placeholder or hidden"
+            )),
+        }
+    }
+}
+
+/// Options for an [`EmbassyInspector`], see [`EmbassyInspector::with_config`].
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub unformattable: UnformattableValues,
+    /// Show the discriminant values next to the state names of async fns, to correlate them with
+    /// the raw memory.
+    pub show_discriminants: bool,
+    /// The memory the backend is able to read, `None` if it can read everything.
+    ///
+    /// Task pools and watches that are not completely inside one of the regions are not read, for
+    /// backends that only have part of the memory like a snapshot.
+    pub readable_regions: Option<Vec<Range<u64>>>,
+    /// The kind of breakpoints to set at the ends of the poll functions.
+    pub breakpoint_kind: BreakpointKind,
+    /// How many elements of arrays and collections to show, the rest is summarized as `… +N more`.
+    ///
+    /// Members of async fns can be expanded to show all elements from the details.
+    pub max_elements: usize,
+    /// How many bytes before the end of a poll function to set its breakpoint, on the return
+    /// instruction. `None` to guess the size of that instruction from the architecture of the
+    /// object file.
+    ///
+    /// Poll functions that got inlined are broken at the first instruction after them instead.
+    pub poll_breakpoint_offset: Option<u64>,
+    /// Pairs of `(from, to)` path prefixes to replace in the source paths from the debug data, for
+    /// firmware built in another location like on CI. The first matching prefix is used.
+    pub source_path_remaps: Vec<(String, String)>,
+    /// How many lines of source code to show before and after the current await point of an async
+    /// fn in its details, `None` to not read the source files.
+    pub source_context_lines: Option<usize>,
+    /// Start with the main menu showing a single line per task pool, the tasks of a pool are shown
+    /// when clicking it. Can also be toggled from the main menu.
+    pub compact_main_menu: bool,
+    /// Only show the spawned tasks of every pool in the main menu, with a line to show the
+    /// uninitialized ones. Clutters the menu a lot less for large pools that are mostly unused.
+    pub hide_uninit_tasks: bool,
+    /// Show the pool of the task generated by `#[embassy_executor::main]` before the other pools,
+    /// instead of between them by size. Off by default.
+    pub pin_main_task: bool,
+    /// Append what every spawned task is waiting on to this file each time the target stops, as
+    /// folded stacks to create a flamegraph of where the tasks spend their time.
+    pub folded_stacks: Option<PathBuf>,
+    /// Only read the values and redraw on every this many hits of the poll breakpoints, the target
+    /// is resumed right away on the others. Makes busy executors cheaper to follow, 1 updates
+    /// after every poll.
+    pub refresh_every_polls: usize,
+    /// Mark spawned tasks as stuck when they stayed in the same state for this many stops at which
+    /// other tasks got polled, like a future that never registered its waker. `None` to never
+    /// mark tasks.
+    pub stuck_after_stops: Option<usize>,
+    /// Draw the pages at most this many columns wide, centered in the terminal, to keep the lines
+    /// readable on wide terminals. `None` to use the full width.
+    pub max_width: Option<u16>,
+    /// Also mark everything that is shown with a color with a symbol, for color blind users and
+    /// terminals without colors. Like `>` for the selected future and `*` for changed watches.
+    pub accessible: bool,
+    /// The colors the terminal can show, colors outside of the palette are replaced by the nearest
+    /// one inside it.
+    pub color_palette: ColorPalette,
+    /// The colors the pages are drawn with, [`Theme::light`] for terminals with a light
+    /// background.
+    pub theme: Theme,
+    /// Write every handled [`Event`] to this file, to replay the session with [`replay_session`]
+    /// together with a transcript from a [`RecordingCallback`].
+    pub record_events: Option<PathBuf>,
+    /// Write the state of all tasks to this file as JSON when `e` is pressed, see
+    /// [`EmbassyInspector::export_json`].
+    pub export_json: Option<PathBuf>,
+    /// Directory to keep the parsed debug data in, so an object file that did not change since
+    /// the last start does not have to be parsed again. See [`Config::default_debug_data_cache`].
+    pub debug_data_cache: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            unformattable: UnformattableValues::default(),
+            show_discriminants: false,
+            readable_regions: None,
+            breakpoint_kind: BreakpointKind::default(),
+            max_elements: 64,
+            poll_breakpoint_offset: None,
+            source_path_remaps: Vec::new(),
+            source_context_lines: Some(2),
+            compact_main_menu: false,
+            hide_uninit_tasks: true,
+            pin_main_task: false,
+            folded_stacks: None,
+            refresh_every_polls: 1,
+            stuck_after_stops: Some(50),
+            max_width: None,
+            accessible: false,
+            color_palette: ColorPalette::default(),
+            theme: Theme::default(),
+            record_events: None,
+            export_json: None,
+            debug_data_cache: None,
+        }
+    }
+}
+
+impl Config {
+    /// The usual place for [`Config::debug_data_cache`], `inspect-embassy` in `$XDG_CACHE_HOME` or
+    /// `~/.cache`. `None` if neither is set.
+    pub fn default_debug_data_cache() -> Option<PathBuf> {
+        let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+        Some(cache_dir.join("inspect-embassy"))
+    }
+
+    fn is_readable(&self, address: u64, size: u64) -> bool {
+        match &self.readable_regions {
+            Some(regions) => regions.iter().any(|region| {
+                region.start <= address && address.saturating_add(size) <= region.end
+            }),
+            None => true,
+        }
+    }
+}
+
+/// A breakpoint at one of the poll done addresses, shown on the diagnostics page.
+#[derive(Debug)]
+pub(crate) struct PollBreakpoint {
+    pub(crate) address: u64,
+    /// The executor polling at this address.
+    pub(crate) executor: ExecutorKind,
+    /// The id given by the backend, or the error if setting the breakpoint failed.
+    pub(crate) id: Result<u64, String>,
+    /// How many times the breakpoint was hit this session.
+    pub(crate) hits: u64,
+}
+
+/// A future outside of the task pools that was added by its address and type, see
+/// [`EmbassyInspector::inspect_future`], or found in a static.
+#[derive(Debug)]
+pub(crate) struct FutureByAddress {
+    pub(crate) address: u64,
+    pub(crate) ty: Type,
+    /// Path of the static the future was found in, `None` if it was added by the user.
+    pub(crate) static_path: Option<String>,
+    size: u64,
+    /// The value from the last stop, or why it could not be read.
+    pub(crate) value: Result<FutureValue, String>,
+}
+
+impl FutureByAddress {
+    fn read<C: Callback>(&mut self, debug_data: &DebugData, config: &Config, callback: &mut C) {
+        self.value = callback
+            .read_memory(self.address, self.size)
+            .map(|bytes| FutureValue::new(&self.ty, self.address, &bytes, &debug_data.future_types))
+            .map_err(|e| format!("{e:#}"));
+        if let Ok(value) = &mut self.value {
+            read_behind_pointers(value, debug_data, config, callback);
+        }
+    }
+}
+
+/// Read the futures behind the `Shared` handles in `future` and the channels and signals its
+/// embassy-sync futures wait on, see [`FutureValue::read_behind_pointers`].
+fn read_behind_pointers<C: Callback>(
+    future: &mut FutureValue,
+    debug_data: &DebugData,
+    config: &Config,
+    callback: &mut C,
+) {
+    let mut read_memory = |address, size| {
+        if !config.is_readable(address, size) {
+            return None;
+        }
+        callback
+            .read_memory(address, size)
+            .inspect_err(|e| log::debug!("Could not read behind a pointer: {e:#}"))
+            .ok()
+    };
+    future.read_behind_pointers(&debug_data.future_types, &mut read_memory, 0);
+}
+
+/// A task to halt the target for once its async fn is in a different state, see
+/// [`UiEvent::RunToNextState`].
+#[derive(Debug)]
+struct StateBreak {
+    pool_path: String,
+    task_idx: usize,
+    /// The discriminant of the state when waiting started, `None` if the task was not spawned.
+    discriminant: Option<u64>,
+}
+
+impl StateBreak {
+    /// Read only the memory of the task again, whether it is in a different state now.
+    fn changed<C: Callback>(&self, debug_data: &DebugData, callback: &mut C) -> Result<bool> {
+        let Some(pool) = debug_data
+            .task_pools
+            .iter()
+            .find(|pool| pool.path == self.pool_path)
+        else {
+            bail!("there is no task pool {}", self.pool_path);
+        };
+        let (address, size) = pool.task_memory(self.task_idx);
+        let bytes = callback.read_memory(address, size)?;
+        Ok(pool.task_discriminant(&bytes)? != self.discriminant)
+    }
 }
 
 /// Contains the full state of the debugger
@@ -79,17 +418,66 @@ pub enum Event {
 pub struct EmbassyInspector<RB: ratatui::backend::Backend> {
     terminal: Terminal<RB>,
     ui_state: UiState,
+    config: Config,
 
-    poll_break_point_ids: Vec<u64>,
+    poll_breakpoints: Vec<PollBreakpoint>,
+    /// Hits of the poll breakpoints since the values were last read, see
+    /// [`Config::refresh_every_polls`].
+    polls_since_refresh: usize,
 
     debug_data: DebugData,
     last_values: Vec<TaskPoolValue>,
+    /// The values before the last update, members that changed since are highlighted.
+    previous_values: Vec<TaskPoolValue>,
     // GDB can only format values containing pointers when the target has been stopped, so we cache
     // formatted values here to use if the screen needs to be refreshed for for example scrolling
     // while the target is still running.
     //
     // This does not work in all cases, but it does help in a lot of them.
-    formating_cache: HashMap<(Vec<u8>, Type), Line<'static>>,
+    formating_cache: HashMap<(Vec<u8>, Type, usize), Line<'static>>,
+
+    /// Memory ranges highlighted when they change between stops.
+    watches: Vec<Watch>,
+    /// Addresses of the members showing all their elements, ignoring [`Config::max_elements`].
+    all_elements: Vec<u64>,
+    /// Futures added by address, next to the ones in the task pools.
+    futures_by_address: Vec<FutureByAddress>,
+    /// When the futures last changed state, to show how long they have been waiting.
+    parked: ParkedTimes,
+    /// Tasks that did not change for a while, see [`Config::stuck_after_stops`].
+    stuck: StuckTasks,
+    /// The source code shown around await points, see [`Config::source_context_lines`].
+    source_files: SourceFiles,
+    /// The task to halt the target for once it changes state, see [`UiEvent::RunToNextState`].
+    state_break: Option<StateBreak>,
+    /// Set when the target was kept halted at a poll breakpoint because the task of the
+    /// [`StateBreak`] changed state or by [`Event::Halt`], until [`UiEvent::Continue`].
+    holding: bool,
+    /// Set when the target stopped for another reason than a poll breakpoint, see
+    /// [`Event::Stoped`]. Unlike with [`Self::holding`] the backend decides when it runs again.
+    halted: bool,
+
+    /// Whether the values are read from a target, `false` for a snapshot loaded with
+    /// [`Self::from_snapshot`].
+    is_live: bool,
+    /// Whether the connection to the target is lost, see [`TargetDisconnected`].
+    disconnected: bool,
+    /// Paths of the task pools outside of the [`Config::readable_regions`].
+    unavailable_pools: Vec<String>,
+    /// Message from the last action, until the next event.
+    status: Option<String>,
+    /// Set by [`Event::Quit`].
+    quit: bool,
+    /// Writes the handled events to [`Config::record_events`].
+    event_recorder: Option<EventRecorder>,
+    /// When the inspector was created, the times of events are counted from it.
+    created: Instant,
+    /// When the event that is handled happened, the recorded time when replaying so the parked
+    /// times come out the same.
+    now: Instant,
+    /// Draws the task graph as an image, see [`Self::set_image_picker`].
+    #[cfg(feature = "task-graph-image")]
+    graph_image: Option<ui::GraphImage>,
 }
 
 impl<RB: ratatui::backend::Backend> EmbassyInspector<RB> {
@@ -97,52 +485,434 @@ impl<RB: ratatui::backend::Backend> EmbassyInspector<RB> {
     ///
     /// The `ratatui_backend` will be drawn to automatically when needed.
     pub fn new<C: Callback>(ratatui_backend: RB, callback: &mut C) -> Result<Self> {
-        let object_file = {
-            let mut object_files = callback.get_objectfiles()?;
-            object_files
-                .next()
-                .ok_or(anyhow!("Need at least one objectfile"))?
-        };
+        Self::with_config(ratatui_backend, callback, Config::default())
+    }
+
+    /// Create a new [`EmbassyInspector`] with non default options.
+    pub fn with_config<C: Callback>(
+        ratatui_backend: RB,
+        callback: &mut C,
+        config: Config,
+    ) -> Result<Self> {
+        let object_files = callback.get_objectfiles()?.collect::<Vec<_>>();
+        let debug_data = Self::load_debug_data(object_files, config.debug_data_cache.as_deref())?;
 
-        let debug_data = DebugData::from_object_file(object_file)?;
+        Self::with_debug_data(ratatui_backend, callback, config, debug_data)
+    }
 
-        let mut poll_break_point_ids = Vec::new();
-        for addr in &debug_data.poll_done_addresses {
-            let id = callback.set_breakpoint(*addr)?;
-            poll_break_point_ids.push(id);
+    /// Create a new [`EmbassyInspector`] from the contents of the object file instead of the files
+    /// from [`Callback::get_objectfiles`], for backends that receive it over the network for
+    /// example.
+    pub fn from_object_bytes<C: Callback>(
+        ratatui_backend: RB,
+        object_bytes: &[u8],
+        callback: &mut C,
+        config: Config,
+    ) -> Result<Self> {
+        let debug_data = DebugData::from_object_bytes(object_bytes)?;
+
+        Self::with_debug_data(ratatui_backend, callback, config, debug_data)
+    }
+
+    fn with_debug_data<C: Callback>(
+        ratatui_backend: RB,
+        callback: &mut C,
+        config: Config,
+        mut debug_data: DebugData,
+    ) -> Result<Self> {
+        debug_data.remap_source_paths(&config.source_path_remaps);
+        if config.pin_main_task {
+            debug_data.pin_main_task();
         }
 
+        // Read together with the task pools below.
+        let futures_by_address = debug_data
+            .static_futures
+            .iter()
+            .map(|static_future| FutureByAddress {
+                address: static_future.address,
+                ty: static_future.ty.clone(),
+                static_path: Some(static_future.path.clone()),
+                size: static_future.size,
+                value: Err(String::from("Not read yet")),
+            })
+            .collect();
+
+        let poll_breakpoints = debug_data
+            .poll_done_addresses(config.poll_breakpoint_offset)
+            .into_iter()
+            .map(|(addr, executor)| PollBreakpoint {
+                address: addr,
+                executor,
+                id: Self::set_poll_breakpoint(addr, config.breakpoint_kind, callback),
+                hits: 0,
+            })
+            .collect();
+
+        let created = Instant::now();
         let mut s = Self {
             terminal: Terminal::new(ratatui_backend)?,
-            poll_break_point_ids,
+            poll_breakpoints,
+            polls_since_refresh: 0,
 
-            ui_state: UiState::new(),
+            ui_state: UiState::new(
+                config.compact_main_menu,
+                config.hide_uninit_tasks,
+                config.max_width,
+            ),
+            stuck: StuckTasks::new(config.stuck_after_stops),
+            config,
 
             debug_data,
             last_values: Vec::new(),
+            previous_values: Vec::new(),
             formating_cache: HashMap::new(),
+
+            watches: Vec::new(),
+            all_elements: Vec::new(),
+            futures_by_address,
+            parked: ParkedTimes::default(),
+            source_files: SourceFiles::default(),
+            state_break: None,
+            holding: false,
+            halted: false,
+
+            is_live: true,
+            disconnected: false,
+            unavailable_pools: Vec::new(),
+            status: None,
+            quit: false,
+            event_recorder: None,
+            created,
+            now: created,
+            #[cfg(feature = "task-graph-image")]
+            graph_image: None,
         };
         s.update_values(callback);
-        s.handle_event(Event::Redraw, callback)?;
+        s.handle_event_at(Event::Redraw, Duration::ZERO, callback)?;
+        // After the first draw, replaying creates a new inspector which draws it again.
+        s.event_recorder = s
+            .config
+            .record_events
+            .as_ref()
+            .map(EventRecorder::new)
+            .transpose()?;
         Ok(s)
     }
 
-    fn update_values<C: Callback>(&mut self, callback: &mut C) {
-        self.last_values.clear();
+    /// Create an [`EmbassyInspector`] showing a snapshot written with [`Self::export_json`]
+    /// instead of a target, to look at a captured failure on a machine without a probe.
+    ///
+    /// Values are shown as they were formatted when exporting and async fns only have their
+    /// current state. Nothing is ever read from or sent to a target, pass a [`SnapshotCallback`] to
+    /// the other methods.
+    pub fn from_snapshot(ratatui_backend: RB, json: &Path) -> Result<Self> {
+        Self::from_snapshot_with_config(ratatui_backend, json, Config::default())
+    }
+
+    /// Create an [`EmbassyInspector`] showing a snapshot with non default options, see
+    /// [`Self::from_snapshot`].
+    pub fn from_snapshot_with_config(
+        ratatui_backend: RB,
+        path: &Path,
+        config: Config,
+    ) -> Result<Self> {
+        let values = Snapshot::from_file(path)?.into_values();
+        let config = Config {
+            readable_regions: Some(Vec::new()),
+            ..config
+        };
+
+        let mut callback = SnapshotCallback;
+        let mut s =
+            Self::with_debug_data(ratatui_backend, &mut callback, config, DebugData::default())?;
+        s.is_live = false;
+        s.last_values = values.task_pools;
+        s.futures_by_address = values
+            .futures
+            .into_iter()
+            .map(|value| FutureByAddress {
+                address: value.address,
+                ty: value.ty.clone(),
+                static_path: None,
+                size: value.size(),
+                value: Ok(value),
+            })
+            .collect();
+        for ((bytes, ty), value) in values.formatted {
+            s.formating_cache
+                .insert((bytes, ty, s.config.max_elements), Line::raw(value));
+        }
+
+        s.status = Some(format!("Showing the snapshot {}", path.display()));
+        s.handle_event(Event::Redraw, &mut callback)?;
+        Ok(s)
+    }
+
+    /// Format values of the type at `path` as the integer type `base` divided by `scale`.
+    ///
+    /// Intended for fixed point newtypes, a `Q16.16` wrapping an `i32` should be registered with a
+    /// scale of `65536.0`. The integer is expected at the start of the value. Overrides any other
+    /// formatting for this type.
+    pub fn register_scaled_number(&mut self, path: &str, base: &str, scale: f64) {
+        self.debug_data.builtin_types.insert(
+            Type::Base(path.to_owned()),
+            BuiltinType::Scaled {
+                base: Type::Base(base.to_owned()),
+                scale,
+            },
+        );
+        self.formating_cache.clear();
+    }
+
+    /// Set the tick rate used to format `embassy_time::Duration` and `embassy_time::Instant`.
+    ///
+    /// Defaults to 1 MHz, the rate without any of embassy-time's `tick-hz-*` features.
+    pub fn set_tick_hz(&mut self, hz: u64) {
+        for builtin_type in self.debug_data.builtin_types.values_mut() {
+            if let BuiltinType::EmbassyTicks { tick_hz, .. } = builtin_type {
+                *tick_hz = hz;
+            }
+        }
         self.formating_cache.clear();
+    }
+
+    /// Draw the task graph as an image with the protocol found by `picker`, like
+    /// `Picker::from_query_stdio` after the terminal is switched to raw mode.
+    ///
+    /// Terminals without an image protocol keep the graph as text, `picker` falls back to half
+    /// blocks there which are too coarse for the labels.
+    #[cfg(feature = "task-graph-image")]
+    pub fn set_image_picker(&mut self, picker: ratatui_image::picker::Picker) {
+        self.graph_image = ui::GraphImage::new(picker);
+    }
+
+    /// Change [`Config::stuck_after_stops`] while running, tasks are marked with the new threshold
+    /// at the next redraw using the stops counted so far.
+    pub fn set_stuck_after_stops(&mut self, stops: Option<usize>) {
+        self.config.stuck_after_stops = stops;
+        self.stuck.set_threshold(stops);
+    }
+
+    /// Check an [`Assertion`] against the values read when the target last stopped, for scripts
+    /// testing the state of the tasks.
+    ///
+    /// Returns why the assertion does not hold. Members are formatted as in the TUI, so the target
+    /// should be readable when this is called.
+    pub fn check_assertion<C: Callback>(
+        &self,
+        assertion: &Assertion,
+        callback: &mut C,
+    ) -> Result<(), String> {
+        assertion.check(&self.last_values, |bytes, ty| {
+            self.format_to_string(bytes, ty, callback)
+        })
+    }
+
+    /// The state of all tasks and other futures at the last stop as JSON, for backends exposing it
+    /// to other tools.
+    ///
+    /// Members are formatted as in the TUI, values formatted by the backend can need the target to
+    /// be readable. The call is written to the [`Config::record_events`] log like an event, as
+    /// replaying has to make the same calls to the callback.
+    pub fn snapshot_json<C: Callback>(&mut self, callback: &mut C) -> String {
+        if let Some(recorder) = &mut self.event_recorder {
+            recorder.record_call(RecordedCall::SnapshotJson);
+        }
+        self.snapshot(callback).to_json()
+    }
+
+    /// The same state as [`Self::snapshot_json`] as a JSON value, for scripts and regression tests
+    /// comparing the state of the target between runs.
+    ///
+    /// Includes the source locations of the await points and the raw bytes of every member. The
+    /// shape of the JSON does not change with the internals of the inspector.
+    pub fn export_json<C: Callback>(&self, callback: &mut C) -> serde_json::Value {
+        self.snapshot(callback).to_value()
+    }
+
+    fn snapshot<C: Callback>(&self, callback: &mut C) -> Snapshot {
+        Snapshot::new(&self.last_values, &self.futures_by_address, |bytes, ty| {
+            self.format_to_string(bytes, ty, callback)
+        })
+    }
+
+    /// Write [`Self::export_json`] to [`Config::export_json`], showing how it went in the status.
+    fn write_export<C: Callback>(&mut self, callback: &mut C) {
+        let Some(path) = &self.config.export_json else {
+            self.status = Some(String::from(
+                "No file to export the tasks to was configured",
+            ));
+            return;
+        };
+
+        let json = self.export_json(callback).to_string();
+        self.status = Some(match std::fs::write(path, json) {
+            Ok(()) => format!("Exported the tasks to {}", path.display()),
+            Err(e) => format!("Could not export the tasks: {e}"),
+        });
+    }
+
+    /// Show the future of the type at `type_path` stored at `address`, for futures that are not
+    /// in a task pool or a static, like one on the stack of a blocking executor.
+    ///
+    /// The type path can also be the path of the async fn returning the future. The future gets
+    /// read again every time the target stops. **The target should be readable when this is
+    /// called.**
+    pub fn inspect_future<C: Callback>(
+        &mut self,
+        address: u64,
+        type_path: &str,
+        callback: &mut C,
+    ) -> Result<()> {
+        self.add_future_by_address(address, type_path, callback)?;
+        self.draw(None, None, None, callback)
+    }
+
+    /// Read the future for [`Self::inspect_future`] and show its page, also typed in the TUI.
+    fn add_future_by_address<C: Callback>(
+        &mut self,
+        address: u64,
+        type_path: &str,
+        callback: &mut C,
+    ) -> Result<()> {
+        if !self.is_live {
+            bail!("A snapshot has no futures to add");
+        }
+
+        let (ty, size) = self
+            .debug_data
+            .find_future_type(type_path)
+            .map_err(|e| anyhow!(e))?;
+
+        let mut future = FutureByAddress {
+            address,
+            ty,
+            static_path: None,
+            size,
+            value: Err(String::from("Not read yet")),
+        };
+        if self.config.is_readable(address, size) {
+            future.read(&self.debug_data, &self.config, callback);
+        } else {
+            future.value = Err(String::from("Outside of the readable memory regions"));
+        }
+        self.futures_by_address.push(future);
+
+        self.ui_state
+            .show_future_by_address(self.futures_by_address.len() - 1);
+        Ok(())
+    }
+
+    /// Load the debug data of the first object file containing task pools, the others can for
+    /// example be stripped binaries next to their separate debug file.
+    fn load_debug_data(object_files: Vec<String>, cache_dir: Option<&Path>) -> Result<DebugData> {
+        if object_files.is_empty() {
+            bail!("Need at least one objectfile");
+        }
+
+        for object_file in object_files.clone() {
+            let debug_data = match cache_dir {
+                Some(cache_dir) => {
+                    DebugData::from_object_file_cached(object_file.clone(), cache_dir)
+                }
+                None => DebugData::from_object_file(object_file.clone()),
+            };
+            match debug_data {
+                Ok(debug_data) if !debug_data.task_pools.is_empty() => return Ok(debug_data),
+                Ok(_) => log::info!("No task pools found in {object_file}"),
+                Err(e) => log::warn!("Could not load the debug data of {object_file}: {e:#}"),
+            }
+        }
+
+        bail!(
+            "None of the object files contain embassy task pools: {}",
+            object_files.join(", ")
+        )
+    }
+
+    fn set_poll_breakpoint<C: Callback>(
+        addr: u64,
+        kind: BreakpointKind,
+        callback: &mut C,
+    ) -> Result<u64, String> {
+        callback.set_breakpoint(addr, kind).map_err(|e| {
+            log::error!("Could not set poll breakpoint at {addr:#x}: {e}");
+            e.to_string()
+        })
+    }
+
+    fn update_values<C: Callback>(&mut self, callback: &mut C) {
+        // The values of a snapshot never change.
+        if !self.is_live {
+            return;
+        }
+
+        let mut values = Vec::new();
+        let mut unavailable_pools = Vec::new();
+
+        let polls = self.poll_breakpoints.iter().map(|b| b.hits).sum();
+        self.stuck.start_stop(polls);
 
         for task_pool in &self.debug_data.task_pools {
+            if !self.config.is_readable(task_pool.address, task_pool.size) {
+                unavailable_pools.push(task_pool.path.clone());
+                continue;
+            }
+
             let bytes = match callback.read_memory(task_pool.address, task_pool.size) {
                 Ok(bytes) => bytes,
+                Err(e) if TargetDisconnected::is_cause_of(&e) => {
+                    // Keep showing the last values, they are more useful than nothing.
+                    log::error!("{e:#}");
+                    self.disconnected = true;
+                    return;
+                }
                 Err(e) => {
                     log::error!("{}", e);
                     continue;
                 }
             };
 
-            let task_pool_value = self.debug_data.get_taskpool_value(task_pool, &bytes);
+            let mut task_pool_value = self.debug_data.get_taskpool_value(task_pool, &bytes);
+            for task in &mut task_pool_value.task_values {
+                if let TaskValue::Init(future, _) = task {
+                    read_behind_pointers(future, &self.debug_data, &self.config, callback);
+                }
+            }
+            self.stuck.update_pool(&task_pool_value);
+
+            values.push(task_pool_value);
+        }
+
+        self.disconnected = false;
+        self.previous_values = std::mem::replace(&mut self.last_values, values);
+        self.unavailable_pools = unavailable_pools;
+        self.parked.update(&self.last_values, self.now);
+        if let Some(path) = &self.config.folded_stacks
+            && let Err(e) = folded::append_folded_stacks(path, &self.last_values)
+        {
+            log::warn!(
+                "Could not write the folded stacks to {}: {e}",
+                path.display()
+            );
+        }
+        self.formating_cache.clear();
+
+        for future in &mut self.futures_by_address {
+            if self.config.is_readable(future.address, future.size) {
+                future.read(&self.debug_data, &self.config, callback);
+            }
+        }
 
-            self.last_values.push(task_pool_value);
+        for watch in &mut self.watches {
+            if !self.config.is_readable(watch.address, watch.size) {
+                continue;
+            }
+            match callback.read_memory(watch.address, watch.size) {
+                Ok(bytes) => watch.update(bytes),
+                Err(e) => log::error!("{}", e),
+            }
         }
     }
 
@@ -151,77 +921,680 @@ impl<RB: ratatui::backend::Backend> EmbassyInspector<RB> {
     /// See [`Event`] for all possible event and whether or not the target needs to be readable when
     /// the event is dispatched.
     pub fn handle_event<C: Callback>(&mut self, event: Event, callback: &mut C) -> Result<()> {
+        // Whole milliseconds, like in the event log, so a replay sees the same times.
+        let elapsed = Duration::from_millis(self.created.elapsed().as_millis() as u64);
+        self.handle_event_at(event, elapsed, callback)
+    }
+
+    /// Process an [`Event`] that happened `elapsed` after the inspector was created.
+    fn handle_event_at<C: Callback>(
+        &mut self,
+        event: Event,
+        elapsed: Duration,
+        callback: &mut C,
+    ) -> Result<()> {
+        self.now = self.created + elapsed;
+        if let Some(recorder) = &mut self.event_recorder {
+            let size = self.terminal.size()?;
+            recorder.record(&event, size.width, size.height, elapsed);
+        }
+
+        if !matches!(event, Event::Redraw | Event::Tick) {
+            self.status = None;
+        }
+
+        // Unless it is typed into the UI, like into the filter of the main menu.
+        let event = match event {
+            Event::Key(KeyCode::Char('q')) if !self.ui_state.takes_text() => Event::Quit,
+            Event::Key(KeyCode::Char('s')) if !self.ui_state.takes_text() => Event::Halt,
+            Event::Key(KeyCode::Char('c')) if !self.ui_state.takes_text() => Event::Continue,
+            event => event,
+        };
+
+        let mut drag = None;
+        let mut scroll = None;
         let click = match event {
-            Event::Redraw => {
-                // We redraw after every event anyway so nothing to do here.
+            Event::Redraw | Event::Tick => {
+                // We redraw after every event anyway so nothing else to do here.
+                callback.on_idle()?;
                 None
             }
             Event::Click(click) => Some(click),
+            Event::Drag(pos) => {
+                drag = Some(pos);
+                None
+            }
             Event::Scroll(s) => {
                 self.ui_state.apply_scroll(s);
                 None
             }
+            Event::ScrollAt { lines, pos } => {
+                scroll = Some((pos, lines));
+                None
+            }
+            Event::Key(KeyCode::Char('e')) if !self.ui_state.takes_text() => {
+                self.write_export(callback);
+                None
+            }
+            Event::Key(key) => {
+                if let Some(event) = self.ui_state.apply_key(key) {
+                    self.apply_inspector_event(event, callback)?;
+                }
+                None
+            }
+            // Backends waiting for the target to halt can report the same stop again while the
+            // target is held.
+            Event::Breakpoint(_) if self.holding => None,
+            Event::Breakpoint(_) | Event::Stoped | Event::Reconnected if !self.is_live => None,
             Event::Breakpoint(i) => {
-                self.update_values(callback);
+                let poll_breakpoint = self
+                    .poll_breakpoints
+                    .iter_mut()
+                    .find(|b| b.id.as_ref().is_ok_and(|id| *id == i));
+                if let Some(poll_breakpoint) = poll_breakpoint {
+                    poll_breakpoint.hits += 1;
+                    // Resumed or held below.
+                    self.halted = false;
 
-                if self.poll_break_point_ids.contains(&i) {
-                    log::error!("Poll hit, coninuing");
+                    if let Some(state_break) = &self.state_break {
+                        match state_break.changed(&self.debug_data, callback) {
+                            Ok(false) => return callback.resume(),
+                            Ok(true) => {
+                                log::info!(
+                                    "Task {}[{}] changed state, holding the target",
+                                    state_break.pool_path,
+                                    state_break.task_idx
+                                );
+                                self.state_break = None;
+                                self.holding = true;
+                                self.polls_since_refresh = 0;
+                                self.update_values(callback);
+                                return self.draw(None, None, None, callback);
+                            }
+                            Err(e) => {
+                                log::error!("Stopped waiting for the task to change state: {e:#}");
+                                self.state_break = None;
+                            }
+                        }
+                    }
+
+                    self.polls_since_refresh += 1;
+                    if self.polls_since_refresh < self.config.refresh_every_polls {
+                        log::trace!("Poll hit, continuing without refreshing");
+                        return callback.resume();
+                    }
+                    self.polls_since_refresh = 0;
+                    self.update_values(callback);
+
+                    log::debug!("Poll hit, continuing");
                     callback.resume()?;
+                } else {
+                    self.halted = true;
+                    self.update_values(callback);
+                    self.follow_current_task(callback);
                 }
                 None
             }
             Event::Stoped => {
+                self.halted = true;
                 self.update_values(callback);
+                self.follow_current_task(callback);
+                None
+            }
+            Event::Disconnected => {
+                self.disconnected = true;
+                // The target may have been reset meanwhile, so it is not held after reconnecting
+                // and "Run to next state" is cancelled.
+                self.halted = false;
+                self.holding = false;
+                self.state_break = None;
+                None
+            }
+            Event::Halt | Event::Continue if !self.is_live || self.disconnected => None,
+            Event::Halt if self.holding => None,
+            Event::Halt => {
+                match callback.halt() {
+                    Ok(()) => {
+                        log::info!("Halted the target");
+                        self.holding = true;
+                        self.update_values(callback);
+                        self.follow_current_task(callback);
+                    }
+                    Err(e) if TargetDisconnected::is_cause_of(&e) => return Err(e),
+                    Err(e) => self.status = Some(format!("Could not halt the target: {e:#}")),
+                }
                 None
             }
+            Event::Continue => {
+                self.apply_inspector_event(UiEvent::Continue, callback)?;
+                None
+            }
+            Event::Reconnected => {
+                for breakpoint in &mut self.poll_breakpoints {
+                    breakpoint.id = Self::set_poll_breakpoint(
+                        breakpoint.address,
+                        self.config.breakpoint_kind,
+                        callback,
+                    );
+                }
+                self.update_values(callback);
+                None
+            }
+            Event::Quit => {
+                self.shutdown(callback)?;
+                self.quit = true;
+                return Ok(());
+            }
         };
 
-        self.terminal.draw(|frame| {
-            let mut ctx = UiDrawCtx {
-                frame,
-                click,
-                values: &self.last_values,
-                try_format_value: &mut |b, ty| {
-                    self.formating_cache
-                        .entry((b.to_vec(), ty.clone()))
-                        .or_insert_with_key(|(b, t)| format_value(b, t, callback))
-                        .clone()
-                },
-            };
+        self.draw(click, drag, scroll, callback)
+    }
+
+    /// Whether [`Event::Quit`] was handled, the backend should stop its event loop.
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    /// Whether the inspector keeps the target halted until [`Event::Continue`], after
+    /// [`Event::Halt`] or when a task changed state. The target won't stop again meanwhile, so
+    /// backends can stop waiting for it.
+    pub fn holding_target(&self) -> bool {
+        self.holding
+    }
 
-            while let Err(event) = self.ui_state.draw(&mut ctx) {
-                self.ui_state.apply_event(event);
-                ctx.click = None;
+    /// Clear the poll breakpoints with [`Callback::clear_breakpoint`] and resume the target if the
+    /// inspector was holding it, leaving the target running like before the inspector was created.
+    ///
+    /// Done when handling [`Event::Quit`]. Backends tearing down the inspector another way, like
+    /// when a window is closed or after an error, should call this so the breakpoints don't keep
+    /// stopping the target in the next debug session. Calling it again does nothing.
+    pub fn shutdown<C: Callback>(&mut self, callback: &mut C) -> Result<()> {
+        self.clear_poll_breakpoints(callback);
+        // The target was running before the inspector held it.
+        if self.holding {
+            self.holding = false;
+            callback.resume()?;
+        }
+        Ok(())
+    }
 
-                ctx.frame
-                    .render_widget(ratatui::widgets::Clear, ctx.frame.area());
+    fn clear_poll_breakpoints<C: Callback>(&mut self, callback: &mut C) {
+        for breakpoint in &mut self.poll_breakpoints {
+            if let Ok(id) = breakpoint.id
+                && let Err(e) = callback.clear_breakpoint(id)
+            {
+                log::error!(
+                    "Could not clear the breakpoint at {:#x}: {e:#}",
+                    breakpoint.address
+                );
             }
-        })?;
+            breakpoint.id = Err(String::from("cleared"));
+        }
+    }
+
+    /// Format a value as plain text the way the TUI shows it, for the uses outside of the TUI.
+    fn format_to_string<C: Callback>(&self, bytes: &[u8], ty: &Type, callback: &mut C) -> String {
+        // Also has the values of a snapshot, which can't be formatted again.
+        let key = (bytes.to_vec(), ty.clone(), self.config.max_elements);
+        if let Some(line) = self.formating_cache.get(&key) {
+            return line.to_string();
+        }
+
+        format::format_value(
+            bytes,
+            ty,
+            &self.debug_data.builtin_types,
+            self.config.unformattable,
+            self.config.max_elements,
+            callback,
+        )
+        .to_string()
+    }
+
+    /// The discriminant of the state of a task at the last stop, `None` if it is not spawned or
+    /// its state could not be read.
+    fn task_discriminant(&self, pool_path: &str, task_idx: usize) -> Option<u64> {
+        let pool = self
+            .last_values
+            .iter()
+            .find(|pool| pool.task_pool.path == pool_path)?;
+        let TaskValue::Init(value, _) = pool.task_values.get(task_idx)? else {
+            return None;
+        };
+        let FutureValueKind::AsyncFn(async_fn) = &value.kind else {
+            return None;
+        };
+        match &async_fn.state_value {
+            Ok(state) => Some(state.discriminant),
+            Err((discriminant, _)) => *discriminant,
+        }
+    }
+
+    /// Show the task that is currently executing if follow mode is enabled.
+    ///
+    /// Only the pool can be found from the program counter, for pools with more than one spawned
+    /// task the first one gets shown. Nothing changes if the program counter is not inside a task.
+    fn follow_current_task<C: Callback>(&mut self, callback: &mut C) {
+        if !self.ui_state.follow() {
+            return;
+        }
+
+        let pc = match callback.read_pc() {
+            Ok(Some(pc)) => pc,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("{}", e);
+                return;
+            }
+        };
+
+        let Some(pool_idx) = self.debug_data.task_pool_for_pc(pc) else {
+            return;
+        };
+        // `last_values` skips pools that could not be read, so look the pool up by its path.
+        let pool_path = &self.debug_data.task_pools[pool_idx].path;
+        let Some((value_idx, pool_value)) = self
+            .last_values
+            .iter()
+            .enumerate()
+            .find(|(_, value)| &value.task_pool.path == pool_path)
+        else {
+            return;
+        };
+        let Some(task_idx) = pool_value
+            .task_values
+            .iter()
+            .position(|task| matches!(task, TaskValue::Init(..)))
+        else {
+            return;
+        };
+
+        self.ui_state.show_task(value_idx, task_idx);
+    }
+
+    /// Redraw the TUI.
+    ///
+    /// Events from the UI that need more than the UI state are collected during drawing and
+    /// applied afterwards, after which the UI gets drawn again.
+    fn draw<C: Callback>(
+        &mut self,
+        mut click: Option<Click>,
+        mut drag: Option<Position>,
+        mut scroll: Option<(Position, i32)>,
+        callback: &mut C,
+    ) -> Result<()> {
+        loop {
+            let mut inspector_events = Vec::new();
+            let mut copied = None;
+            let mut copy_screen = false;
+            let mut selectable_rows = 0;
+
+            let completed = self.terminal.draw(|frame| {
+                let mut ctx = UiDrawCtx {
+                    frame,
+                    click,
+                    drag,
+                    scroll,
+                    values: &self.last_values,
+                    previous_values: &self.previous_values,
+                    futures_by_address: &self.futures_by_address,
+                    unavailable_pools: &self.unavailable_pools,
+                    overlapping_pools: &self.debug_data.overlapping_pools,
+                    watches: &self.watches,
+                    all_elements: &self.all_elements,
+                    max_elements: self.config.max_elements,
+                    parked: &self.parked,
+                    now: self.now,
+                    stuck: &self.stuck,
+                    source_files: &mut self.source_files,
+                    source_context_lines: self.config.source_context_lines,
+                    poll_breakpoints: &self.poll_breakpoints,
+                    disconnected: self.disconnected,
+                    state_break: self
+                        .state_break
+                        .as_ref()
+                        .map(|state_break| (state_break.pool_path.as_str(), state_break.task_idx)),
+                    holding: self.holding,
+                    show_discriminants: self.config.show_discriminants,
+                    show_legend: self.ui_state.show_legend(),
+                    show_raw_bytes: self.ui_state.show_raw_bytes(),
+                    highlight_changes: self.ui_state.highlight_changes(),
+                    accessible: self.config.accessible,
+                    theme: self.config.theme,
+                    #[cfg(feature = "task-graph-image")]
+                    graph_image: self.graph_image.as_mut(),
+                    status: self.status.as_deref(),
+                    selection: self.ui_state.selection(),
+                    selectable_rows: 0,
+                    try_format_value: &mut |b, ty, max_elements| {
+                        self.formating_cache
+                            .entry((b.to_vec(), ty.clone(), max_elements))
+                            .or_insert_with_key(|(b, t, max_elements)| {
+                                format::format_value(
+                                    b,
+                                    t,
+                                    &self.debug_data.builtin_types,
+                                    self.config.unformattable,
+                                    *max_elements,
+                                    callback,
+                                )
+                            })
+                            .clone()
+                    },
+                };
+
+                while let Err(event) = self.ui_state.draw(&mut ctx) {
+                    match event {
+                        event @ (UiEvent::ToggleWatch { .. }
+                        | UiEvent::ToggleAllElements(_)
+                        | UiEvent::WriteMemory { .. }
+                        | UiEvent::InspectFuture { .. }
+                        | UiEvent::RunToNextState(_)
+                        | UiEvent::Continue) => inspector_events.push(event),
+                        UiEvent::CopyPage => match self.ui_state.page_text(&mut ctx) {
+                            Some(text) => copied = Some(text),
+                            // Copied once the page is fully drawn.
+                            None => copy_screen = true,
+                        },
+                        UiEvent::CopyToClipboard(text) => copied = Some(text),
+                        event => {
+                            self.ui_state.apply_event(event);
+                            ctx.show_legend = self.ui_state.show_legend();
+                            ctx.show_raw_bytes = self.ui_state.show_raw_bytes();
+                            ctx.highlight_changes = self.ui_state.highlight_changes();
+                        }
+                    }
+                    ctx.click = None;
+                    ctx.drag = None;
+                    ctx.scroll = None;
+                    // Enter activates the selected row once, like a click.
+                    self.ui_state.selection_activated();
+                    ctx.selection = self.ui_state.selection();
+
+                    ctx.frame
+                        .render_widget(ratatui::widgets::Clear, ctx.frame.area());
+                }
+
+                selectable_rows = ctx.selectable_rows;
+
+                if self.config.color_palette == ColorPalette::Ansi16 {
+                    palette::limit_to_ansi_colors(ctx.frame.buffer_mut());
+                }
+            })?;
+            self.ui_state.set_selectable_rows(selectable_rows);
+            if copy_screen {
+                copied = Some(ui::screen_to_text(completed.buffer));
+            }
+
+            if let Some(text) = copied {
+                self.status = Some(match callback.copy_to_clipboard(&text) {
+                    Ok(message) => message,
+                    Err(e) => format!("Could not copy: {e}"),
+                });
+            } else if inspector_events.is_empty() {
+                return Ok(());
+            }
+
+            click = None;
+            drag = None;
+            scroll = None;
+            for event in inspector_events {
+                self.apply_inspector_event(event, callback)?;
+            }
+        }
+    }
+
+    /// Apply an event from the UI that needs access to the inspector or the target.
+    fn apply_inspector_event<C: Callback>(
+        &mut self,
+        event: UiEvent,
+        callback: &mut C,
+    ) -> Result<()> {
+        match event {
+            UiEvent::ToggleWatch { address, size } => {
+                if let Some(idx) = self.watches.iter().position(|w| w.matches(address, size)) {
+                    self.watches.remove(idx);
+                } else {
+                    let mut watch = Watch::new(address, size);
+                    if self.config.is_readable(address, size) {
+                        match callback.read_memory(address, size) {
+                            Ok(bytes) => watch.update(bytes),
+                            Err(e) => log::error!("{}", e),
+                        }
+                    }
+                    self.watches.push(watch);
+                }
+            }
+            UiEvent::ToggleAllElements(address) => {
+                match self.all_elements.iter().position(|a| *a == address) {
+                    Some(idx) => {
+                        self.all_elements.remove(idx);
+                    }
+                    None => self.all_elements.push(address),
+                }
+            }
+            UiEvent::RunToNextState(Some((pool_path, task_idx))) => {
+                let discriminant = self.task_discriminant(&pool_path, task_idx);
+                self.state_break = Some(StateBreak {
+                    pool_path,
+                    task_idx,
+                    discriminant,
+                });
+                if self.holding {
+                    self.holding = false;
+                    callback.resume()?;
+                }
+            }
+            UiEvent::RunToNextState(None) => self.state_break = None,
+            UiEvent::WriteMemory { .. } if !self.is_live => {
+                self.status = Some(String::from("A snapshot can't be written to"));
+            }
+            // The running target could overwrite the bytes again before they are read back.
+            UiEvent::WriteMemory { .. } if !self.holding && !self.halted => {
+                self.status = Some(String::from("Halt the target before writing its memory"));
+            }
+            UiEvent::WriteMemory { address, bytes } => {
+                self.status = Some(match callback.write_memory(address, &bytes) {
+                    Ok(()) => {
+                        log::info!("Wrote {bytes:02x?} at {address:#x}");
+                        // Show what the target does with the new bytes.
+                        self.update_values(callback);
+                        format!("Wrote {} bytes at {address:#x}", bytes.len())
+                    }
+                    Err(e) => format!("Could not write memory: {e:#}"),
+                });
+            }
+            UiEvent::Continue => {
+                if self.holding {
+                    self.holding = false;
+                    callback.resume()?;
+                }
+            }
+            UiEvent::InspectFuture { address, type_path } => {
+                if let Err(e) = self.add_future_by_address(address, &type_path, callback) {
+                    self.status = Some(format!("Could not show the future: {e:#}"));
+                }
+            }
+            other => self.ui_state.apply_event(other),
+        }
 
         Ok(())
     }
 }
 
-/// Format a value using the callback.
-///
-/// Falls back to just printing a list of bytes if the formatter in the backend fails.
-fn format_value<C: Callback>(bytes: &[u8], ty: &Type, callback: &mut C) -> Line<'static> {
-    match callback
-        .try_format_value(&bytes, &ty)
-        .and_then(|formatted| ansi_to_tui::IntoText::into_text(&formatted).ok())
-        .map(|text| text.into_iter().flatten())
-    {
-        Some(spans) => Line::from_iter(spans),
-        None => Line::from_iter([
-            Span::raw("bytes ["),
-            Span::raw(
-                bytes
-                    .iter()
-                    .map(|b| format!(" {b:0>2x}"))
-                    .collect::<String>(),
-            )
-            .blue(),
-            Span::raw(" ]"),
-        ]),
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Callback for a target with the memory of a single task.
+    struct TaskMemory {
+        address: u64,
+        bytes: Vec<u8>,
+    }
+
+    impl Callback for TaskMemory {
+        fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+            Ok(std::iter::empty())
+        }
+
+        fn set_breakpoint(&mut self, addr: u64, _kind: BreakpointKind) -> Result<u64> {
+            Ok(addr)
+        }
+
+        fn resume(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
+            assert_eq!(addr, self.address);
+            Ok(self.bytes[..len as usize].to_vec())
+        }
+
+        fn try_format_value(&mut self, _bytes: &[u8], _ty: &Type) -> Option<String> {
+            None
+        }
+    }
+
+    #[test]
+    fn state_break_changed() -> Result<()> {
+        let debug_data = model::fixtures::debug_data("app.elf");
+        let pool = debug_data
+            .task_pools
+            .iter()
+            .find(|pool| pool.path == "app::worker")
+            .unwrap();
+        let (address, size) = pool.task_memory(1);
+        // The `state` of the `TaskHeader` is at 0x10 according to `llvm-dwarfdump`.
+        let spawned = 0x10;
+        let discriminant = (pool.future_offset + pool.async_fn_type.state_member.offset) as usize;
+        let mut target = TaskMemory {
+            address,
+            bytes: vec![0; size as usize],
+        };
+
+        let mut state_break = StateBreak {
+            pool_path: pool.path.clone(),
+            task_idx: 1,
+            discriminant: None,
+        };
+        assert!(!state_break.changed(&debug_data, &mut target)?);
+
+        // Spawned, in the `Unresumed` state.
+        target.bytes[spawned] = 1;
+        assert!(state_break.changed(&debug_data, &mut target)?);
+        state_break.discriminant = Some(0);
+        assert!(!state_break.changed(&debug_data, &mut target)?);
+
+        target.bytes[discriminant] = 3;
+        assert!(state_break.changed(&debug_data, &mut target)?);
+
+        // Done, the task is not spawned anymore.
+        target.bytes[spawned] = 0;
+        assert!(state_break.changed(&debug_data, &mut target)?);
+
+        state_break.pool_path = String::from("app::missing");
+        assert!(state_break.changed(&debug_data, &mut target).is_err());
+        Ok(())
+    }
+
+    /// Callback for a target running the app fixture, keeping the writes to its memory.
+    #[derive(Default)]
+    struct WrittenMemory {
+        writes: Vec<(u64, Vec<u8>)>,
+    }
+
+    impl Callback for WrittenMemory {
+        fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+            Ok([model::fixtures::path("app.elf")].into_iter())
+        }
+
+        fn set_breakpoint(&mut self, addr: u64, _kind: BreakpointKind) -> Result<u64> {
+            Ok(addr)
+        }
+
+        fn resume(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn halt(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_memory(&mut self, _addr: u64, len: u64) -> Result<Vec<u8>> {
+            Ok(vec![0; len as usize])
+        }
+
+        fn write_memory(&mut self, addr: u64, bytes: &[u8]) -> Result<()> {
+            self.writes.push((addr, bytes.to_vec()));
+            Ok(())
+        }
+
+        fn try_format_value(&mut self, _bytes: &[u8], _ty: &Type) -> Option<String> {
+            None
+        }
+    }
+
+    #[test]
+    fn write_only_when_stopped() -> Result<()> {
+        let mut target = WrittenMemory::default();
+        let mut inspector = EmbassyInspector::with_config(
+            ratatui::backend::TestBackend::new(100, 30),
+            &mut target,
+            Config::default(),
+        )?;
+        let write = || UiEvent::WriteMemory {
+            address: 0x2000_0000,
+            bytes: vec![1, 2],
+        };
+
+        inspector.apply_inspector_event(write(), &mut target)?;
+        assert!(target.writes.is_empty());
+
+        inspector.handle_event(Event::Stoped, &mut target)?;
+        inspector.apply_inspector_event(write(), &mut target)?;
+        assert_eq!(target.writes, [(0x2000_0000, vec![1, 2])]);
+
+        // At a poll breakpoint the target runs again right away.
+        let poll = inspector.poll_breakpoints[0].address;
+        inspector.handle_event(Event::Breakpoint(poll), &mut target)?;
+        inspector.apply_inspector_event(write(), &mut target)?;
+        assert_eq!(target.writes.len(), 1);
+
+        inspector.handle_event(Event::Halt, &mut target)?;
+        inspector.apply_inspector_event(write(), &mut target)?;
+        assert_eq!(target.writes.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn not_held_after_disconnect() -> Result<()> {
+        let mut target = WrittenMemory::default();
+        let mut inspector = EmbassyInspector::with_config(
+            ratatui::backend::TestBackend::new(100, 30),
+            &mut target,
+            Config::default(),
+        )?;
+        let write = || UiEvent::WriteMemory {
+            address: 0x2000_0000,
+            bytes: vec![1, 2],
+        };
+
+        inspector.handle_event(Event::Halt, &mut target)?;
+        inspector.apply_inspector_event(
+            UiEvent::RunToNextState(Some((String::from("app::worker"), 1))),
+            &mut target,
+        )?;
+        inspector.handle_event(Event::Halt, &mut target)?;
+        assert!(inspector.holding);
+        assert!(inspector.state_break.is_some());
+
+        inspector.handle_event(Event::Disconnected, &mut target)?;
+        assert!(!inspector.holding);
+        assert!(inspector.state_break.is_none());
+
+        inspector.handle_event(Event::Reconnected, &mut target)?;
+        inspector.apply_inspector_event(write(), &mut target)?;
+        assert!(target.writes.is_empty());
+        Ok(())
     }
 }
@@ -0,0 +1,161 @@
+//! A logger keeping the most recent log records in memory to show them in the TUI.
+//!
+//! Logging to the terminal would draw over the TUI, so backends without a place of their own to
+//! put the logs can install this logger with [`init_tui_logger`].
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// How many records are kept, older ones are dropped.
+const MAX_RECORDS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LogRecord {
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    pub(crate) message: String,
+}
+
+#[derive(Debug)]
+struct RingBuffer {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, record: LogRecord) {
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// The last `count` records of at least the given level, oldest first.
+    fn recent(&self, level: Level, count: usize) -> Vec<LogRecord> {
+        let mut recent = self
+            .records
+            .iter()
+            .rev()
+            .filter(|record| record.level <= level)
+            .take(count)
+            .cloned()
+            .collect::<Vec<_>>();
+        recent.reverse();
+        recent
+    }
+}
+
+struct TuiLogger {
+    buffer: Mutex<RingBuffer>,
+}
+
+impl Log for TuiLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        // Filtering is done by the max level set in `init_tui_logger`
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let Ok(mut buffer) = self.buffer.lock() else {
+            return;
+        };
+        buffer.push(LogRecord {
+            level: record.level(),
+            target: record.target().to_owned(),
+            message: record.args().to_string(),
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: TuiLogger = TuiLogger {
+    buffer: Mutex::new(RingBuffer::new(MAX_RECORDS)),
+};
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Install a logger that keeps the recent records up to `max_level` to show them in the log pane
+/// of the TUI.
+///
+/// Fails if a different logger has already been installed.
+pub fn init_tui_logger(max_level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(max_level);
+    INSTALLED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Whether [`init_tui_logger`] was used, otherwise there is nothing to show in the log pane.
+pub(crate) fn is_installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// The last `count` log records of at least the given level, oldest first.
+pub(crate) fn recent_records(level: Level, count: usize) -> Vec<LogRecord> {
+    match LOGGER.buffer.lock() {
+        Ok(buffer) => buffer.recent(level, count),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(level: Level, message: &str) -> LogRecord {
+        LogRecord {
+            level,
+            target: String::from("test"),
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn drops_oldest() {
+        let mut buffer = RingBuffer::new(2);
+        buffer.push(record(Level::Info, "a"));
+        buffer.push(record(Level::Info, "b"));
+        buffer.push(record(Level::Info, "c"));
+
+        assert_eq!(
+            buffer.recent(Level::Trace, 10),
+            vec![record(Level::Info, "b"), record(Level::Info, "c")]
+        );
+    }
+
+    #[test]
+    fn level_filter() {
+        let mut buffer = RingBuffer::new(10);
+        buffer.push(record(Level::Error, "a"));
+        buffer.push(record(Level::Debug, "b"));
+        buffer.push(record(Level::Warn, "c"));
+        buffer.push(record(Level::Error, "d"));
+
+        assert_eq!(
+            buffer.recent(Level::Warn, 10),
+            vec![
+                record(Level::Error, "a"),
+                record(Level::Warn, "c"),
+                record(Level::Error, "d"),
+            ]
+        );
+        assert_eq!(
+            buffer.recent(Level::Warn, 2),
+            vec![record(Level::Warn, "c"), record(Level::Error, "d")]
+        );
+    }
+}
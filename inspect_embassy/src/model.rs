@@ -1,17 +1,31 @@
 //! Contains types to model the memory layout of the future types of a particular program and to
 //! store the value of those types in a running target.
 
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use anyhow::Result;
 
-use ddbug_parser::FileHash;
+use ddbug_parser::{FileHash, TypeKind};
+use serde::{Deserialize, Serialize};
 
 use task_pool::{TaskPool, TaskPoolValue};
 
-use self::{future::FutureType, task_pool::HeaderLayout, ty::Type};
+use self::{
+    builtin::BuiltinType,
+    future::{FutureType, FutureTypeKind},
+    task_pool::HeaderLayout,
+    ty::Type,
+};
 
 pub(crate) mod async_fn;
+pub(crate) mod builtin;
+mod cache;
+pub(crate) mod embassy_sync;
 pub(crate) mod future;
 pub(crate) mod task_pool;
 pub(crate) mod ty;
@@ -49,7 +63,7 @@ fn from_namespace_and_name(
 }
 
 /// A location in the source code.
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub(crate) struct Source {
     pub(crate) path: String,
     pub(crate) line: u32,
@@ -82,69 +96,522 @@ impl Source {
     }
 }
 
+/// Replace the first `from` prefix of `path` in `remaps` by its `to`, like the `substitute-path` of
+/// GDB. Prefixes only match whole path components, `/build` does not match `/builds/app.rs`.
+pub(crate) fn remap_path(path: &str, remaps: &[(String, String)]) -> Option<String> {
+    remaps.iter().find_map(|(from, to)| {
+        let from = from.trim_end_matches(['/', '\\']);
+        let rest = path.strip_prefix(from)?;
+        if !(rest.is_empty() || rest.starts_with(['/', '\\'])) {
+            return None;
+        }
+        Some(format!("{}{rest}", to.trim_end_matches(['/', '\\'])))
+    })
+}
+
 /// The full model extracted from the debug data.
-#[derive(Debug, Clone)]
-pub(crate) struct DebugData {
-    /// Address of the ends of the poll functions.
+///
+/// Also usable on its own by tools that only need to look things up in the debug data, like
+/// [`DebugData::task_for_pc`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DebugData {
+    /// The ends of the poll functions.
     ///
     /// Can be more than one because of the use of multiple executors or inlining.
-    pub(crate) poll_done_addresses: Vec<u64>,
+    pub(crate) poll_ends: Vec<PollEnd>,
+    /// Size of the instruction functions end with on the architecture of the object file, see
+    /// [`return_instruction_size`].
+    pub(crate) return_instruction_size: u64,
+    /// Address ranges of the poll functions, including where they got inlined.
+    pub(crate) poll_ranges: Vec<Range<u64>>,
 
+    #[serde(with = "cache::map_as_pairs")]
     pub(crate) future_types: HashMap<Type, FutureType>,
+    /// Types that can be formatted without the backend.
+    #[serde(with = "cache::map_as_pairs")]
+    pub(crate) builtin_types: HashMap<Type, BuiltinType>,
     pub(crate) task_pools: Vec<TaskPool>,
+    /// Paths of the task pools whose memory overlaps, see [`overlapping_ranges`].
+    pub(crate) overlapping_pools: Vec<(String, String)>,
+    /// Futures found in other statics, see [`StaticFuture`].
+    pub(crate) static_futures: Vec<StaticFuture>,
+}
+
+/// How deep to look into the types of statics for a future, enough for the
+/// `StaticCell` → `UnsafeCell` → `MaybeUninit` → `ManuallyDrop` around it.
+const MAX_STATIC_FUTURE_DEPTH: usize = 5;
+
+/// A future stored in a `static` outside of the task pools, like in a `StaticCell` of an
+/// executor driven by hand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StaticFuture {
+    /// Path of the static.
+    pub(crate) path: String,
+    /// Address of the future itself, inside the static.
+    pub(crate) address: u64,
+    pub(crate) ty: Type,
+    pub(crate) size: u64,
+}
+
+impl StaticFuture {
+    /// Only recognizes the future types that are in `future_types`, to not show random statics.
+    fn from_ddbug_var(
+        unit_var: &ddbug_parser::Variable<'_>,
+        future_types: &HashMap<Type, FutureType>,
+        file_hash: &FileHash<'_>,
+    ) -> Option<Self> {
+        let name = unit_var.name()?;
+        let address = unit_var.address()?;
+        let ty = unit_var.ty(file_hash)?;
+
+        let (offset, ty) =
+            Self::find_future(&ty, future_types, file_hash, MAX_STATIC_FUTURE_DEPTH)?;
+        let size = future_types.get(&ty)?.size?;
+
+        let path = match unit_var.namespace() {
+            Some(namespace) => format!("{}::{name}", namespace_to_path(namespace)),
+            None => name.to_owned(),
+        };
+
+        Some(Self {
+            path,
+            address: address + offset,
+            ty,
+            size,
+        })
+    }
+
+    /// Look through the members of structs and unions for a future type, returning its offset.
+    fn find_future(
+        ty: &ddbug_parser::Type<'_>,
+        future_types: &HashMap<Type, FutureType>,
+        file_hash: &FileHash<'_>,
+        depth: usize,
+    ) -> Option<(u64, Type)> {
+        let found = Type::from_ddbug_type(ty, file_hash);
+        if future_types.contains_key(&found) {
+            return Some((0, found));
+        }
+        if depth == 0 {
+            return None;
+        }
+
+        let members = match ty.kind() {
+            TypeKind::Struct(struct_type) => struct_type.members(),
+            TypeKind::Union(union_type) => union_type.members(),
+            _ => return None,
+        };
+        members.iter().find_map(|member| {
+            let member_ty = member.ty(file_hash)?;
+            let (offset, ty) = Self::find_future(&member_ty, future_types, file_hash, depth - 1)?;
+            Some((member.bit_offset() / 8 + offset, ty))
+        })
+    }
 }
 
 impl DebugData {
-    pub(crate) fn from_object_file(path: String) -> Result<Self> {
-        let file = ddbug_parser::File::parse(path)?;
+    /// Parse the debug data from the contents of an object file, for backends that don't have it
+    /// on the filesystem.
+    ///
+    /// ddbug can only parse files by their path, so for now the bytes are written to a temporary
+    /// file that gets parsed.
+    pub(crate) fn from_object_bytes(bytes: &[u8]) -> Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "inspect-embassy-{}-{}.elf",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, bytes)?;
+
+        let debug_data = Self::from_object_file(path.to_string_lossy().into_owned());
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::warn!("Could not remove {}: {e}", path.display());
+        }
+        debug_data
+    }
+
+    /// Parse the debug data of the object file at `path`.
+    pub fn from_object_file(path: String) -> Result<Self> {
+        let file = ddbug_parser::File::parse(path.clone())?;
         let file = file.file();
         let file_hash = FileHash::new(file);
 
         let mut future_types = HashMap::new();
+        let mut builtin_types = HashMap::new();
         for unit in file.units() {
             for unit_type in unit.types() {
                 if let Some(future) = FutureType::from_ddbug_type(unit_type, &file_hash)? {
                     let ty = Type::from_ddbug_type(unit_type, &file_hash);
                     future_types.insert(ty, future);
                 }
+                if let Some(builtin_type) = BuiltinType::from_ddbug_type(unit_type, &file_hash) {
+                    let ty = Type::from_ddbug_type(unit_type, &file_hash);
+                    builtin_types.insert(ty, builtin_type);
+                }
             }
         }
+        FutureType::remove_non_future_options(&mut future_types);
 
         let header_layout = HeaderLayout::from_ddbug_data(&file_hash)?;
 
         let mut task_pools = HashMap::new();
+        let mut static_futures = HashMap::new();
         for unit in file.units() {
             for unit_var in unit.variables() {
                 if let Some(task_pool) =
                     TaskPool::from_ddbug_var(unit_var, &future_types, &header_layout, &file_hash)?
                 {
                     task_pools.insert(task_pool.path.clone(), task_pool);
+                } else if let Some(static_future) =
+                    StaticFuture::from_ddbug_var(unit_var, &future_types, &file_hash)
+                {
+                    static_futures.insert(static_future.path.clone(), static_future);
                 }
             }
         }
+        let mut static_futures = static_futures.into_values().collect::<Vec<_>>();
+        static_futures.sort_unstable_by(|a, b| a.path.cmp(&b.path));
         let mut task_pools = task_pools.into_values().collect::<Vec<_>>();
         task_pools.sort_unstable_by_key(|task| std::cmp::Reverse(task.async_fn_type.total_size));
+        find_inlined_task_ranges(&mut task_pools, &file_hash);
+
+        let overlapping_pools = overlapping_ranges(
+            task_pools
+                .iter()
+                .map(|pool| (pool.path.as_str(), pool.address..pool.address + pool.size)),
+        );
+        for (a, b) in &overlapping_pools {
+            log::warn!("The memory of task pools {a} and {b} overlaps, their values can be wrong");
+        }
 
-        let poll_done_addresses = find_poll_function_addresses(&file_hash);
-        if poll_done_addresses.is_empty() {
+        let (poll_ends, poll_ranges) = find_poll_function(&file_hash);
+        if poll_ends.is_empty() {
             log::warn!(
                 "Could't not find the poll function, manualy break the target to update the display"
             );
         }
+        let function_end = poll_ends.iter().find_map(|poll_end| match poll_end {
+            PollEnd::Function(end) => Some(*end),
+            PollEnd::Inlined(..) => None,
+        });
+        let return_instruction_size = return_instruction_size(&path, function_end);
 
         Ok(Self {
-            poll_done_addresses,
+            poll_ends,
+            return_instruction_size,
+            poll_ranges,
             task_pools,
+            overlapping_pools,
             future_types,
+            builtin_types,
+            static_futures,
         })
     }
 
+    /// Find a future type by its path, and its size.
+    ///
+    /// The path can also be that of the item containing the future, like the path of an async fn
+    /// for the future it returns, as long as only a single future type is inside it.
+    pub(crate) fn find_future_type(&self, path: &str) -> Result<(Type, u64), String> {
+        let exact = Type::Base(path.to_owned());
+        let (ty, future_type) = match self.future_types.get_key_value(&exact) {
+            Some(found) => found,
+            None => {
+                let mut found = self.future_types.iter().filter(|(ty, _)| match ty {
+                    Type::Base(name) => task_pool::is_in_path(name, path),
+                    _ => false,
+                });
+                match (found.next(), found.next()) {
+                    (Some(found), None) => found,
+                    (Some(_), Some(_)) => {
+                        return Err(format!("More than one future type inside {path}"));
+                    }
+                    (None, _) => return Err(format!("{path} is not a known future type")),
+                }
+            }
+        };
+
+        let size = future_type
+            .size
+            .ok_or_else(|| format!("The size of {ty} is unknown"))?;
+        Ok((ty.clone(), size))
+    }
+
+    /// Index of the task pool whose code contains the given address.
+    pub(crate) fn task_pool_for_pc(&self, pc: u64) -> Option<usize> {
+        self.task_pools.iter().position(|pool| pool.contains_pc(pc))
+    }
+
+    /// Find the task whose code contains the address `pc`, for example to show where the target
+    /// stopped.
+    ///
+    /// Returns the path of its task pool and whether `pc` is inside the executor's poll function,
+    /// which happens when the code of the task got inlined into it.
+    pub fn task_for_pc(&self, pc: u64) -> Option<(&str, bool)> {
+        let pool = &self.task_pools[self.task_pool_for_pc(pc)?];
+        let in_poll = self.poll_ranges.iter().any(|range| range.contains(&pc));
+        Some((&pool.path, in_poll))
+    }
+
     pub(crate) fn get_taskpool_value(&self, task_pool: &TaskPool, bytes: &[u8]) -> TaskPoolValue {
         TaskPoolValue::new(task_pool, bytes, &self.future_types)
     }
+
+    /// Move the pool of the main task in front of the others, if there is one.
+    pub(crate) fn pin_main_task(&mut self) {
+        if let Some(main_idx) = self.task_pools.iter().position(TaskPool::is_main) {
+            self.task_pools[..=main_idx].rotate_right(1);
+        }
+    }
+
+    /// Apply [`remap_path`] to the paths of all sources.
+    pub(crate) fn remap_source_paths(&mut self, remaps: &[(String, String)]) {
+        if remaps.is_empty() {
+            return;
+        }
+
+        let async_fn_types = self
+            .future_types
+            .values_mut()
+            .filter_map(|future_type| match &mut future_type.kind {
+                FutureTypeKind::AsyncFn(async_fn_type) => Some(async_fn_type),
+                _ => None,
+            })
+            .chain(
+                self.task_pools
+                    .iter_mut()
+                    .map(|pool| &mut pool.async_fn_type),
+            );
+        for async_fn_type in async_fn_types {
+            for source in async_fn_type
+                .states
+                .iter_mut()
+                .filter_map(|s| s.source.as_mut())
+            {
+                if let Some(path) = remap_path(&source.path, remaps) {
+                    source.path = path;
+                }
+            }
+        }
+    }
+
+    /// The addresses to break at once a poll is done with the executor polling there, `offset`
+    /// overrides the [`Self::return_instruction_size`] for the poll functions that were not
+    /// inlined.
+    pub(crate) fn poll_done_addresses(&self, offset: Option<u64>) -> Vec<(u64, ExecutorKind)> {
+        let offset = offset.unwrap_or(self.return_instruction_size);
+        self.poll_ends
+            .iter()
+            .filter_map(|poll_end| match poll_end {
+                PollEnd::Function(end) => match end.checked_sub(offset) {
+                    Some(address) => Some((address, ExecutorKind::Shared)),
+                    None => {
+                        log::warn!("The offset {offset} is larger than the poll end {end:#x}");
+                        None
+                    }
+                },
+                PollEnd::Inlined(end, kind) => Some((*end, *kind)),
+            })
+            .collect()
+    }
+}
+
+/// Where a poll function ends.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum PollEnd {
+    /// The end of the code of a poll function, the breakpoint goes on the return instruction
+    /// before it.
+    Function(u64),
+    /// The end of the code of an inlined poll function, which is the first instruction after it,
+    /// with the executor it got inlined into.
+    Inlined(u64, ExecutorKind),
+}
+
+/// The kind of executor a poll function belongs to.
+///
+/// All executors poll through the same `SyncExecutor`, only where its poll function got inlined
+/// tells them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum ExecutorKind {
+    /// The poll function was not inlined, every executor calls it.
+    Shared,
+    /// `embassy_executor::Executor`, polling in thread mode.
+    Thread,
+    /// `embassy_executor::InterruptExecutor`, polling from an interrupt handler.
+    Interrupt,
+    /// Inlined into something else, like an executor built on `embassy_executor::raw`.
+    Other,
+}
+
+/// Namespaces of the executors of the `arch-*` features of embassy-executor, the thread mode
+/// `Executor` has the same path on every architecture.
+const EXECUTOR_NAMESPACES: &[(&str, ExecutorKind)] = &[
+    ("embassy_executor::arch::thread", ExecutorKind::Thread),
+    ("embassy_executor::arch::interrupt", ExecutorKind::Interrupt),
+];
+
+impl ExecutorKind {
+    /// The kind of the executor with code in the given namespace, `None` if it is not one.
+    fn from_namespace(namespace: &str) -> Option<Self> {
+        EXECUTOR_NAMESPACES
+            .iter()
+            .find(|(path, _)| task_pool::is_in_path(namespace, path))
+            .map(|(_, kind)| *kind)
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ExecutorKind::Shared => "all executors",
+            ExecutorKind::Thread => "thread mode",
+            ExecutorKind::Interrupt => "interrupt",
+            ExecutorKind::Other => "other executor",
+        }
+    }
 }
 
-fn find_poll_function_addresses(file_hash: &FileHash) -> Vec<u64> {
+const EM_386: u16 = 3;
+const EM_ARM: u16 = 40;
+const EM_X86_64: u16 = 62;
+const EM_XTENSA: u16 = 94;
+const EM_RISCV: u16 = 243;
+const EF_RISCV_RVC: u32 = 0x1;
+
+/// Guess the size of the return instruction at the end of functions from the machine in the ELF
+/// header of the object file, 4 bytes if it is not known. On Arm the code before `function_end`,
+/// the end of the poll function that was not inlined, tells the size.
+fn return_instruction_size(path: &str, function_end: Option<u64>) -> u64 {
+    let elf = std::fs::read(path).unwrap_or_default();
+    if elf.len() < 52 || !elf.starts_with(b"\x7fELF") {
+        log::debug!("{path} is not an ELF file, guessing 4 byte return instructions");
+        return 4;
+    }
+    return_instruction_size_from_elf(&elf, function_end)
+}
+
+fn return_instruction_size_from_elf(elf: &[u8], function_end: Option<u64>) -> u64 {
+    let elf = Elf {
+        data: elf,
+        big_endian: elf[5] == 2,
+        is_64_bit: elf[4] == 2,
+    };
+
+    match elf.u16_at(18) {
+        // Embedded arm cores run Thumb code, mostly ending functions with a 2 byte `pop {.., pc}`
+        // or `bx lr`.
+        Some(EM_ARM) => function_end
+            .and_then(|end| elf.code_before(end))
+            .map_or(2, thumb_return_size),
+        // The compressed `c.jr ra` if the C extension is used.
+        Some(EM_RISCV) => {
+            let flags_offset = if elf.is_64_bit { 48 } else { 36 };
+            match elf.u32_at(flags_offset) {
+                Some(flags) if flags & EF_RISCV_RVC != 0 => 2,
+                _ => 4,
+            }
+        }
+        // The narrow `retw.n` of the windowed ABI used by the ESP32s.
+        Some(EM_XTENSA) => 2,
+        Some(EM_386 | EM_X86_64) => 1,
+        _ => 4,
+    }
+}
+
+/// Size of the last instruction of Thumb code ending with these 4 bytes.
+///
+/// `pop {.., pc}` and `bx lr` are 2 bytes. Otherwise a 32 bit instruction ends there if the first
+/// halfword starts one, like a `pop.w {.., pc}` or the `b.w` of a tail call.
+fn thumb_return_size(code: [u8; 4]) -> u64 {
+    let first = u16::from_le_bytes([code[0], code[1]]);
+    let last = u16::from_le_bytes([code[2], code[3]]);
+    if last & 0xff00 == 0xbd00 || last == 0x4770 {
+        return 2;
+    }
+    match first >> 11 {
+        0b11101 | 0b11110 | 0b11111 => 4,
+        _ => 2,
+    }
+}
+
+const SHT_PROGBITS: u32 = 1;
+
+/// Just enough of an ELF file to find the code at an address, without another dependency.
+struct Elf<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    is_64_bit: bool,
+}
+
+impl Elf<'_> {
+    fn bytes_at<const N: usize>(&self, offset: u64) -> Option<[u8; N]> {
+        let offset = usize::try_from(offset).ok()?;
+        self.data
+            .get(offset..offset.checked_add(N)?)?
+            .try_into()
+            .ok()
+    }
+
+    fn u16_at(&self, offset: u64) -> Option<u16> {
+        let bytes = self.bytes_at(offset)?;
+        Some(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32_at(&self, offset: u64) -> Option<u32> {
+        let bytes = self.bytes_at(offset)?;
+        Some(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    /// A word of the size of an address, `Elf32_Addr` and `Elf32_Off` or their 64 bit versions.
+    fn word_at(&self, offset: u64) -> Option<u64> {
+        match self.is_64_bit {
+            true => {
+                let bytes = self.bytes_at(offset)?;
+                Some(match self.big_endian {
+                    true => u64::from_be_bytes(bytes),
+                    false => u64::from_le_bytes(bytes),
+                })
+            }
+            false => self.u32_at(offset).map(u64::from),
+        }
+    }
+
+    /// The 4 bytes of code before `end`, from the section that contains them.
+    fn code_before(&self, end: u64) -> Option<[u8; 4]> {
+        let start = end.checked_sub(4)?;
+        let (shoff, shentsize, shnum) = match self.is_64_bit {
+            true => (self.word_at(0x28)?, self.u16_at(0x3a)?, self.u16_at(0x3c)?),
+            false => (self.word_at(0x20)?, self.u16_at(0x2e)?, self.u16_at(0x30)?),
+        };
+        let (addr, offset, size) = match self.is_64_bit {
+            true => (16, 24, 32),
+            false => (12, 16, 20),
+        };
+
+        (0..u64::from(shnum)).find_map(|idx| {
+            let header = shoff.checked_add(idx * u64::from(shentsize))?;
+            if self.u32_at(header.checked_add(4)?)? != SHT_PROGBITS {
+                return None;
+            }
+            let section_addr = self.word_at(header.checked_add(addr)?)?;
+            let section_size = self.word_at(header.checked_add(size)?)?;
+            if start < section_addr || end > section_addr.checked_add(section_size)? {
+                return None;
+            }
+            let section_offset = self.word_at(header.checked_add(offset)?)?;
+            self.bytes_at(section_offset.checked_add(start - section_addr)?)
+        })
+    }
+}
+
+/// Find the addresses of the ends of the poll functions and the address ranges of their code.
+fn find_poll_function(file_hash: &FileHash) -> (Vec<PollEnd>, Vec<Range<u64>>) {
     // Searches for a function with the path: embassy_executor::raw::{impl#9}::poll::{closure#0}
     // where #9 can be replaced with anything.
     let poll_function = 'main: {
@@ -160,64 +627,442 @@ fn find_poll_function_addresses(file_hash: &FileHash) -> Vec<u64> {
                     && namespace.ends_with("poll")
                 {
                     if let [range] = unit_fn.ranges() {
-                        return vec![range.end - 4];
+                        return (
+                            vec![PollEnd::Function(range.end)],
+                            vec![range.begin..range.end],
+                        );
                     } else if unit_fn.is_inline() {
                         break 'main unit_fn;
                     }
                 }
             }
         }
-        return Vec::new();
+        return (Vec::new(), Vec::new());
     };
 
     // Poll function got inlined, search all functions for where it ended up.
-    let mut addresses = Vec::new();
+    let mut found = Vec::new();
     for unit in file_hash.file.units() {
         for unit_fn in unit.functions() {
+            let kind = unit_fn
+                .namespace()
+                .and_then(|namespace| ExecutorKind::from_namespace(&namespace_to_path(namespace)))
+                .unwrap_or(ExecutorKind::Other);
             let details = unit_fn.details(file_hash);
 
             for inlined_function in details.inlined_functions() {
                 find_function_in_inlined(
                     poll_function,
                     inlined_function,
+                    kind,
                     file_hash,
-                    &mut addresses,
+                    &mut found,
                 );
             }
         }
     }
 
-    return addresses;
+    let ends = found
+        .iter()
+        .map(|(range, kind)| PollEnd::Inlined(range.end, *kind))
+        .collect();
+    let ranges = found.into_iter().map(|(range, _)| range).collect();
+    return (ends, ranges);
 }
 
 /// Recursively look for all locations the given function is inlined into the given inlined_function.
-/// Adds the found address to the found_addresses.
+/// Adds the address ranges of the found locations to the found_ranges, with the kind of the
+/// innermost executor function they are inside of, starting with `kind`.
 fn find_function_in_inlined(
     function: &ddbug_parser::Function,
     inlined_function: &ddbug_parser::InlinedFunction,
+    mut kind: ExecutorKind,
     file_hash: &FileHash,
-    found_addresses: &mut Vec<u64>,
+    found_ranges: &mut Vec<(Range<u64>, ExecutorKind)>,
 ) {
-    if let Some(resolved_inlined_function) = inlined_function.abstract_origin(file_hash)
-        && ddbug_parser::Function::<'_>::cmp_id(
+    if let Some(resolved_inlined_function) = inlined_function.abstract_origin(file_hash) {
+        if ddbug_parser::Function::<'_>::cmp_id(
             file_hash,
             resolved_inlined_function,
             file_hash,
             function,
         )
         .is_eq()
-    {
-        for range in inlined_function.ranges() {
-            if range.begin == 0 {
-                // Strange bug where every inlined function also has a range staring at 0.
-                // Just ignoring it here.
-                continue;
+        {
+            for range in inlined_function.ranges() {
+                if range.begin == 0 {
+                    // Strange bug where every inlined function also has a range staring at 0.
+                    // Just ignoring it here.
+                    continue;
+                }
+                found_ranges.push((range.begin..range.end, kind));
+            }
+        } else if let Some(namespace) = resolved_inlined_function.namespace()
+            && let Some(executor_kind) = ExecutorKind::from_namespace(&namespace_to_path(namespace))
+        {
+            // Like `Executor::run` inlined into `main`.
+            kind = executor_kind;
+        }
+    }
+
+    for inlined_function in inlined_function.inlined_functions() {
+        find_function_in_inlined(function, inlined_function, kind, file_hash, found_ranges);
+    }
+}
+
+/// The pairs of names whose address ranges overlap.
+///
+/// Two statics never share memory, so an overlap means the debug data was parsed wrong, for
+/// example a union taken for a task pool.
+fn overlapping_ranges<'a>(
+    ranges: impl IntoIterator<Item = (&'a str, Range<u64>)>,
+) -> Vec<(String, String)> {
+    let mut ranges = ranges
+        .into_iter()
+        .filter(|(_, range)| !range.is_empty())
+        .collect::<Vec<_>>();
+    ranges.sort_unstable_by_key(|(_, range)| range.start);
+
+    let mut overlapping = Vec::new();
+    for (idx, (name, range)) in ranges.iter().enumerate() {
+        // Sorted by start, so only the ranges directly after this one can start inside it.
+        for (other_name, _) in ranges[idx + 1..]
+            .iter()
+            .take_while(|(_, other)| other.start < range.end)
+        {
+            overlapping.push((name.to_string(), other_name.to_string()));
+        }
+    }
+    overlapping
+}
+
+/// Add the locations the code of the tasks got inlined into to their code ranges.
+fn find_inlined_task_ranges(task_pools: &mut [TaskPool], file_hash: &FileHash) {
+    if task_pools.is_empty() {
+        return;
+    }
+
+    for unit in file_hash.file.units() {
+        for unit_fn in unit.functions() {
+            let details = unit_fn.details(file_hash);
+
+            for inlined_function in details.inlined_functions() {
+                find_tasks_in_inlined(task_pools, inlined_function, file_hash);
             }
-            found_addresses.push(range.end);
         }
     }
+}
+
+/// Recursively look for inlined task code in the given inlined_function, see
+/// [`find_inlined_task_ranges`].
+fn find_tasks_in_inlined(
+    task_pools: &mut [TaskPool],
+    inlined_function: &ddbug_parser::InlinedFunction,
+    file_hash: &FileHash,
+) {
+    if let Some(origin) = inlined_function.abstract_origin(file_hash)
+        && let Some(namespace) = origin.namespace()
+        && let namespace = namespace_to_path(namespace)
+        && let Some(task_pool) = task_pools
+            .iter_mut()
+            .find(|pool| task_pool::is_in_path(&namespace, &pool.task_name))
+    {
+        task_pool.code_ranges.extend(
+            inlined_function
+                .ranges()
+                .iter()
+                // Same bug as in `find_function_in_inlined`.
+                .filter(|range| range.begin != 0)
+                .map(|range| range.begin..range.end),
+        );
+    }
 
     for inlined_function in inlined_function.inlined_functions() {
-        find_function_in_inlined(function, inlined_function, file_hash, found_addresses);
+        find_tasks_in_inlined(task_pools, inlined_function, file_hash);
+    }
+}
+
+/// The object files in `fixtures/` to test parsing with, see the README there for how they are built.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use ddbug_parser::FileHash;
+
+    use super::DebugData;
+
+    /// Path of the fixture with the given file name.
+    pub(crate) fn path(name: &str) -> String {
+        format!("{}/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    /// The model of the debug data of a fixture.
+    pub(crate) fn debug_data(name: &str) -> DebugData {
+        DebugData::from_object_file(path(name)).unwrap()
+    }
+
+    /// Call `f` with every type in the debug data of a fixture.
+    pub(crate) fn for_each_type(
+        name: &str,
+        mut f: impl FnMut(&ddbug_parser::Type<'_>, &FileHash<'_>),
+    ) {
+        let file = ddbug_parser::File::parse(path(name)).unwrap();
+        let file = file.file();
+        let file_hash = FileHash::new(file);
+        for unit in file.units() {
+            for unit_type in unit.types() {
+                f(unit_type, &file_hash);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn elf_header(is_64_bit: bool, machine: u16, flags: u32) -> Vec<u8> {
+        let mut header = vec![0; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[4] = if is_64_bit { 2 } else { 1 };
+        header[5] = 1;
+        header[18..20].copy_from_slice(&machine.to_le_bytes());
+        let flags_offset = if is_64_bit { 48 } else { 36 };
+        header[flags_offset..flags_offset + 4].copy_from_slice(&flags.to_le_bytes());
+        header
+    }
+
+    /// An Arm ELF file with a single section of `code` at address 0x1000.
+    fn arm_elf(code: &[u8]) -> Vec<u8> {
+        let mut elf = elf_header(false, EM_ARM, 0x5000400);
+        let code_offset = elf.len() as u32;
+        elf.extend_from_slice(code);
+        let section_headers_offset = elf.len() as u32;
+        // The null section first.
+        elf.extend_from_slice(&[0; 40]);
+        let mut section = [0; 40];
+        section[4..8].copy_from_slice(&SHT_PROGBITS.to_le_bytes());
+        section[12..16].copy_from_slice(&0x1000u32.to_le_bytes());
+        section[16..20].copy_from_slice(&code_offset.to_le_bytes());
+        section[20..24].copy_from_slice(&(code.len() as u32).to_le_bytes());
+        elf.extend_from_slice(&section);
+
+        elf[0x20..0x24].copy_from_slice(&section_headers_offset.to_le_bytes());
+        elf[0x2e..0x30].copy_from_slice(&40u16.to_le_bytes());
+        elf[0x30..0x32].copy_from_slice(&2u16.to_le_bytes());
+        elf
+    }
+
+    #[test]
+    fn return_instruction_sizes() {
+        let size = |header: Vec<u8>| return_instruction_size_from_elf(&header, None);
+
+        assert_eq!(size(elf_header(false, EM_ARM, 0x5000400)), 2);
+        assert_eq!(size(elf_header(false, EM_RISCV, EF_RISCV_RVC)), 2);
+        assert_eq!(size(elf_header(false, EM_RISCV, 0)), 4);
+        assert_eq!(size(elf_header(true, EM_RISCV, EF_RISCV_RVC)), 2);
+        assert_eq!(size(elf_header(true, EM_X86_64, 0)), 1);
+        // AArch64
+        assert_eq!(size(elf_header(true, 183, 0)), 4);
+    }
+
+    #[test]
+    fn thumb_return_sizes() {
+        // movs r0, #0; pop {r4, pc}
+        assert_eq!(thumb_return_size([0x00, 0x20, 0x10, 0xbd]), 2);
+        // movs r0, #0; bx lr
+        assert_eq!(thumb_return_size([0x00, 0x20, 0x70, 0x47]), 2);
+        // pop.w {r4, r5, r6, pc}
+        assert_eq!(thumb_return_size([0xbd, 0xe8, 0x70, 0x80]), 4);
+        // The b.w of a tail call.
+        assert_eq!(thumb_return_size([0x00, 0xf0, 0x00, 0xb8]), 4);
+        // movs r0, #0; b .
+        assert_eq!(thumb_return_size([0x00, 0x20, 0xfe, 0xe7]), 2);
+
+        // bx lr; pop {r4, pc}; pop.w {r4, r5, r6, pc}
+        let elf = arm_elf(&[0x70, 0x47, 0x10, 0xbd, 0xbd, 0xe8, 0x70, 0x80]);
+        let size = |end| return_instruction_size_from_elf(&elf, Some(end));
+        assert_eq!(size(0x1008), 4);
+        assert_eq!(size(0x1004), 2);
+        // Outside of the code, the usual 2 bytes.
+        assert_eq!(size(0x1002), 2);
+        assert_eq!(size(0x100a), 2);
+    }
+
+    #[test]
+    fn remap_paths() {
+        let remaps = [
+            ("/builds/ci/app/".to_owned(), "/home/me/app".to_owned()),
+            (
+                "/rustc/abc123".to_owned(),
+                "/home/me/.rustup/src".to_owned(),
+            ),
+        ];
+
+        assert_eq!(
+            remap_path("/builds/ci/app/src/main.rs", &remaps).as_deref(),
+            Some("/home/me/app/src/main.rs")
+        );
+        assert_eq!(
+            remap_path("/rustc/abc123/library/core/src/future.rs", &remaps).as_deref(),
+            Some("/home/me/.rustup/src/library/core/src/future.rs")
+        );
+        assert_eq!(remap_path("/builds/ci/application/x.rs", &remaps), None);
+        assert_eq!(remap_path("src/main.rs", &remaps), None);
+    }
+
+    #[test]
+    fn overlapping() {
+        let ranges = [
+            ("c", 0x300..0x340),
+            ("a", 0x100..0x200),
+            ("b", 0x180..0x300),
+            ("d", 0x1a0..0x1b0),
+            ("empty", 0x200..0x200),
+        ];
+        assert_eq!(
+            overlapping_ranges(ranges),
+            vec![
+                ("a".to_owned(), "b".to_owned()),
+                ("a".to_owned(), "d".to_owned()),
+                ("b".to_owned(), "d".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn poll_done_addresses() {
+        let debug_data = DebugData {
+            poll_ends: vec![
+                PollEnd::Function(0x1000),
+                PollEnd::Inlined(0x2000, ExecutorKind::Interrupt),
+            ],
+            return_instruction_size: 2,
+            poll_ranges: Vec::new(),
+            future_types: HashMap::new(),
+            builtin_types: HashMap::new(),
+            task_pools: Vec::new(),
+            overlapping_pools: Vec::new(),
+            static_futures: Vec::new(),
+        };
+
+        assert_eq!(
+            debug_data.poll_done_addresses(None),
+            vec![
+                (0xffe, ExecutorKind::Shared),
+                (0x2000, ExecutorKind::Interrupt)
+            ]
+        );
+        assert_eq!(
+            debug_data.poll_done_addresses(Some(4)),
+            vec![
+                (0xffc, ExecutorKind::Shared),
+                (0x2000, ExecutorKind::Interrupt)
+            ]
+        );
+        // An offset larger than the end of the function skips it instead of wrapping around.
+        assert_eq!(
+            debug_data.poll_done_addresses(Some(0x1001)),
+            vec![(0x2000, ExecutorKind::Interrupt)]
+        );
+    }
+
+    #[test]
+    fn tasks_for_pcs() {
+        let mut debug_data = fixtures::debug_data("app.elf");
+
+        // The tasks got inlined into the poll functions of their `TaskStorage`, at these addresses
+        // according to `llvm-dwarfdump`.
+        assert_eq!(
+            debug_data.task_for_pc(0x201488),
+            Some(("app::collect", false))
+        );
+        assert_eq!(
+            debug_data.task_for_pc(0x201565),
+            Some(("app::collect", false))
+        );
+        assert_eq!(
+            debug_data.task_for_pc(0x201621),
+            Some(("app::worker", false))
+        );
+        // Between the tasks, and inside the poll function of the executor.
+        assert_eq!(debug_data.task_for_pc(0x201566), None);
+        assert_eq!(debug_data.task_for_pc(0x2018bb), None);
+
+        // Like a task inlined into the poll function.
+        debug_data.poll_ranges.push(0x201600..0x201700);
+        assert_eq!(
+            debug_data.task_for_pc(0x201621),
+            Some(("app::worker", true))
+        );
+    }
+
+    #[test]
+    fn poll_inlined_into_executors() {
+        let debug_data = fixtures::debug_data("multiprio.elf");
+
+        // The closure polling a task got inlined into `Executor::run` and
+        // `InterruptExecutor::on_interrupt`, ending at these addresses according to
+        // `llvm-dwarfdump`.
+        let mut poll_done = debug_data.poll_done_addresses(None);
+        poll_done.sort_by_key(|(address, _)| *address);
+        assert_eq!(
+            poll_done,
+            vec![
+                (0x2016bd, ExecutorKind::Thread),
+                (0x20170d, ExecutorKind::Interrupt)
+            ]
+        );
+
+        let mut paths = debug_data
+            .task_pools
+            .iter()
+            .map(|pool| pool.path.as_str())
+            .collect::<Vec<_>>();
+        paths.sort_unstable();
+        assert_eq!(paths, ["multiprio::run_high", "multiprio::run_low"]);
+
+        // Both ranges of each task, which got inlined into the poll function of its `TaskStorage`.
+        for (pc, task) in [
+            (0x2013f1, "multiprio::run_high"),
+            (0x201453, "multiprio::run_high"),
+            (0x201461, "multiprio::run_low"),
+            (0x201529, "multiprio::run_low"),
+        ] {
+            assert_eq!(debug_data.task_for_pc(pc), Some((task, false)));
+        }
+        // Between the ranges of a task, and in the inlined poll closure.
+        assert_eq!(debug_data.task_for_pc(0x201451), None);
+        assert_eq!(debug_data.task_for_pc(0x2016ab), None);
+    }
+
+    #[test]
+    fn static_cell_future() {
+        let debug_data = fixtures::debug_data("nightly.elf");
+
+        // `nightly::MANUAL` is at 0x202764, the future follows the `used` flag of the `StaticCell`.
+        let manual = debug_data
+            .static_futures
+            .iter()
+            .find(|future| future.path == "nightly::MANUAL")
+            .unwrap();
+        assert_eq!(manual.address, 0x202765);
+        assert_eq!(manual.size, 3);
+        assert_eq!(manual.ty.to_string(), "nightly::manual::{async_fn_env#0}");
+    }
+
+    #[test]
+    fn executor_kinds() {
+        assert_eq!(
+            ExecutorKind::from_namespace("embassy_executor::arch::thread::Executor"),
+            Some(ExecutorKind::Thread)
+        );
+        assert_eq!(
+            ExecutorKind::from_namespace("embassy_executor::arch::interrupt::{impl#2}"),
+            Some(ExecutorKind::Interrupt)
+        );
+        assert_eq!(
+            ExecutorKind::from_namespace("embassy_executor::arch::threads"),
+            None
+        );
+        assert_eq!(ExecutorKind::from_namespace("app::main"), None);
     }
 }
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 
 use ddbug_parser::{FileHash, Result, StructType, TypeKind};
+use serde::{Deserialize, Serialize};
 
 use super::{
     Source,
@@ -23,7 +24,7 @@ const FUTURE_TYPE_NAMES: &[&str] = &[
     "async_gen_fn",
 ];
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub(crate) struct Member {
     pub(crate) name: String,
     pub(crate) ty: Type,
@@ -39,10 +40,15 @@ impl Member {
         let ty = Type::from_maybe_ddbug_type(member.ty(file_hash), file_hash);
 
         let offset = member.bit_offset() / 8;
-        let size = member
-            .bit_size(file_hash)
-            .ok_or("member should have known sizes")?
-            / 8;
+        // ZST's and some opaque types don't have a size in the debug data, treating these as zero
+        // sized so they get skipped like any other ZST instead of failing the whole future type.
+        let size = match member.bit_size(file_hash) {
+            Some(bit_size) => bit_size / 8,
+            None => {
+                log::debug!("Member `{name}` has no known size, treating it as zero sized");
+                0
+            }
+        };
 
         Ok(Self {
             name,
@@ -53,9 +59,11 @@ impl Member {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct State {
-    pub(crate) discriminant_value: u64,
+    /// `None` for a variant without a discriminant value, it is the state for every value that
+    /// none of the other states have.
+    pub(crate) discriminant_value: Option<u64>,
     pub(crate) active_members: Vec<usize>,
 
     pub(crate) awaitee: Option<Member>,
@@ -64,33 +72,51 @@ pub(crate) struct State {
 }
 
 impl State {
+    /// What the [`Self::source`] of this state points to.
+    ///
+    /// For await points it is the `.await` the future is suspended at, polling it again resumes
+    /// by polling the awaitee there. The source of `Unresumed` is the start of the body.
+    pub(crate) fn source_label(&self) -> &'static str {
+        match (self.awaitee.is_some(), self.name.as_str()) {
+            (true, _) => "suspended at",
+            (false, "Unresumed") => "starts at",
+            (false, _) => "at",
+        }
+    }
+
+    /// The discriminant value to show, `default` for the state without one.
+    pub(crate) fn discriminant_label(&self) -> String {
+        match self.discriminant_value {
+            Some(discriminant_value) => discriminant_value.to_string(),
+            None => String::from("default"),
+        }
+    }
+
     fn from_ddbug_variant(
         variant: &ddbug_parser::Variant<'_>,
         active_members: Vec<usize>,
         awaitee: Option<Member>,
-    ) -> Result<Self> {
+    ) -> Self {
         let state_name = variant.name().unwrap_or("<unknown>").to_owned();
-        let Some(discriminant_value) = variant.discriminant_value() else {
-            return Err("future type varaints should always have discriminant values".into());
-        };
 
-        Ok(State {
+        State {
             name: state_name,
-            discriminant_value,
+            discriminant_value: variant.discriminant_value(),
             active_members,
             awaitee,
             source: Source::from_ddbug(variant.source()),
-        })
+        }
     }
 }
 
 /// The layout of a future type
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub(crate) struct AsyncFnType {
     pub(crate) members: Vec<Member>,
 
     pub(crate) state_member: Member,
 
+    /// Size of the whole future, computed from the members if the debug data has no size.
     pub(crate) total_size: u64,
 
     pub(crate) states: Vec<State>,
@@ -169,7 +195,11 @@ impl AsyncFnType {
                 }
             }
 
-            states.push(State::from_ddbug_variant(variant, active_members, awaitee)?);
+            states.push(State::from_ddbug_variant(variant, active_members, awaitee));
+        }
+        let default_states = states.iter().filter(|s| s.discriminant_value.is_none());
+        if default_states.count() > 1 {
+            return Err("future type has multiple variants without a discriminant value".into());
         }
 
         let [state_member] = ddbug_type.members() else {
@@ -180,22 +210,44 @@ impl AsyncFnType {
             return Err("Future types should always have a member named __state".into());
         }
 
-        let Some(total_size) = ddbug_type.byte_size() else {
-            return Err("Future types should have a size".into());
-        };
-
         let mut s = Self {
             members,
             state_member,
-            total_size,
+            total_size: 0,
             states,
         };
+        s.total_size = match ddbug_type.byte_size() {
+            Some(total_size) => total_size,
+            None => {
+                log::debug!(
+                    "Future type {} has no size, using the end of its last member",
+                    ddbug_type.name().unwrap_or("<unknown>")
+                );
+                s.end_of_members()
+            }
+        };
 
         s.sort_members_by_offset();
 
         Ok(s)
     }
 
+    /// The offset just past the member that ends last, including the awaitees and the state. Used
+    /// as the size when the debug data has none, this misses any padding at the end.
+    fn end_of_members(&self) -> u64 {
+        let awaitees = self
+            .states
+            .iter()
+            .filter_map(|state| state.awaitee.as_ref());
+        self.members
+            .iter()
+            .chain(awaitees)
+            .chain([&self.state_member])
+            .map(|member| member.offset + member.size)
+            .max()
+            .unwrap_or(0)
+    }
+
     /// Sort the members from small to biggest offset while keeping all id references intact.
     fn sort_members_by_offset(&mut self) {
         let mut old_ids = (0..self.members.len()).collect::<Vec<_>>();
@@ -218,17 +270,75 @@ impl AsyncFnType {
     }
 }
 
-impl AsyncFnType {}
+impl AsyncFnType {
+    /// The state with the given discriminant value, or the one without a discriminant value if
+    /// no state has it.
+    fn state_for(&self, discriminant: u64) -> Option<&State> {
+        self.states
+            .iter()
+            .find(|s| s.discriminant_value == Some(discriminant))
+            .or_else(|| self.states.iter().find(|s| s.discriminant_value.is_none()))
+    }
+
+    /// Read the discriminant of the current state from the bytes of the future.
+    pub(crate) fn read_discriminant(&self, bytes: &[u8]) -> Result<u64> {
+        let offset = self.state_member.offset as usize;
+        let size = self.state_member.size as usize;
+        if !matches!(size, 1 | 2 | 4 | 8) {
+            return Err("discriminants should be 1, 2, 4 or 8 bytes".into());
+        }
+        let Some(bytes) = bytes.get(offset..offset + size) else {
+            return Err("the bytes of the future end before its discriminant".into());
+        };
+        let mut discriminant = [0; 8];
+        discriminant[..size].copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(discriminant))
+    }
+
+    /// Whether the future in `bytes` returned, read from its discriminant only as the members of
+    /// a returned async fn are gone.
+    pub(crate) fn has_returned(&self, bytes: &[u8]) -> bool {
+        self.read_discriminant(bytes)
+            .ok()
+            .and_then(|discriminant| self.state_for(discriminant))
+            .is_some_and(|state| state.name == "Returned")
+    }
+
+    /// The position of `state` among the await points of this async fn and the total number of
+    /// them, `None` if the state is not an await point like `Unresumed` or `Returned`.
+    ///
+    /// The await points are ordered by their discriminant values, which the compiler assigns in
+    /// the order they appear in the body.
+    pub(crate) fn await_progress(&self, state: &State) -> Option<(usize, usize)> {
+        state.awaitee.as_ref()?;
 
-#[derive(Debug)]
+        let mut await_points = self
+            .states
+            .iter()
+            .filter(|s| s.awaitee.is_some())
+            .map(|s| s.discriminant_value)
+            .collect::<Vec<_>>();
+        await_points.sort_unstable();
+
+        let position = await_points
+            .iter()
+            .position(|d| *d == state.discriminant_value)?;
+        Some((position + 1, await_points.len()))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct MemberValue {
     pub(crate) member: Member,
     pub(crate) bytes: Vec<u8>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct StateValue {
     pub(crate) state: State,
+    /// The discriminant value read from the target, which differs from the one of the state for
+    /// the state without a discriminant value.
+    pub(crate) discriminant: u64,
 
     pub(crate) members: Vec<MemberValue>,
     pub(crate) awaitee: Option<Box<FutureValue>>,
@@ -237,6 +347,8 @@ pub(crate) struct StateValue {
 impl StateValue {
     fn new(
         state: &State,
+        discriminant: u64,
+        address: u64,
         bytes: &[u8],
         async_fn_type: &AsyncFnType,
         future_types: &HashMap<Type, FutureType>,
@@ -257,57 +369,239 @@ impl StateValue {
         let awaitee = state.awaitee.as_ref().map(|awaitee| {
             let bytes = &bytes[awaitee.offset as usize..][..awaitee.size as usize];
 
-            let future_value = FutureValue::new(&awaitee.ty, bytes, future_types);
+            let future_value =
+                FutureValue::new(&awaitee.ty, address + awaitee.offset, bytes, future_types);
 
             Box::new(future_value)
         });
 
         Self {
             state: state.clone(),
+            discriminant,
             members,
             awaitee,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct AsyncFnValue {
     pub(crate) ty: AsyncFnType,
 
-    /// Err value is the found discriminant value that does not have a corresponding State and the
-    /// bytes that supposedly contained the AsyncFn.
-    pub(crate) state_value: std::result::Result<StateValue, (u64, Vec<u8>)>,
+    /// Err value is the found discriminant value that does not have a corresponding State, `None`
+    /// if it could not be read, and the bytes that supposedly contained the AsyncFn.
+    pub(crate) state_value: std::result::Result<StateValue, (Option<u64>, Vec<u8>)>,
 }
 
 impl AsyncFnValue {
+    /// Parse the bytes of an async fn future located at `address` on the target.
     pub(crate) fn new(
         async_fn_type: &AsyncFnType,
+        address: u64,
         bytes: &[u8],
         future_types: &HashMap<Type, FutureType>,
     ) -> Self {
-        let state_discriminant = {
-            let bytes = &bytes[async_fn_type.state_member.offset as usize..];
-            match async_fn_type.state_member.size {
-                1 => u8::from_le_bytes(bytes[..1].try_into().unwrap()) as u64,
-                2 => u16::from_le_bytes(bytes[..2].try_into().unwrap()) as u64,
-                4 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as u64,
-                8 => u64::from_le_bytes(bytes[..8].try_into().unwrap()),
-                _ => unreachable!(),
+        let state_value = match async_fn_type.read_discriminant(bytes) {
+            Ok(discriminant) => match async_fn_type.state_for(discriminant) {
+                Some(state) => Ok(StateValue::new(
+                    state,
+                    discriminant,
+                    address,
+                    bytes,
+                    async_fn_type,
+                    future_types,
+                )),
+                None => Err((Some(discriminant), bytes.to_vec())),
+            },
+            Err(e) => {
+                log::warn!("Could not read the state of the future at {address:#x}: {e}");
+                Err((None, bytes.to_vec()))
             }
         };
 
-        let state = async_fn_type
-            .states
-            .iter()
-            .find(|s| s.discriminant_value == state_discriminant);
-
-        let state_value = state
-            .map(|s| StateValue::new(s, bytes, async_fn_type, future_types))
-            .ok_or((state_discriminant, bytes.to_vec()));
-
         Self {
             ty: async_fn_type.clone(),
             state_value,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::fixtures;
+
+    /// Parse the async fn type at `path` from the debug data of a fixture.
+    fn fixture_async_fn(fixture: &str, path: &str) -> AsyncFnType {
+        let mut found = None;
+        fixtures::for_each_type(fixture, |ty, file_hash| {
+            if Type::from_ddbug_type(ty, file_hash) == Type::Base(path.to_owned()) {
+                found = AsyncFnType::from_ddbug_type(ty, file_hash).unwrap();
+            }
+        });
+        found.unwrap_or_else(|| panic!("{path} is not an async fn in {fixture}"))
+    }
+
+    fn state(name: &str, discriminant_value: u64, awaiting: bool) -> State {
+        State {
+            discriminant_value: Some(discriminant_value),
+            active_members: Vec::new(),
+            awaitee: awaiting.then(Member::default),
+            name: name.to_owned(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn size_from_members() {
+        let member = |name: &str, offset, size| Member {
+            name: name.to_owned(),
+            ty: Type::Unknown,
+            offset,
+            size,
+        };
+
+        let mut suspend = state("Suspend0", 3, false);
+        suspend.awaitee = Some(member("__awaitee", 8, 24));
+        let ty = AsyncFnType {
+            members: vec![member("a", 0, 4), member("b", 4, 2)],
+            state_member: member("__state", 32, 1),
+            total_size: 0,
+            states: vec![state("Unresumed", 0, false), suspend],
+        };
+        assert_eq!(ty.end_of_members(), 33);
+
+        let ty = AsyncFnType {
+            state_member: member("__state", 6, 1),
+            states: vec![state("Unresumed", 0, false)],
+            ..ty
+        };
+        assert_eq!(ty.end_of_members(), 7);
+    }
+
+    #[test]
+    fn source_labels() {
+        assert_eq!(state("Suspend0", 3, true).source_label(), "suspended at");
+        assert_eq!(state("Unresumed", 0, false).source_label(), "starts at");
+        assert_eq!(state("Returned", 1, false).source_label(), "at");
+    }
+
+    #[test]
+    fn await_progress() {
+        let ty = AsyncFnType {
+            members: Vec::new(),
+            state_member: Member::default(),
+            total_size: 1,
+            states: vec![
+                state("Unresumed", 0, false),
+                state("Returned", 1, false),
+                state("Panicked", 2, false),
+                state("Suspend1", 4, true),
+                state("Suspend0", 3, true),
+                state("Suspend2", 5, true),
+            ],
+        };
+
+        assert_eq!(ty.await_progress(&ty.states[4]), Some((1, 3)));
+        assert_eq!(ty.await_progress(&ty.states[3]), Some((2, 3)));
+        assert_eq!(ty.await_progress(&ty.states[5]), Some((3, 3)));
+        assert_eq!(ty.await_progress(&ty.states[0]), None);
+    }
+
+    #[test]
+    fn default_state() {
+        let mut default = state("Suspend0", 0, false);
+        default.discriminant_value = None;
+        let ty = AsyncFnType {
+            members: Vec::new(),
+            state_member: Member {
+                size: 1,
+                ..Member::default()
+            },
+            total_size: 1,
+            states: vec![
+                state("Unresumed", 0, false),
+                default,
+                state("Returned", 1, false),
+            ],
+        };
+
+        let state_name = |bytes: &[u8]| {
+            let value = AsyncFnValue::new(&ty, 0x100, bytes, &HashMap::new());
+            let state = value.state_value.unwrap();
+            (state.state.name, state.discriminant)
+        };
+        assert_eq!(state_name(&[0]), (String::from("Unresumed"), 0));
+        assert_eq!(state_name(&[1]), (String::from("Returned"), 1));
+        assert_eq!(state_name(&[7]), (String::from("Suspend0"), 7));
+    }
+
+    #[test]
+    fn member_without_size() {
+        let ty = fixture_async_fn("handwritten.elf", "fixture::missing_size::{async_fn_env#0}");
+
+        let names = ty
+            .members
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["x"]);
+        let suspend = ty.states.iter().find(|s| s.name == "Suspend0").unwrap();
+        assert_eq!(suspend.active_members, [0]);
+        assert_eq!(suspend.awaitee.as_ref().unwrap().offset, 8);
+        assert_eq!(ty.total_size, 16);
+    }
+
+    #[test]
+    fn variant_without_discriminant() {
+        let ty = fixture_async_fn(
+            "handwritten.elf",
+            "fixture::default_variant::{async_fn_env#0}",
+        );
+        let suspend = ty.states.iter().find(|s| s.name == "Suspend0").unwrap();
+        assert_eq!(suspend.discriminant_value, None);
+
+        let state_name = |discriminant: u8| {
+            let bytes = [0, 0, 0, 0, discriminant, 0, 0, 0];
+            let value = AsyncFnValue::new(&ty, 0x100, &bytes, &HashMap::new());
+            value.state_value.unwrap().state.name
+        };
+        assert_eq!(state_name(0), "Unresumed");
+        assert_eq!(state_name(1), "Returned");
+        assert_eq!(state_name(3), "Suspend0");
+        assert_eq!(state_name(9), "Suspend0");
+    }
+
+    #[test]
+    fn future_without_size() {
+        let ty = fixture_async_fn("handwritten.elf", "fixture::no_size::{async_fn_env#0}");
+
+        // The state at 8 ends last, after the awaitee at 4.
+        assert_eq!(ty.total_size, 9);
+        let suspend = ty.states.iter().find(|s| s.name == "Suspend0").unwrap();
+        assert_eq!(suspend.awaitee.as_ref().unwrap().offset, 4);
+    }
+
+    #[test]
+    fn unreadable_discriminant() {
+        let mut ty = AsyncFnType {
+            members: Vec::new(),
+            state_member: Member {
+                offset: 2,
+                size: 2,
+                ..Member::default()
+            },
+            total_size: 4,
+            states: vec![state("Unresumed", 0, false)],
+        };
+        assert_eq!(ty.read_discriminant(&[0xff, 0xff, 1, 2]).unwrap(), 0x201);
+
+        // Memory that ends before the state.
+        assert!(ty.read_discriminant(&[0, 0, 0]).is_err());
+        let value = AsyncFnValue::new(&ty, 0x100, &[0, 0, 0], &HashMap::new());
+        assert!(matches!(value.state_value, Err((None, _))));
+
+        ty.state_member.size = 3;
+        assert!(ty.read_discriminant(&[0; 8]).is_err());
+    }
+}
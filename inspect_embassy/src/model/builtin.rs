@@ -0,0 +1,345 @@
+//! Layouts of well known types, allowing them to be formatted without the help of the backend.
+
+use ddbug_parser::{FileHash, TypeKind};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    namespace_to_path,
+    ty::{Type, tuple_members},
+};
+
+/// A single field of a struct.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) struct Field {
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+    pub(crate) ty: Type,
+}
+
+impl Field {
+    /// Find the field with the given name in the struct, `None` if it does not exist or has an
+    /// unknown size.
+    fn from_struct(
+        struct_type: &ddbug_parser::StructType<'_>,
+        name: &str,
+        file_hash: &FileHash,
+    ) -> Option<Self> {
+        Self::from_members(struct_type.members(), name, file_hash)
+    }
+
+    fn from_members(
+        members: &[ddbug_parser::Member<'_>],
+        name: &str,
+        file_hash: &FileHash,
+    ) -> Option<Self> {
+        let member = members.iter().find(|member| member.name() == Some(name))?;
+        Self::from_member(member, file_hash)
+    }
+
+    fn from_member(member: &ddbug_parser::Member<'_>, file_hash: &FileHash) -> Option<Self> {
+        Some(Self {
+            offset: member.bit_offset() / 8,
+            size: member.bit_size(file_hash)? / 8,
+            ty: Type::from_maybe_ddbug_type(member.ty(file_hash), file_hash),
+        })
+    }
+
+    /// Find a field nested inside the structs of the fields before it in the path, like
+    /// `["waker", "vtable"]`, with the offset from the start of the outer struct.
+    fn from_members_path(
+        members: &[ddbug_parser::Member<'_>],
+        path: &[&str],
+        file_hash: &FileHash,
+    ) -> Option<Self> {
+        let [name, rest @ ..] = path else {
+            return None;
+        };
+        if rest.is_empty() {
+            return Self::from_members(members, name, file_hash);
+        }
+
+        let member = members.iter().find(|member| member.name() == Some(name))?;
+        let ty = member.ty(file_hash)?;
+        let TypeKind::Struct(inner) = ty.kind() else {
+            return None;
+        };
+        let field = Self::from_members_path(inner.members(), rest, file_hash)?;
+
+        Some(Self {
+            offset: member.bit_offset() / 8 + field.offset,
+            ..field
+        })
+    }
+
+    /// Get the bytes of this field from the bytes of the containing struct.
+    pub(crate) fn bytes<'a>(&self, bytes: &'a [u8]) -> Option<&'a [u8]> {
+        bytes.get(self.offset as usize..)?.get(..self.size as usize)
+    }
+}
+
+/// Tick rate of `embassy_time` without any `tick-hz-*` feature enabled.
+pub(crate) const DEFAULT_TICK_HZ: u64 = 1_000_000;
+
+/// Layout of a type that gets special formatting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum BuiltinType {
+    /// Wrapper that gets formatted as just its inner value, like `core::cell::UnsafeCell`.
+    Transparent { value: Field },
+    /// `core::cell::Cell`, formatted as its inner value.
+    Cell { value: Field },
+    /// `core::cell::RefCell`, formatted as its inner value and borrow state.
+    RefCell { borrow: Field, value: Field },
+    /// Newtype around a primitive integer `base` that represents `integer / scale`, like fixed
+    /// point numbers. These are not recognized from the debug data but registered by the user, see
+    /// [`EmbassyInspector::register_scaled_number`](crate::EmbassyInspector::register_scaled_number).
+    Scaled { base: Type, scale: f64 },
+    /// `embassy_time::Duration` or, if `instant` is set, `embassy_time::Instant`. Both store a
+    /// number of ticks running at `tick_hz`.
+    EmbassyTicks {
+        ticks: Field,
+        tick_hz: u64,
+        instant: bool,
+    },
+    /// `core::time::Duration`
+    CoreDuration { secs: Field, nanos: Field },
+    /// Collection with its elements in an inline array, like `heapless::Vec`,
+    /// `arrayvec::ArrayVec` and `tinyvec::ArrayVec`, formatted as its first `len` elements.
+    InlineVec { len: Field, buffer: ArrayField },
+    /// `heapless::String`, wrapping a `heapless::Vec<u8, N>`.
+    HeaplessString { vec: Field },
+    /// `embassy_sync::waitqueue::WakerRegistration`, formatted as whether a waker is registered.
+    ///
+    /// The `Option<Waker>` inside uses the vtable reference of the waker as niche, so the vtable
+    /// is zero when no waker is registered.
+    WakerRegistration { vtable: Field },
+    /// `core::sync::atomic::Atomic*`, formatted as the value it holds at the moment it was read.
+    Atomic { value: Field },
+    /// Tuple like `(u32, bool)`, formatted as its elements. The elements are in order, their
+    /// offsets are not always increasing as rustc reorders the fields.
+    Tuple { elements: Vec<Field> },
+}
+
+/// A field containing a fixed size array.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ArrayField {
+    pub(crate) offset: u64,
+    pub(crate) element_size: u64,
+    pub(crate) element_type: Type,
+    pub(crate) count: u64,
+}
+
+impl ArrayField {
+    /// Find the array field with the given name, also looking into a struct with a single array
+    /// field as used for the storage of newer heapless versions.
+    fn from_struct(
+        struct_type: &ddbug_parser::StructType<'_>,
+        name: &str,
+        file_hash: &FileHash,
+    ) -> Option<Self> {
+        let member = struct_type
+            .members()
+            .iter()
+            .find(|member| member.name() == Some(name))?;
+        let offset = member.bit_offset() / 8;
+
+        match member.ty(file_hash)?.kind() {
+            TypeKind::Array(array_type) => {
+                let count = array_type.counts().next().flatten()?;
+                let size = array_type.byte_size(file_hash)?;
+
+                Some(Self {
+                    offset,
+                    element_size: size.checked_div(count).unwrap_or(0),
+                    element_type: Type::from_maybe_ddbug_type(
+                        array_type.element_type(file_hash),
+                        file_hash,
+                    ),
+                    count,
+                })
+            }
+            TypeKind::Struct(storage) => {
+                let [inner] = storage.members() else {
+                    return None;
+                };
+                let inner = Self::from_struct(storage, inner.name()?, file_hash)?;
+
+                Some(Self {
+                    offset: offset + inner.offset,
+                    ..inner
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the bytes of the first `len` elements from the bytes of the containing struct.
+    pub(crate) fn elements<'a>(&self, bytes: &'a [u8], len: u64) -> Option<Vec<&'a [u8]>> {
+        if len > self.count {
+            return None;
+        }
+
+        (0..len)
+            .map(|i| {
+                let offset = self.offset + i * self.element_size;
+                bytes
+                    .get(offset as usize..)?
+                    .get(..self.element_size as usize)
+            })
+            .collect()
+    }
+}
+
+impl BuiltinType {
+    pub(crate) fn from_ddbug_type(
+        ddbug_type: &ddbug_parser::Type<'_>,
+        file_hash: &FileHash,
+    ) -> Option<Self> {
+        let struct_type = match ddbug_type.kind() {
+            TypeKind::Struct(struct_type) => struct_type,
+            TypeKind::Union(union_type) => return Self::from_ddbug_union(union_type, file_hash),
+            _ => return None,
+        };
+        if let Some(members) = tuple_members(struct_type) {
+            return Some(Self::Tuple {
+                elements: members
+                    .iter()
+                    .map(|member| Field::from_member(member, file_hash))
+                    .collect::<Option<_>>()?,
+            });
+        }
+
+        let name = struct_type.name()?;
+        let path = struct_type
+            .namespace()
+            .map(namespace_to_path)
+            .unwrap_or_default();
+
+        match (path.as_str(), without_generics(name)) {
+            ("core::cell", "UnsafeCell") => Some(Self::Transparent {
+                value: Field::from_struct(struct_type, "value", file_hash)?,
+            }),
+            ("core::cell", "Cell") => Some(Self::Cell {
+                value: Field::from_struct(struct_type, "value", file_hash)?,
+            }),
+            ("core::cell", "RefCell") => Some(Self::RefCell {
+                borrow: Field::from_struct(struct_type, "borrow", file_hash)?,
+                value: Field::from_struct(struct_type, "value", file_hash)?,
+            }),
+            ("embassy_time::duration", "Duration") => Some(Self::EmbassyTicks {
+                ticks: Field::from_struct(struct_type, "ticks", file_hash)?,
+                tick_hz: DEFAULT_TICK_HZ,
+                instant: false,
+            }),
+            ("embassy_time::instant", "Instant") => Some(Self::EmbassyTicks {
+                ticks: Field::from_struct(struct_type, "ticks", file_hash)?,
+                tick_hz: DEFAULT_TICK_HZ,
+                instant: true,
+            }),
+            ("core::time", "Duration") => Some(Self::CoreDuration {
+                secs: Field::from_struct(struct_type, "secs", file_hash)?,
+                nanos: Field::from_struct(struct_type, "nanos", file_hash)?,
+            }),
+            ("core::mem::manually_drop", "ManuallyDrop") => Some(Self::Transparent {
+                value: Field::from_struct(struct_type, "value", file_hash)?,
+            }),
+            // Newer heapless versions have a generic `VecInner` with a `Vec` type alias.
+            ("heapless::vec", "Vec" | "VecInner") => {
+                Self::inline_vec(struct_type, "len", "buffer", file_hash)
+            }
+            ("arrayvec::arrayvec", "ArrayVec") => {
+                Self::inline_vec(struct_type, "len", "xs", file_hash)
+            }
+            ("tinyvec::arrayvec", "ArrayVec") => {
+                Self::inline_vec(struct_type, "len", "data", file_hash)
+            }
+            ("heapless::string", "String" | "StringInner") => Some(Self::HeaplessString {
+                vec: Field::from_struct(struct_type, "vec", file_hash)?,
+            }),
+            ("embassy_sync::waitqueue::waker_registration", "WakerRegistration") => {
+                Some(Self::WakerRegistration {
+                    vtable: waker_vtable(struct_type, file_hash)?,
+                })
+            }
+            ("core::sync::atomic", atomic) if atomic.starts_with("Atomic") => {
+                // `AtomicPtr` calls its `UnsafeCell` `p`, the others `v`.
+                let value = Field::from_struct(struct_type, "v", file_hash)
+                    .or_else(|| Field::from_struct(struct_type, "p", file_hash))?;
+
+                // `AtomicBool` stores an `u8`, it is an `Atomic<bool>` on newer versions of core.
+                let value = match atomic == "AtomicBool" || name == "Atomic<bool>" {
+                    true => Field {
+                        ty: Type::Base(String::from("bool")),
+                        ..value
+                    },
+                    false => value,
+                };
+                Some(Self::Atomic { value })
+            }
+            _ => None,
+        }
+    }
+
+    /// An [`InlineVec`](Self::InlineVec) with the length and the array in the fields with the
+    /// given names.
+    fn inline_vec(
+        struct_type: &ddbug_parser::StructType<'_>,
+        len: &str,
+        buffer: &str,
+        file_hash: &FileHash,
+    ) -> Option<Self> {
+        Some(Self::InlineVec {
+            len: Field::from_struct(struct_type, len, file_hash)?,
+            buffer: ArrayField::from_struct(struct_type, buffer, file_hash)?,
+        })
+    }
+
+    fn from_ddbug_union(
+        union_type: &ddbug_parser::UnionType<'_>,
+        file_hash: &FileHash,
+    ) -> Option<Self> {
+        let name = union_type.name()?;
+        let path = union_type
+            .namespace()
+            .map(namespace_to_path)
+            .unwrap_or_default();
+
+        match (path.as_str(), without_generics(name)) {
+            // Only used for initialized elements, like the first `len` elements of a
+            // `heapless::Vec`.
+            ("core::mem::maybe_uninit", "MaybeUninit") => Some(Self::Transparent {
+                value: Field::from_members(union_type.members(), "value", file_hash)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Find the vtable of the waker in the `waker: Option<Waker>` field of a `WakerRegistration`.
+fn waker_vtable(struct_type: &ddbug_parser::StructType<'_>, file_hash: &FileHash) -> Option<Field> {
+    let member = struct_type
+        .members()
+        .iter()
+        .find(|member| member.name() == Some("waker"))?;
+    let ty = member.ty(file_hash)?;
+    let TypeKind::Struct(option) = ty.kind() else {
+        return None;
+    };
+    let [variant_part] = option.variant_parts() else {
+        return None;
+    };
+    let some = variant_part
+        .variants()
+        .iter()
+        .find(|variant| variant.name() == Some("Some"))?;
+    let vtable = Field::from_members_path(some.members(), &["__0", "waker", "vtable"], file_hash)?;
+
+    Some(Field {
+        offset: member.bit_offset() / 8 + vtable.offset,
+        ..vtable
+    })
+}
+
+/// Strip the generic arguments from a type name: `Cell<u32>` becomes `Cell`.
+fn without_generics(name: &str) -> &str {
+    name.split('<').next().unwrap_or(name)
+}
@@ -0,0 +1,203 @@
+//! Caching the [`DebugData`] parsed from an object file on disk, as parsing the DWARF of a large
+//! firmware takes seconds on every start.
+//!
+//! The cache of an object file is only used while its path, modification time and size stay the
+//! same and it was written by the same version of the inspector, otherwise the object file is
+//! parsed again and the cache rewritten.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::DebugData;
+
+/// Version of the serialized models, bump when they change in between releases.
+const FORMAT_VERSION: u32 = 3;
+
+/// What the cached debug data was parsed from, the first line of a cache file.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CacheKey {
+    format_version: u32,
+    inspector_version: String,
+    path: PathBuf,
+    modified: SystemTime,
+    size: u64,
+}
+
+impl CacheKey {
+    fn new(path: &str) -> Result<Self> {
+        let path = std::fs::canonicalize(path)?;
+        let metadata = std::fs::metadata(&path)?;
+
+        Ok(Self {
+            format_version: FORMAT_VERSION,
+            inspector_version: env!("CARGO_PKG_VERSION").to_owned(),
+            modified: metadata.modified()?,
+            size: metadata.len(),
+            path,
+        })
+    }
+
+    /// The cache file in `cache_dir`, named after a hash of the path of the object file.
+    fn cache_file(&self, cache_dir: &Path) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        self.path.hash(&mut hasher);
+        cache_dir.join(format!("debug-data-{:016x}.json", hasher.finish()))
+    }
+}
+
+impl DebugData {
+    /// [`Self::from_object_file`], but loaded from the cache in `cache_dir` when the object file
+    /// did not change since it was written.
+    pub(crate) fn from_object_file_cached(path: String, cache_dir: &Path) -> Result<Self> {
+        let key = match CacheKey::new(&path) {
+            Ok(key) => key,
+            Err(e) => {
+                log::warn!("Not caching the debug data of {path}: {e}");
+                return Self::from_object_file(path);
+            }
+        };
+        let cache_file = key.cache_file(cache_dir);
+
+        match read_cache(&cache_file, &key) {
+            Ok(Some(debug_data)) => {
+                log::info!(
+                    "Loaded the debug data of {path} from the cache {}",
+                    cache_file.display()
+                );
+                return Ok(debug_data);
+            }
+            Ok(None) => log::info!("The cached debug data of {path} is outdated, parsing it"),
+            Err(e) => log::info!("No usable cached debug data of {path} ({e}), parsing it"),
+        }
+
+        let debug_data = Self::from_object_file(path)?;
+        if let Err(e) = write_cache(&cache_file, &key, &debug_data) {
+            log::warn!(
+                "Could not write the debug data cache {}: {e}",
+                cache_file.display()
+            );
+        }
+        Ok(debug_data)
+    }
+}
+
+/// The debug data in the cache file, `None` if it was written for another `key`.
+fn read_cache(cache_file: &Path, key: &CacheKey) -> Result<Option<DebugData>> {
+    let mut reader = BufReader::new(File::open(cache_file)?);
+
+    // The key is on its own line, so an outdated cache is recognized without parsing the rest.
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if serde_json::from_str::<CacheKey>(&line).ok().as_ref() != Some(key) {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_reader(reader)?))
+}
+
+fn write_cache(cache_file: &Path, key: &CacheKey, debug_data: &DebugData) -> Result<()> {
+    if let Some(cache_dir) = cache_file.parent() {
+        std::fs::create_dir_all(cache_dir)?;
+    }
+
+    // Renamed once complete, so another inspector starting at the same time never reads half of
+    // it.
+    let partial_file = cache_file.with_extension("json.partial");
+    let mut writer = BufWriter::new(File::create(&partial_file)?);
+    serde_json::to_writer(&mut writer, key)?;
+    writer.write_all(b"\n")?;
+    serde_json::to_writer(&mut writer, debug_data)?;
+    writer.flush()?;
+    drop(writer);
+
+    std::fs::rename(partial_file, cache_file)?;
+    Ok(())
+}
+
+/// Serializes a map as a list of key and value pairs, for keys without a representation in JSON
+/// like a [`Type`](super::ty::Type).
+pub(super) mod map_as_pairs {
+    use super::*;
+
+    pub(crate) fn serialize<K: Serialize, V: Serialize, S: Serializer>(
+        map: &HashMap<K, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map)
+    }
+
+    pub(crate) fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::model::{
+        builtin::{BuiltinType, Field},
+        ty::Type,
+    };
+
+    #[test]
+    fn cache_round_trip() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("inspect-embassy-cache-test-{}", std::process::id()));
+        let key = CacheKey {
+            format_version: FORMAT_VERSION,
+            inspector_version: String::from("0.1.0"),
+            path: PathBuf::from("/firmware.elf"),
+            modified: SystemTime::UNIX_EPOCH,
+            size: 1024,
+        };
+        let cache_file = key.cache_file(&cache_dir);
+
+        let mut debug_data = DebugData {
+            return_instruction_size: 2,
+            ..DebugData::default()
+        };
+        debug_data.builtin_types.insert(
+            Type::Array {
+                inner: Box::new(Type::Base(String::from("u8"))),
+                count: 4,
+            },
+            BuiltinType::Cell {
+                value: Field {
+                    offset: 0,
+                    size: 4,
+                    ty: Type::Base(String::from("u32")),
+                },
+            },
+        );
+        write_cache(&cache_file, &key, &debug_data).unwrap();
+
+        let cached = read_cache(&cache_file, &key).unwrap().unwrap();
+        assert_eq!(cached.return_instruction_size, 2);
+        assert!(matches!(
+            cached.builtin_types.values().next(),
+            Some(BuiltinType::Cell { value }) if value.size == 4
+        ));
+
+        let changed = CacheKey { size: 2048, ..key };
+        assert!(read_cache(&cache_file, &changed).unwrap().is_none());
+
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
@@ -0,0 +1,489 @@
+//! Models for the futures of the channels and signals of embassy-sync.
+//!
+//! These futures only hold a reference to the channel or signal they wait on, what is queued or
+//! signaled is read through that pointer with [`FutureValue::read_behind_pointers`](
+//! super::future::FutureValue::read_behind_pointers).
+
+use std::{borrow::Cow, fmt::Display};
+
+use ddbug_parser::{FileHash, TypeKind, TypeModifierKind};
+use serde::{Deserialize, Serialize};
+
+use super::{future::member, namespace_to_path};
+
+/// How many structs deep the queue of a channel or the state of a signal is searched for, they are
+/// wrapped in a `Mutex`, a `RefCell` or `Cell` and their `UnsafeCell`s.
+const MAX_NESTING: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SyncFutureKind {
+    /// `ReceiveFuture` and `ReceiveReadyFuture` of a `Channel`.
+    Receive,
+    /// `SendFuture` and `SendReadyFuture` of a `Channel`.
+    Send,
+    /// The future returned by `Signal::wait`.
+    Signal,
+}
+
+/// Where the `heapless::Deque` of a `Channel` keeps its indices, relative to the channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ChannelLayout {
+    pub(crate) front_offset: u64,
+    pub(crate) back_offset: u64,
+    /// Size of both `front` and `back`, which are `usize`s.
+    pub(crate) index_size: u64,
+    /// Offset of the bool telling a deque with `front == back` is full instead of empty.
+    pub(crate) full_offset: u64,
+    pub(crate) capacity: u64,
+}
+
+impl ChannelLayout {
+    fn from_ddbug_type(channel: &ddbug_parser::Type<'_>, file_hash: &FileHash<'_>) -> Option<Self> {
+        // Channel { inner: Mutex<M, RefCell<ChannelState<T, N>>> }, ChannelState { queue: Deque, .. }
+        let (deque_offset, deque) = find_nested(channel, file_hash, 0, &|ty| {
+            ["buffer", "front", "back", "full"]
+                .iter()
+                .all(|name| member(ty, name, file_hash).is_some())
+        })?;
+
+        // Deque { buffer: [MaybeUninit<T>; N], front: usize, back: usize, full: bool }
+        let (_, buffer) = member(&deque, "buffer", file_hash)?;
+        let TypeKind::Array(buffer) = buffer.kind() else {
+            return None;
+        };
+        let capacity = buffer.counts().next().flatten()?;
+
+        let (front_offset, front) = member(&deque, "front", file_hash)?;
+        let (back_offset, _) = member(&deque, "back", file_hash)?;
+        let (full_offset, _) = member(&deque, "full", file_hash)?;
+        let index_size = front.byte_size(file_hash)?;
+        if ![1, 2, 4, 8].contains(&index_size) {
+            return None;
+        }
+
+        Some(Self {
+            front_offset: deque_offset + front_offset,
+            back_offset: deque_offset + back_offset,
+            index_size,
+            full_offset: deque_offset + full_offset,
+            capacity,
+        })
+    }
+
+    /// The number of queued messages in the bytes of the channel starting at `start`, `None` if
+    /// the indices are out of bounds.
+    fn len(&self, bytes: &[u8], start: u64) -> Option<u64> {
+        let at = |offset: u64| &bytes[(offset - start) as usize..];
+        let front = read_uint(at(self.front_offset), self.index_size);
+        let back = read_uint(at(self.back_offset), self.index_size);
+        let full = at(self.full_offset)[0] != 0;
+        if front >= self.capacity || back >= self.capacity {
+            return None;
+        }
+
+        Some(deque_len(front, back, full, self.capacity))
+    }
+}
+
+/// The number of elements in a ring buffer with `capacity` elements, from the index of the first
+/// element to the index after the last one.
+fn deque_len(front: u64, back: u64, full: bool, capacity: u64) -> u64 {
+    if full {
+        capacity
+    } else if back >= front {
+        back - front
+    } else {
+        capacity - front + back
+    }
+}
+
+/// The state of a `Signal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SignalState {
+    /// `State::None`, not signaled and never waited on since the last value was taken.
+    None,
+    /// `State::Waiting`, a waker is registered to wake once signaled.
+    Waiting,
+    /// `State::Signaled`, the value was not taken yet.
+    Signaled,
+}
+
+/// Where a `Signal` keeps its `State` enum, relative to the signal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SignalLayout {
+    pub(crate) discriminant_offset: u64,
+    pub(crate) discriminant_size: u64,
+    /// The states by discriminant, `None` for the variant using a niche.
+    pub(crate) variants: Vec<(Option<u64>, SignalState)>,
+}
+
+impl SignalLayout {
+    fn from_ddbug_type(signal: &ddbug_parser::Type<'_>, file_hash: &FileHash<'_>) -> Option<Self> {
+        // Signal { state: Mutex<M, Cell<State<T>>> }, State { None, Waiting(Waker), Signaled(T) }
+        let is_state = |ty: &ddbug_parser::Type<'_>| {
+            let TypeKind::Struct(struct_type) = ty.kind() else {
+                return false;
+            };
+            let [variant_part] = struct_type.variant_parts() else {
+                return false;
+            };
+            variant_part
+                .variants()
+                .iter()
+                .all(|variant| signal_state(variant.name()).is_some())
+        };
+        let (state_offset, state) = find_nested(signal, file_hash, 0, &is_state)?;
+        let TypeKind::Struct(state) = state.kind() else {
+            return None;
+        };
+        let [variant_part] = state.variant_parts() else {
+            return None;
+        };
+
+        let discriminant = variant_part.discriminant(state.members())?;
+        let discriminant_size = discriminant.bit_size(file_hash)? / 8;
+        if ![1, 2, 4, 8].contains(&discriminant_size) {
+            return None;
+        }
+        let variants = variant_part
+            .variants()
+            .iter()
+            .map(|variant| Some((variant.discriminant_value(), signal_state(variant.name())?)))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            discriminant_offset: state_offset + discriminant.bit_offset() / 8,
+            discriminant_size,
+            variants,
+        })
+    }
+
+    /// The state selected by `discriminant`, the variant without a discriminant is used for every
+    /// value the other variants don't have.
+    fn state(&self, discriminant: u64) -> Option<SignalState> {
+        self.variants
+            .iter()
+            .find(|(value, _)| *value == Some(discriminant))
+            .or_else(|| self.variants.iter().find(|(value, _)| value.is_none()))
+            .map(|(_, state)| *state)
+    }
+}
+
+fn signal_state(name: Option<&str>) -> Option<SignalState> {
+    match name? {
+        "None" => Some(SignalState::None),
+        "Waiting" => Some(SignalState::Waiting),
+        "Signaled" => Some(SignalState::Signaled),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum SyncTarget {
+    Channel(ChannelLayout),
+    Signal(SignalLayout),
+}
+
+impl SyncTarget {
+    /// The offset and size of the part of the channel or signal that has to be read.
+    fn span(&self) -> (u64, u64) {
+        match self {
+            SyncTarget::Channel(channel) => {
+                let fields = [
+                    (channel.front_offset, channel.index_size),
+                    (channel.back_offset, channel.index_size),
+                    (channel.full_offset, 1),
+                ];
+                let start = fields.iter().map(|(offset, _)| *offset).min().unwrap();
+                let end = fields.iter().map(|(offset, size)| offset + size).max();
+                (start, end.unwrap() - start)
+            }
+            SyncTarget::Signal(signal) => (signal.discriminant_offset, signal.discriminant_size),
+        }
+    }
+}
+
+/// A future of embassy-sync holding a reference to the `Channel` or `Signal` it waits on.
+///
+/// Only the futures of a `Channel` with its type in the future are recognized, the futures of a
+/// `DynamicReceiver` and `DynamicSender` point to the channel through a `dyn` reference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EmbassySyncFuture {
+    pub(crate) kind: SyncFutureKind,
+    pub(crate) pointer_offset: u64,
+    pub(crate) pointer_size: u64,
+    pub(crate) target: SyncTarget,
+}
+
+impl EmbassySyncFuture {
+    pub(crate) fn from_ddbug_type(
+        ddbug_type: &ddbug_parser::Type<'_>,
+        file_hash: &FileHash<'_>,
+    ) -> Option<Self> {
+        let TypeKind::Struct(struct_type) = ddbug_type.kind() else {
+            return None;
+        };
+        let name = struct_type.name()?;
+        let namespace = struct_type.namespace()?;
+
+        if namespace.name() == Some("channel")
+            && namespace.parent().and_then(|n| n.name()) == Some("embassy_sync")
+        {
+            let kind = match name.split('<').next()? {
+                "ReceiveFuture" | "ReceiveReadyFuture" => SyncFutureKind::Receive,
+                "SendFuture" | "SendReadyFuture" => SyncFutureKind::Send,
+                _ => return None,
+            };
+            // ReceiveFuture { channel: &Channel<M, T, N> }, the send futures also hold the message.
+            let (pointer_offset, pointer) = member(ddbug_type, "channel", file_hash)?;
+            let (pointer_size, channel) = thin_pointer(&pointer, file_hash)?;
+            return Some(Self {
+                kind,
+                pointer_offset,
+                pointer_size,
+                target: SyncTarget::Channel(ChannelLayout::from_ddbug_type(&channel, file_hash)?),
+            });
+        }
+
+        // `Signal::wait` returns `poll_fn(move |cx| self.poll_wait(cx))`, so a `PollFn` of a
+        // closure in the signal module capturing the reference to the signal.
+        if name.starts_with("PollFn<") && namespace.name() == Some("poll_fn") {
+            let (closure_offset, closure) = member(ddbug_type, "f", file_hash)?;
+            let TypeKind::Struct(closure_struct) = closure.kind() else {
+                return None;
+            };
+            if !namespace_to_path(closure_struct.namespace()?).starts_with("embassy_sync::signal::")
+            {
+                return None;
+            }
+            let [captured] = closure_struct.members() else {
+                return None;
+            };
+            let (pointer_size, signal) = thin_pointer(&captured.ty(file_hash)?, file_hash)?;
+            return Some(Self {
+                kind: SyncFutureKind::Signal,
+                pointer_offset: closure_offset + captured.bit_offset() / 8,
+                pointer_size,
+                target: SyncTarget::Signal(SignalLayout::from_ddbug_type(&signal, file_hash)?),
+            });
+        }
+
+        None
+    }
+
+    /// Read what is queued in the channel or the state of the signal at `pointer`, `None` if it
+    /// could not be read.
+    ///
+    /// `read_memory` reads the given number of bytes at an address, `None` if they are not
+    /// readable.
+    pub(crate) fn read_state(
+        &self,
+        pointer: u64,
+        read_memory: &mut impl FnMut(u64, u64) -> Option<Vec<u8>>,
+    ) -> Option<SyncState> {
+        let (start, size) = self.target.span();
+        let bytes = read_memory(pointer + start, size)?;
+        if bytes.len() < size as usize {
+            return None;
+        }
+
+        match &self.target {
+            SyncTarget::Channel(channel) => Some(SyncState::Channel {
+                len: channel.len(&bytes, start)?,
+                capacity: channel.capacity,
+            }),
+            SyncTarget::Signal(signal) => signal
+                .state(read_uint(&bytes, signal.discriminant_size))
+                .map(SyncState::Signal),
+        }
+    }
+}
+
+/// The size of a pointer that is not a fat pointer and the type it points to.
+fn thin_pointer<'a, 'input>(
+    pointer: &ddbug_parser::Type<'input>,
+    file_hash: &'a FileHash<'input>,
+) -> Option<(u64, Cow<'a, ddbug_parser::Type<'input>>)> {
+    let pointer_size = pointer.byte_size(file_hash)?;
+    if ![1, 2, 4, 8].contains(&pointer_size) {
+        return None;
+    }
+    let TypeKind::Modifier(pointer) = pointer.kind() else {
+        return None;
+    };
+    if !matches!(pointer.kind(), TypeModifierKind::Pointer) {
+        return None;
+    }
+    Some((pointer_size, pointer.ty(file_hash)?))
+}
+
+/// The offset and type of the first struct nested in the members of `ty` for which `is_match`
+/// returns true, searched depth first.
+fn find_nested<'a, 'input>(
+    ty: &ddbug_parser::Type<'input>,
+    file_hash: &'a FileHash<'input>,
+    depth: usize,
+    is_match: &impl Fn(&ddbug_parser::Type<'input>) -> bool,
+) -> Option<(u64, Cow<'a, ddbug_parser::Type<'input>>)> {
+    let TypeKind::Struct(struct_type) = ty.kind() else {
+        return None;
+    };
+    if depth >= MAX_NESTING {
+        return None;
+    }
+
+    struct_type.members().iter().find_map(|member| {
+        let offset = member.bit_offset() / 8;
+        let member_type = member.ty(file_hash)?;
+        if is_match(&member_type) {
+            return Some((offset, member_type));
+        }
+        find_nested(&member_type, file_hash, depth + 1, is_match)
+            .map(|(nested_offset, nested)| (offset + nested_offset, nested))
+    })
+}
+
+fn read_uint(bytes: &[u8], size: u64) -> u64 {
+    match size {
+        1 => u8::from_le_bytes(bytes[..1].try_into().unwrap()) as u64,
+        2 => u16::from_le_bytes(bytes[..2].try_into().unwrap()) as u64,
+        4 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as u64,
+        8 => u64::from_le_bytes(bytes[..8].try_into().unwrap()),
+        _ => unreachable!(),
+    }
+}
+
+/// What the channel or signal a future waits on currently holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SyncState {
+    Channel { len: u64, capacity: u64 },
+    Signal(SignalState),
+}
+
+impl Display for SyncState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncState::Channel { len, capacity } => write!(f, "{len}/{capacity} queued"),
+            SyncState::Signal(SignalState::None) => write!(f, "not signaled"),
+            SyncState::Signal(SignalState::Waiting) => write!(f, "waker registered"),
+            SyncState::Signal(SignalState::Signaled) => write!(f, "signaled"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct EmbassySyncValue {
+    pub(crate) kind: SyncFutureKind,
+    /// Address of the channel or signal, `None` if the pointer is null or could not be read.
+    pub(crate) pointer: Option<u64>,
+    /// Whether the bytes of the future hold the pointer, they end early when the future was read
+    /// short, like an arm of a select.
+    pub(crate) pointer_read: bool,
+    /// What the channel or signal holds, `None` until read by
+    /// [`FutureValue::read_behind_pointers`](super::future::FutureValue::read_behind_pointers) or
+    /// when it could not be read.
+    pub(crate) state: Option<SyncState>,
+    /// The size of the future itself.
+    pub(crate) size: u64,
+}
+
+impl EmbassySyncValue {
+    pub(crate) fn new(sync_type: &EmbassySyncFuture, bytes: &[u8], size: u64) -> Self {
+        let start = sync_type.pointer_offset as usize;
+        let pointer = bytes
+            .get(start..start + sync_type.pointer_size as usize)
+            .map(|pointer_bytes| read_uint(pointer_bytes, sync_type.pointer_size));
+
+        Self {
+            kind: sync_type.kind,
+            pointer: pointer.filter(|pointer| *pointer != 0),
+            pointer_read: pointer.is_some(),
+            state: None,
+            size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deque_lengths() {
+        assert_eq!(deque_len(0, 0, false, 4), 0);
+        assert_eq!(deque_len(1, 3, false, 4), 2);
+        // Wrapped around the end of the buffer.
+        assert_eq!(deque_len(3, 1, false, 4), 2);
+        // `front == back` is either empty or full.
+        assert_eq!(deque_len(2, 2, true, 4), 4);
+    }
+
+    #[test]
+    fn read_states() {
+        let channel = EmbassySyncFuture {
+            kind: SyncFutureKind::Receive,
+            pointer_offset: 0,
+            pointer_size: 4,
+            target: SyncTarget::Channel(ChannelLayout {
+                front_offset: 8,
+                back_offset: 12,
+                index_size: 4,
+                full_offset: 16,
+                capacity: 4,
+            }),
+        };
+        let mut reads = Vec::new();
+        let mut read_memory = |address, size| {
+            reads.push((address, size));
+            Some(vec![3, 0, 0, 0, 1, 0, 0, 0, 0])
+        };
+        let value = EmbassySyncValue::new(&channel, &[0, 0x10, 0, 0x20], 4);
+        assert_eq!(value.pointer, Some(0x2000_1000));
+        assert_eq!(
+            channel.read_state(0x2000_1000, &mut read_memory),
+            Some(SyncState::Channel {
+                len: 2,
+                capacity: 4
+            })
+        );
+        assert_eq!(reads, [(0x2000_1008, 9)]);
+
+        // Indices past the end of the buffer are not a channel.
+        let mut garbage = |_, _| Some(vec![7, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(channel.read_state(0x2000_1000, &mut garbage), None);
+        assert_eq!(channel.read_state(0x2000_1000, &mut |_, _| None), None);
+        assert_eq!(EmbassySyncValue::new(&channel, &[0; 4], 4).pointer, None);
+        assert!(EmbassySyncValue::new(&channel, &[0; 4], 4).pointer_read);
+
+        // A future read short has no pointer.
+        let short = EmbassySyncValue::new(&channel, &[0, 0x10], 4);
+        assert_eq!(short.pointer, None);
+        assert!(!short.pointer_read);
+
+        let signal = EmbassySyncFuture {
+            kind: SyncFutureKind::Signal,
+            pointer_offset: 0,
+            pointer_size: 4,
+            target: SyncTarget::Signal(SignalLayout {
+                discriminant_offset: 4,
+                discriminant_size: 4,
+                // The waker's vtable pointer is the niche.
+                variants: vec![
+                    (Some(0), SignalState::None),
+                    (None, SignalState::Waiting),
+                    (Some(2), SignalState::Signaled),
+                ],
+            }),
+        };
+        let mut read_memory = |_, _| Some(vec![2, 0, 0, 0]);
+        assert_eq!(
+            signal.read_state(0x2000_1000, &mut read_memory),
+            Some(SyncState::Signal(SignalState::Signaled))
+        );
+        let mut read_memory = |_, _| Some(vec![0x40, 0x10, 0, 0x08]);
+        assert_eq!(
+            signal.read_state(0x2000_1000, &mut read_memory),
+            Some(SyncState::Signal(SignalState::Waiting))
+        );
+    }
+}
@@ -1,15 +1,74 @@
-//! Models for the memory layout of join and select futures.
+//! Models for the memory layout of join, select, optional and shared futures, timers and the
+//! futures of embassy-sync.
 
-use ddbug_parser::{FileHash, Result, TypeKind};
+use std::{borrow::Cow, collections::HashMap};
+
+use ddbug_parser::{FileHash, Result, TypeKind, TypeModifierKind};
+use serde::{Deserialize, Serialize};
 
 use super::{
     async_fn::{AsyncFnType, AsyncFnValue},
+    embassy_sync::{EmbassySyncFuture, EmbassySyncValue},
     ty::Type,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct SelectFuture {
     pub(crate) awaitees: Box<[(u64, Type)]>,
+    /// The discriminant of a select that is an enum, `None` for the selects of embassy-futures,
+    /// which are structs of their arms.
+    #[serde(default)]
+    pub(crate) discriminant: Option<SelectDiscriminant>,
+}
+
+/// Where a select keeps whether it completed, see [`SelectFuture::discriminant`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SelectDiscriminant {
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+    /// The discriminant of the variant without arms, once the select completed.
+    pub(crate) done: u64,
+}
+
+impl SelectDiscriminant {
+    fn from_ddbug_variant_part(
+        variant_part: &ddbug_parser::VariantPart<'_>,
+        members: &[ddbug_parser::Member<'_>],
+        file_hash: &FileHash<'_>,
+    ) -> Result<Self> {
+        let discriminant = variant_part
+            .discriminant(members)
+            .ok_or("Expected the variants of a select to have a discriminant")?;
+        let size = discriminant
+            .bit_size(file_hash)
+            .ok_or("Could not determain the size of the discriminant of a select")?
+            / 8;
+        let done = variant_part
+            .variants()
+            .iter()
+            .find(|variant| variant.members().is_empty())
+            .and_then(|variant| variant.discriminant_value())
+            .ok_or("Expected a select to have a variant without arms")?;
+
+        Ok(Self {
+            offset: discriminant.bit_offset() / 8,
+            size,
+            done,
+        })
+    }
+
+    /// Whether the select in `bytes` completed, `false` if the discriminant can't be read.
+    fn is_done(&self, bytes: &[u8]) -> bool {
+        let (offset, size) = (self.offset as usize, self.size as usize);
+        match bytes.get(offset..offset + size) {
+            Some(discriminant) if size <= 8 => {
+                let mut value = [0; 8];
+                value[..size].copy_from_slice(discriminant);
+                u64::from_le_bytes(value) == self.done
+            }
+            _ => false,
+        }
+    }
 }
 
 impl SelectFuture {
@@ -43,21 +102,43 @@ impl SelectFuture {
         let size = array_type
             .byte_size(file_hash)
             .ok_or("Could not determain the size of the SelectArray")?;
-        let size_of_element = size / count;
+        // A zero length array is legal, it just has no awaitees.
+        let size_of_element = size.checked_div(count).unwrap_or(0);
 
         let awaitees = (0..count)
             .map(|i| (size_of_element * i, ty.clone()))
             .collect();
 
-        Ok(Self { awaitees })
+        Ok(Self {
+            awaitees,
+            discriminant: None,
+        })
     }
 
     fn from_ddbug_select_fixed_size(
         ddbug_type: &ddbug_parser::StructType<'_>,
         file_hash: &FileHash<'_>,
     ) -> Result<Self> {
-        let awaitees = ddbug_type
-            .members()
+        let members = ddbug_type.members();
+        // The members of a select that is an enum are its discriminant, the arms are in the variants.
+        let (discriminant, arms) = match ddbug_type.variant_parts() {
+            [] => (None, members.iter().collect::<Vec<_>>()),
+            [variant_part] => (
+                Some(SelectDiscriminant::from_ddbug_variant_part(
+                    variant_part,
+                    members,
+                    file_hash,
+                )?),
+                variant_part
+                    .variants()
+                    .iter()
+                    .flat_map(|variant| variant.members())
+                    .collect(),
+            ),
+            _ => return Err("Expected a select to have at most one set of variants".into()),
+        };
+
+        let awaitees = arms
             .into_iter()
             .map(|member| {
                 let offset = member.bit_offset() / 8;
@@ -66,7 +147,10 @@ impl SelectFuture {
             })
             .collect();
 
-        Ok(Self { awaitees })
+        Ok(Self {
+            awaitees,
+            discriminant,
+        })
     }
 
     fn from_ddbug_type(
@@ -109,24 +193,41 @@ impl SelectFuture {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct JoinAwaiteeTypeVariant {
-    pub(crate) discriminant: u64,
+    /// `None` for the variant without a discriminant value, like the variant with the data of an
+    /// enum using a niche, which is used for every value the other variants don't have.
+    pub(crate) discriminant: Option<u64>,
     pub(crate) offset: u64,
     pub(crate) size: u64,
     pub(crate) ty: Type,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct JoinAwaiteeType {
     pub(crate) discriminant_offset: u64,
     pub(crate) discriminant_size: u64,
 
     pub(crate) future_variant: JoinAwaiteeTypeVariant,
     pub(crate) done_variant: JoinAwaiteeTypeVariant,
+    /// The discriminant of the variant after the output has been taken.
+    pub(crate) gone_discriminant: Option<u64>,
 }
 
 impl JoinAwaiteeType {
+    /// Whether the discriminant value read from the target selects `variant`.
+    fn selects(&self, variant: &JoinAwaiteeTypeVariant, discriminant: u64) -> bool {
+        match variant.discriminant {
+            Some(variant_discriminant) => variant_discriminant == discriminant,
+            None => ![
+                self.future_variant.discriminant,
+                self.done_variant.discriminant,
+                self.gone_discriminant,
+            ]
+            .contains(&Some(discriminant)),
+        }
+    }
+
     fn from_ddbug_type(ty: &ddbug_parser::Type, file_hash: &FileHash) -> Option<Self> {
         let TypeKind::Struct(ty) = ty.kind() else {
             return None;
@@ -141,9 +242,11 @@ impl JoinAwaiteeType {
 
         let mut future_variant = None;
         let mut done_variant = None;
+        let mut gone_discriminant = None;
         for variant in variant_part.variants() {
             let name = variant.name()?;
             if name == "Gone" {
+                gone_discriminant = variant.discriminant_value();
                 continue;
             }
 
@@ -152,14 +255,15 @@ impl JoinAwaiteeType {
             };
 
             let variant = JoinAwaiteeTypeVariant {
-                discriminant: variant.discriminant_value()?,
+                discriminant: variant.discriminant_value(),
                 offset: member.bit_offset() / 8,
                 size: member.bit_size(file_hash)? / 8,
                 ty: Type::from_maybe_ddbug_type(member.ty(file_hash), file_hash),
             };
             match name {
                 "Future" => future_variant = Some(variant),
-                "Done" => done_variant = Some(variant),
+                // `Output` in the `FutureOrOutput` of `Shared`, which is a `MaybeDone` without `Gone`.
+                "Done" | "Output" => done_variant = Some(variant),
                 _ => return None,
             }
         }
@@ -170,11 +274,57 @@ impl JoinAwaiteeType {
 
             future_variant: future_variant?,
             done_variant: done_variant?,
+            gone_discriminant,
         })
     }
+
+    /// The future or output in the `bytes` of this enum located at `address`.
+    fn value(
+        &self,
+        address: u64,
+        bytes: &[u8],
+        future_types: &HashMap<Type, FutureType>,
+    ) -> FutureValue {
+        let disc_bytes = &bytes[self.discriminant_offset as usize..];
+        let discriminant = match self.discriminant_size {
+            1 => u8::from_le_bytes(disc_bytes[..1].try_into().unwrap()) as u64,
+            2 => u16::from_le_bytes(disc_bytes[..2].try_into().unwrap()) as u64,
+            4 => u32::from_le_bytes(disc_bytes[..4].try_into().unwrap()) as u64,
+            8 => u64::from_le_bytes(disc_bytes[..8].try_into().unwrap()),
+            _ => unreachable!(),
+        };
+
+        if self.selects(&self.future_variant, discriminant) {
+            let bytes =
+                &bytes[self.future_variant.offset as usize..][..self.future_variant.size as usize];
+            FutureValue::new(
+                &self.future_variant.ty,
+                address + self.future_variant.offset,
+                bytes,
+                future_types,
+            )
+        } else if self.selects(&self.done_variant, discriminant) {
+            let bytes =
+                &bytes[self.done_variant.offset as usize..][..self.done_variant.size as usize];
+
+            FutureValue {
+                ty: self.done_variant.ty.clone(),
+                address: address + self.done_variant.offset,
+                kind: FutureValueKind::Output(bytes.to_vec()),
+            }
+        } else {
+            // The value has been taken by calling `take_output`
+            // TODO: return something more usefull
+            FutureValue {
+                ty: Type::Void,
+                address,
+                kind: FutureValueKind::Unknown(Vec::new()),
+            }
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct JoinFuture {
     pub(crate) awaitees: Box<[(u64, JoinAwaiteeType)]>,
 }
@@ -213,7 +363,8 @@ impl JoinFuture {
         let size = array_type
             .byte_size(file_hash)
             .ok_or("Could not determain the size of the JoinArray")?;
-        let size_of_element = size / count;
+        // A zero length array is legal, it just has no awaitees.
+        let size_of_element = size.checked_div(count).unwrap_or(0);
 
         let awaitees = (0..count)
             .map(|i| (size_of_element * i, ty.clone()))
@@ -283,16 +434,257 @@ impl JoinFuture {
     }
 }
 
-#[derive(Debug, Clone)]
+/// `Option<F>`, `futures::future::OptionFuture<F>` or `futures::future::Fuse<F>`, only a future
+/// type if `F` is one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct OptionFuture {
+    pub(crate) discriminant_offset: u64,
+    pub(crate) discriminant_size: u64,
+    /// The discriminant of `None`, any other value is `Some`, as the discriminant can be a niche in
+    /// the future.
+    pub(crate) none_discriminant: u64,
+    /// Offset and type of the future inside `Some`.
+    pub(crate) some: (u64, Type),
+    /// Whether this is a `Fuse`, which is `None` once the future inside completed.
+    pub(crate) fused: bool,
+}
+
+impl OptionFuture {
+    fn from_ddbug_option(
+        struct_type: &ddbug_parser::StructType<'_>,
+        file_hash: &FileHash<'_>,
+    ) -> Option<Self> {
+        let [variant_part] = struct_type.variant_parts() else {
+            return None;
+        };
+
+        let discriminant = variant_part.discriminant(struct_type.members())?;
+        let discriminant_offset = discriminant.bit_offset() / 8;
+        let discriminant_size = discriminant.bit_size(file_hash)? / 8;
+        if ![1, 2, 4, 8].contains(&discriminant_size) {
+            return None;
+        }
+
+        let mut none_discriminant = None;
+        let mut some = None;
+        for variant in variant_part.variants() {
+            match variant.name()? {
+                "None" => none_discriminant = Some(variant.discriminant_value()?),
+                "Some" => {
+                    let [member] = variant.members() else {
+                        return None;
+                    };
+                    some = Some((
+                        member.bit_offset() / 8,
+                        Type::from_maybe_ddbug_type(member.ty(file_hash), file_hash),
+                    ));
+                }
+                _ => return None,
+            }
+        }
+
+        Some(Self {
+            discriminant_offset,
+            discriminant_size,
+            none_discriminant: none_discriminant?,
+            some: some?,
+            fused: false,
+        })
+    }
+
+    /// Recognizes all `Option` types, the ones not containing a future get removed once all future
+    /// types are known.
+    fn from_ddbug_type(
+        ddbug_type: &ddbug_parser::Type<'_>,
+        file_hash: &FileHash<'_>,
+    ) -> Option<Self> {
+        let TypeKind::Struct(struct_type) = ddbug_type.kind() else {
+            return None;
+        };
+        let name = struct_type.name()?;
+        let namespace = struct_type.namespace()?;
+
+        if name.starts_with("Option<")
+            && namespace.name() == Some("option")
+            && namespace.parent().and_then(|n| n.name()) == Some("core")
+        {
+            return Self::from_ddbug_option(struct_type, file_hash);
+        }
+
+        // futures_util::future::option::OptionFuture and futures_util::future::future::fuse::Fuse,
+        // wrapping an `Option<F>` named `inner`.
+        let fused = name.starts_with("Fuse<") && namespace.name() == Some("fuse");
+        if fused || (name.starts_with("OptionFuture<") && namespace.name() == Some("option")) {
+            let inner = struct_type
+                .members()
+                .iter()
+                .find(|member| member.name() == Some("inner"))?;
+            let inner_type = inner.ty(file_hash)?;
+            let TypeKind::Struct(option) = inner_type.kind() else {
+                return None;
+            };
+            let option = Self::from_ddbug_option(option, file_hash)?;
+
+            let offset = inner.bit_offset() / 8;
+            return Some(Self {
+                discriminant_offset: offset + option.discriminant_offset,
+                some: (offset + option.some.0, option.some.1),
+                fused,
+                ..option
+            });
+        }
+
+        None
+    }
+}
+
+/// `futures::future::Shared<F>`, a handle to a future behind an `Arc` shared by all clones of the
+/// handle. The future is not in the memory of the handle, it is read separately with
+/// [`FutureValue::read_behind_pointers`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SharedFuture {
+    /// Offset and size of the pointer to the `ArcInner`, which is null once the handle completed
+    /// and dropped its reference.
+    pub(crate) pointer_offset: u64,
+    pub(crate) pointer_size: u64,
+    /// Offset of the `FutureOrOutput` from the address the pointer points to.
+    pub(crate) future_offset: u64,
+    pub(crate) future_size: u64,
+    pub(crate) future_or_output: JoinAwaiteeType,
+}
+
+impl SharedFuture {
+    fn from_ddbug_type(
+        ddbug_type: &ddbug_parser::Type<'_>,
+        file_hash: &FileHash<'_>,
+    ) -> Option<Self> {
+        let TypeKind::Struct(struct_type) = ddbug_type.kind() else {
+            return None;
+        };
+        if !struct_type.name()?.starts_with("Shared<")
+            || struct_type.namespace()?.name() != Some("shared")
+        {
+            return None;
+        }
+
+        // Shared { inner: Option<Arc<Inner<F>>> }, where the `Option` uses the null pointer niche.
+        let (inner_offset, option) = member(ddbug_type, "inner", file_hash)?;
+        let TypeKind::Struct(option) = option.kind() else {
+            return None;
+        };
+        let [variant_part] = option.variant_parts() else {
+            return None;
+        };
+        let some = variant_part
+            .variants()
+            .iter()
+            .find(|variant| variant.name() == Some("Some"))?;
+        let [arc] = some.members() else {
+            return None;
+        };
+        let arc_offset = arc.bit_offset() / 8;
+
+        // Arc { ptr: NonNull<ArcInner<Inner<F>>> }, NonNull { pointer: *const ArcInner<Inner<F>> }
+        let (ptr_offset, non_null) = member(&arc.ty(file_hash)?, "ptr", file_hash)?;
+        let (pointer_offset, pointer) = member(&non_null, "pointer", file_hash)?;
+        let pointer_size = pointer.byte_size(file_hash)?;
+        if ![1, 2, 4, 8].contains(&pointer_size) {
+            return None;
+        }
+        let TypeKind::Modifier(pointer) = pointer.kind() else {
+            return None;
+        };
+        if !matches!(pointer.kind(), TypeModifierKind::Pointer) {
+            return None;
+        }
+
+        // ArcInner { data: Inner { future_or_output: UnsafeCell { value: FutureOrOutput<F> } } }
+        let (data_offset, data) = member(&pointer.ty(file_hash)?, "data", file_hash)?;
+        let (cell_offset, cell) = member(&data, "future_or_output", file_hash)?;
+        let (value_offset, future_or_output) = member(&cell, "value", file_hash)?;
+
+        Some(Self {
+            pointer_offset: inner_offset + arc_offset + ptr_offset + pointer_offset,
+            pointer_size,
+            future_offset: data_offset + cell_offset + value_offset,
+            future_size: future_or_output.byte_size(file_hash)?,
+            future_or_output: JoinAwaiteeType::from_ddbug_type(&future_or_output, file_hash)?,
+        })
+    }
+}
+
+/// `embassy_time::Timer`, which waits until the `embassy_time::Instant` it expires at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TimerFuture {
+    pub(crate) expires_at_offset: u64,
+    pub(crate) expires_at_size: u64,
+    /// The `embassy_time::Instant` type, formatted with the configured tick rate.
+    pub(crate) expires_at_type: Type,
+    /// Offset of the bool that is set once the timer was polled and scheduled its wake up.
+    pub(crate) yielded_once_offset: u64,
+}
+
+impl TimerFuture {
+    fn from_ddbug_type(
+        ddbug_type: &ddbug_parser::Type<'_>,
+        file_hash: &FileHash<'_>,
+    ) -> Option<Self> {
+        let TypeKind::Struct(struct_type) = ddbug_type.kind() else {
+            return None;
+        };
+        let namespace = struct_type.namespace()?;
+        if struct_type.name()? != "Timer"
+            || namespace.name() != Some("timer")
+            || namespace.parent().and_then(|n| n.name()) != Some("embassy_time")
+        {
+            return None;
+        }
+
+        // Timer { expires_at: Instant, yielded_once: bool }
+        let (expires_at_offset, expires_at) = member(ddbug_type, "expires_at", file_hash)?;
+        let (yielded_once_offset, _) = member(ddbug_type, "yielded_once", file_hash)?;
+
+        Some(Self {
+            expires_at_offset,
+            expires_at_size: expires_at.byte_size(file_hash)?,
+            expires_at_type: Type::from_ddbug_type(&expires_at, file_hash),
+            yielded_once_offset,
+        })
+    }
+}
+
+/// The offset and type of the member `name` of the struct `ty`.
+pub(super) fn member<'a, 'input>(
+    ty: &ddbug_parser::Type<'input>,
+    name: &str,
+    file_hash: &'a FileHash<'input>,
+) -> Option<(u64, Cow<'a, ddbug_parser::Type<'input>>)> {
+    let TypeKind::Struct(struct_type) = ty.kind() else {
+        return None;
+    };
+    let member = struct_type
+        .members()
+        .iter()
+        .find(|member| member.name() == Some(name))?;
+    Some((member.bit_offset() / 8, member.ty(file_hash)?))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum FutureTypeKind {
     AsyncFn(AsyncFnType),
     Select(SelectFuture),
     Join(JoinFuture),
+    Option(OptionFuture),
+    Shared(SharedFuture),
+    Timer(TimerFuture),
+    EmbassySync(EmbassySyncFuture),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct FutureType {
     pub(crate) kind: FutureTypeKind,
+    /// Size of the whole future, `None` if the debug data does not contain it.
+    pub(crate) size: Option<u64>,
 }
 
 impl FutureType {
@@ -300,8 +692,12 @@ impl FutureType {
         ddbug_type: &ddbug_parser::Type<'_>,
         file_hash: &FileHash,
     ) -> Result<Option<Self>> {
+        let size = ddbug_type.byte_size(file_hash);
+
         if let Some(async_fn_type) = AsyncFnType::from_ddbug_type(ddbug_type, file_hash)? {
             return Ok(Some(Self {
+                // Also known when the debug data has no size, see `AsyncFnType::total_size`.
+                size: Some(async_fn_type.total_size),
                 kind: FutureTypeKind::AsyncFn(async_fn_type),
             }));
         }
@@ -309,44 +705,146 @@ impl FutureType {
         if let Some(select_future_type) = SelectFuture::from_ddbug_type(ddbug_type, file_hash)? {
             return Ok(Some(Self {
                 kind: FutureTypeKind::Select(select_future_type),
+                size,
             }));
         }
 
         if let Some(join_future_type) = JoinFuture::from_ddbug_type(ddbug_type, file_hash)? {
             return Ok(Some(Self {
                 kind: FutureTypeKind::Join(join_future_type),
+                size,
+            }));
+        }
+
+        if let Some(option_future_type) = OptionFuture::from_ddbug_type(ddbug_type, file_hash) {
+            return Ok(Some(Self {
+                kind: FutureTypeKind::Option(option_future_type),
+                size,
+            }));
+        }
+
+        if let Some(shared_future_type) = SharedFuture::from_ddbug_type(ddbug_type, file_hash) {
+            return Ok(Some(Self {
+                kind: FutureTypeKind::Shared(shared_future_type),
+                size,
+            }));
+        }
+
+        if let Some(timer_future_type) = TimerFuture::from_ddbug_type(ddbug_type, file_hash) {
+            return Ok(Some(Self {
+                kind: FutureTypeKind::Timer(timer_future_type),
+                size,
+            }));
+        }
+
+        if let Some(sync_future_type) = EmbassySyncFuture::from_ddbug_type(ddbug_type, file_hash) {
+            return Ok(Some(Self {
+                kind: FutureTypeKind::EmbassySync(sync_future_type),
+                size,
             }));
         }
 
         Ok(None)
     }
+
+    /// Remove the `Option` types that don't contain a future, which is only known once all future
+    /// types are found.
+    pub(crate) fn remove_non_future_options(future_types: &mut HashMap<Type, FutureType>) {
+        // Repeated for options of options.
+        loop {
+            let not_futures = future_types
+                .iter()
+                .filter(|(_, future_type)| match &future_type.kind {
+                    FutureTypeKind::Option(option) => !future_types.contains_key(&option.some.1),
+                    _ => false,
+                })
+                .map(|(ty, _)| ty.clone())
+                .collect::<Vec<_>>();
+            if not_futures.is_empty() {
+                break;
+            }
+
+            for ty in not_futures {
+                future_types.remove(&ty);
+            }
+        }
+    }
 }
 
-#[derive(Debug)]
+/// How many `Shared` handles inside the futures behind other handles are followed, see
+/// [`FutureValue::read_behind_pointers`].
+const MAX_SHARED_DEPTH: usize = 4;
+
+#[derive(Debug, Clone)]
 pub(crate) struct SelectValue {
+    /// The arms, the resolved one as [`FutureValueKind::Output`] of its bytes.
     pub(crate) awaitees: Box<[FutureValue]>,
+    /// Index of the async fn arm that returned, which completed the select.
+    ///
+    /// The selects of embassy-futures have no state of their own to tell this, and do not keep the
+    /// output of the arm. A select with a returned arm was already polled ready and is only still
+    /// in memory until the future holding it continues.
+    pub(crate) resolved: Option<usize>,
+    /// Whether the select was already polled ready, by its own discriminant or a resolved arm.
+    pub(crate) done: bool,
 }
 
 impl SelectValue {
     fn new(
         select_type: &SelectFuture,
+        address: u64,
         bytes: &[u8],
-        future_types: &std::collections::HashMap<Type, FutureType>,
+        future_types: &HashMap<Type, FutureType>,
     ) -> Self {
-        let awaitees = select_type
-            .awaitees
-            .iter()
-            .map(|(offset, ty)| {
+        let done = select_type
+            .discriminant
+            .as_ref()
+            .is_some_and(|discriminant| discriminant.is_done(bytes));
+        // The arms of a completed select are gone, like the members of a returned async fn.
+        if done {
+            return Self {
+                awaitees: Box::new([]),
+                resolved: None,
+                done,
+            };
+        }
+
+        let mut resolved = None;
+        let awaitees = (0..)
+            .zip(&select_type.awaitees)
+            .map(|(i, (offset, ty))| {
                 let bytes = &bytes[*offset as usize..];
-                FutureValue::new(ty, bytes, future_types)
+                let future_type = future_types.get(ty);
+                match future_type.map(|future_type| &future_type.kind) {
+                    Some(FutureTypeKind::AsyncFn(async_fn))
+                        if resolved.is_none() && async_fn.has_returned(bytes) =>
+                    {
+                        resolved = Some(i);
+                        let size = future_type.and_then(|future_type| future_type.size);
+                        let bytes = match size {
+                            Some(size) => &bytes[..(size as usize).min(bytes.len())],
+                            None => bytes,
+                        };
+                        FutureValue {
+                            ty: ty.clone(),
+                            address: address + offset,
+                            kind: FutureValueKind::Output(bytes.to_vec()),
+                        }
+                    }
+                    _ => FutureValue::new(ty, address + offset, bytes, future_types),
+                }
             })
-            .collect();
+            .collect::<Box<[_]>>();
 
-        Self { awaitees }
+        Self {
+            awaitees,
+            resolved,
+            done: resolved.is_some(),
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct JoinValue {
     pub(crate) awaitees: Box<[FutureValue]>,
 }
@@ -354,44 +852,15 @@ pub(crate) struct JoinValue {
 impl JoinValue {
     fn new(
         select_type: &JoinFuture,
+        address: u64,
         bytes: &[u8],
-        future_types: &std::collections::HashMap<Type, FutureType>,
+        future_types: &HashMap<Type, FutureType>,
     ) -> Self {
         let awaitees = select_type
             .awaitees
             .iter()
             .map(|(offset, ty)| {
-                let bytes = &bytes[*offset as usize..];
-
-                let disc_bytes = &bytes[ty.discriminant_offset as usize..];
-                let discriminant = match ty.discriminant_size {
-                    1 => u8::from_le_bytes(disc_bytes[..1].try_into().unwrap()) as u64,
-                    2 => u16::from_le_bytes(disc_bytes[..2].try_into().unwrap()) as u64,
-                    4 => u32::from_le_bytes(disc_bytes[..4].try_into().unwrap()) as u64,
-                    8 => u64::from_le_bytes(disc_bytes[..8].try_into().unwrap()),
-                    _ => unreachable!(),
-                };
-
-                if discriminant == ty.future_variant.discriminant {
-                    let bytes = &bytes[ty.future_variant.offset as usize..]
-                        [..ty.future_variant.size as usize];
-                    FutureValue::new(&ty.future_variant.ty, bytes, future_types)
-                } else if discriminant == ty.done_variant.discriminant {
-                    let bytes =
-                        &bytes[ty.done_variant.offset as usize..][..ty.done_variant.size as usize];
-
-                    FutureValue {
-                        ty: ty.done_variant.ty.clone(),
-                        kind: FutureValueKind::Unknown(bytes.to_vec()),
-                    }
-                } else {
-                    // The value has been taken by calling `take_output`
-                    // TODO: return something more usefull
-                    FutureValue {
-                        ty: Type::Void,
-                        kind: FutureValueKind::Unknown(Vec::new()),
-                    }
-                }
+                ty.value(address + offset, &bytes[*offset as usize..], future_types)
             })
             .collect();
 
@@ -399,51 +868,714 @@ impl JoinValue {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub(crate) struct OptionValue {
+    /// The future inside `Some`, `None` if there is no future.
+    pub(crate) awaitee: Option<Box<FutureValue>>,
+    /// Whether this is a `Fuse`, which is terminated when there is no future.
+    pub(crate) fused: bool,
+}
+
+impl OptionValue {
+    fn new(
+        option_type: &OptionFuture,
+        address: u64,
+        bytes: &[u8],
+        future_types: &HashMap<Type, FutureType>,
+    ) -> Self {
+        let disc_bytes = &bytes[option_type.discriminant_offset as usize..];
+        let discriminant = match option_type.discriminant_size {
+            1 => u8::from_le_bytes(disc_bytes[..1].try_into().unwrap()) as u64,
+            2 => u16::from_le_bytes(disc_bytes[..2].try_into().unwrap()) as u64,
+            4 => u32::from_le_bytes(disc_bytes[..4].try_into().unwrap()) as u64,
+            8 => u64::from_le_bytes(disc_bytes[..8].try_into().unwrap()),
+            _ => unreachable!(),
+        };
+
+        let awaitee = (discriminant != option_type.none_discriminant).then(|| {
+            let (offset, ty) = &option_type.some;
+            let bytes = &bytes[*offset as usize..];
+            Box::new(FutureValue::new(ty, address + offset, bytes, future_types))
+        });
+
+        Self {
+            awaitee,
+            fused: option_type.fused,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SharedValue {
+    /// Address of the `ArcInner` holding the future, `None` once the handle completed.
+    pub(crate) pointer: Option<u64>,
+    /// The future or its output behind the pointer, `None` until read by
+    /// [`FutureValue::read_behind_pointers`] or when it could not be read.
+    pub(crate) awaitee: Option<Box<FutureValue>>,
+    /// The size of the handle itself.
+    pub(crate) size: u64,
+}
+
+impl SharedValue {
+    fn new(shared_type: &SharedFuture, bytes: &[u8], size: u64) -> Self {
+        let pointer_bytes = &bytes[shared_type.pointer_offset as usize..];
+        let pointer = match shared_type.pointer_size {
+            1 => u8::from_le_bytes(pointer_bytes[..1].try_into().unwrap()) as u64,
+            2 => u16::from_le_bytes(pointer_bytes[..2].try_into().unwrap()) as u64,
+            4 => u32::from_le_bytes(pointer_bytes[..4].try_into().unwrap()) as u64,
+            8 => u64::from_le_bytes(pointer_bytes[..8].try_into().unwrap()),
+            _ => unreachable!(),
+        };
+
+        Self {
+            pointer: (pointer != 0).then_some(pointer),
+            awaitee: None,
+            size,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TimerValue {
+    /// The bytes of the `embassy_time::Instant` the timer expires at.
+    pub(crate) expires_at: Vec<u8>,
+    pub(crate) expires_at_type: Type,
+    /// Whether the timer was polled, before that it did not schedule its wake up yet.
+    pub(crate) yielded_once: bool,
+    /// The size of the timer itself.
+    size: u64,
+}
+
+impl TimerValue {
+    fn new(timer_type: &TimerFuture, bytes: &[u8], size: u64) -> Self {
+        let start = timer_type.expires_at_offset as usize;
+        let end = start + timer_type.expires_at_size as usize;
+
+        Self {
+            expires_at: bytes[start..end].to_vec(),
+            expires_at_type: timer_type.expires_at_type.clone(),
+            yielded_once: bytes[timer_type.yielded_once_offset as usize] != 0,
+            size,
+        }
+    }
+
+    /// The number of ticks of the deadline, `None` if the `Instant` is not a plain integer.
+    pub(crate) fn ticks(&self) -> Option<u64> {
+        let bytes = self.expires_at.as_slice().try_into().ok()?;
+        Some(u64::from_le_bytes(bytes))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) enum FutureValueKind {
     AsyncFn(AsyncFnValue),
     SelectValue(SelectValue),
     JoinValue(JoinValue),
+    OptionValue(OptionValue),
+    SharedValue(SharedValue),
+    TimerValue(TimerValue),
+    EmbassySyncValue(EmbassySyncValue),
+    /// The output of a join arm that is done, the type of the value is the output type.
+    Output(Vec<u8>),
     Unknown(Vec<u8>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct FutureValue {
     pub(crate) ty: Type,
+    /// Absolute address of the future on the target.
+    pub(crate) address: u64,
     pub(crate) kind: FutureValueKind,
 }
 
 impl FutureValue {
     pub(crate) fn new(
         ty: &Type,
+        address: u64,
         bytes: &[u8],
-        future_types: &std::collections::HashMap<Type, FutureType>,
+        future_types: &HashMap<Type, FutureType>,
     ) -> Self {
         let future_type = future_types.get(ty);
 
         let kind = match future_type.map(|f| &f.kind) {
-            Some(FutureTypeKind::AsyncFn(async_fn_type)) => {
-                FutureValueKind::AsyncFn(AsyncFnValue::new(async_fn_type, bytes, future_types))
+            Some(FutureTypeKind::AsyncFn(async_fn_type)) => FutureValueKind::AsyncFn(
+                AsyncFnValue::new(async_fn_type, address, bytes, future_types),
+            ),
+            Some(FutureTypeKind::Select(select_type)) => FutureValueKind::SelectValue(
+                SelectValue::new(select_type, address, bytes, future_types),
+            ),
+            Some(FutureTypeKind::Join(join_type)) => {
+                FutureValueKind::JoinValue(JoinValue::new(join_type, address, bytes, future_types))
             }
-            Some(FutureTypeKind::Select(select_type)) => {
-                FutureValueKind::SelectValue(SelectValue::new(select_type, bytes, future_types))
+            Some(FutureTypeKind::Option(option_type)) => FutureValueKind::OptionValue(
+                OptionValue::new(option_type, address, bytes, future_types),
+            ),
+            Some(FutureTypeKind::Shared(shared_type)) => {
+                let size = future_type
+                    .and_then(|f| f.size)
+                    .unwrap_or(bytes.len() as u64);
+                FutureValueKind::SharedValue(SharedValue::new(shared_type, bytes, size))
             }
-            Some(FutureTypeKind::Join(join_type)) => {
-                FutureValueKind::JoinValue(JoinValue::new(join_type, bytes, future_types))
+            Some(FutureTypeKind::Timer(timer_type)) => {
+                let size = future_type
+                    .and_then(|f| f.size)
+                    .unwrap_or(bytes.len() as u64);
+                FutureValueKind::TimerValue(TimerValue::new(timer_type, bytes, size))
+            }
+            Some(FutureTypeKind::EmbassySync(sync_type)) => {
+                let size = future_type
+                    .and_then(|f| f.size)
+                    .unwrap_or(bytes.len() as u64);
+                FutureValueKind::EmbassySyncValue(EmbassySyncValue::new(sync_type, bytes, size))
             }
             None => FutureValueKind::Unknown(bytes.to_vec()),
         };
 
         Self {
             ty: ty.clone(),
+            address,
             kind,
         }
     }
 
-    pub(crate) fn async_fn(ty: &Type, async_fn_value: AsyncFnValue) -> FutureValue {
+    pub(crate) fn async_fn(ty: &Type, address: u64, async_fn_value: AsyncFnValue) -> FutureValue {
         Self {
             ty: ty.clone(),
+            address,
             kind: FutureValueKind::AsyncFn(async_fn_value),
         }
     }
+
+    /// The futures this future is waiting on, with an id to identify them within this future.
+    ///
+    /// The id is the discriminant of the current state for async fns, so the awaitee of a
+    /// different await point gets a different id, and the index for joins and selects.
+    pub(crate) fn children(&self) -> Vec<(u64, &FutureValue)> {
+        match &self.kind {
+            FutureValueKind::AsyncFn(value) => match &value.state_value {
+                Ok(state) => state
+                    .awaitee
+                    .as_deref()
+                    .map(|awaitee| (state.discriminant, awaitee))
+                    .into_iter()
+                    .collect(),
+                Err(_) => Vec::new(),
+            },
+            FutureValueKind::SelectValue(value) => (0..).zip(&value.awaitees).collect(),
+            FutureValueKind::JoinValue(value) => (0..).zip(&value.awaitees).collect(),
+            FutureValueKind::OptionValue(OptionValue { awaitee, .. })
+            | FutureValueKind::SharedValue(SharedValue { awaitee, .. }) => awaitee
+                .as_deref()
+                .map(|awaitee| (0, awaitee))
+                .into_iter()
+                .collect(),
+            FutureValueKind::TimerValue(_)
+            | FutureValueKind::EmbassySyncValue(_)
+            | FutureValueKind::Output(_)
+            | FutureValueKind::Unknown(_) => Vec::new(),
+        }
+    }
+
+    /// The futures this future is waiting on, like [`Self::children`].
+    fn children_mut(&mut self) -> Vec<&mut FutureValue> {
+        match &mut self.kind {
+            FutureValueKind::AsyncFn(value) => match &mut value.state_value {
+                Ok(state) => state.awaitee.as_deref_mut().into_iter().collect(),
+                Err(_) => Vec::new(),
+            },
+            FutureValueKind::SelectValue(SelectValue { awaitees, .. })
+            | FutureValueKind::JoinValue(JoinValue { awaitees }) => awaitees.iter_mut().collect(),
+            FutureValueKind::OptionValue(OptionValue { awaitee, .. })
+            | FutureValueKind::SharedValue(SharedValue { awaitee, .. }) => {
+                awaitee.as_deref_mut().into_iter().collect()
+            }
+            FutureValueKind::TimerValue(_)
+            | FutureValueKind::EmbassySyncValue(_)
+            | FutureValueKind::Output(_)
+            | FutureValueKind::Unknown(_) => Vec::new(),
+        }
+    }
+
+    /// Read the futures behind the `Shared` handles in this future and the futures it is waiting
+    /// on, which are not in the memory of the future holding the handle, and the channels and
+    /// signals the futures of embassy-sync are waiting on.
+    ///
+    /// `read_memory` reads the given number of bytes at an address, `None` if they are not
+    /// readable. The futures behind a handle can hold handles themselves, which are only followed
+    /// upto [`MAX_SHARED_DEPTH`] deep, so a future holding a handle to itself does not loop.
+    pub(crate) fn read_behind_pointers(
+        &mut self,
+        future_types: &HashMap<Type, FutureType>,
+        read_memory: &mut impl FnMut(u64, u64) -> Option<Vec<u8>>,
+        depth: usize,
+    ) {
+        if let FutureValueKind::SharedValue(value) = &mut self.kind
+            && let Some(pointer) = value.pointer
+            && depth < MAX_SHARED_DEPTH
+            && let Some(FutureTypeKind::Shared(shared_type)) =
+                future_types.get(&self.ty).map(|f| &f.kind)
+        {
+            let address = pointer + shared_type.future_offset;
+            value.awaitee = read_memory(address, shared_type.future_size).map(|bytes| {
+                let future_or_output = &shared_type.future_or_output;
+                let mut awaitee = future_or_output.value(address, &bytes, future_types);
+                awaitee.read_behind_pointers(future_types, read_memory, depth + 1);
+                Box::new(awaitee)
+            });
+            return;
+        }
+
+        if let FutureValueKind::EmbassySyncValue(value) = &mut self.kind
+            && let Some(pointer) = value.pointer
+            && let Some(FutureTypeKind::EmbassySync(sync_type)) =
+                future_types.get(&self.ty).map(|f| &f.kind)
+        {
+            value.state = sync_type.read_state(pointer, read_memory);
+            return;
+        }
+
+        for child in self.children_mut() {
+            child.read_behind_pointers(future_types, read_memory, depth);
+        }
+    }
+
+    /// Estimate of the memory this future takes, in bytes.
+    ///
+    /// Async fns have their own size, which includes what they are awaiting. Selects and joins are
+    /// the sum of their arms, without any padding in between. Unknown futures are counted by the
+    /// bytes read for them, which can include the memory after them for the arms of a select.
+    /// Shared futures only count the handle, the future itself is behind a pointer, like the
+    /// channels and signals of the futures of embassy-sync.
+    pub(crate) fn size(&self) -> u64 {
+        match &self.kind {
+            FutureValueKind::AsyncFn(value) => value.ty.total_size,
+            FutureValueKind::SelectValue(value) => {
+                value.awaitees.iter().map(FutureValue::size).sum()
+            }
+            FutureValueKind::JoinValue(value) => value.awaitees.iter().map(FutureValue::size).sum(),
+            FutureValueKind::OptionValue(value) => {
+                value.awaitee.as_ref().map_or(0, |awaitee| awaitee.size())
+            }
+            FutureValueKind::SharedValue(value) => value.size,
+            FutureValueKind::TimerValue(value) => value.size,
+            FutureValueKind::EmbassySyncValue(value) => value.size,
+            FutureValueKind::Output(bytes) | FutureValueKind::Unknown(bytes) => bytes.len() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::model::async_fn::{Member, State};
+
+    fn future_types() -> HashMap<Type, FutureType> {
+        let select = FutureType {
+            kind: FutureTypeKind::Select(SelectFuture {
+                awaitees: Box::new([]),
+                discriminant: None,
+            }),
+            size: Some(0),
+        };
+        let option = FutureType {
+            kind: FutureTypeKind::Option(OptionFuture {
+                discriminant_offset: 0,
+                discriminant_size: 1,
+                none_discriminant: 0,
+                some: (4, Type::Base("Select".to_owned())),
+                fused: false,
+            }),
+            size: Some(4),
+        };
+        let not_a_future = FutureType {
+            kind: FutureTypeKind::Option(OptionFuture {
+                discriminant_offset: 0,
+                discriminant_size: 1,
+                none_discriminant: 0,
+                some: (4, Type::Base("u32".to_owned())),
+                fused: false,
+            }),
+            size: Some(8),
+        };
+
+        HashMap::from([
+            (Type::Base("Select".to_owned()), select),
+            (Type::Base("Option<Select>".to_owned()), option),
+            (Type::Base("Option<u32>".to_owned()), not_a_future),
+        ])
+    }
+
+    #[test]
+    fn option_values() {
+        let mut future_types = future_types();
+        FutureType::remove_non_future_options(&mut future_types);
+        assert!(!future_types.contains_key(&Type::Base("Option<u32>".to_owned())));
+
+        let ty = Type::Base("Option<Select>".to_owned());
+
+        let none = FutureValue::new(&ty, 0x100, &[0, 0, 0, 0], &future_types);
+        let FutureValueKind::OptionValue(none) = none.kind else {
+            panic!("expected an option value");
+        };
+        assert!(none.awaitee.is_none());
+
+        let some = FutureValue::new(&ty, 0x100, &[1, 0, 0, 0], &future_types);
+        assert_eq!(some.children().len(), 1);
+        let FutureValueKind::OptionValue(some) = some.kind else {
+            panic!("expected an option value");
+        };
+        let awaitee = some.awaitee.unwrap();
+        assert_eq!(awaitee.address, 0x104);
+        assert!(matches!(awaitee.kind, FutureValueKind::SelectValue(_)));
+    }
+
+    #[test]
+    fn fused_async_fn() {
+        let state = |name: &str, discriminant_value| State {
+            discriminant_value: Some(discriminant_value),
+            active_members: Vec::new(),
+            awaitee: None,
+            name: name.to_owned(),
+            source: None,
+        };
+        let async_fn = FutureType {
+            kind: FutureTypeKind::AsyncFn(AsyncFnType {
+                members: Vec::new(),
+                state_member: Member {
+                    name: "__state".to_owned(),
+                    ty: Type::Base("u8".to_owned()),
+                    offset: 0,
+                    size: 1,
+                },
+                total_size: 1,
+                states: vec![state("Unresumed", 0), state("Returned", 1)],
+            }),
+            size: Some(1),
+        };
+        // `None` is a niche in the state of the async fn.
+        let fuse = FutureType {
+            kind: FutureTypeKind::Option(OptionFuture {
+                discriminant_offset: 0,
+                discriminant_size: 1,
+                none_discriminant: 3,
+                some: (0, Type::Base("{async_fn_env#0}".to_owned())),
+                fused: true,
+            }),
+            size: Some(1),
+        };
+        let future_types = HashMap::from([
+            (Type::Base("{async_fn_env#0}".to_owned()), async_fn),
+            (Type::Base("Fuse<{async_fn_env#0}>".to_owned()), fuse),
+        ]);
+        let ty = Type::Base("Fuse<{async_fn_env#0}>".to_owned());
+
+        let running = FutureValue::new(&ty, 0x100, &[0], &future_types);
+        let FutureValueKind::OptionValue(running) = running.kind else {
+            panic!("expected an option value");
+        };
+        assert!(running.fused);
+        let FutureValueKind::AsyncFn(async_fn) = running.awaitee.unwrap().kind else {
+            panic!("expected the async fn inside the fuse");
+        };
+        assert_eq!(async_fn.state_value.unwrap().state.name, "Unresumed");
+
+        let terminated = FutureValue::new(&ty, 0x100, &[3], &future_types);
+        let FutureValueKind::OptionValue(terminated) = terminated.kind else {
+            panic!("expected an option value");
+        };
+        assert!(terminated.fused);
+        assert!(terminated.awaitee.is_none());
+    }
+
+    #[test]
+    fn fuse_from_fixture() {
+        let future_types = crate::model::fixtures::debug_data("app.elf").future_types;
+        let ty = Type::Base(
+            "futures_util::future::future::fuse::Fuse<app::tick::{async_fn_env#0}>".to_owned(),
+        );
+
+        // The `Option` inside has its own discriminant at 0, with the future after it.
+        let Some(FutureTypeKind::Option(fuse)) = future_types.get(&ty).map(|f| &f.kind) else {
+            panic!("expected the fuse to be a future type");
+        };
+        assert!(fuse.fused);
+        assert_eq!((fuse.discriminant_offset, fuse.none_discriminant), (0, 0));
+        assert_eq!(fuse.some.0, 1);
+
+        let running = FutureValue::new(&ty, 0x100, &[1, 0, 0], &future_types);
+        let FutureValueKind::OptionValue(running) = running.kind else {
+            panic!("expected an option value");
+        };
+        let awaitee = running.awaitee.unwrap();
+        assert_eq!(awaitee.address, 0x101);
+        let FutureValueKind::AsyncFn(async_fn) = awaitee.kind else {
+            panic!("expected the async fn inside the fuse");
+        };
+        assert_eq!(async_fn.state_value.unwrap().state.name, "Unresumed");
+
+        let terminated = FutureValue::new(&ty, 0x100, &[0, 0xaa, 0xaa], &future_types);
+        let FutureValueKind::OptionValue(terminated) = terminated.kind else {
+            panic!("expected an option value");
+        };
+        assert!(terminated.fused);
+        assert!(terminated.awaitee.is_none());
+    }
+
+    #[test]
+    fn timer_values() {
+        let timer = FutureType {
+            kind: FutureTypeKind::Timer(TimerFuture {
+                expires_at_offset: 0,
+                expires_at_size: 8,
+                expires_at_type: Type::Base("embassy_time::instant::Instant".to_owned()),
+                yielded_once_offset: 8,
+            }),
+            size: Some(16),
+        };
+        let ty = Type::Base("embassy_time::timer::Timer".to_owned());
+        let future_types = HashMap::from([(ty.clone(), timer)]);
+
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&1_500_000u64.to_le_bytes());
+        bytes[8] = 1;
+        let value = FutureValue::new(&ty, 0x100, &bytes, &future_types);
+        assert_eq!(value.size(), 16);
+        assert!(value.children().is_empty());
+        let FutureValueKind::TimerValue(timer) = value.kind else {
+            panic!("expected a timer value");
+        };
+        assert_eq!(timer.ticks(), Some(1_500_000));
+        assert!(timer.yielded_once);
+    }
+
+    #[test]
+    fn resolved_select() {
+        let state = |name: &str, discriminant_value| State {
+            discriminant_value: Some(discriminant_value),
+            active_members: Vec::new(),
+            awaitee: None,
+            name: name.to_owned(),
+            source: None,
+        };
+        let async_fn = FutureType {
+            kind: FutureTypeKind::AsyncFn(AsyncFnType {
+                members: Vec::new(),
+                state_member: Member {
+                    name: "__state".to_owned(),
+                    ty: Type::Base("u8".to_owned()),
+                    offset: 0,
+                    size: 1,
+                },
+                total_size: 1,
+                states: vec![state("Unresumed", 0), state("Returned", 1)],
+            }),
+            size: Some(1),
+        };
+        let async_fn_ty = Type::Base("{async_fn_env#0}".to_owned());
+        let select = FutureType {
+            kind: FutureTypeKind::Select(SelectFuture {
+                awaitees: Box::new([(0, async_fn_ty.clone()), (1, async_fn_ty.clone())]),
+                discriminant: None,
+            }),
+            size: Some(2),
+        };
+        let ty = Type::Base("Select<{async_fn_env#0}, {async_fn_env#0}>".to_owned());
+        // The same arms in an enum, with the discriminant after them.
+        let select_enum = FutureType {
+            kind: FutureTypeKind::Select(SelectFuture {
+                awaitees: Box::new([(0, async_fn_ty.clone()), (1, async_fn_ty.clone())]),
+                discriminant: Some(SelectDiscriminant {
+                    offset: 2,
+                    size: 1,
+                    done: 1,
+                }),
+            }),
+            size: Some(3),
+        };
+        let enum_ty = Type::Base("SelectEnum".to_owned());
+        let future_types = HashMap::from([
+            (async_fn_ty, async_fn),
+            (ty.clone(), select),
+            (enum_ty.clone(), select_enum),
+        ]);
+        let select_value = |ty: &Type, bytes: &[u8]| {
+            let FutureValueKind::SelectValue(value) =
+                FutureValue::new(ty, 0x100, bytes, &future_types).kind
+            else {
+                panic!("expected a select value");
+            };
+            value
+        };
+
+        let waiting = select_value(&ty, &[0, 0]);
+        assert_eq!(waiting.resolved, None);
+        assert!(!waiting.done);
+        assert!(matches!(
+            waiting.awaitees[1].kind,
+            FutureValueKind::AsyncFn(_)
+        ));
+
+        // The returned arm is not read as an async fn, its bytes are the output.
+        let resolved = select_value(&ty, &[0, 1]);
+        assert_eq!(resolved.resolved, Some(1));
+        assert!(resolved.done);
+        assert!(matches!(
+            resolved.awaitees[0].kind,
+            FutureValueKind::AsyncFn(_)
+        ));
+        assert_eq!(resolved.awaitees[1].address, 0x101);
+        assert!(
+            matches!(&resolved.awaitees[1].kind, FutureValueKind::Output(bytes) if bytes == &[1])
+        );
+
+        assert!(!select_value(&enum_ty, &[0, 0, 0]).done);
+        let done = select_value(&enum_ty, &[0xaa, 0xaa, 1]);
+        assert!(done.done);
+        assert_eq!(done.resolved, None);
+        assert!(done.awaitees.is_empty());
+        // Memory that ends before the discriminant.
+        assert!(!select_value(&enum_ty, &[0, 0]).done);
+    }
+
+    #[test]
+    fn shared_values() {
+        let variant = |discriminant, ty: &str| JoinAwaiteeTypeVariant {
+            discriminant: Some(discriminant),
+            offset: 4,
+            size: 4,
+            ty: Type::Base(ty.to_owned()),
+        };
+        let shared = FutureType {
+            kind: FutureTypeKind::Shared(SharedFuture {
+                pointer_offset: 0,
+                pointer_size: 4,
+                future_offset: 8,
+                future_size: 8,
+                future_or_output: JoinAwaiteeType {
+                    discriminant_offset: 0,
+                    discriminant_size: 1,
+                    future_variant: variant(0, "Select"),
+                    done_variant: variant(1, "u32"),
+                    gone_discriminant: None,
+                },
+            }),
+            size: Some(8),
+        };
+        let mut future_types = future_types();
+        future_types.insert(Type::Base("Shared<Select>".to_owned()), shared);
+        let ty = Type::Base("Shared<Select>".to_owned());
+
+        let mut reads = Vec::new();
+        let mut read_memory = |address: u64, size: u64| {
+            reads.push((address, size));
+            Some(vec![1, 0, 0, 0, 42, 0, 0, 0])
+        };
+
+        let mut value =
+            FutureValue::new(&ty, 0x100, &[0, 0x10, 0, 0x20, 0, 0, 0, 0], &future_types);
+        value.read_behind_pointers(&future_types, &mut read_memory, 0);
+        assert_eq!(value.size(), 8);
+        let FutureValueKind::SharedValue(shared) = value.kind else {
+            panic!("expected a shared value");
+        };
+        assert_eq!(shared.pointer, Some(0x2000_1000));
+        let output = shared.awaitee.unwrap();
+        assert_eq!(output.address, 0x2000_100c);
+        assert!(
+            matches!(output.kind, FutureValueKind::Output(ref bytes) if bytes == &[42, 0, 0, 0])
+        );
+
+        // The handle of a completed shared future no longer points to it.
+        let mut completed = FutureValue::new(&ty, 0x100, &[0; 8], &future_types);
+        completed.read_behind_pointers(&future_types, &mut read_memory, 0);
+        assert!(completed.children().is_empty());
+        assert_eq!(reads, [(0x2000_1008, 8)]);
+    }
+
+    #[test]
+    fn join_outputs() {
+        let variant = |discriminant, ty: &str| JoinAwaiteeTypeVariant {
+            discriminant: Some(discriminant),
+            offset: 4,
+            size: 4,
+            ty: Type::Base(ty.to_owned()),
+        };
+        let arm = JoinAwaiteeType {
+            discriminant_offset: 0,
+            discriminant_size: 1,
+            future_variant: variant(0, "Select"),
+            done_variant: variant(1, "Result<u32, ()>"),
+            gone_discriminant: Some(2),
+        };
+        let join = JoinFuture {
+            awaitees: Box::new([(0, arm.clone()), (8, arm)]),
+        };
+
+        let bytes = [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 42, 0, 0, 0];
+        let value = JoinValue::new(&join, 0x100, &bytes, &future_types());
+        assert!(matches!(
+            value.awaitees[0].kind,
+            FutureValueKind::SelectValue(_)
+        ));
+
+        let done = &value.awaitees[1];
+        assert_eq!(done.ty, Type::Base("Result<u32, ()>".to_owned()));
+        assert_eq!(done.address, 0x10c);
+        let FutureValueKind::Output(output) = &done.kind else {
+            panic!("expected the output of the arm");
+        };
+        assert_eq!(output, &[42, 0, 0, 0]);
+    }
+
+    #[test]
+    fn sizes() {
+        let unknown = |size| FutureValue {
+            ty: Type::Unknown,
+            address: 0,
+            kind: FutureValueKind::Unknown(vec![0; size]),
+        };
+        let join = FutureValue {
+            ty: Type::Unknown,
+            address: 0,
+            kind: FutureValueKind::JoinValue(JoinValue {
+                awaitees: Box::new([unknown(8), unknown(4)]),
+            }),
+        };
+        assert_eq!(join.size(), 12);
+
+        let empty = FutureValue {
+            ty: Type::Unknown,
+            address: 0,
+            kind: FutureValueKind::OptionValue(OptionValue {
+                awaitee: None,
+                fused: false,
+            }),
+        };
+        assert_eq!(empty.size(), 0);
+    }
+
+    #[test]
+    fn join_niche_variant() {
+        let variant = |discriminant| JoinAwaiteeTypeVariant {
+            discriminant,
+            offset: 0,
+            size: 4,
+            ty: Type::Unknown,
+        };
+        let ty = JoinAwaiteeType {
+            discriminant_offset: 0,
+            discriminant_size: 1,
+            future_variant: variant(None),
+            done_variant: variant(Some(3)),
+            gone_discriminant: Some(4),
+        };
+
+        assert!(ty.selects(&ty.future_variant, 0));
+        assert!(ty.selects(&ty.future_variant, 2));
+        assert!(!ty.selects(&ty.future_variant, 3));
+        assert!(!ty.selects(&ty.future_variant, 4));
+        assert!(ty.selects(&ty.done_variant, 3));
+        assert!(!ty.selects(&ty.done_variant, 2));
+    }
 }
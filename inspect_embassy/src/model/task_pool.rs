@@ -1,6 +1,6 @@
 //! Models for the memory layout and locations of task pools.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
 use super::{
     async_fn::{AsyncFnType, AsyncFnValue},
@@ -10,8 +10,9 @@ use super::{
 };
 
 use ddbug_parser::{FileHash, Result, TypeKind};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum StateType {
     /// State is a single u8, using
     /// - 0b00 for uninit
@@ -25,12 +26,20 @@ pub(crate) enum StateType {
     U32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct HeaderLayout {
     state_offset: u64,
     state_type: StateType,
+    /// Offset and size of a counter of the polls of the task, see [`POLL_COUNT_FIELDS`].
+    poll_count: Option<(u64, u64)>,
 }
 
+/// Names of a counter in the `TaskHeader` that is incremented every time the task is polled.
+///
+/// embassy-executor does not count polls itself, the field is only there when the executor is
+/// patched or built with a feature adding such instrumentation. Without it no count is shown.
+const POLL_COUNT_FIELDS: &[&str] = &["poll_count", "polls"];
+
 impl HeaderLayout {
     fn from_ddbug_type(
         ddbug_type: &ddbug_parser::Type<'_>,
@@ -53,6 +62,7 @@ impl HeaderLayout {
         }
 
         let mut state = None;
+        let mut poll_count = None;
         for member in struct_type.members() {
             match member.name() {
                 Some("state") => {
@@ -64,6 +74,12 @@ impl HeaderLayout {
                     };
                     state = Some((state_offset, state_type))
                 }
+                Some(name) if POLL_COUNT_FIELDS.contains(&name) => {
+                    let size = member.bit_size(&file_hash).map(|bits| bits / 8);
+                    if let Some(size @ (1 | 2 | 4 | 8)) = size {
+                        poll_count = Some((member.bit_offset() / 8, size));
+                    }
+                }
                 _ => {}
             }
         }
@@ -75,6 +91,7 @@ impl HeaderLayout {
         Ok(Some(Self {
             state_offset: state.0,
             state_type: state.1,
+            poll_count,
         }))
     }
 
@@ -90,19 +107,48 @@ impl HeaderLayout {
         Err("Could not find `TaskHeader` in debug data".into())
     }
 
-    fn is_init(&self, bytes: &[u8]) -> bool {
+    /// Decode the state in the header of a task, see [`StateType`] for the encodings.
+    fn task_state(&self, bytes: &[u8]) -> TaskRunState {
         let bytes = &bytes[self.state_offset as usize..];
 
-        match self.state_type {
-            StateType::U8 => bytes[0] > 0,
-            StateType::U32 => u32::from_ne_bytes(bytes[..4].try_into().unwrap()) > 0,
+        let (state, queued_bit) = match self.state_type {
+            StateType::U8 => (bytes[0] as u32, 0b10),
+            StateType::U32 => (
+                u32::from_ne_bytes(bytes[..4].try_into().unwrap()),
+                0b1_0000_0000,
+            ),
+        };
+
+        if state == 0 {
+            TaskRunState::Uninit
+        } else if state & queued_bit != 0 {
+            TaskRunState::Queued
+        } else {
+            TaskRunState::Spawned
         }
     }
+
+    /// Decode the number of times a task was polled from its header, `None` if the header has no
+    /// counter.
+    fn poll_count(&self, bytes: &[u8]) -> Option<u64> {
+        let (offset, size) = self.poll_count?;
+        let bytes = &bytes[offset as usize..];
+
+        Some(match size {
+            1 => bytes[0] as u64,
+            2 => u16::from_ne_bytes(bytes[..2].try_into().unwrap()) as u64,
+            4 => u32::from_ne_bytes(bytes[..4].try_into().unwrap()) as u64,
+            8 => u64::from_ne_bytes(bytes[..8].try_into().unwrap()),
+            _ => unreachable!(),
+        })
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TaskPool {
     pub(crate) path: String,
+    // Path of the async fn generated by the task macro.
+    pub(crate) task_name: String,
 
     // Address where the bytes are stored.
     pub(crate) address: u64,
@@ -118,21 +164,111 @@ pub(crate) struct TaskPool {
     pub(crate) async_fn_type: AsyncFnType,
     pub(crate) async_fn_base_type: Type,
 
+    // Address ranges of the code polling the future of this pool, including where it got inlined.
+    pub(crate) code_ranges: Vec<Range<u64>>,
+
     pub(crate) header_layout: HeaderLayout,
 }
 
+/// Split the generic arguments of a type name like `TaskPool<a::B<C, 3>, 1>` at the top level.
+///
+/// Returns `None` if the name has no generic arguments.
+fn generic_arguments(name: &str) -> Option<Vec<&str>> {
+    let start = name.find('<')?;
+    let inner = name[start + 1..].strip_suffix('>')?;
+
+    let mut arguments = Vec::new();
+    let mut depth = 0usize;
+    let mut argument_start = 0;
+    let mut previous = None;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            // The `>` of a `->` in a function type does not close anything.
+            '>' if previous == Some('-') => {}
+            '>' | ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                arguments.push(inner[argument_start..i].trim());
+                argument_start = i + 1;
+            }
+            _ => {}
+        }
+        previous = Some(c);
+    }
+    arguments.push(inner[argument_start..].trim());
+
+    Some(arguments)
+}
+
+/// Whether `name` is the item at `path` or something inside it, and not an item with a longer
+/// name that starts with the same characters.
+pub(super) fn is_in_path(name: &str, path: &str) -> bool {
+    match name.strip_prefix(path) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::") || rest.starts_with('<'),
+        None => false,
+    }
+}
+
+/// Name of the task function `#[embassy_executor::main]` turns the main function into.
+const MAIN_TASK_FUNCTION: &str = "__embassy_main";
+
+/// Whether the task pool at `path` belongs to the task generated by `#[embassy_executor::main]`.
+fn is_main_task_path(path: &str) -> bool {
+    path.rsplit("::").next() == Some(MAIN_TASK_FUNCTION)
+}
+
 impl TaskPool {
+    /// A pool only known from a [snapshot](crate::snapshot), without a location in memory or a
+    /// layout from the debug data.
+    pub(crate) fn from_snapshot(path: String, number_of_tasks: usize) -> Self {
+        Self {
+            task_name: path.clone(),
+            path,
+            address: 0,
+            size: 0,
+            future_offset: 0,
+            number_of_tasks,
+            async_fn_type: AsyncFnType::default(),
+            async_fn_base_type: Type::Unknown,
+            code_ranges: Vec::new(),
+            header_layout: HeaderLayout {
+                state_offset: 0,
+                state_type: StateType::U8,
+                poll_count: None,
+            },
+        }
+    }
+
+    /// Address and size of the memory of a single task, its header and future.
+    pub(crate) fn task_memory(&self, task_idx: usize) -> (u64, u64) {
+        // Pools from snapshots can have no tasks.
+        let size = self.size / self.number_of_tasks.max(1) as u64;
+        (self.address + size * task_idx as u64, size)
+    }
+
+    /// The discriminant of the state of a task's async fn from the bytes of its
+    /// [`Self::task_memory`], `None` if the task is not spawned.
+    pub(crate) fn task_discriminant(&self, bytes: &[u8]) -> Result<Option<u64>> {
+        if self.header_layout.task_state(bytes) == TaskRunState::Uninit {
+            return Ok(None);
+        }
+        let future = bytes.get(self.future_offset as usize..).unwrap_or_default();
+        self.async_fn_type.read_discriminant(future).map(Some)
+    }
+
     pub(crate) fn find_taks_pool<'a>(
         task_name: &str,
         file_hash: &'a FileHash<'a>,
     ) -> Option<&'a ddbug_parser::StructType<'a>> {
-        let task_pool_name_prefix = String::from("TaskPool<") + task_name;
-
         for unit in file_hash.file.units() {
             for unit_type in unit.types() {
                 if let ddbug_parser::TypeKind::Struct(struct_type) = unit_type.kind() {
                     if let Some(name) = struct_type.name()
-                        && name.starts_with(&task_pool_name_prefix)
+                        && name.starts_with("TaskPool<")
+                        && let Some(arguments) = generic_arguments(name)
+                        && arguments
+                            .first()
+                            .is_some_and(|future| is_in_path(future, task_name))
                     {
                         return Some(struct_type);
                     }
@@ -143,6 +279,73 @@ impl TaskPool {
         return None;
     }
 
+    /// Find the future type stored in the task pool type.
+    ///
+    /// The future is the first generic argument of the task pool type, which is the complete
+    /// monomorphized name, so different instantiations of the same task don't get mixed up. Falls
+    /// back to the first future type inside the task's path if the argument can't be found.
+    fn find_future_type<'a>(
+        task_pool_type: &ddbug_parser::StructType<'_>,
+        task_name: &str,
+        future_types: &'a HashMap<Type, FutureType>,
+    ) -> Option<(&'a Type, &'a FutureType)> {
+        let future_name = task_pool_type
+            .name()
+            .and_then(generic_arguments)
+            .and_then(|arguments| arguments.first().copied());
+
+        if let Some(future_name) = future_name
+            && let Some(found) = future_types
+                .iter()
+                .find(|(ty, _)| matches!(ty, Type::Base(name) if name == future_name))
+        {
+            return Some(found);
+        }
+
+        future_types.iter().find(|(ty, _)| match ty {
+            Type::Base(name) => is_in_path(name, task_name),
+            _ => false,
+        })
+    }
+
+    /// Find the code of the async fn generated by the task macro, and of any closures in it.
+    fn find_code_ranges(task_name: &str, file_hash: &FileHash<'_>) -> Vec<Range<u64>> {
+        let mut ranges = Vec::new();
+
+        for unit in file_hash.file.units() {
+            for unit_fn in unit.functions() {
+                if let Some(namespace) = unit_fn.namespace()
+                    && is_in_path(&namespace_to_path(namespace), task_name)
+                {
+                    ranges.extend(
+                        unit_fn
+                            .ranges()
+                            .iter()
+                            .filter(|range| range.begin != 0)
+                            .map(|range| range.begin..range.end),
+                    );
+                }
+            }
+        }
+
+        ranges
+    }
+
+    /// The `pool_size` given to the task macro, the maximum number of tasks this pool can hold.
+    pub(crate) fn pool_size(&self) -> usize {
+        self.number_of_tasks
+    }
+
+    /// Whether this is the pool of the main task, see [`is_main_task_path`].
+    pub(crate) fn is_main(&self) -> bool {
+        is_main_task_path(&self.path)
+    }
+
+    /// Whether the given address is in the code polling the future of this pool.
+    pub(crate) fn contains_pc(&self, pc: u64) -> bool {
+        self.code_ranges.iter().any(|range| range.contains(&pc))
+    }
+
     fn find_future_offset_task_storage(task_storage: &ddbug_parser::Type<'_>) -> Option<u64> {
         match task_storage.kind() {
             TypeKind::Struct(struct_type) => {
@@ -159,7 +362,6 @@ impl TaskPool {
         }
     }
 
-    // TODO: make this work when embassy is compiled with nightly
     pub(crate) fn from_ddbug_var(
         unit_var: &ddbug_parser::Variable<'_>,
         future_types: &HashMap<Type, FutureType>,
@@ -172,21 +374,16 @@ impl TaskPool {
         let Some(ty) = unit_var.ty(file_hash) else {
             return Ok(None);
         };
-        match ty.kind() {
-            ddbug_parser::TypeKind::Struct(struct_type) => {
-                let Some(name) = struct_type.name() else {
-                    return Ok(None);
-                };
-                if !name.starts_with("TaskPoolHolder") {
-                    return Ok(None);
-                }
-            }
-            _ => return Ok(None),
-        }
+        let ddbug_parser::TypeKind::Struct(pool_static_type) = ty.kind() else {
+            return Ok(None);
+        };
+        let Some(pool_static) = pool_static_type.name().and_then(PoolStatic::from_type_name) else {
+            return Ok(None);
+        };
 
         let namespace = unit_var
             .namespace()
-            .ok_or("TaskPoolHolder needs a namespace")?;
+            .ok_or("The POOL static needs a namespace")?;
 
         // The task macro generates a namespace with the name of the function, so the path generated
         // from only the namespaces will actually end in the name of the original task function.
@@ -194,23 +391,28 @@ impl TaskPool {
         let task_name = namespace_to_path(
             namespace
                 .parent()
-                .ok_or("TaskPoolHolder's namespace needs a parent")?,
+                .ok_or("The POOL static's namespace needs a parent")?,
         );
         let task_name = task_name
             + "::__"
             + namespace
                 .name()
-                .ok_or("TaskPoolHolder's namespace parent needs a name")?
+                .ok_or("The POOL static's namespace needs a name")?
             + "_task";
 
-        let address = unit_var.address().ok_or("TaskPoolHolder needs a address")?;
+        let address = unit_var
+            .address()
+            .ok_or("The POOL static needs a address")?;
         let size = unit_var
             .byte_size(file_hash)
-            .ok_or("TaskPoolHolder needs a sie")?;
+            .ok_or("The POOL static needs a size")?;
 
-        let task_pool_type = Self::find_taks_pool(&task_name, file_hash).ok_or(format!(
-            "Could not find task pool type for task pool: {task_name}"
-        ))?;
+        let task_pool_type = match pool_static {
+            PoolStatic::TaskPool => pool_static_type,
+            PoolStatic::Holder => Self::find_taks_pool(&task_name, file_hash).ok_or(format!(
+                "Could not find task pool type for task pool: {task_name}"
+            ))?,
+        };
         let [task_pool_member] = task_pool_type.members() else {
             return Err("TaskPool needs a single member".into());
         };
@@ -238,13 +440,8 @@ impl TaskPool {
         let future_offset = Self::find_future_offset_task_storage(&storage_type)
             .ok_or("Could not find future_offset in TaskStorage")?;
 
-        let (async_fn_base_type, async_fn_type) = future_types
-            .iter()
-            .find(|(ty, _)| match ty {
-                Type::Base(name) => name.starts_with(&task_name),
-                _ => false,
-            })
-            .ok_or(format!(
+        let (async_fn_base_type, async_fn_type) =
+            Self::find_future_type(task_pool_type, &task_name, future_types).ok_or(format!(
                 "Could not find future type for task pool: {}",
                 task_name
             ))?;
@@ -253,8 +450,11 @@ impl TaskPool {
             return Err("Task pool had a non async fn future type".into());
         };
 
+        let code_ranges = Self::find_code_ranges(&task_name, file_hash);
+
         Ok(Some(Self {
             path,
+            task_name,
 
             address,
             size,
@@ -264,14 +464,49 @@ impl TaskPool {
             async_fn_type: async_fn_type.clone(),
             header_layout: header_layout.clone(),
             async_fn_base_type: async_fn_base_type.clone(),
+            code_ranges,
         }))
     }
 }
 
+/// How the task macro stores a task pool in its `POOL` static.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PoolStatic {
+    /// A `TaskPoolHolder` with the bytes of the `TaskPool`, without the `nightly` feature of
+    /// embassy-executor. The `TaskPool` type is found separately by the name of the task.
+    Holder,
+    /// The `TaskPool` itself, with the `nightly` feature the future type can be named through a
+    /// `type_alias_impl_trait`.
+    TaskPool,
+}
+
+impl PoolStatic {
+    fn from_type_name(name: &str) -> Option<Self> {
+        if name.starts_with("TaskPoolHolder") {
+            Some(Self::Holder)
+        } else if name.starts_with("TaskPool<") {
+            Some(Self::TaskPool)
+        } else {
+            None
+        }
+    }
+}
+
+/// The state of a task according to its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TaskRunState {
+    Uninit,
+    /// Spawned and waiting to be woken.
+    Spawned,
+    /// Woken and in the run queue, it gets polled the next time the executor runs.
+    Queued,
+}
+
 #[derive(Debug)]
 pub(crate) enum TaskValue {
     Uninit,
-    Init(FutureValue),
+    /// A spawned task, the run state is never [`TaskRunState::Uninit`].
+    Init(FutureValue, TaskRunState),
 }
 
 #[derive(Debug)]
@@ -279,6 +514,9 @@ pub(crate) struct TaskPoolValue {
     pub(crate) task_pool: TaskPool,
 
     pub(crate) task_values: Vec<TaskValue>,
+    /// How often each spawned task was polled, when the executor counts it, see
+    /// [`POLL_COUNT_FIELDS`].
+    pub(crate) poll_counts: Vec<Option<u64>>,
 }
 
 impl TaskPoolValue {
@@ -289,31 +527,205 @@ impl TaskPoolValue {
     ) -> Self {
         assert_eq!(bytes.len() as u64, task_pool.size);
         let mut task_values = Vec::new();
+        let mut poll_counts = Vec::new();
 
-        let len_single_task = task_pool.size / task_pool.number_of_tasks as u64;
+        let len_single_task = task_pool.size / task_pool.number_of_tasks.max(1) as u64;
 
         for task in 0..task_pool.number_of_tasks {
             let task_offset = len_single_task as usize * task;
 
             let bytes = &bytes[task_offset..];
 
-            let task_value = if task_pool.header_layout.is_init(bytes) {
+            let run_state = task_pool.header_layout.task_state(bytes);
+            let task_value = if run_state != TaskRunState::Uninit {
                 let bytes = &bytes[task_pool.future_offset as usize..];
-
-                TaskValue::Init(FutureValue::async_fn(
-                    &task_pool.async_fn_base_type,
-                    AsyncFnValue::new(&task_pool.async_fn_type, bytes, future_types),
-                ))
+                let address = task_pool.address + task_offset as u64 + task_pool.future_offset;
+
+                TaskValue::Init(
+                    FutureValue::async_fn(
+                        &task_pool.async_fn_base_type,
+                        address,
+                        AsyncFnValue::new(&task_pool.async_fn_type, address, bytes, future_types),
+                    ),
+                    run_state,
+                )
             } else {
                 TaskValue::Uninit
             };
 
-            task_values.push(task_value)
+            task_values.push(task_value);
+            poll_counts.push(
+                (run_state != TaskRunState::Uninit)
+                    .then(|| task_pool.header_layout.poll_count(bytes))
+                    .flatten(),
+            );
         }
 
         Self {
             task_pool: task_pool.clone(),
             task_values,
+            poll_counts,
         }
     }
+
+    /// Number of tasks currently spawned in this pool.
+    pub(crate) fn spawned_count(&self) -> usize {
+        self.task_values
+            .iter()
+            .filter(|task| matches!(task, TaskValue::Init(..)))
+            .count()
+    }
+
+    /// Whether every task of the pool is spawned, spawning another one fails until one of them
+    /// returns.
+    pub(crate) fn is_full(&self) -> bool {
+        self.spawned_count() >= self.task_pool.pool_size()
+    }
+
+    /// How often the task at `task_idx` was polled, `None` if it is not spawned or the executor does
+    /// not count polls.
+    pub(crate) fn poll_count(&self, task_idx: usize) -> Option<u64> {
+        self.poll_counts.get(task_idx).copied().flatten()
+    }
+
+    /// Label showing the usage of the pool, like `spawned 1 / pool_size 4`.
+    pub(crate) fn usage_label(&self) -> String {
+        format!(
+            "spawned {} / pool_size {}",
+            self.spawned_count(),
+            self.task_pool.pool_size()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_generic_arguments() {
+        assert_eq!(
+            generic_arguments("TaskPool<app::__blink_task::{async_fn_env#0}, 1>"),
+            Some(vec!["app::__blink_task::{async_fn_env#0}", "1"])
+        );
+        assert_eq!(
+            generic_arguments("TaskPool<app::__blink_task<3, u8>::{async_fn_env#0}, 2>"),
+            Some(vec!["app::__blink_task<3, u8>::{async_fn_env#0}", "2"])
+        );
+        assert_eq!(
+            generic_arguments("Wrapper<fn(u8, u16) -> u8, [u8; 4]>"),
+            Some(vec!["fn(u8, u16) -> u8", "[u8; 4]"])
+        );
+        assert_eq!(generic_arguments("TaskPool"), None);
+    }
+
+    #[test]
+    fn path_matching() {
+        let task_name = "app::__blink_task";
+        assert!(is_in_path("app::__blink_task::{async_fn_env#0}", task_name));
+        assert!(is_in_path(
+            "app::__blink_task<3>::{async_fn_env#0}",
+            task_name
+        ));
+        assert!(is_in_path("app::__blink_task", task_name));
+        assert!(!is_in_path(
+            "app::__blink_task_fast::{async_fn_env#0}",
+            task_name
+        ));
+    }
+
+    #[test]
+    fn pool_statics() {
+        assert_eq!(
+            PoolStatic::from_type_name("TaskPoolHolder<176, 4>"),
+            Some(PoolStatic::Holder)
+        );
+        assert_eq!(
+            PoolStatic::from_type_name("TaskPool<app::__blink_task::{async_fn_env#0}, 1>"),
+            Some(PoolStatic::TaskPool)
+        );
+        assert_eq!(PoolStatic::from_type_name("TaskStorage<u8>"), None);
+    }
+
+    #[test]
+    fn main_task() {
+        assert!(is_main_task_path("app::__embassy_main"));
+        assert!(!is_main_task_path("app::main"));
+        assert!(!is_main_task_path("app::__embassy_main_loop"));
+        assert!(!is_main_task_path("app::__embassy_main::inner"));
+    }
+
+    #[test]
+    fn task_run_states() {
+        let u8_layout = HeaderLayout {
+            state_offset: 1,
+            state_type: StateType::U8,
+            poll_count: None,
+        };
+        assert_eq!(u8_layout.task_state(&[0xff, 0b00]), TaskRunState::Uninit);
+        assert_eq!(u8_layout.task_state(&[0xff, 0b01]), TaskRunState::Spawned);
+        assert_eq!(u8_layout.task_state(&[0xff, 0b11]), TaskRunState::Queued);
+
+        let u32_layout = HeaderLayout {
+            state_offset: 0,
+            state_type: StateType::U32,
+            poll_count: None,
+        };
+        let state = |state: u32| u32_layout.task_state(&state.to_ne_bytes());
+        assert_eq!(state(0), TaskRunState::Uninit);
+        assert_eq!(state(0b0_0000_0001), TaskRunState::Spawned);
+        assert_eq!(state(0b1_0000_0001), TaskRunState::Queued);
+    }
+
+    #[test]
+    fn poll_counts() {
+        let layout = HeaderLayout {
+            state_offset: 0,
+            state_type: StateType::U8,
+            poll_count: Some((4, 4)),
+        };
+        let mut bytes = vec![0b01, 0, 0, 0];
+        bytes.extend(1234u32.to_ne_bytes());
+        assert_eq!(layout.poll_count(&bytes), Some(1234));
+
+        let without_counter = HeaderLayout {
+            poll_count: None,
+            ..layout
+        };
+        assert_eq!(without_counter.poll_count(&bytes), None);
+    }
+
+    #[test]
+    fn empty_snapshot_pool() {
+        let pool = TaskPool::from_snapshot(String::from("app::worker"), 0);
+        assert_eq!(pool.task_memory(0), (0, 0));
+
+        let value = TaskPoolValue::new(&pool, &[], &HashMap::new());
+        assert!(value.task_values.is_empty());
+    }
+
+    #[test]
+    fn pool_statics_from_fixtures() {
+        let pool = |fixture: &str, path: &str| {
+            let debug_data = crate::model::fixtures::debug_data(fixture);
+            let pool = debug_data.task_pools.iter().find(|pool| pool.path == path);
+            pool.unwrap_or_else(|| panic!("{path} is not a task pool in {fixture}"))
+                .clone()
+        };
+
+        // A `TaskPoolHolder<80, 8>` at 0x202920 according to `llvm-dwarfdump`.
+        let holder = pool("app.elf", "app::worker");
+        assert_eq!((holder.address, holder.size), (0x202920, 0x50));
+        assert_eq!(holder.number_of_tasks, 2);
+        assert_eq!(holder.task_name, "app::__worker_task");
+
+        // The `TaskPool<nightly::__blink_task::{async_fn_env#0}, 2>` itself at 0x202770.
+        let task_pool = pool("nightly.elf", "nightly::blink");
+        assert_eq!((task_pool.address, task_pool.size), (0x202770, 0x50));
+        assert_eq!(task_pool.number_of_tasks, 2);
+        assert_eq!(
+            task_pool.async_fn_base_type.to_string(),
+            "nightly::__blink_task::{async_fn_env#0}"
+        );
+    }
 }
@@ -1,6 +1,7 @@
 //! Identifier for some type on the target program.
 
 use ddbug_parser::{FileHash, TypeKind};
+use serde::{Deserialize, Serialize};
 
 use super::from_namespace_and_name;
 
@@ -12,7 +13,7 @@ use super::from_namespace_and_name;
 /// the reconstruction on the GDB side.
 ///
 /// All other types are just stored as the name of the types.
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Type {
     #[default]
     Unknown,
@@ -26,6 +27,17 @@ pub enum Type {
     Refrence(Box<Type>),
     /// Any type that has a clear name
     Base(String),
+    /// Fieldless enum, with the name of the type and the discriminant and name of each variant.
+    Enum {
+        name: String,
+        variants: Vec<(u64, String)>,
+    },
+    /// Tuple like `(u32, bool)`, with the types of its elements in order.
+    ///
+    /// GDB can't look these up by name, so they are formatted as a [`BuiltinType`] instead.
+    ///
+    /// [`BuiltinType`]: super::builtin::BuiltinType
+    Tuple(Vec<Type>),
 }
 
 impl std::fmt::Display for Type {
@@ -36,7 +48,21 @@ impl std::fmt::Display for Type {
             Type::Array { inner, count } => write!(f, "[{inner}; {count}]"),
             Type::Pointer(inner) => write!(f, "*{inner}"),
             Type::Refrence(inner) => write!(f, "&{inner}"),
-            Type::Base(name) => write!(f, "{name}"),
+            Type::Base(name) | Type::Enum { name, .. } => write!(f, "{name}"),
+            Type::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                // A tuple with a single element is written as `(T,)`.
+                if elements.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
@@ -58,15 +84,34 @@ impl Type {
             TypeKind::Def(type_def) => {
                 Self::from_namespace_and_name(type_def.namespace(), type_def.name())
             }
-            TypeKind::Struct(struct_type) => {
-                Self::from_namespace_and_name(struct_type.namespace(), struct_type.name())
-            }
+            TypeKind::Struct(struct_type) => match tuple_members(struct_type) {
+                Some(members) => Self::Tuple(
+                    members
+                        .iter()
+                        .map(|member| Self::from_maybe_ddbug_type(member.ty(file_hash), file_hash))
+                        .collect(),
+                ),
+                None => Self::from_namespace_and_name(struct_type.namespace(), struct_type.name()),
+            },
             TypeKind::Union(union_type) => {
                 Self::from_namespace_and_name(union_type.namespace(), union_type.name())
             }
-            TypeKind::Enumeration(enumeration_type) => {
-                Self::from_namespace_and_name(enumeration_type.namespace(), enumeration_type.name())
-            }
+            TypeKind::Enumeration(enumeration_type) => Self::Enum {
+                name: from_namespace_and_name(
+                    enumeration_type.namespace(),
+                    enumeration_type.name(),
+                ),
+                variants: enumeration_type
+                    .enumerators(file_hash)
+                    .iter()
+                    .map(|enumerator| {
+                        (
+                            enumerator.value() as u64,
+                            enumerator.name().unwrap_or("<unknown>").to_owned(),
+                        )
+                    })
+                    .collect(),
+            },
             TypeKind::Array(array_type) => {
                 let inner =
                     Self::from_maybe_ddbug_type(array_type.element_type(file_hash), file_hash);
@@ -146,3 +191,25 @@ impl Type {
             .unwrap_or_default()
     }
 }
+
+/// The members of a tuple in the order of its elements, `None` if the struct is not a tuple.
+///
+/// Rustc emits tuples as structs named like `(u32, bool)` with the members `__0`, `__1`, etc.
+pub(crate) fn tuple_members<'a, 'input>(
+    struct_type: &'a ddbug_parser::StructType<'input>,
+) -> Option<Vec<&'a ddbug_parser::Member<'input>>> {
+    if !struct_type.name()?.starts_with('(') {
+        return None;
+    }
+
+    let mut members = struct_type
+        .members()
+        .iter()
+        .map(|member| {
+            let index = member.name()?.strip_prefix("__")?.parse::<usize>().ok()?;
+            Some((index, member))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    members.sort_by_key(|(index, _)| *index);
+    Some(members.into_iter().map(|(_, member)| member).collect())
+}
@@ -0,0 +1,125 @@
+//! Reducing the colors of a drawn frame to the 16 ANSI colors, for terminals like the GDB TUI that
+//! show RGB and 256 colors as garbage.
+//!
+//! The colors can come from anywhere, like the ANSI escape codes in values formatted by the
+//! backend, so they are mapped once the whole frame is drawn.
+
+use ratatui::{buffer::Buffer, style::Color};
+
+/// Which colors the terminal can show.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum ColorPalette {
+    /// All colors are shown as they are.
+    #[default]
+    Full,
+    /// RGB and 256 colors are replaced by the nearest of the 16 ANSI colors.
+    Ansi16,
+}
+
+impl std::str::FromStr for ColorPalette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "16" => Ok(Self::Ansi16),
+            _ => Err(format!("unknown value `{s}`, expected full or 16")),
+        }
+    }
+}
+
+/// The 16 ANSI colors in the order of their index, with the values xterm uses for them.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (128, 0, 0)),
+    (Color::Green, (0, 128, 0)),
+    (Color::Yellow, (128, 128, 0)),
+    (Color::Blue, (0, 0, 128)),
+    (Color::Magenta, (128, 0, 128)),
+    (Color::Cyan, (0, 128, 128)),
+    (Color::Gray, (192, 192, 192)),
+    (Color::DarkGray, (128, 128, 128)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (0, 0, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The levels of the red, green and blue components of the 6x6x6 color cube of the 256 colors.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The RGB value of one of the 256 colors.
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..16 => ANSI_COLORS[index as usize].1,
+        16..232 => {
+            let cube = index - 16;
+            (
+                CUBE_LEVELS[(cube / 36) as usize],
+                CUBE_LEVELS[(cube / 6 % 6) as usize],
+                CUBE_LEVELS[(cube % 6) as usize],
+            )
+        }
+        232.. => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// The ANSI color nearest to `color`, the other colors are returned as they are.
+pub(crate) fn nearest_ansi_color(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(index) => indexed_to_rgb(index),
+        color => return color,
+    };
+
+    let distance = |(ar, ag, ab): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(ar, r) + d(ag, g) + d(ab, b)
+    };
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+/// Replace the colors of all cells in `buffer` that are not one of the ANSI colors.
+pub(crate) fn limit_to_ansi_colors(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        cell.fg = nearest_ansi_color(cell.fg);
+        cell.bg = nearest_ansi_color(cell.bg);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nearest_colors() {
+        assert_eq!(nearest_ansi_color(Color::Rgb(250, 10, 20)), Color::LightRed);
+        assert_eq!(
+            nearest_ansi_color(Color::Rgb(100, 100, 110)),
+            Color::DarkGray
+        );
+        assert_eq!(nearest_ansi_color(Color::Rgb(0, 0, 0)), Color::Black);
+
+        assert_eq!(nearest_ansi_color(Color::Indexed(1)), Color::Red);
+        // 1 red, 0 green and 5 blue in the color cube.
+        assert_eq!(
+            nearest_ansi_color(Color::Indexed(16 + 36 + 5)),
+            Color::LightBlue
+        );
+        // The lightest gray of the gray ramp.
+        assert_eq!(nearest_ansi_color(Color::Indexed(255)), Color::White);
+
+        assert_eq!(nearest_ansi_color(Color::Reset), Color::Reset);
+        assert_eq!(nearest_ansi_color(Color::Cyan), Color::Cyan);
+    }
+}
@@ -0,0 +1,104 @@
+//! Estimation of how long futures have been waiting at their current await point.
+//!
+//! The time is only measured when the target stops, so it is the time since the first stop at
+//! which the future was seen in its current state.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::model::{
+    future::{FutureValue, FutureValueKind},
+    task_pool::{TaskPoolValue, TaskValue},
+};
+
+/// Identifies a future by the path of its task pool, the index of the task in the pool and the
+/// path ids from the future of the task down to it.
+type FutureKey = (String, usize, Vec<u64>);
+
+/// When the state of every async fn future last changed.
+#[derive(Debug, Default)]
+pub(crate) struct ParkedTimes {
+    since: HashMap<FutureKey, (u64, Instant)>,
+}
+
+impl ParkedTimes {
+    /// Record the states of the newly read values, futures that are gone are forgotten.
+    pub(crate) fn update(&mut self, values: &[TaskPoolValue], now: Instant) {
+        let mut since = HashMap::new();
+
+        for pool in values {
+            for (task_idx, task) in pool.task_values.iter().enumerate() {
+                if let TaskValue::Init(value, _) = task {
+                    let mut key = (pool.task_pool.path.clone(), task_idx, Vec::new());
+                    self.update_future(value, &mut key, now, &mut since);
+                }
+            }
+        }
+
+        self.since = since;
+    }
+
+    fn update_future(
+        &self,
+        value: &FutureValue,
+        key: &mut FutureKey,
+        now: Instant,
+        since: &mut HashMap<FutureKey, (u64, Instant)>,
+    ) {
+        if let FutureValueKind::AsyncFn(async_fn) = &value.kind
+            && let Ok(state) = &async_fn.state_value
+        {
+            let discriminant = state.discriminant;
+            let changed_at = match self.since.get(key) {
+                Some((last, changed_at)) if *last == discriminant => *changed_at,
+                _ => now,
+            };
+            since.insert(key.clone(), (discriminant, changed_at));
+        }
+
+        for (path_id, child) in value.children() {
+            key.2.push(path_id);
+            self.update_future(child, key, now, since);
+            key.2.pop();
+        }
+    }
+
+    /// How long the future has been in its current state, `None` if it is not an async fn.
+    pub(crate) fn parked_for(
+        &self,
+        pool_path: &str,
+        task_idx: usize,
+        path: &[u64],
+        now: Instant,
+    ) -> Option<Duration> {
+        let (_, changed_at) = self
+            .since
+            .get(&(pool_path.to_owned(), task_idx, path.to_vec()))?;
+        Some(now.saturating_duration_since(*changed_at))
+    }
+}
+
+/// Format the time a future has been parked, only as precise as the stops allow.
+pub(crate) fn format_parked(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("~{:.1}s", duration.as_secs_f64()),
+        60..3600 => format!("~{}m{}s", secs / 60, secs % 60),
+        _ => format!("~{}h{}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parked_formatting() {
+        assert_eq!(format_parked(Duration::from_millis(3200)), "~3.2s");
+        assert_eq!(format_parked(Duration::from_millis(40)), "~0.0s");
+        assert_eq!(format_parked(Duration::from_secs(125)), "~2m5s");
+        assert_eq!(format_parked(Duration::from_secs(7380)), "~2h3m");
+    }
+}
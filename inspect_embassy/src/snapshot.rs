@@ -0,0 +1,566 @@
+//! The state of all tasks as plain data, for backends exposing it to other tools as JSON.
+//!
+//! These structs are separate from the models so the JSON keeps the same shape when the models
+//! change, scripts and regression tests can depend on it. An exported snapshot can be loaded again
+//! with [`EmbassyInspector::from_snapshot`](crate::EmbassyInspector::from_snapshot), which rebuilds
+//! the values from it without the debug data.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BreakpointKind, Callback, FutureByAddress,
+    model::{
+        Source,
+        async_fn::{AsyncFnType, AsyncFnValue, Member, MemberValue, State, StateValue},
+        embassy_sync::SyncFutureKind,
+        future::{FutureValue, FutureValueKind, JoinValue, OptionValue, SelectValue},
+        task_pool::{TaskPool, TaskPoolValue, TaskRunState, TaskValue},
+        ty::Type,
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    task_pools: Vec<PoolSnapshot>,
+    /// The futures outside of the task pools, from statics or added by address.
+    futures: Vec<FutureSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PoolSnapshot {
+    path: String,
+    pool_size: usize,
+    tasks: Vec<TaskSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TaskSnapshot {
+    index: usize,
+    /// `uninit`, `spawned` or `queued`.
+    run_state: String,
+    /// How often the task was polled, only when the executor counts it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poll_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    future: Option<FutureSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FutureSnapshot {
+    #[serde(rename = "type")]
+    ty: String,
+    address: u64,
+    /// `async_fn`, `select`, `join`, `option`, `fuse`, `shared`, `timer`, `receive`, `send`,
+    /// `signal`, `output` or `unknown`.
+    kind: String,
+    /// The name of the state of an async fn, or `done` for a select that already completed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    discriminant: Option<u64>,
+    /// Where in the source the async fn is waiting, as `path:line:column`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    /// The members of the current state of an async fn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    members: Vec<MemberSnapshot>,
+    /// The formatted output of a finished join arm.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    /// The raw bytes of the output of a finished join arm or of a future without special handling.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bytes: Vec<u8>,
+    /// The formatted instant a timer expires at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<String>,
+    /// What the channel or signal of an embassy-sync future holds, like `2/4 queued`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sync_state: Option<String>,
+    /// The futures this one is waiting on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    awaiting: Vec<FutureSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MemberSnapshot {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    value: String,
+    /// The raw bytes of the member, for values that are not formatted.
+    bytes: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Capture the values, `format` formats the bytes of a value as they are shown in the TUI.
+    pub(crate) fn new(
+        values: &[TaskPoolValue],
+        futures_by_address: &[FutureByAddress],
+        mut format: impl FnMut(&[u8], &Type) -> String,
+    ) -> Self {
+        let task_pools = values
+            .iter()
+            .map(|pool| PoolSnapshot {
+                path: pool.task_pool.path.clone(),
+                pool_size: pool.task_pool.pool_size(),
+                tasks: pool
+                    .task_values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, task)| match task {
+                        TaskValue::Uninit => TaskSnapshot {
+                            index,
+                            run_state: "uninit".into(),
+                            poll_count: None,
+                            future: None,
+                        },
+                        TaskValue::Init(future, run_state) => TaskSnapshot {
+                            index,
+                            run_state: match run_state {
+                                TaskRunState::Uninit => "uninit",
+                                TaskRunState::Spawned => "spawned",
+                                TaskRunState::Queued => "queued",
+                            }
+                            .into(),
+                            poll_count: pool.poll_count(index),
+                            future: Some(FutureSnapshot::new(future, &mut format)),
+                        },
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let futures = futures_by_address
+            .iter()
+            .filter_map(|future| future.value.as_ref().ok())
+            .map(|future| FutureSnapshot::new(future, &mut format))
+            .collect();
+
+        Self {
+            task_pools,
+            futures,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("snapshots only contain strings and numbers")
+    }
+
+    pub(crate) fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("snapshots only contain strings and numbers")
+    }
+
+    pub(crate) fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Rebuild the values the snapshot was made from, as far as they are in it.
+    ///
+    /// Every type becomes a [`Type::Base`] with the name from the snapshot, async fns only know
+    /// their current state and timers, shared futures and the futures of embassy-sync become
+    /// futures without special handling.
+    pub(crate) fn into_values(self) -> SnapshotValues {
+        let mut formatted = HashMap::new();
+
+        let task_pools = self
+            .task_pools
+            .into_iter()
+            .map(|pool| {
+                let mut task_values = (0..pool.pool_size)
+                    .map(|_| TaskValue::Uninit)
+                    .collect::<Vec<_>>();
+                let mut poll_counts = vec![None; pool.pool_size];
+                for task in pool.tasks {
+                    let run_state = match task.run_state.as_str() {
+                        "queued" => TaskRunState::Queued,
+                        _ => TaskRunState::Spawned,
+                    };
+                    if let (Some(future), Some(task_value)) =
+                        (task.future, task_values.get_mut(task.index))
+                    {
+                        *task_value = TaskValue::Init(future.into_value(&mut formatted), run_state);
+                        poll_counts[task.index] = task.poll_count;
+                    }
+                }
+
+                TaskPoolValue {
+                    task_pool: TaskPool::from_snapshot(pool.path, pool.pool_size),
+                    task_values,
+                    poll_counts,
+                }
+            })
+            .collect();
+
+        let futures = self
+            .futures
+            .into_iter()
+            .map(|future| future.into_value(&mut formatted))
+            .collect();
+
+        SnapshotValues {
+            task_pools,
+            futures,
+            formatted,
+        }
+    }
+}
+
+/// The values rebuilt from a [`Snapshot`].
+pub(crate) struct SnapshotValues {
+    pub(crate) task_pools: Vec<TaskPoolValue>,
+    pub(crate) futures: Vec<FutureValue>,
+    /// The values as they were formatted when exporting, by their bytes and type.
+    pub(crate) formatted: HashMap<(Vec<u8>, Type), String>,
+}
+
+impl FutureSnapshot {
+    fn new(value: &FutureValue, format: &mut impl FnMut(&[u8], &Type) -> String) -> Self {
+        let mut snapshot = Self {
+            ty: value.ty.to_string(),
+            address: value.address,
+            kind: "unknown".into(),
+            state: None,
+            discriminant: None,
+            source: None,
+            members: Vec::new(),
+            output: None,
+            bytes: Vec::new(),
+            expires_at: None,
+            sync_state: None,
+            awaiting: value
+                .children()
+                .into_iter()
+                .map(|(_, child)| Self::new(child, format))
+                .collect(),
+        };
+
+        match &value.kind {
+            FutureValueKind::AsyncFn(async_fn) => {
+                snapshot.kind = "async_fn".into();
+                match &async_fn.state_value {
+                    Ok(state) => {
+                        snapshot.state = Some(state.state.name.clone());
+                        snapshot.discriminant = Some(state.discriminant);
+                        snapshot.source = state.state.source.as_ref().map(ToString::to_string);
+                        snapshot.members = state
+                            .members
+                            .iter()
+                            .map(|member| MemberSnapshot {
+                                name: member.member.name.clone(),
+                                ty: member.member.ty.to_string(),
+                                value: format(&member.bytes, &member.member.ty),
+                                bytes: member.bytes.clone(),
+                            })
+                            .collect();
+                    }
+                    Err((discriminant, _)) => snapshot.discriminant = *discriminant,
+                }
+            }
+            FutureValueKind::SelectValue(value) => {
+                snapshot.kind = "select".into();
+                snapshot.state = value.done.then(|| "done".into());
+            }
+            FutureValueKind::JoinValue(_) => snapshot.kind = "join".into(),
+            FutureValueKind::OptionValue(value) if value.fused => snapshot.kind = "fuse".into(),
+            FutureValueKind::OptionValue(_) => snapshot.kind = "option".into(),
+            FutureValueKind::SharedValue(_) => snapshot.kind = "shared".into(),
+            FutureValueKind::TimerValue(timer) => {
+                snapshot.kind = "timer".into();
+                snapshot.expires_at = Some(format(&timer.expires_at, &timer.expires_at_type));
+            }
+            FutureValueKind::EmbassySyncValue(value) => {
+                snapshot.kind = match value.kind {
+                    SyncFutureKind::Receive => "receive",
+                    SyncFutureKind::Send => "send",
+                    SyncFutureKind::Signal => "signal",
+                }
+                .into();
+                snapshot.sync_state = value.state.map(|state| state.to_string());
+            }
+            FutureValueKind::Output(bytes) => {
+                snapshot.kind = "output".into();
+                snapshot.output = Some(format(bytes, &value.ty));
+                snapshot.bytes = bytes.clone();
+            }
+            FutureValueKind::Unknown(bytes) => snapshot.bytes = bytes.clone(),
+        }
+
+        snapshot
+    }
+
+    fn into_value(self, formatted: &mut HashMap<(Vec<u8>, Type), String>) -> FutureValue {
+        let ty = Type::Base(self.ty);
+        let mut awaiting = self
+            .awaiting
+            .into_iter()
+            .map(|child| child.into_value(formatted))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        let kind = match self.kind.as_str() {
+            "async_fn" => FutureValueKind::AsyncFn(match self.state {
+                Some(name) => async_fn_value(
+                    name,
+                    self.discriminant.unwrap_or_default(),
+                    self.source.as_deref().map(parse_source),
+                    self.members,
+                    awaiting.next(),
+                    formatted,
+                ),
+                None => AsyncFnValue {
+                    ty: AsyncFnType::default(),
+                    state_value: Err((self.discriminant, Vec::new())),
+                },
+            }),
+            "select" => {
+                let awaitees = awaiting.collect::<Box<[_]>>();
+                // The resolved arm is exported as its output.
+                let resolved = awaitees
+                    .iter()
+                    .position(|awaitee| matches!(awaitee.kind, FutureValueKind::Output(_)));
+                FutureValueKind::SelectValue(SelectValue {
+                    awaitees,
+                    resolved,
+                    done: resolved.is_some() || self.state.as_deref() == Some("done"),
+                })
+            }
+            "join" => FutureValueKind::JoinValue(JoinValue {
+                awaitees: awaiting.collect(),
+            }),
+            "option" | "fuse" => FutureValueKind::OptionValue(OptionValue {
+                awaitee: awaiting.next().map(Box::new),
+                fused: self.kind == "fuse",
+            }),
+            "output" => {
+                if let Some(output) = self.output {
+                    formatted.insert((self.bytes.clone(), ty.clone()), output);
+                }
+                FutureValueKind::Output(self.bytes)
+            }
+            _ => FutureValueKind::Unknown(self.bytes),
+        };
+
+        FutureValue {
+            ty,
+            address: self.address,
+            kind,
+        }
+    }
+}
+
+/// An async fn with a single state, with the members laid out one after the other.
+fn async_fn_value(
+    name: String,
+    discriminant: u64,
+    source: Option<Source>,
+    members: Vec<MemberSnapshot>,
+    awaitee: Option<FutureValue>,
+    formatted: &mut HashMap<(Vec<u8>, Type), String>,
+) -> AsyncFnValue {
+    let mut offset = 0;
+    let members = members
+        .into_iter()
+        .map(|member| {
+            let ty = Type::Base(member.ty);
+            let size = member.bytes.len() as u64;
+            formatted.insert((member.bytes.clone(), ty.clone()), member.value);
+
+            let value = MemberValue {
+                member: Member {
+                    name: member.name,
+                    ty,
+                    offset,
+                    size,
+                },
+                bytes: member.bytes,
+            };
+            offset += size;
+            value
+        })
+        .collect::<Vec<_>>();
+
+    let state = State {
+        discriminant_value: Some(discriminant),
+        active_members: (0..members.len()).collect(),
+        awaitee: awaitee.as_ref().map(|awaitee| Member {
+            name: String::from("__awaitee"),
+            ty: awaitee.ty.clone(),
+            offset,
+            size: awaitee.size(),
+        }),
+        name,
+        source,
+    };
+
+    AsyncFnValue {
+        ty: AsyncFnType {
+            members: members.iter().map(|member| member.member.clone()).collect(),
+            state_member: Member::default(),
+            total_size: offset + awaitee.as_ref().map_or(0, FutureValue::size),
+            states: vec![state.clone()],
+        },
+        state_value: Ok(StateValue {
+            state,
+            discriminant,
+            members,
+            awaitee: awaitee.map(Box::new),
+        }),
+    }
+}
+
+/// Parse a [`Source`] from its `path:line:column` form, the line and column are left out when
+/// they are zero.
+fn parse_source(text: &str) -> Source {
+    let mut path = text;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2
+        && let Some((rest, number)) = path.rsplit_once(':')
+        && let Ok(number) = number.parse::<u32>()
+    {
+        numbers.insert(0, number);
+        path = rest;
+    }
+
+    Source {
+        path: path.to_owned(),
+        line: numbers.first().copied().unwrap_or_default(),
+        column: numbers.get(1).copied().unwrap_or_default(),
+    }
+}
+
+/// The [`Callback`] for an inspector showing a snapshot, see
+/// [`EmbassyInspector::from_snapshot`](crate::EmbassyInspector::from_snapshot).
+///
+/// There is no target, so setting breakpoints, resuming and reading memory fail.
+#[derive(Debug, Default)]
+pub struct SnapshotCallback;
+
+impl Callback for SnapshotCallback {
+    fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+        Ok(std::iter::empty())
+    }
+
+    fn set_breakpoint(&mut self, _addr: u64, _kind: BreakpointKind) -> Result<u64> {
+        bail!("A snapshot has no target to set breakpoints on")
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        bail!("A snapshot has no target to resume")
+    }
+
+    fn read_memory(&mut self, _addr: u64, _len: u64) -> Result<Vec<u8>> {
+        bail!("A snapshot has no target to read memory from")
+    }
+
+    fn try_format_value(&mut self, _bytes: &[u8], _ty: &Type) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::model::future::JoinValue;
+
+    #[test]
+    fn join_json() {
+        let future = |kind| FutureValue {
+            ty: Type::Base(String::from("u32")),
+            address: 0x104,
+            kind,
+        };
+        let join = FutureValue {
+            ty: Type::Base(String::from("Join")),
+            address: 0x100,
+            kind: FutureValueKind::JoinValue(JoinValue {
+                awaitees: Box::new([
+                    future(FutureValueKind::Unknown(vec![0; 4])),
+                    future(FutureValueKind::Output(vec![7, 0, 0, 0])),
+                ]),
+            }),
+        };
+
+        let snapshot = FutureSnapshot::new(&join, &mut |bytes, _| format!("{}", bytes[0]));
+        assert_eq!(
+            serde_json::to_string(&snapshot).unwrap(),
+            concat!(
+                r#"{"type":"Join","address":256,"kind":"join","awaiting":["#,
+                r#"{"type":"u32","address":260,"kind":"unknown","bytes":[0,0,0,0]},"#,
+                r#"{"type":"u32","address":260,"kind":"output","output":"7","bytes":[7,0,0,0]}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn rebuild_values() {
+        let json = concat!(
+            r#"{"task_pools":[{"path":"app::blink","pool_size":2,"tasks":["#,
+            r#"{"index":0,"run_state":"uninit"},"#,
+            r#"{"index":1,"run_state":"queued","future":{"type":"{async_fn_env#0}","#,
+            r#""address":256,"kind":"async_fn","state":"Suspend0","discriminant":3,"#,
+            r#""source":"src/main.rs:12:5","members":[{"name":"led","type":"u8","value":"on","bytes":[1]}],"#,
+            r#""awaiting":[{"type":"Timer","address":264,"kind":"timer","expires_at":"1s"}]}}]}],"#,
+            r#""futures":[]}"#
+        );
+        let values = serde_json::from_str::<Snapshot>(json)
+            .unwrap()
+            .into_values();
+
+        let [pool] = &values.task_pools[..] else {
+            panic!("expected a single pool");
+        };
+        assert_eq!(pool.task_pool.path, "app::blink");
+        assert!(matches!(pool.task_values[0], TaskValue::Uninit));
+        let TaskValue::Init(future, TaskRunState::Queued) = &pool.task_values[1] else {
+            panic!("expected a queued task");
+        };
+        let FutureValueKind::AsyncFn(AsyncFnValue {
+            state_value: Ok(state),
+            ..
+        }) = &future.kind
+        else {
+            panic!("expected an async fn in a known state");
+        };
+        assert_eq!(state.state.name, "Suspend0");
+        assert_eq!(state.discriminant, 3);
+        assert_eq!(
+            state.state.source,
+            Some(Source {
+                path: String::from("src/main.rs"),
+                line: 12,
+                column: 5,
+            })
+        );
+        assert_eq!(state.members[0].bytes, [1]);
+        assert!(matches!(
+            state.awaitee.as_deref(),
+            Some(FutureValue {
+                address: 264,
+                kind: FutureValueKind::Unknown(_),
+                ..
+            })
+        ));
+        assert_eq!(
+            values
+                .formatted
+                .get(&(vec![1], Type::Base(String::from("u8")))),
+            Some(&String::from("on"))
+        );
+    }
+
+    #[test]
+    fn source_round_trip() {
+        for source in [
+            "src/main.rs:12:5",
+            "src/main.rs:12",
+            "src/main.rs",
+            "C:\\src\\main.rs:3:1",
+        ] {
+            assert_eq!(parse_source(source).to_string(), source);
+        }
+    }
+}
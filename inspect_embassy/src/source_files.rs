@@ -0,0 +1,110 @@
+//! Reading the lines of source code around the await points of async fns, to show them in the
+//! details of a future.
+//!
+//! The paths come from the debug data, after [`Config::source_path_remaps`](
+//! crate::Config::source_path_remaps). They often don't exist on the debugging host, like for
+//! firmware built on CI, which is shown instead of the code.
+
+use std::collections::HashMap;
+
+use crate::model::Source;
+
+/// The source files read so far, files are read once and kept for the rest of the session.
+#[derive(Debug, Default)]
+pub(crate) struct SourceFiles {
+    /// The lines of every file by path, `None` if it could not be read.
+    files: HashMap<String, Option<Vec<String>>>,
+}
+
+impl SourceFiles {
+    /// The lines around `source` with their line numbers, `context` lines before and after the line
+    /// itself. `None` if the file can't be read or is shorter than the line.
+    pub(crate) fn context(&mut self, source: &Source, context: usize) -> Option<Vec<(u32, &str)>> {
+        let lines = self
+            .files
+            .entry(source.path.clone())
+            .or_insert_with_key(|path| read_lines(path))
+            .as_ref()?;
+
+        let range = context_range(source.line, context, lines.len())?;
+        Some(
+            range
+                .map(|idx| (idx as u32 + 1, lines[idx].as_str()))
+                .collect(),
+        )
+    }
+}
+
+fn read_lines(path: &str) -> Option<Vec<String>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Some(
+            String::from_utf8_lossy(&bytes)
+                .lines()
+                // Tabs have no width in the TUI.
+                .map(|line| line.replace('\t', "    "))
+                .collect(),
+        ),
+        Err(e) => {
+            log::debug!("Could not read source file {path}: {e}");
+            None
+        }
+    }
+}
+
+/// The indices of the lines to show around the 1-based `line` of a file with `len` lines.
+fn context_range(line: u32, context: usize, len: usize) -> Option<std::ops::Range<usize>> {
+    let idx = (line as usize).checked_sub(1)?;
+    if idx >= len {
+        return None;
+    }
+
+    Some(idx.saturating_sub(context)..(idx + context + 1).min(len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn context_ranges() {
+        assert_eq!(context_range(5, 2, 10), Some(2..7));
+        // Clamped to the start and end of the file.
+        assert_eq!(context_range(1, 2, 10), Some(0..3));
+        assert_eq!(context_range(10, 2, 10), Some(7..10));
+        assert_eq!(context_range(3, 0, 10), Some(2..3));
+        // Line 0 is an unknown line.
+        assert_eq!(context_range(0, 2, 10), None);
+        assert_eq!(context_range(11, 2, 10), None);
+    }
+
+    #[test]
+    fn read_context() {
+        let path = std::env::temp_dir().join("inspect-embassy-source-files-test.rs");
+        std::fs::write(&path, "fn main() {\n\tfoo().await;\n}\n").unwrap();
+
+        let mut files = SourceFiles::default();
+        let source = Source {
+            path: path.display().to_string(),
+            line: 2,
+            column: 5,
+        };
+        assert_eq!(
+            files.context(&source, 1),
+            Some(vec![(1, "fn main() {"), (2, "    foo().await;"), (3, "}")])
+        );
+
+        // The file is only read once.
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            files.context(&source, 0),
+            Some(vec![(2, "    foo().await;")])
+        );
+
+        let missing = Source {
+            path: String::from("/does/not/exist.rs"),
+            line: 1,
+            column: 0,
+        };
+        assert_eq!(files.context(&missing, 2), None);
+    }
+}
@@ -0,0 +1,249 @@
+//! Heuristic for finding tasks that might never get polled again, like a future that forgot to
+//! register its waker.
+//!
+//! A task that is spawned but not queued and that stayed in the same state of its async fn over
+//! many stops at which the executor did poll other tasks is probably waiting on something that will
+//! not wake it. This is only a hint, a task waiting on a long timer or a rare interrupt looks the
+//! same.
+
+use std::collections::HashMap;
+
+use crate::model::{
+    future::FutureValueKind,
+    task_pool::{TaskPoolValue, TaskRunState, TaskValue},
+};
+
+/// How long every spawned task has been in the same state.
+#[derive(Debug)]
+pub(crate) struct StuckTasks {
+    /// After how many unchanged stops a task is stuck, `None` to never mark a task.
+    threshold: Option<usize>,
+    /// The discriminant of the state of every waiting task by pool path and task index, with the
+    /// number of stops at which it was the same while other tasks got polled.
+    unchanged: HashMap<(String, usize), (u64, usize)>,
+    /// Total hits of the poll breakpoints at the last stop.
+    last_polls: u64,
+    /// Whether the executor polled something since the last stop.
+    executor_active: bool,
+}
+
+impl StuckTasks {
+    pub(crate) fn new(threshold: Option<usize>) -> Self {
+        Self {
+            threshold,
+            unchanged: HashMap::new(),
+            last_polls: 0,
+            executor_active: false,
+        }
+    }
+
+    /// Change after how many unchanged stops a task is stuck, the stops counted so far are kept.
+    pub(crate) fn set_threshold(&mut self, threshold: Option<usize>) {
+        self.threshold = threshold;
+    }
+
+    /// Start recording a stop, `polls` is the total number of hits of the poll breakpoints. Stops
+    /// without any polls since the last one don't count, an idle executor polls nothing.
+    pub(crate) fn start_stop(&mut self, polls: u64) {
+        self.executor_active = polls != self.last_polls;
+        self.last_polls = polls;
+    }
+
+    /// Record the tasks of a pool read at this stop.
+    pub(crate) fn update_pool(&mut self, pool: &TaskPoolValue) {
+        for (task_idx, task) in pool.task_values.iter().enumerate() {
+            let key = (pool.task_pool.path.clone(), task_idx);
+            // Only the states of async fns are known, the discriminant is also read for a state
+            // that isn't in the debug info.
+            let discriminant = match task {
+                TaskValue::Init(future, TaskRunState::Spawned) => match &future.kind {
+                    FutureValueKind::AsyncFn(async_fn) => match &async_fn.state_value {
+                        Ok(state) => Some(state.discriminant),
+                        Err((discriminant, _)) => *discriminant,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            };
+            let Some(discriminant) = discriminant else {
+                self.unchanged.remove(&key);
+                continue;
+            };
+
+            match self.unchanged.get_mut(&key) {
+                Some((last_discriminant, stops)) if *last_discriminant == discriminant => {
+                    if self.executor_active {
+                        *stops += 1;
+                    }
+                }
+                _ => {
+                    self.unchanged.insert(key, (discriminant, 0));
+                }
+            }
+        }
+    }
+
+    /// Whether the task has been in the same state for at least the threshold number of stops.
+    pub(crate) fn is_stuck(&self, pool_path: &str, task_idx: usize) -> bool {
+        let Some(threshold) = self.threshold else {
+            return false;
+        };
+        self.unchanged
+            .get(&(pool_path.to_owned(), task_idx))
+            .is_some_and(|(_, stops)| *stops >= threshold)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::model::{
+        async_fn::{AsyncFnType, AsyncFnValue, Member, State},
+        future::FutureValue,
+        task_pool::TaskPool,
+        ty::Type,
+    };
+
+    /// A pool with a single task in this run state, an async fn with its discriminant in the first
+    /// byte and a member in the second.
+    fn pool(run_state: TaskRunState, bytes: &[u8]) -> TaskPoolValue {
+        let member = |name: &str, offset| Member {
+            name: name.to_owned(),
+            ty: Type::Unknown,
+            offset,
+            size: 1,
+        };
+        let state = |name: &str, discriminant_value| State {
+            discriminant_value: Some(discriminant_value),
+            active_members: vec![0],
+            awaitee: None,
+            name: name.to_owned(),
+            source: None,
+        };
+        let ty = AsyncFnType {
+            members: vec![member("a", 1)],
+            state_member: member("__state", 0),
+            total_size: 2,
+            states: vec![state("Suspend0", 3), state("Suspend1", 4)],
+        };
+
+        let task = match run_state {
+            TaskRunState::Uninit => TaskValue::Uninit,
+            run_state => TaskValue::Init(
+                FutureValue {
+                    ty: Type::Unknown,
+                    address: 0,
+                    kind: FutureValueKind::AsyncFn(AsyncFnValue::new(
+                        &ty,
+                        0,
+                        bytes,
+                        &HashMap::new(),
+                    )),
+                },
+                run_state,
+            ),
+        };
+        TaskPoolValue {
+            task_pool: TaskPool::from_snapshot(String::from("app::worker"), 1),
+            task_values: vec![task],
+            poll_counts: vec![None],
+        }
+    }
+
+    #[test]
+    fn counts_unchanged_stops() {
+        let mut stuck = StuckTasks::new(Some(3));
+
+        // Only the state counts, not the members.
+        for polls in 1..=3 {
+            stuck.start_stop(polls);
+            stuck.update_pool(&pool(TaskRunState::Spawned, &[3, polls as u8]));
+            assert!(!stuck.is_stuck("app::worker", 0));
+        }
+        stuck.start_stop(4);
+        stuck.update_pool(&pool(TaskRunState::Spawned, &[3, 0]));
+        assert!(stuck.is_stuck("app::worker", 0));
+        assert!(!stuck.is_stuck("app::other", 0));
+
+        // A discriminant without a state in the debug info is counted as well.
+        let mut unknown = StuckTasks::new(Some(1));
+        for polls in 1..=2 {
+            unknown.start_stop(polls);
+            unknown.update_pool(&pool(TaskRunState::Spawned, &[7, 0]));
+        }
+        assert!(unknown.is_stuck("app::worker", 0));
+
+        let mut never = StuckTasks::new(None);
+        for polls in 1..=10 {
+            never.start_stop(polls);
+            never.update_pool(&pool(TaskRunState::Spawned, &[3, 0]));
+        }
+        assert!(!never.is_stuck("app::worker", 0));
+    }
+
+    #[test]
+    fn resets() {
+        let spawned = pool(TaskRunState::Spawned, &[3, 0]);
+        let stuck_after = |stuck: &mut StuckTasks| {
+            for polls in 1..=2 {
+                stuck.start_stop(polls);
+                stuck.update_pool(&spawned);
+            }
+            assert!(stuck.is_stuck("app::worker", 0));
+        };
+
+        // Another state starts counting again.
+        let mut stuck = StuckTasks::new(Some(1));
+        stuck_after(&mut stuck);
+        stuck.start_stop(3);
+        stuck.update_pool(&pool(TaskRunState::Spawned, &[4, 0]));
+        assert!(!stuck.is_stuck("app::worker", 0));
+
+        // Queued and uninitialized tasks are not waiting.
+        for run_state in [TaskRunState::Queued, TaskRunState::Uninit] {
+            let mut stuck = StuckTasks::new(Some(1));
+            stuck_after(&mut stuck);
+            stuck.start_stop(3);
+            stuck.update_pool(&pool(run_state, &[3, 0]));
+            assert!(!stuck.is_stuck("app::worker", 0));
+        }
+    }
+
+    #[test]
+    fn idle_executor() {
+        let mut stuck = StuckTasks::new(Some(1));
+        let spawned = pool(TaskRunState::Spawned, &[3, 0]);
+
+        stuck.start_stop(1);
+        stuck.update_pool(&spawned);
+        // Nothing got polled, like when halting an idle target by hand.
+        for _ in 0..5 {
+            stuck.start_stop(1);
+            stuck.update_pool(&spawned);
+        }
+        assert!(!stuck.is_stuck("app::worker", 0));
+
+        stuck.start_stop(2);
+        stuck.update_pool(&spawned);
+        assert!(stuck.is_stuck("app::worker", 0));
+    }
+
+    #[test]
+    fn lower_threshold() {
+        let mut stuck = StuckTasks::new(Some(10));
+        let spawned = pool(TaskRunState::Spawned, &[3, 0]);
+
+        for polls in 1..=5 {
+            stuck.start_stop(polls);
+            stuck.update_pool(&spawned);
+        }
+        assert!(!stuck.is_stuck("app::worker", 0));
+
+        // The stops counted before are kept, without a new stop.
+        stuck.set_threshold(Some(3));
+        assert!(stuck.is_stuck("app::worker", 0));
+        stuck.set_threshold(None);
+        assert!(!stuck.is_stuck("app::worker", 0));
+    }
+}
@@ -0,0 +1,92 @@
+//! The colors the pages are drawn with, so they stay readable on terminals with a light
+//! background.
+//!
+//! Only the colors with a role in the layout are part of the theme. Red, green and yellow keep
+//! marking errors, progress and changes, and values formatted by the backend keep their own ANSI
+//! colors.
+
+use ratatui::style::Color;
+
+/// The colors of the pages, see [`Config::theme`](crate::Config::theme).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Theme {
+    /// Text of the title bar and its buttons.
+    pub header_fg: Color,
+    /// Background of the title bar and its buttons.
+    pub header_bg: Color,
+    /// Background of the highlighted line, like the future the details are shown for, the current
+    /// state of an async fn and the current line of its source.
+    pub highlight: Color,
+    /// Background of the future selected in the tree.
+    pub selected: Color,
+    /// Text that is less important, like addresses, sizes, hints and the paths of type names.
+    pub dim: Color,
+    /// Text in place of a value that isn't there or can't be shown, like uninitialized tasks and
+    /// members that could not be formatted.
+    pub missing: Color,
+    /// Text that stands out, like state names, sources and the names of types.
+    pub accent: Color,
+}
+
+impl Theme {
+    /// The colors for terminals with a dark background, the default.
+    pub const fn dark() -> Self {
+        Self {
+            header_fg: Color::Black,
+            header_bg: Color::White,
+            highlight: Color::Blue,
+            selected: Color::DarkGray,
+            dim: Color::DarkGray,
+            missing: Color::Gray,
+            accent: Color::Blue,
+        }
+    }
+
+    /// The colors for terminals with a light background.
+    pub const fn light() -> Self {
+        Self {
+            header_fg: Color::White,
+            header_bg: Color::DarkGray,
+            highlight: Color::LightCyan,
+            selected: Color::Gray,
+            dim: Color::DarkGray,
+            missing: Color::DarkGray,
+            accent: Color::Blue,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            _ => Err(format!("unknown theme `{s}`, expected dark or light")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_themes() {
+        assert_eq!("dark".parse(), Ok(Theme::dark()));
+        assert_eq!("light".parse(), Ok(Theme::light()));
+        assert_eq!(
+            "solarized".parse::<Theme>(),
+            Err(String::from(
+                "unknown theme `solarized`, expected dark or light"
+            ))
+        );
+    }
+}
@@ -0,0 +1,507 @@
+//! Recording and replaying of all [`Callback`] calls.
+//!
+//! A [`RecordingCallback`] wraps the callback of a backend during a live session and writes every
+//! call and its result to a transcript file, one JSON object per line. A [`ReplayCallback`] reads
+//! such a file and answers the calls with the recorded results, allowing a session that was
+//! recorded on hardware to be replayed without it.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{BreakpointKind, Callback, Type};
+
+/// A single call to a [`Callback`] and its result, errors are stored as their message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+enum TranscriptEntry {
+    GetObjectfiles {
+        result: Result<Vec<String>, String>,
+    },
+    SetBreakpoint {
+        addr: u64,
+        /// Missing in transcripts recorded before the kind could be chosen.
+        #[serde(default)]
+        kind: BreakpointKind,
+        result: Result<u64, String>,
+    },
+    ClearBreakpoint {
+        id: u64,
+        result: Result<(), String>,
+    },
+    Resume {
+        result: Result<(), String>,
+    },
+    Halt {
+        result: Result<(), String>,
+    },
+    ReadMemory {
+        addr: u64,
+        len: u64,
+        result: Result<Vec<u8>, String>,
+    },
+    WriteMemory {
+        addr: u64,
+        bytes: Vec<u8>,
+        result: Result<(), String>,
+    },
+    TryFormatValue {
+        bytes: Vec<u8>,
+        /// The type as shown by its [`Display`](std::fmt::Display) implementation.
+        ty: String,
+        result: Option<String>,
+    },
+    ReadPc {
+        result: Result<Option<u64>, String>,
+    },
+}
+
+impl TranscriptEntry {
+    /// Name of the call, used in errors.
+    fn call(&self) -> &'static str {
+        match self {
+            Self::GetObjectfiles { .. } => "get_objectfiles",
+            Self::SetBreakpoint { .. } => "set_breakpoint",
+            Self::ClearBreakpoint { .. } => "clear_breakpoint",
+            Self::Resume { .. } => "resume",
+            Self::Halt { .. } => "halt",
+            Self::ReadMemory { .. } => "read_memory",
+            Self::WriteMemory { .. } => "write_memory",
+            Self::TryFormatValue { .. } => "try_format_value",
+            Self::ReadPc { .. } => "read_pc",
+        }
+    }
+}
+
+/// Turn an error into its message to store it in the transcript, keeping the result for the caller.
+fn record_result<T: Clone>(result: Result<T>) -> (Result<T>, Result<T, String>) {
+    match result {
+        Ok(value) => (Ok(value.clone()), Ok(value)),
+        Err(e) => {
+            let message = format!("{e:#}");
+            (Err(e), Err(message))
+        }
+    }
+}
+
+/// Replay a recorded result.
+fn replay_result<T>(result: Result<T, String>) -> Result<T> {
+    result.map_err(|e| anyhow!(e))
+}
+
+/// [`Callback`] wrapper writing every call to a transcript file, see the [module](self) docs.
+#[derive(Debug)]
+pub struct RecordingCallback<C: Callback> {
+    inner: C,
+    writer: BufWriter<File>,
+}
+
+impl<C: Callback> RecordingCallback<C> {
+    /// Wrap the callback, creating or truncating the transcript file at `path`.
+    pub fn new(inner: C, path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            inner,
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn record(&mut self, entry: &TranscriptEntry) {
+        let result = serde_json::to_writer(&mut self.writer, entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| Ok(self.writer.write_all(b"\n")?))
+            // Flushing every entry so the transcript is complete even if the backend crashes.
+            .and_then(|()| Ok(self.writer.flush()?));
+
+        if let Err(e) = result {
+            log::error!("Could not write to the transcript: {e}");
+        }
+    }
+}
+
+impl<C: Callback> Callback for RecordingCallback<C> {
+    fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+        let result = self
+            .inner
+            .get_objectfiles()
+            .map(|files| files.collect::<Vec<_>>());
+        let (result, recorded) = record_result(result);
+        self.record(&TranscriptEntry::GetObjectfiles { result: recorded });
+
+        result.map(Vec::into_iter)
+    }
+
+    fn set_breakpoint(&mut self, addr: u64, kind: BreakpointKind) -> Result<u64> {
+        let (result, recorded) = record_result(self.inner.set_breakpoint(addr, kind));
+        self.record(&TranscriptEntry::SetBreakpoint {
+            addr,
+            kind,
+            result: recorded,
+        });
+        result
+    }
+
+    fn clear_breakpoint(&mut self, id: u64) -> Result<()> {
+        let (result, recorded) = record_result(self.inner.clear_breakpoint(id));
+        self.record(&TranscriptEntry::ClearBreakpoint {
+            id,
+            result: recorded,
+        });
+        result
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        let (result, recorded) = record_result(self.inner.resume());
+        self.record(&TranscriptEntry::Resume { result: recorded });
+        result
+    }
+
+    fn halt(&mut self) -> Result<()> {
+        let (result, recorded) = record_result(self.inner.halt());
+        self.record(&TranscriptEntry::Halt { result: recorded });
+        result
+    }
+
+    fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
+        let (result, recorded) = record_result(self.inner.read_memory(addr, len));
+        self.record(&TranscriptEntry::ReadMemory {
+            addr,
+            len,
+            result: recorded,
+        });
+        result
+    }
+
+    fn write_memory(&mut self, addr: u64, bytes: &[u8]) -> Result<()> {
+        let (result, recorded) = record_result(self.inner.write_memory(addr, bytes));
+        self.record(&TranscriptEntry::WriteMemory {
+            addr,
+            bytes: bytes.to_vec(),
+            result: recorded,
+        });
+        result
+    }
+
+    fn try_format_value(&mut self, bytes: &[u8], ty: &Type) -> Option<String> {
+        let result = self.inner.try_format_value(bytes, ty);
+        self.record(&TranscriptEntry::TryFormatValue {
+            bytes: bytes.to_vec(),
+            ty: ty.to_string(),
+            result: result.clone(),
+        });
+        result
+    }
+
+    fn read_pc(&mut self) -> Result<Option<u64>> {
+        let (result, recorded) = record_result(self.inner.read_pc());
+        self.record(&TranscriptEntry::ReadPc { result: recorded });
+        result
+    }
+
+    // Not recorded, these do not interact with the target.
+    fn on_idle(&mut self) -> Result<()> {
+        self.inner.on_idle()
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str) -> Result<String> {
+        self.inner.copy_to_clipboard(text)
+    }
+}
+
+/// [`Callback`] answering calls from a transcript written by a [`RecordingCallback`].
+///
+/// Calls have to be made in the same order as during recording, a call that does not match the
+/// next recorded call returns an error. The exception is `try_format_value`, which depends on what
+/// is visible on the screen, these are looked up by their arguments and return `None` if they were
+/// never recorded.
+#[derive(Debug)]
+pub struct ReplayCallback {
+    entries: VecDeque<TranscriptEntry>,
+    formatted: HashMap<(Vec<u8>, String), Option<String>>,
+}
+
+impl ReplayCallback {
+    /// Read the transcript file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str(&line)?);
+        }
+
+        Ok(Self::from_entries(entries))
+    }
+
+    fn from_entries(entries: Vec<TranscriptEntry>) -> Self {
+        let mut formatted = HashMap::new();
+        let mut calls = VecDeque::new();
+
+        for entry in entries {
+            match entry {
+                TranscriptEntry::TryFormatValue { bytes, ty, result } => {
+                    formatted.insert((bytes, ty), result);
+                }
+                entry => calls.push_back(entry),
+            }
+        }
+
+        Self {
+            entries: calls,
+            formatted,
+        }
+    }
+
+    /// Whether all recorded calls have been replayed.
+    pub fn is_done(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn next(&mut self, call: &'static str) -> Result<TranscriptEntry> {
+        match self.entries.pop_front() {
+            Some(entry) if entry.call() == call => Ok(entry),
+            Some(entry) => bail!(
+                "Transcript mismatch: expected a call to {}, got {call}",
+                entry.call()
+            ),
+            None => bail!("Transcript ended, got a call to {call}"),
+        }
+    }
+}
+
+impl Callback for ReplayCallback {
+    fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+        match self.next("get_objectfiles")? {
+            TranscriptEntry::GetObjectfiles { result } => Ok(replay_result(result)?.into_iter()),
+            _ => unreachable!(),
+        }
+    }
+
+    fn set_breakpoint(&mut self, addr: u64, kind: BreakpointKind) -> Result<u64> {
+        match self.next("set_breakpoint")? {
+            TranscriptEntry::SetBreakpoint {
+                addr: recorded_addr,
+                kind: recorded_kind,
+                result,
+            } => {
+                if (recorded_addr, recorded_kind) != (addr, kind) {
+                    bail!(
+                        "Transcript mismatch: expected a {recorded_kind:?} breakpoint at {recorded_addr:#x}, got a {kind:?} breakpoint at {addr:#x}"
+                    );
+                }
+                replay_result(result)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn clear_breakpoint(&mut self, id: u64) -> Result<()> {
+        match self.next("clear_breakpoint")? {
+            TranscriptEntry::ClearBreakpoint {
+                id: recorded_id,
+                result,
+            } => {
+                if recorded_id != id {
+                    bail!(
+                        "Transcript mismatch: expected clearing breakpoint {recorded_id}, got {id}"
+                    );
+                }
+                replay_result(result)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        match self.next("resume")? {
+            TranscriptEntry::Resume { result } => replay_result(result),
+            _ => unreachable!(),
+        }
+    }
+
+    fn halt(&mut self) -> Result<()> {
+        match self.next("halt")? {
+            TranscriptEntry::Halt { result } => replay_result(result),
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
+        match self.next("read_memory")? {
+            TranscriptEntry::ReadMemory {
+                addr: recorded_addr,
+                len: recorded_len,
+                result,
+            } => {
+                if (recorded_addr, recorded_len) != (addr, len) {
+                    bail!(
+                        "Transcript mismatch: expected a read of {recorded_len} bytes at {recorded_addr:#x}, got {len} bytes at {addr:#x}"
+                    );
+                }
+                replay_result(result)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn write_memory(&mut self, addr: u64, bytes: &[u8]) -> Result<()> {
+        match self.next("write_memory")? {
+            TranscriptEntry::WriteMemory {
+                addr: recorded_addr,
+                bytes: recorded_bytes,
+                result,
+            } => {
+                if (recorded_addr, recorded_bytes.as_slice()) != (addr, bytes) {
+                    bail!(
+                        "Transcript mismatch: expected writing {recorded_bytes:x?} at {recorded_addr:#x}, got {bytes:x?} at {addr:#x}"
+                    );
+                }
+                replay_result(result)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn try_format_value(&mut self, bytes: &[u8], ty: &Type) -> Option<String> {
+        self.formatted
+            .get(&(bytes.to_vec(), ty.to_string()))
+            .cloned()
+            .flatten()
+    }
+
+    fn read_pc(&mut self) -> Result<Option<u64>> {
+        match self.next("read_pc")? {
+            TranscriptEntry::ReadPc { result } => replay_result(result),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Callback for a fake target with 16 bytes of memory counting up from 0.
+    struct FakeTarget;
+
+    impl Callback for FakeTarget {
+        fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
+            Ok(["firmware.elf".to_owned()].into_iter())
+        }
+
+        fn set_breakpoint(&mut self, addr: u64, _kind: BreakpointKind) -> Result<u64> {
+            Ok(addr + 1)
+        }
+
+        fn resume(&mut self) -> Result<()> {
+            bail!("target is not halted")
+        }
+
+        fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
+            Ok((addr..addr + len).map(|b| b as u8).collect())
+        }
+
+        fn try_format_value(&mut self, bytes: &[u8], _ty: &Type) -> Option<String> {
+            Some(format!("{bytes:?}"))
+        }
+    }
+
+    #[test]
+    fn record_and_replay() -> Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "inspect-embassy-transcript-{}.jsonl",
+            std::process::id()
+        ));
+        let u8_type = Type::Base("u8".to_owned());
+
+        let mut recording = RecordingCallback::new(FakeTarget, &path)?;
+        recording.get_objectfiles()?.for_each(drop);
+        recording.set_breakpoint(0x100, BreakpointKind::Software)?;
+        assert!(recording.resume().is_err());
+        assert!(recording.halt().is_err());
+        recording.read_memory(4, 2)?;
+        recording.try_format_value(&[1], &u8_type);
+        recording.read_pc()?;
+        drop(recording);
+
+        let mut replay = ReplayCallback::from_file(&path)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(
+            replay.get_objectfiles()?.collect::<Vec<_>>(),
+            ["firmware.elf"]
+        );
+        assert_eq!(
+            replay.set_breakpoint(0x100, BreakpointKind::Software)?,
+            0x101
+        );
+        assert_eq!(
+            replay.resume().unwrap_err().to_string(),
+            "target is not halted"
+        );
+        assert_eq!(
+            replay.halt().unwrap_err().to_string(),
+            "This backend can't halt the target"
+        );
+        assert_eq!(replay.read_memory(4, 2)?, [4, 5]);
+        assert_eq!(replay.read_pc()?, None);
+
+        assert_eq!(
+            replay.try_format_value(&[1], &u8_type).as_deref(),
+            Some("[1]")
+        );
+        assert_eq!(replay.try_format_value(&[2], &u8_type), None);
+        assert!(replay.is_done());
+
+        Ok(())
+    }
+
+    #[test]
+    fn mismatched_arguments() {
+        let mut replay = ReplayCallback::from_entries(vec![TranscriptEntry::ReadMemory {
+            addr: 4,
+            len: 2,
+            result: Ok(vec![4, 5]),
+        }]);
+
+        assert!(replay.read_memory(8, 2).is_err());
+        // The mismatched entry is consumed
+        assert!(replay.read_memory(4, 2).is_err());
+
+        let mut replay =
+            ReplayCallback::from_entries(vec![TranscriptEntry::Resume { result: Ok(()) }]);
+        assert!(replay.read_pc().is_err());
+
+        let mut replay = ReplayCallback::from_entries(vec![TranscriptEntry::SetBreakpoint {
+            addr: 0x100,
+            kind: BreakpointKind::Software,
+            result: Ok(0x100),
+        }]);
+        assert!(
+            replay
+                .set_breakpoint(0x100, BreakpointKind::Hardware)
+                .is_err()
+        );
+    }
+}
@@ -1,31 +1,54 @@
+mod diagnostics;
+#[cfg(feature = "task-graph-image")]
+mod graph_image;
 mod scroll_view;
+mod task_graph;
+mod type_name;
 
 use std::{
     collections::HashMap,
+    fmt::Write as _,
     ops::{Deref, DerefMut},
+    time::Instant,
 };
 
 use ratatui::{
     Frame,
-    layout::{Constraint, Layout, Rect},
-    style::Stylize,
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Position, Rect},
+    style::{Style, Stylize},
     text::{Line, Span, Text},
-    widgets::{Block, Padding, Paragraph},
+    widgets::{Block, Borders, Padding, Paragraph},
 };
 
 use crate::{
-    Click, ClickButton,
+    Click, ClickButton, FutureByAddress, KeyCode, PollBreakpoint,
+    format::{format_bytes, format_raw_bytes, parse_raw_bytes},
+    logger,
     model::{
+        Source,
         async_fn::Member,
-        async_fn::{AsyncFnType, AsyncFnValue},
+        async_fn::{AsyncFnType, AsyncFnValue, MemberValue},
+        embassy_sync::SyncFutureKind,
         future::FutureValue,
         future::FutureValueKind,
-        task_pool::{TaskPoolValue, TaskValue},
+        future::SelectValue,
+        task_pool::{TaskPool, TaskPoolValue, TaskRunState, TaskValue},
         ty::Type,
     },
+    parked::{ParkedTimes, format_parked},
+    source_files::SourceFiles,
+    stuck::StuckTasks,
+    theme::Theme,
+    watch::Watch,
 };
 
+use diagnostics::Diagnostics;
+#[cfg(feature = "task-graph-image")]
+pub(crate) use graph_image::GraphImage;
 use scroll_view::ScrollView;
+use task_graph::TaskGraphPage;
+use type_name::{short_type_name, style_type_name};
 
 fn is_clicked_left(area: &Rect, click: Option<Click>) -> bool {
     match click {
@@ -34,11 +57,157 @@ fn is_clicked_left(area: &Rect, click: Option<Click>) -> bool {
     }
 }
 
+fn is_clicked_middle(area: &Rect, click: Option<Click>) -> bool {
+    match click {
+        Some(click) => click.button == ClickButton::Middle && area.contains(click.pos),
+        None => false,
+    }
+}
+
+/// The row of the current page selected with the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Selection {
+    /// Index of the row in the order the page draws its selectable rows.
+    pub(crate) row: usize,
+    /// Scroll the row into view, set when the selection moved.
+    pub(crate) reveal: bool,
+    /// Handle the row as if it was clicked, set when enter was pressed.
+    pub(crate) activate: bool,
+    /// Copy the text of the row, set when `y` was pressed, see [`UiDrawCtx::copyable_row`].
+    pub(crate) copy: bool,
+}
+
+/// The styled path of a task pool, labeled `main` for the pool of the main task.
+fn pool_name(task_pool: &TaskPool, theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    if task_pool.is_main() {
+        spans.push(Span::raw("main ").green().bold());
+    }
+    spans.extend(style_type_name(&task_pool.path, theme));
+    spans
+}
+
 pub struct UiDrawCtx<'a, 'b> {
     pub(crate) frame: &'a mut Frame<'b>,
     pub(crate) click: Option<Click>,
+    /// Position of the mouse while it is dragged with the left button held down.
+    pub(crate) drag: Option<Position>,
+    /// Position of the mouse and the lines scrolled over it, see [`crate::Event::ScrollAt`].
+    pub(crate) scroll: Option<(Position, i32)>,
     pub(crate) values: &'a [TaskPoolValue],
-    pub(crate) try_format_value: &'a mut dyn FnMut(&[u8], &Type) -> Line<'static>,
+    /// The values before the last update, to highlight what changed since.
+    pub(crate) previous_values: &'a [TaskPoolValue],
+    pub(crate) futures_by_address: &'a [FutureByAddress],
+    /// Paths of the task pools that can't be read by the backend.
+    pub(crate) unavailable_pools: &'a [String],
+    /// Paths of the task pools whose memory overlaps with another pool.
+    pub(crate) overlapping_pools: &'a [(String, String)],
+    pub(crate) watches: &'a [Watch],
+    /// Addresses of the members showing all their elements.
+    pub(crate) all_elements: &'a [u64],
+    pub(crate) max_elements: usize,
+    pub(crate) parked: &'a ParkedTimes,
+    /// The time to show the parked times at, see [`ParkedTimes::parked_for`].
+    pub(crate) now: Instant,
+    pub(crate) stuck: &'a StuckTasks,
+    pub(crate) source_files: &'a mut SourceFiles,
+    /// See [`Config::source_context_lines`](crate::Config::source_context_lines).
+    pub(crate) source_context_lines: Option<usize>,
+    pub(crate) poll_breakpoints: &'a [PollBreakpoint],
+    /// Whether the connection to the target is lost, the values are from before.
+    pub(crate) disconnected: bool,
+    /// The pool path and index of the task the target runs until it changes state.
+    pub(crate) state_break: Option<(&'a str, usize)>,
+    /// Whether the inspector holds the target after a task changed state.
+    pub(crate) holding: bool,
+    /// Whether to show the discriminant values next to the state names.
+    pub(crate) show_discriminants: bool,
+    /// Whether to explain the state table of async fns above it.
+    pub(crate) show_legend: bool,
+    /// Whether to show the raw bytes after the decoded values of members.
+    pub(crate) show_raw_bytes: bool,
+    /// Whether to highlight the members that changed since the previous values.
+    pub(crate) highlight_changes: bool,
+    /// See [`Config::accessible`](crate::Config::accessible).
+    pub(crate) accessible: bool,
+    /// See [`Config::theme`](crate::Config::theme).
+    pub(crate) theme: Theme,
+    /// Draws the task graph as an image, `None` when the terminal can't show images.
+    #[cfg(feature = "task-graph-image")]
+    pub(crate) graph_image: Option<&'a mut GraphImage>,
+    /// Message from the last action, shown instead of the title.
+    pub(crate) status: Option<&'a str>,
+    /// The row selected with the keyboard, `None` until a key moved the selection.
+    pub(crate) selection: Option<Selection>,
+    /// Number of rows the page drew that can be selected, counted by [`Self::selectable_row`].
+    pub(crate) selectable_rows: usize,
+    /// Format a value showing at most the given number of elements of arrays and collections.
+    pub(crate) try_format_value: &'a mut dyn FnMut(&[u8], &Type, usize) -> Line<'static>,
+}
+
+impl UiDrawCtx<'_, '_> {
+    /// Count a row that can be selected with the keyboard, drawn at `area` of the scroll view and
+    /// visible at `vis_area` of the screen. Returns whether it is clicked or activated with enter.
+    ///
+    /// Pages call this for their rows in the order they are drawn, which is the order up and down
+    /// move the selection through them.
+    fn selectable_row(
+        &mut self,
+        scroll_view: &mut ScrollView,
+        area: Rect,
+        vis_area: Rect,
+    ) -> Result<bool, UiEvent> {
+        self.selectable_row_scrolled(scroll_view, area, vis_area, UiEvent::SetScroll)
+    }
+
+    /// Like [`Self::selectable_row`] for a row with text to copy like the value of a member, which
+    /// is copied when `y` is pressed while it is selected.
+    fn copyable_row(
+        &mut self,
+        scroll_view: &mut ScrollView,
+        area: Rect,
+        vis_area: Rect,
+        copy: &str,
+        scroll_event: fn(i32) -> UiEvent,
+    ) -> Result<bool, UiEvent> {
+        let row = self.selectable_rows;
+        if self
+            .selection
+            .is_some_and(|selection| selection.row == row && selection.copy)
+        {
+            return Err(UiEvent::CopyToClipboard(copy.to_owned()));
+        }
+        self.selectable_row_scrolled(scroll_view, area, vis_area, scroll_event)
+    }
+
+    /// Like [`Self::selectable_row`], revealing the row with `scroll_event` for scroll views other
+    /// than the one of the page, like the details pane.
+    fn selectable_row_scrolled(
+        &mut self,
+        scroll_view: &mut ScrollView,
+        area: Rect,
+        vis_area: Rect,
+        scroll_event: fn(i32) -> UiEvent,
+    ) -> Result<bool, UiEvent> {
+        let row = self.selectable_rows;
+        self.selectable_rows += 1;
+
+        let clicked = is_clicked_left(&vis_area, self.click);
+        let Some(selection) = self.selection.filter(|selection| selection.row == row) else {
+            return Ok(clicked);
+        };
+        if clicked || selection.activate {
+            return Ok(true);
+        }
+        if selection.reveal
+            && let Some(scroll) = scroll_view.scroll_into_view(area)
+        {
+            return Err(scroll_event(scroll));
+        }
+
+        scroll_view.set_style(area, Style::new().reversed());
+        Ok(false)
+    }
 }
 
 #[derive(Debug)]
@@ -48,6 +217,87 @@ pub enum UiEvent {
     SetScroll(i32),
     ToggleClosed(Vec<u64>),
     ToggleDetails(Vec<u64>),
+    /// Start or stop watching a range of target memory, handled by the inspector itself.
+    ToggleWatch {
+        address: u64,
+        size: u64,
+    },
+    /// Start typing new bytes for the memory at `address`, which now contains `bytes`.
+    EditMemory {
+        address: u64,
+        bytes: Vec<u8>,
+    },
+    /// Write the bytes to the target, handled by the inspector itself.
+    WriteMemory {
+        address: u64,
+        bytes: Vec<u8>,
+    },
+    /// Start typing the address and type of a future that is not in a task pool.
+    PromptFuture,
+    /// Show the future of the type at `type_path` stored at `address`, handled by the inspector
+    /// itself, see [`EmbassyInspector::inspect_future`](crate::EmbassyInspector::inspect_future).
+    InspectFuture {
+        address: u64,
+        type_path: String,
+    },
+    /// Toggle automatically showing the task that is executing when the target stops.
+    ToggleFollow,
+    /// Toggle the legend explaining the state table of async fns.
+    ToggleLegend,
+    /// Toggle showing the raw bytes after the decoded values of members.
+    ToggleRawBytes,
+    /// Toggle highlighting the members whose bytes changed since the previous stop.
+    ToggleChanges,
+    /// Show or hide the pane with the recent log records.
+    ToggleLogs,
+    /// Show the log records of the next more verbose level, wrapping around to only errors.
+    CycleLogLevel,
+    /// Pin a snapshot of a task's future to compare later stops against, `None` to unpin.
+    SetPinned(Option<Box<FutureValue>>),
+    /// Switch the task page between inline details and a separate details pane.
+    ToggleSplit,
+    /// Show the details of the future at this path in the details pane.
+    SelectFuture(Vec<u64>),
+    /// Switch the main menu between a block per pool and a single line per pool.
+    ToggleCompact,
+    /// Show or hide the tasks of the pool with this index in the compact main menu.
+    TogglePoolExpanded(usize),
+    /// Show or hide the uninitialized tasks of the pool with this index in the main menu.
+    ToggleUninitTasks(usize),
+    /// Only show the task pools whose path contains this text in the main menu, ignoring case.
+    SetFilter(String),
+    /// Start or stop typing into the filter of the main menu.
+    TypeFilter(bool),
+    /// Open every future in the tree of the task page.
+    ExpandAll,
+    /// Close every future in the tree of the task page, also the ones shown later.
+    CollapseAll,
+    /// Toggle listing the await points of all states of async fns on the task page.
+    ToggleAwaitPoints,
+    /// Show the estimated memory of every future in the tree of a task.
+    ToggleSizes,
+    /// Show the absolute address of every future in the tree of a task.
+    ToggleAddresses,
+    /// Select and scroll to the parent of the selected future on the task page.
+    SelectParent,
+    /// Set the width of the details pane.
+    SetDetailWidth(u16),
+    /// Scroll the current page, for a scroll of the mouse that no pane below it took.
+    Scroll(i32),
+    /// Set the scroll of the details pane.
+    SetDetailScroll(i32),
+    /// Copy the current page as plain text, handled by the inspector itself.
+    CopyPage,
+    /// Copy this text, like the decoded value of a member, handled by the inspector itself.
+    CopyToClipboard(String),
+    /// Toggle showing all elements of the collection at this address, handled by the inspector
+    /// itself.
+    ToggleAllElements(u64),
+    /// Halt the target once the task with this pool path and index changes state, `None` to stop
+    /// waiting for it. Handled by the inspector itself.
+    RunToNextState(Option<(String, usize)>),
+    /// Resume the target held after a task changed state, handled by the inspector itself.
+    Continue,
 }
 
 pub trait UiPage: std::fmt::Debug {
@@ -55,19 +305,316 @@ pub trait UiPage: std::fmt::Debug {
 
     fn apply_event(&mut self, event: UiEvent);
 
-    fn title(&self, values: &[TaskPoolValue]) -> String;
+    fn title(&self, ctx: &UiDrawCtx) -> String;
+
+    /// Buttons shown on the right side of the title bar, with the event to send when clicked.
+    fn buttons(&self, _ctx: &UiDrawCtx) -> Vec<(&'static str, UiEvent)> {
+        Vec::new()
+    }
 
     fn draw(&self, ctx: &mut UiDrawCtx, area: Rect) -> Result<(), UiEvent>;
+
+    /// The pool and task index if this page shows a single task.
+    fn task(&self) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// The index of the future added by address if this page shows one.
+    fn future_by_address(&self) -> Option<usize> {
+        None
+    }
+
+    /// Which futures are open on this page, kept by [`UiState`] for the page of a task so going
+    /// back to the task later shows the same futures.
+    fn item_state(&self) -> Option<&ItemState> {
+        None
+    }
+
+    /// Continue with the state returned by [`Self::item_state`] on an earlier page of the task.
+    fn restore_item_state(&mut self, _item_state: ItemState) {}
+
+    /// The event for a pressed key, `None` to handle it like on every page, see
+    /// [`UiState::apply_key`].
+    fn key_event(&self, _key: KeyCode) -> Option<UiEvent> {
+        None
+    }
+
+    /// Whether typed characters go to this page, instead of `q` quitting.
+    fn takes_text(&self) -> bool {
+        false
+    }
+
+    /// The full content of this page as plain text, including what is scrolled out of view.
+    ///
+    /// Pages returning `None` are copied as they are visible on the screen.
+    fn to_text(&self, _ctx: &mut UiDrawCtx) -> Option<String> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
 struct MainMenu {
     scroll: i32,
+
+    /// Show every pool as a single line that can be expanded, instead of a block with all tasks.
+    compact: bool,
+    /// Indices of the pools showing their tasks in the compact layout.
+    expanded: Vec<usize>,
+    /// Only show the spawned tasks of pools, unless they are in `uninit_shown`.
+    hide_uninit: bool,
+    /// Whether the uninitialized tasks are shown by pool index, for the pools where they were
+    /// toggled.
+    uninit_shown: HashMap<usize, bool>,
+    /// Only the pools with this in their path are shown, ignoring case.
+    filter: String,
+    /// Whether typed characters go into the filter.
+    typing_filter: bool,
 }
 
 impl MainMenu {
-    fn new() -> Self {
-        Self { scroll: 0 }
+    fn new(compact: bool, hide_uninit: bool) -> Self {
+        Self {
+            scroll: 0,
+            compact,
+            expanded: Vec::new(),
+            hide_uninit,
+            uninit_shown: HashMap::new(),
+            filter: String::new(),
+            typing_filter: false,
+        }
+    }
+
+    /// Whether the pool with this path is shown with the current filter.
+    fn matches_filter(&self, path: &str) -> bool {
+        path.to_lowercase().contains(&self.filter.to_lowercase())
+    }
+
+    /// The pools that match the filter, with their index in all pools.
+    fn shown_pools<'v>(&self, values: &'v [TaskPoolValue]) -> Vec<(usize, &'v TaskPoolValue)> {
+        values
+            .iter()
+            .enumerate()
+            .filter(|(_, pool)| self.matches_filter(&pool.task_pool.path))
+            .collect()
+    }
+
+    /// The line telling how many pools the filter hides, `None` if it hides none.
+    fn hidden_pools_line(&self, ctx: &UiDrawCtx) -> Option<String> {
+        let paths = ctx.values.iter().map(|pool| pool.task_pool.path.as_str());
+        let hidden = paths
+            .chain(ctx.unavailable_pools.iter().map(String::as_str))
+            .filter(|path| !self.matches_filter(path))
+            .count();
+        match hidden {
+            0 => None,
+            1 => Some(format!(
+                "1 task pool hidden by the filter \"{}\"",
+                self.filter
+            )),
+            hidden => Some(format!(
+                "{hidden} task pools hidden by the filter \"{}\"",
+                self.filter
+            )),
+        }
+    }
+
+    /// Whether the uninitialized tasks of the pool with this index are shown.
+    fn shows_uninit(&self, pool_idx: usize) -> bool {
+        self.uninit_shown
+            .get(&pool_idx)
+            .copied()
+            .unwrap_or(!self.hide_uninit)
+    }
+
+    /// The tasks of the pool that are shown, with their index in the pool.
+    fn shown_tasks<'v>(
+        &self,
+        pool_idx: usize,
+        pool: &'v TaskPoolValue,
+    ) -> Vec<(usize, &'v TaskValue)> {
+        let show_uninit = self.shows_uninit(pool_idx);
+        pool.task_values
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| show_uninit || !matches!(task, TaskValue::Uninit))
+            .collect()
+    }
+
+    /// The line below the tasks of a pool to show or hide its uninitialized tasks, `None` if it
+    /// has none.
+    fn uninit_line(&self, pool_idx: usize, pool: &TaskPoolValue) -> Option<String> {
+        let uninit = pool
+            .task_values
+            .iter()
+            .filter(|task| matches!(task, TaskValue::Uninit))
+            .count();
+        match (uninit, self.shows_uninit(pool_idx)) {
+            (0, _) => None,
+            (uninit, true) => Some(format!("(hide {uninit} uninitialized)")),
+            (uninit, false) => Some(format!("({uninit} uninitialized hidden)")),
+        }
+    }
+
+    /// `stuck` marks the task as one that might never be polled again, see
+    /// [`StuckTasks`](crate::stuck::StuckTasks). The poll count is only known when the executor
+    /// counts polls.
+    fn task_line(
+        task_idx: usize,
+        task: &TaskValue,
+        poll_count: Option<u64>,
+        stuck: bool,
+        theme: &Theme,
+    ) -> Line<'static> {
+        let mut line = Line::raw(format!("- {task_idx}: "));
+        match task {
+            TaskValue::Uninit => line.push_span(Span::raw("uninitialized").fg(theme.missing)),
+            TaskValue::Init(value, run_state) => {
+                match run_state {
+                    TaskRunState::Queued => line.push_span(Span::raw("queued").green()),
+                    _ => line.push_span(Span::raw("spawned").fg(theme.accent)),
+                }
+
+                if let FutureValueKind::AsyncFn(async_fn) = &value.kind
+                    && let Ok(state) = &async_fn.state_value
+                    && let Some(source) = &state.state.source
+                {
+                    line.push_span(Span::raw(format!(" {} (", state.state.source_label())));
+                    line.push_span(Span::from(source.to_string()).fg(theme.accent));
+                    line.push_span(Span::raw(")"));
+                }
+                if let Some(poll_count) = poll_count {
+                    line.push_span(Span::raw(format!(" polled {poll_count} times")).fg(theme.dim));
+                }
+                if stuck {
+                    line.push_span(Span::raw(" stuck").red());
+                }
+            }
+        }
+        line
+    }
+
+    fn draw_pools(&self, ctx: &mut UiDrawCtx, scroll_view: &mut ScrollView) -> Result<(), UiEvent> {
+        for (pool_idx, pool) in self.shown_pools(ctx.values) {
+            let tasks = self.shown_tasks(pool_idx, pool);
+            let uninit_line = self.uninit_line(pool_idx, pool);
+            let area = scroll_view.next_area(3 + tasks.len() as u16 + uninit_line.is_some() as u16);
+
+            let block = Block::bordered()
+                .title(Line::from(pool_name(&pool.task_pool, &ctx.theme)))
+                .title(Line::raw(format!(" {} ", pool.usage_label())).right_aligned());
+            let block = match pool.is_full() {
+                true => block.title(Line::raw(" FULL ").white().on_red().bold().right_aligned()),
+                false => block,
+            };
+            scroll_view.render_widget(&block, area);
+
+            let mut area = block.inner(area);
+            area.height = 1;
+
+            scroll_view.render_widget(Line::raw("Tasks in pool:"), area);
+            area.y += 1;
+            for (task_idx, task) in tasks {
+                let stuck = ctx.stuck.is_stuck(&pool.task_pool.path, task_idx);
+                let line =
+                    Self::task_line(task_idx, task, pool.poll_count(task_idx), stuck, &ctx.theme);
+                let vis_area = scroll_view.render_widget(line, area);
+                if ctx.selectable_row(scroll_view, area, vis_area)? {
+                    return Err(UiEvent::AddPage(Box::new(Task::new(pool_idx, task_idx))));
+                }
+                area.y += 1;
+            }
+            if let Some(uninit_line) = uninit_line {
+                let vis_area =
+                    scroll_view.render_widget(Line::raw(uninit_line).fg(ctx.theme.dim), area);
+                if ctx.selectable_row(scroll_view, area, vis_area)? {
+                    return Err(UiEvent::ToggleUninitTasks(pool_idx));
+                }
+            }
+        }
+
+        for path in ctx.unavailable_pools {
+            if !self.matches_filter(path) {
+                continue;
+            }
+            let area = scroll_view.next_area(3);
+
+            let block = Block::bordered().title(Line::from(style_type_name(path, &ctx.theme)));
+            scroll_view.render_widget(&block, area);
+            scroll_view.render_widget(
+                Line::raw("Region unavailable, the backend can't read the memory of this pool")
+                    .fg(ctx.theme.dim),
+                block.inner(area),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// One line per pool, the tasks are only shown below the expanded ones.
+    fn draw_pools_compact(
+        &self,
+        ctx: &mut UiDrawCtx,
+        scroll_view: &mut ScrollView,
+    ) -> Result<(), UiEvent> {
+        for (pool_idx, pool) in self.shown_pools(ctx.values) {
+            let expanded = self.expanded.contains(&pool_idx);
+
+            let mut line = Line::raw(match expanded {
+                true => "- ",
+                false => "+ ",
+            });
+            line.extend(pool_name(&pool.task_pool, &ctx.theme));
+            line.push_span(Span::raw(format!(" {}", pool.usage_label())).fg(ctx.theme.dim));
+            if pool.is_full() {
+                line.push_span(Span::raw(" "));
+                line.push_span(Span::raw("FULL").white().on_red().bold());
+            }
+            let area = scroll_view.next_area(1);
+            let vis_area = scroll_view.render_widget(line, area);
+            if ctx.selectable_row(scroll_view, area, vis_area)? {
+                return Err(UiEvent::TogglePoolExpanded(pool_idx));
+            }
+
+            if !expanded {
+                continue;
+            }
+            let indented_area = |scroll_view: &mut ScrollView| {
+                let mut area = scroll_view.next_area(1);
+                area.x += 2;
+                area.width = area.width.saturating_sub(2);
+                area
+            };
+            for (task_idx, task) in self.shown_tasks(pool_idx, pool) {
+                let area = indented_area(scroll_view);
+                let stuck = ctx.stuck.is_stuck(&pool.task_pool.path, task_idx);
+                let line =
+                    Self::task_line(task_idx, task, pool.poll_count(task_idx), stuck, &ctx.theme);
+                let vis_area = scroll_view.render_widget(line, area);
+                if ctx.selectable_row(scroll_view, area, vis_area)? {
+                    return Err(UiEvent::AddPage(Box::new(Task::new(pool_idx, task_idx))));
+                }
+            }
+            if let Some(uninit_line) = self.uninit_line(pool_idx, pool) {
+                let area = indented_area(scroll_view);
+                let vis_area =
+                    scroll_view.render_widget(Line::raw(uninit_line).fg(ctx.theme.dim), area);
+                if ctx.selectable_row(scroll_view, area, vis_area)? {
+                    return Err(UiEvent::ToggleUninitTasks(pool_idx));
+                }
+            }
+        }
+
+        for path in ctx.unavailable_pools {
+            if !self.matches_filter(path) {
+                continue;
+            }
+            let mut line = Line::raw("  ");
+            line.extend(style_type_name(path, &ctx.theme));
+            line.push_span(Span::raw(" region unavailable").fg(ctx.theme.dim));
+            scroll_view.render_next_widget(line, 1);
+        }
+
+        Ok(())
     }
 }
 
@@ -80,60 +627,207 @@ impl UiPage for MainMenu {
     fn apply_event(&mut self, event: UiEvent) {
         match event {
             UiEvent::SetScroll(scroll) => self.scroll = scroll,
+            UiEvent::ToggleCompact => self.compact = !self.compact,
+            UiEvent::TogglePoolExpanded(pool_idx) => {
+                match self.expanded.iter().position(|idx| *idx == pool_idx) {
+                    Some(position) => {
+                        self.expanded.remove(position);
+                    }
+                    None => self.expanded.push(pool_idx),
+                }
+            }
+            UiEvent::ToggleUninitTasks(pool_idx) => {
+                let shown = self.shows_uninit(pool_idx);
+                self.uninit_shown.insert(pool_idx, !shown);
+            }
+            UiEvent::SetFilter(filter) => self.filter = filter,
+            UiEvent::TypeFilter(typing) => self.typing_filter = typing,
             _ => {}
         }
     }
 
-    fn title(&self, _values: &[TaskPoolValue]) -> String {
-        String::from("Main menu")
-    }
-
-    fn draw(&self, ctx: &mut UiDrawCtx, area: Rect) -> Result<(), UiEvent> {
-        let [header, rest] =
-            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+    /// `/` starts typing into the filter, enter or escape stop it. Escape also clears the filter
+    /// when not typing.
+    fn key_event(&self, key: KeyCode) -> Option<UiEvent> {
+        if !self.typing_filter {
+            return match key {
+                KeyCode::Char('/') => Some(UiEvent::TypeFilter(true)),
+                KeyCode::Esc if !self.filter.is_empty() => Some(UiEvent::SetFilter(String::new())),
+                _ => None,
+            };
+        }
 
-        ctx.frame
-            .render_widget(Text::from("Found task pools:"), header);
+        match key {
+            KeyCode::Char(c) => Some(UiEvent::SetFilter(format!("{}{c}", self.filter))),
+            KeyCode::Backspace if self.filter.is_empty() => Some(UiEvent::TypeFilter(false)),
+            KeyCode::Backspace => {
+                let mut filter = self.filter.clone();
+                filter.pop();
+                Some(UiEvent::SetFilter(filter))
+            }
+            KeyCode::Enter | KeyCode::Esc => Some(UiEvent::TypeFilter(false)),
+            _ => None,
+        }
+    }
 
-        let mut scroll_view = ScrollView::new(rest, self.scroll);
+    fn takes_text(&self) -> bool {
+        self.typing_filter
+    }
 
-        for (pool_idx, pool) in ctx.values.iter().enumerate() {
-            let area = scroll_view.next_area(3 + pool.task_pool.number_of_tasks as u16);
+    fn title(&self, _ctx: &UiDrawCtx) -> String {
+        String::from("Main menu")
+    }
 
-            let block = Block::bordered().title(pool.task_pool.path.clone().blue());
-            scroll_view.render_widget(&block, area);
+    fn to_text(&self, ctx: &mut UiDrawCtx) -> Option<String> {
+        let mut text = String::from("Found task pools:\n");
 
-            let mut area = block.inner(area);
-            area.height = 1;
-
-            scroll_view.render_widget(Line::raw("Tasks in pool:"), area);
-            area.y += 1;
-            for (task_idx, task) in pool.task_values.iter().enumerate() {
-                let mut line = Line::raw(format!("- {task_idx}: "));
+        for (pool_idx, pool) in self.shown_pools(ctx.values) {
+            if pool.task_pool.is_main() {
+                text.push_str("main ");
+            }
+            let full = match pool.is_full() {
+                true => ", FULL",
+                false => "",
+            };
+            let _ = writeln!(
+                text,
+                "{} ({}{full})",
+                pool.task_pool.path,
+                pool.usage_label()
+            );
+            for (task_idx, task) in self.shown_tasks(pool_idx, pool) {
+                let _ = write!(text, "- {task_idx}: ");
                 match task {
-                    TaskValue::Uninit => line.push_span(Span::raw("uninitialized").gray()),
-                    TaskValue::Init(value) => {
-                        line.push_span(Span::raw("spawned").blue());
-
+                    TaskValue::Uninit => text.push_str("uninitialized"),
+                    TaskValue::Init(value, run_state) => {
+                        text.push_str(match run_state {
+                            TaskRunState::Queued => "queued",
+                            _ => "spawned",
+                        });
                         if let FutureValueKind::AsyncFn(async_fn) = &value.kind
                             && let Ok(state) = &async_fn.state_value
                             && let Some(source) = &state.state.source
                         {
-                            line.push_span(Span::raw(" waiting at ("));
-                            line.push_span(Span::from(source.to_string()).blue());
-                            line.push_span(Span::raw(")"));
+                            let _ = write!(text, " {} ({source})", state.state.source_label());
+                        }
+                        if let Some(poll_count) = pool.poll_count(task_idx) {
+                            let _ = write!(text, " (polled {poll_count} times)");
+                        }
+                        if ctx.stuck.is_stuck(&pool.task_pool.path, task_idx) {
+                            text.push_str(" (stuck)");
                         }
                     }
                 }
-                let vis_area = scroll_view.render_widget(line, area);
-                if is_clicked_left(&vis_area, ctx.click) {
-                    return Err(UiEvent::AddPage(Box::new(Task::new(pool_idx, task_idx))));
-                }
-                area.y += 1;
+                text.push('\n');
+            }
+            if let Some(uninit_line) = self.uninit_line(pool_idx, pool) {
+                text.push_str(&uninit_line);
+                text.push('\n');
+            }
+        }
+        for path in ctx.unavailable_pools {
+            if self.matches_filter(path) {
+                let _ = writeln!(text, "{path} (region unavailable)");
+            }
+        }
+        if let Some(hidden) = self.hidden_pools_line(ctx) {
+            text.push_str(&hidden);
+            text.push('\n');
+        }
+        if !ctx.futures_by_address.is_empty() {
+            text.push_str("Futures outside of task pools:\n");
+        }
+        for future in ctx.futures_by_address {
+            let _ = write!(text, "- {}", future.ty);
+            if let Some(path) = &future.static_path {
+                let _ = write!(text, " in {path}");
             }
+            let _ = writeln!(text, " at {:#x}", future.address);
         }
 
-        scroll_view.render_next_widget(Line::raw("Click on a task for details"), 1);
+        Some(text)
+    }
+
+    fn buttons(&self, _ctx: &UiDrawCtx) -> Vec<(&'static str, UiEvent)> {
+        vec![
+            (
+                "Diagnostics",
+                UiEvent::AddPage(Box::new(Diagnostics::new())),
+            ),
+            (
+                "Task graph",
+                UiEvent::AddPage(Box::new(TaskGraphPage::new())),
+            ),
+            ("Add future", UiEvent::PromptFuture),
+            match self.compact {
+                true => ("Compact [x]", UiEvent::ToggleCompact),
+                false => ("Compact [ ]", UiEvent::ToggleCompact),
+            },
+        ]
+    }
+
+    fn draw(&self, ctx: &mut UiDrawCtx, area: Rect) -> Result<(), UiEvent> {
+        let [header, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+
+        let filter = match (self.typing_filter, self.filter.is_empty()) {
+            (true, _) => Line::raw(format!(" Filter: {}_ ", self.filter))
+                .fg(ctx.theme.header_fg)
+                .bg(ctx.theme.header_bg),
+            (false, false) => Line::raw(format!(" Filter: {} ", self.filter)),
+            (false, true) => Line::raw(" Press / or click here to filter ").fg(ctx.theme.dim),
+        };
+        let [header, filter_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(filter.width() as u16),
+        ])
+        .areas(header);
+        if is_clicked_left(&filter_area, ctx.click) {
+            return Err(UiEvent::TypeFilter(!self.typing_filter));
+        }
+
+        ctx.frame
+            .render_widget(Text::from("Found task pools:"), header);
+        ctx.frame.render_widget(filter, filter_area);
+
+        let mut scroll_view = ScrollView::new(rest, self.scroll).with_scrollbar(true);
+
+        match self.compact {
+            true => self.draw_pools_compact(ctx, &mut scroll_view)?,
+            false => self.draw_pools(ctx, &mut scroll_view)?,
+        }
+        if let Some(hidden) = self.hidden_pools_line(ctx) {
+            scroll_view.render_next_widget(Line::raw(hidden).fg(ctx.theme.dim), 1);
+        }
+
+        if !ctx.futures_by_address.is_empty() {
+            scroll_view.render_next_widget(Line::raw("Futures outside of task pools:"), 1);
+        }
+        for (idx, future) in ctx.futures_by_address.iter().enumerate() {
+            let mut line = Line::raw("- ");
+            line.extend(style_type_name(&future.ty.to_string(), &ctx.theme));
+            if let Some(path) = &future.static_path {
+                line.push_span(Span::raw(" in "));
+                line.extend(style_type_name(path, &ctx.theme));
+            }
+            line.push_span(Span::raw(" at "));
+            line.push_span(Span::raw(format!("{:#x}", future.address)).fg(ctx.theme.accent));
+            if future.value.is_err() {
+                line.push_span(Span::raw(" (unreadable)").red());
+            }
+            let area = scroll_view.next_area(1);
+            let vis_area = scroll_view.render_widget(line, area);
+            if ctx.selectable_row(&mut scroll_view, area, vis_area)? {
+                return Err(UiEvent::AddPage(Box::new(Task::from_source(
+                    FutureSource::Address(idx),
+                ))));
+            }
+        }
+
+        scroll_view.render_next_widget(
+            Line::raw("Click on a task for details, or select it with the arrow keys and enter"),
+            1,
+        );
 
         if scroll_view.max_scroll() < self.scroll {
             return Err(UiEvent::SetScroll(scroll_view.max_scroll()));
@@ -146,27 +840,52 @@ impl UiPage for MainMenu {
 }
 
 #[derive(Debug, Clone)]
-struct ItemState {
+pub(crate) struct ItemState {
     closed: bool,
     details_open: bool,
     children: HashMap<u64, ItemState>,
+    /// Whether the children that are not in `children` yet are closed, set by
+    /// [`UiEvent::CollapseAll`].
+    children_closed: bool,
 }
 
 impl Default for ItemState {
     fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl ItemState {
+    /// The state of a future never toggled, with all its children also `closed` or not.
+    fn new(closed: bool) -> Self {
         Self {
-            closed: false,
+            closed,
             details_open: false,
             children: HashMap::new(),
+            children_closed: closed,
+        }
+    }
+
+    fn child_mut(&mut self, path_id: u64) -> &mut ItemState {
+        let children_closed = self.children_closed;
+        self.children
+            .entry(path_id)
+            .or_insert_with(|| Self::new(children_closed))
+    }
+
+    /// Open or close this future and all futures below it, including the ones not drawn yet.
+    fn set_all_closed(&mut self, closed: bool) {
+        self.closed = closed;
+        self.children_closed = closed;
+        for child in self.children.values_mut() {
+            child.set_all_closed(closed);
         }
     }
-}
 
-impl ItemState {
     fn toggle_closed(&mut self, path: &[u64]) {
         match path {
             [head, rest @ ..] => {
-                self.children.entry(*head).or_default().toggle_closed(rest);
+                self.child_mut(*head).toggle_closed(rest);
             }
             [] => {
                 self.closed = !self.closed;
@@ -176,7 +895,7 @@ impl ItemState {
     fn toggle_details(&mut self, path: &[u64]) {
         match path {
             [head, rest @ ..] => {
-                self.children.entry(*head).or_default().toggle_details(rest);
+                self.child_mut(*head).toggle_details(rest);
             }
             [] => {
                 self.details_open = !self.details_open;
@@ -185,61 +904,354 @@ impl ItemState {
     }
 }
 
+/// A line in the details of a future, optionally with its own action when clicked.
+struct DetailLine<'a> {
+    line: Line<'a>,
+    on_click: Option<UiEvent>,
+    /// Plain text copied to the clipboard when the line is clicked with the middle button.
+    copy: Option<String>,
+}
+
+impl<'a> From<Line<'a>> for DetailLine<'a> {
+    fn from(line: Line<'a>) -> Self {
+        Self {
+            line,
+            on_click: None,
+            copy: None,
+        }
+    }
+}
+
 struct TreeData<'a> {
     value: &'a FutureValue,
+    /// The same future in the pinned snapshot, if any.
+    pinned: Option<&'a FutureValue>,
+    /// The same future before the last update, see [`PageFuture::previous`].
+    previous: Option<&'a FutureValue>,
     path: Vec<u64>,
     item_state: &'a ItemState,
+    /// The path of the task pool and the index of the task this future belongs to, `None` for
+    /// futures added by address.
+    task: Option<(&'a str, usize)>,
+    /// Path of the future shown in the details pane, `None` if details are shown inline.
+    selected: Option<&'a [u64]>,
+    /// Whether to list the await points of all states of async fns, see
+    /// [`Task::await_point_lines`].
+    show_await_points: bool,
+    /// Whether to show the memory of every future, see [`FutureValue::size`].
+    show_sizes: bool,
+    /// Whether to show the absolute address of every future.
+    show_addresses: bool,
+    /// Path of the future to highlight, see [`Task::highlighted`].
+    highlighted: Option<&'a [u64]>,
+    /// Scroll to the highlighted future once it is found.
+    scroll_to_highlighted: bool,
+}
+
+/// Width the task page needs before the details can be shown in a separate pane.
+const MIN_SPLIT_WIDTH: u16 = 100;
+const MIN_TREE_WIDTH: u16 = 40;
+const MIN_DETAIL_WIDTH: u16 = 30;
+const DEFAULT_DETAIL_WIDTH: u16 = 60;
+/// How far from the divider a drag can be to move it, drag events skip columns when the mouse
+/// moves fast.
+const DIVIDER_GRAB_DISTANCE: u16 = 2;
+
+/// Where the future shown on a [`Task`] page comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FutureSource {
+    Task {
+        pool_idx: usize,
+        task_idx: usize,
+    },
+    /// Index into the futures added by address.
+    Address(usize),
+}
+
+/// The future shown on a [`Task`] page as found in the latest values.
+struct PageFuture<'a> {
+    title: String,
+    /// The future, or a message why there is none.
+    value: Result<&'a FutureValue, &'a str>,
+    /// The same future in the values before the last update, `None` if it was not spawned yet or
+    /// changes are not highlighted.
+    previous: Option<&'a FutureValue>,
+    /// The task pool path and task index, `None` for futures added by address.
+    task: Option<(&'a str, usize)>,
 }
 
 #[derive(Debug, Clone)]
 struct Task {
-    pool_idx: usize,
-    task_idx: usize,
+    source: FutureSource,
 
     item_state: ItemState,
     scroll: i32,
+
+    /// Snapshot of the task's future, shown next to the live values to see what changed.
+    pinned: Option<Box<FutureValue>>,
+
+    /// Width of the details pane, `None` if details are shown inline in the tree.
+    detail_width: Option<u16>,
+    /// Scroll of the details pane, back at the top when another future gets selected.
+    detail_scroll: i32,
+    /// Path of the future shown in the details pane.
+    selected: Vec<u64>,
+    /// List the await points of all states of async fns below them, not just the current one.
+    show_await_points: bool,
+    /// Show the estimated memory of every future after its line in the tree.
+    show_sizes: bool,
+    /// Show the address of every future after its line in the tree, to find it in a memory dump.
+    show_addresses: bool,
+    /// The parent jumped to with [`UiEvent::SelectParent`], highlighted until the next event.
+    highlighted: Option<Vec<u64>>,
+    /// Set until the tree scrolled to the highlighted future.
+    scroll_to_highlighted: bool,
 }
 
 impl Task {
     fn new(pool_idx: usize, task_idx: usize) -> Self {
+        Self::from_source(FutureSource::Task { pool_idx, task_idx })
+    }
+
+    fn from_source(source: FutureSource) -> Self {
         Self {
-            pool_idx,
-            task_idx,
+            source,
             item_state: ItemState::default(),
             scroll: 0,
+            pinned: None,
+            detail_width: None,
+            detail_scroll: 0,
+            selected: Vec::new(),
+            show_await_points: false,
+            show_sizes: false,
+            show_addresses: false,
+            highlighted: None,
+            scroll_to_highlighted: false,
         }
     }
 
-    fn add_future(
-        tree_data: &TreeData,
-        scroll_view: &mut ScrollView,
+    /// The future shown on this page, `None` if it does not exist anymore.
+    fn page_future<'a>(&self, ctx: &UiDrawCtx<'a, '_>) -> Option<PageFuture<'a>> {
+        let values: &'a [TaskPoolValue] = ctx.values;
+        let futures_by_address: &'a [FutureByAddress] = ctx.futures_by_address;
+
+        match self.source {
+            FutureSource::Task { pool_idx, task_idx } => {
+                let pool = values.get(pool_idx)?;
+                let value = match pool.task_values.get(task_idx)? {
+                    TaskValue::Uninit => Err("Task is uninitialized"),
+                    TaskValue::Init(value, _) => Ok(value),
+                };
+                // The pools that could not be read are skipped, so the index can differ.
+                let previous_values: &'a [TaskPoolValue] = ctx.previous_values;
+                let previous = previous_values
+                    .iter()
+                    .find(|previous| previous.task_pool.path == pool.task_pool.path)
+                    .and_then(|previous| match previous.task_values.get(task_idx)? {
+                        TaskValue::Uninit => None,
+                        TaskValue::Init(value, _) => Some(value),
+                    })
+                    .filter(|_| ctx.highlight_changes);
+                Some(PageFuture {
+                    title: match pool.task_pool.is_main() {
+                        true => format!("Main task: {}[{}]", pool.task_pool.path, task_idx),
+                        false => format!("Task: {}[{}]", pool.task_pool.path, task_idx),
+                    },
+                    value,
+                    previous,
+                    task: Some((&pool.task_pool.path, task_idx)),
+                })
+            }
+            FutureSource::Address(idx) => {
+                let future = futures_by_address.get(idx)?;
+                Some(PageFuture {
+                    title: format!("Future: {} at {:#x}", future.ty, future.address),
+                    value: future.value.as_ref().map_err(String::as_str),
+                    previous: None,
+                    task: None,
+                })
+            }
+        }
+    }
+
+    /// Find the future at the given path, together with the same future in the pinned snapshot.
+    fn find_future<'a>(
+        mut value: &'a FutureValue,
+        mut pinned: Option<&'a FutureValue>,
+        path: &[u64],
+    ) -> Option<(&'a FutureValue, Option<&'a FutureValue>)> {
+        for path_id in path {
+            value = Self::child_future(value, *path_id)?;
+            pinned = pinned.and_then(|pinned| Self::child_future(pinned, *path_id));
+        }
+        Some((value, pinned))
+    }
+
+    /// Find the child future with the given path id, see [`FutureValue::children`] for how the ids
+    /// are assigned.
+    fn child_future(value: &FutureValue, path_id: u64) -> Option<&FutureValue> {
+        value
+            .children()
+            .into_iter()
+            .find(|(id, _)| *id == path_id)
+            .map(|(_, child)| child)
+    }
+
+    /// Lines listing what each state of an async fn awaits, in the order of the await points, empty
+    /// for other futures or if they are not shown.
+    fn await_point_lines(tree_data: &TreeData, theme: &Theme) -> Vec<Line<'static>> {
+        let FutureValueKind::AsyncFn(value) = &tree_data.value.kind else {
+            return Vec::new();
+        };
+        if !tree_data.show_await_points {
+            return Vec::new();
+        }
+
+        let mut await_points = value
+            .ty
+            .states
+            .iter()
+            .filter_map(|state| Some((state, state.awaitee.as_ref()?)))
+            .collect::<Vec<_>>();
+        if await_points.is_empty() {
+            return Vec::new();
+        }
+        await_points.sort_unstable_by_key(|(state, _)| state.discriminant_value);
+
+        let current = value
+            .state_value
+            .as_ref()
+            .ok()
+            .map(|state| state.state.discriminant_value);
+
+        let mut lines = vec![Line::raw("possible await points:").fg(theme.dim)];
+        for (state, awaitee) in await_points {
+            let mut text = format!(
+                "  {}: {}",
+                state.name,
+                short_type_name(&awaitee.ty.to_string())
+            );
+            if let Some(source) = &state.source {
+                let _ = write!(text, " ({source})");
+            }
+            if current == Some(state.discriminant_value) {
+                text.push_str(" (current)");
+            }
+            lines.push(Line::raw(text).fg(theme.dim));
+        }
+        lines
+    }
+
+    /// The line describing a future in the tree, and the children to show below it with their
+    /// path ids.
+    fn future_line<'a>(
+        tree_data: &TreeData<'a>,
         ctx: &mut UiDrawCtx,
-    ) -> Result<(), UiEvent> {
+    ) -> (Line<'a>, Vec<(&'a FutureValue, u64)>) {
         let mut children = Vec::<(&FutureValue, u64)>::new();
 
-        let line = match &tree_data.value.kind {
+        let mut line = match &tree_data.value.kind {
             FutureValueKind::AsyncFn(value) => {
-                let mut line = Line::from_iter([
-                    Span::raw("Function "),
-                    Span::raw(tree_data.value.ty.to_string()).blue(),
-                    Span::raw(" is waiting at "),
-                ]);
+                let mut line = Line::raw("Function ");
+                line.extend(style_type_name(&tree_data.value.ty.to_string(), &ctx.theme));
+                line.push_span(Span::raw(" is waiting at "));
                 match &value.state_value {
                     Ok(state) => {
-                        line.push_span(Span::raw(&state.state.name).blue());
+                        line.push_span(Span::raw(&state.state.name).fg(ctx.theme.accent));
+                        if ctx.show_discriminants {
+                            line.push_span(Span::raw(format!(" (disc={})", state.discriminant)));
+                        }
+                        if let Some((position, total)) = value.ty.await_progress(&state.state) {
+                            line.push_span(
+                                Span::raw(format!(" await {position}/{total}")).fg(ctx.theme.dim),
+                            );
+                        }
                         if let Some(source) = &state.state.source {
-                            line.push_span(Span::raw(" ("));
-                            line.push_span(Span::raw(source.to_string()).blue());
+                            line.push_span(Span::raw(format!(" ({} ", state.state.source_label())));
+                            line.push_span(Span::raw(source.to_string()).fg(ctx.theme.accent));
                             line.push_span(Span::raw(")"));
                         }
+                        // Only on the deepest async fn, the ones above are waiting on it.
+                        let is_leaf = state.awaitee.as_ref().is_none_or(|awaitee| {
+                            matches!(
+                                awaitee.kind,
+                                FutureValueKind::TimerValue(_)
+                                    | FutureValueKind::EmbassySyncValue(_)
+                                    | FutureValueKind::Unknown(_)
+                            )
+                        });
+                        if is_leaf
+                            && let Some((pool_path, task_idx)) = tree_data.task
+                            && let Some(parked) =
+                                ctx.parked
+                                    .parked_for(pool_path, task_idx, &tree_data.path, ctx.now)
+                        {
+                            line.push_span(Span::raw(format!(" parked {}", format_parked(parked))));
+                        }
                         if let Some(awaitee) = &state.awaitee {
                             line.push_span(Span::raw(" on:"));
 
-                            children.push((awaitee, state.state.discriminant_value));
+                            children.push((awaitee, state.discriminant));
                         }
                     }
-                    Err((err_discr, _)) => {
-                        line.push_span(format!("<invalid discriminant {err_discr}>").blue());
+                    Err((err_discr, bytes)) => {
+                        let error = match err_discr {
+                            Some(discriminant) => format!("<invalid discriminant {discriminant}>"),
+                            None => String::from("<unreadable discriminant>"),
+                        };
+                        line.push_span(error.fg(ctx.theme.accent));
+                        let state_bytes = member_bytes(bytes, &value.ty.state_member);
+                        line.push_span(
+                            Span::raw(format!(" {}", format_raw_bytes(state_bytes)))
+                                .fg(ctx.theme.dim),
+                        );
+                    }
+                }
+
+                if let Some(pinned) = tree_data.pinned
+                    && let FutureValueKind::AsyncFn(pinned) = &pinned.kind
+                    && let Ok(pinned_state) = &pinned.state_value
+                    && value
+                        .state_value
+                        .as_ref()
+                        .ok()
+                        .map(|s| s.state.discriminant_value)
+                        != Some(pinned_state.state.discriminant_value)
+                {
+                    line.push_span(
+                        Span::raw(format!(" (pinned at {})", pinned_state.state.name))
+                            .fg(ctx.theme.dim),
+                    );
+                }
+                line
+            }
+            FutureValueKind::SelectValue(value) if value.done && value.awaitees.is_empty() => {
+                Line::raw("Select already completed, its arms are gone")
+            }
+            FutureValueKind::SelectValue(value) if value.awaitees.is_empty() => {
+                Line::raw("Select over 0 futures, this will never complete")
+            }
+            FutureValueKind::SelectValue(SelectValue {
+                awaitees,
+                resolved: Some(arm),
+                ..
+            }) => {
+                let mut line = Line::from_iter([
+                    Span::raw("Select completed by arm "),
+                    Span::raw((arm + 1).to_string()).fg(ctx.theme.accent),
+                    Span::raw(format!(" of {} futures ", awaitees.len())),
+                    Span::raw(arm_summary(awaitees)).fg(ctx.theme.dim),
+                ]);
+                let output = &awaitees[*arm];
+                if let FutureValueKind::Output(bytes) = &output.kind {
+                    line.push_span(Span::raw(" with "));
+                    line.extend((ctx.try_format_value)(bytes, &output.ty, ctx.max_elements));
+                }
+                line.push_span(Span::raw(":"));
+                // The resolved arm returned, there is nothing left to show below it.
+                for (i, awaitee) in awaitees.iter().enumerate() {
+                    if i != *arm {
+                        children.push((awaitee, i as u64));
                     }
                 }
                 line
@@ -247,68 +1259,153 @@ impl Task {
             FutureValueKind::SelectValue(value) => {
                 let line = Line::from_iter([
                     Span::raw("Select waiting on one of "),
-                    Span::raw(value.awaitees.len().to_string()).blue(),
-                    Span::raw(" futures:"),
+                    Span::raw(value.awaitees.len().to_string()).fg(ctx.theme.accent),
+                    Span::raw(" futures "),
+                    Span::raw(arm_summary(&value.awaitees)).fg(ctx.theme.dim),
+                    Span::raw(":"),
                 ]);
                 for (i, awaitee) in value.awaitees.iter().enumerate() {
                     children.push((awaitee, i as u64));
                 }
                 line
             }
+            FutureValueKind::JoinValue(value) if value.awaitees.is_empty() => {
+                Line::raw("Join over 0 futures, this completes immediately")
+            }
             FutureValueKind::JoinValue(value) => {
                 let line = Line::from_iter([
                     Span::raw("Join waiting on "),
-                    Span::raw(value.awaitees.len().to_string()).blue(),
-                    Span::raw(" futures:"),
+                    Span::raw(value.awaitees.len().to_string()).fg(ctx.theme.accent),
+                    Span::raw(" futures "),
+                    Span::raw(arm_summary(&value.awaitees)).fg(ctx.theme.dim),
+                    Span::raw(":"),
                 ]);
                 for (i, awaitee) in value.awaitees.iter().enumerate() {
                     children.push((awaitee, i as u64));
                 }
                 line
             }
-            FutureValueKind::Unknown { .. } => Line::raw(tree_data.value.ty.to_string()),
-        };
-        let details = if tree_data.item_state.details_open {
-            let text = match &tree_data.value.kind {
-                FutureValueKind::AsyncFn(value) => {
-                    let mut text = Text::raw("");
-                    if let Err((_, bytes)) = &value.state_value {
-                        let line = Line::from_iter([
-                            Span::raw("bytes ["),
-                            Span::raw(
-                                bytes
-                                    .iter()
-                                    .map(|b| format!(" {b:0>2x}"))
-                                    .collect::<String>(),
-                            )
-                            .blue(),
-                            Span::raw(" ]"),
-                        ]);
-                        text.push_line(line);
+            FutureValueKind::OptionValue(value) if value.fused => match &value.awaitee {
+                Some(awaitee) => {
+                    children.push((awaitee, 0));
+                    Line::raw("Fuse (fused, terminated=false) waiting on:")
+                }
+                None => Line::raw("Fuse (fused, terminated=true)").fg(ctx.theme.dim),
+            },
+            FutureValueKind::OptionValue(value) => match &value.awaitee {
+                Some(awaitee) => {
+                    children.push((awaitee, 0));
+                    Line::raw("Option waiting on:")
+                }
+                None => Line::raw("Option without a future").fg(ctx.theme.dim),
+            },
+            FutureValueKind::SharedValue(value) => match (value.pointer, &value.awaitee) {
+                (Some(pointer), Some(awaitee)) => {
+                    children.push((awaitee, 0));
+                    Line::from_iter([
+                        Span::raw("Shared at "),
+                        Span::raw(format!("{pointer:#x}")).fg(ctx.theme.accent),
+                        Span::raw(" waiting on:"),
+                    ])
+                }
+                (Some(pointer), None) => Line::from_iter([
+                    Span::raw("Shared at "),
+                    Span::raw(format!("{pointer:#x}")).fg(ctx.theme.accent),
+                    Span::raw(", which could not be read").fg(ctx.theme.dim),
+                ]),
+                (None, _) => Line::raw("Shared handle that completed").fg(ctx.theme.dim),
+            },
+            FutureValueKind::EmbassySyncValue(value) => {
+                let (prefix, target) = match value.kind {
+                    SyncFutureKind::Receive => ("Receive waiting on ", "channel"),
+                    SyncFutureKind::Send => ("Send waiting on ", "channel"),
+                    SyncFutureKind::Signal => ("Waiting on ", "signal"),
+                };
+                match (value.pointer, value.state) {
+                    (Some(pointer), Some(state)) => Line::from_iter([
+                        Span::raw(format!("{prefix}{target} at ")),
+                        Span::raw(format!("{pointer:#x}")).fg(ctx.theme.accent),
+                        Span::raw(format!(" ({state})")),
+                    ]),
+                    (Some(pointer), None) => Line::from_iter([
+                        Span::raw(format!("{prefix}{target} at ")),
+                        Span::raw(format!("{pointer:#x}")).fg(ctx.theme.accent),
+                        Span::raw(", which could not be read").fg(ctx.theme.dim),
+                    ]),
+                    (None, _) if !value.pointer_read => {
+                        Line::raw(format!("{prefix}a {target}, which could not be read"))
+                            .fg(ctx.theme.dim)
                     }
-                    text.extend(async_fn_to_text(
-                        &value.ty,
-                        Some(value),
-                        &mut ctx.try_format_value,
-                    ));
-                    text
+                    (None, _) => Line::raw(format!("{prefix}a {target} behind a null pointer"))
+                        .fg(ctx.theme.dim),
                 }
-                FutureValueKind::SelectValue(_) => {
-                    Text::from("Select polls ready the moment one of its childs is ready")
+            }
+            FutureValueKind::TimerValue(value) => {
+                let mut line = Line::raw("Timer until ");
+                line.extend((ctx.try_format_value)(
+                    &value.expires_at,
+                    &value.expires_at_type,
+                    ctx.max_elements,
+                ));
+                if let Some(ticks) = value.ticks() {
+                    line.push_span(Span::raw(format!(" ({ticks} ticks)")).fg(ctx.theme.dim));
                 }
-                FutureValueKind::JoinValue(_) => {
-                    Text::from("Select polls ready once all of its children have polled ready once")
+                // Fired timers wake the task, which then returns from the timer at the next poll.
+                let queued = tree_data.task.is_some_and(|(pool_path, task_idx)| {
+                    ctx.values.iter().any(|pool| {
+                        pool.task_pool.path == pool_path
+                            && matches!(
+                                pool.task_values.get(task_idx),
+                                Some(TaskValue::Init(_, TaskRunState::Queued))
+                            )
+                    })
+                });
+                if !value.yielded_once {
+                    line.push_span(Span::raw(", not polled yet").fg(ctx.theme.dim));
+                } else if queued {
+                    line.push_span(Span::raw(", fired").green());
                 }
-                FutureValueKind::Unknown(bytes) => {
-                    Text::from((ctx.try_format_value)(bytes, &tree_data.value.ty))
+                line
+            }
+            FutureValueKind::Output(bytes) => {
+                let mut line = match ctx.accessible {
+                    true => Line::raw("✓ Done with "),
+                    false => Line::raw("Done with "),
+                };
+                line.extend(style_type_name(&tree_data.value.ty.to_string(), &ctx.theme));
+                let formatted =
+                    (ctx.try_format_value)(bytes, &tree_data.value.ty, ctx.max_elements);
+                // Empty when unformattable values are hidden, see `UnformattableValues::Hidden`.
+                if !formatted.spans.is_empty() {
+                    line.push_span(" = ");
+                    line.extend(formatted);
                 }
-            };
-
-            Some(Paragraph::new(text).wrap(Default::default()))
-        } else {
-            None
+                line
+            }
+            FutureValueKind::Unknown { .. } => {
+                Line::from(style_type_name(&tree_data.value.ty.to_string(), &ctx.theme))
+            }
         };
 
+        if tree_data.show_sizes {
+            line.push_span(Span::raw(format!(" ({} B)", tree_data.value.size())).fg(ctx.theme.dim));
+        }
+        if tree_data.show_addresses {
+            line.push_span(
+                Span::raw(format!(" @ {:#x}", tree_data.value.address)).fg(ctx.theme.dim),
+            );
+        }
+
+        (line, children)
+    }
+
+    fn add_future(
+        tree_data: &TreeData,
+        scroll_view: &mut ScrollView,
+        ctx: &mut UiDrawCtx,
+    ) -> Result<(), UiEvent> {
+        let (mut line, children) = Self::future_line(tree_data, ctx);
+
         let indent = tree_data.path.len() as u16 * 2;
         let text_width = scroll_view
             .frame_size()
@@ -319,18 +1416,61 @@ impl Task {
             return Ok(());
         }
 
+        let details = if tree_data.item_state.details_open && tree_data.selected.is_none() {
+            let mut lines =
+                Self::details(tree_data.value, tree_data.pinned, tree_data.previous, ctx);
+            if let FutureValueKind::AsyncFn(_) = &tree_data.value.kind {
+                lines.insert(0, Line::raw("").into());
+            }
+
+            let lines = lines
+                .into_iter()
+                .map(|detail| {
+                    let paragraph = Paragraph::new(detail.line).wrap(Default::default());
+                    let height = paragraph.line_count(text_width) as u16;
+                    (paragraph, height, detail.on_click, detail.copy)
+                })
+                .collect::<Vec<_>>();
+
+            Some(lines)
+        } else {
+            None
+        };
+
+        let is_selected = tree_data.selected == Some(tree_data.path.as_slice());
+        let is_highlighted = tree_data.highlighted == Some(tree_data.path.as_slice());
+        if ctx.accessible && (is_selected || is_highlighted) {
+            line.spans.insert(0, Span::raw("> "));
+        }
+        let line = match is_selected {
+            true => line.bg(ctx.theme.selected),
+            false => line,
+        };
+        let line = match is_highlighted {
+            true => line.bg(ctx.theme.highlight),
+            false => line,
+        };
         let line = Paragraph::new(line).wrap(Default::default());
 
         let line_height = line.line_count(text_width);
         let detail_height = if let Some(details) = &details {
             // Adding one for the border
-            details.line_count(text_width) + 1
+            details
+                .iter()
+                .map(|(_, height, _, _)| *height as usize)
+                .sum::<usize>()
+                + 1
         } else {
             0
         };
         let total_height = line_height + detail_height;
 
         let mut area = scroll_view.next_area(total_height as u16);
+        if tree_data.scroll_to_highlighted
+            && tree_data.highlighted == Some(tree_data.path.as_slice())
+        {
+            return Err(UiEvent::SetScroll(area.y as i32));
+        }
         area.x += indent;
         area.width -= indent;
 
@@ -350,45 +1490,399 @@ impl Task {
 
         area.x += 1;
         area.width = area.width.saturating_sub(1);
-        if let Some(detail) = details {
+        // The block goes first as the line is drawn over its top border.
+        let details = details.map(|detail| {
             let block = Block::bordered().padding(Padding::top(line_height as u16 - 1));
             let detail_area = block.inner(area);
             scroll_view.render_widget(block, area);
-            let area = scroll_view.render_widget(detail, detail_area);
-            if is_clicked_left(&area, ctx.click) {
-                return Err(UiEvent::ToggleDetails(tree_data.path.clone()));
-            }
-        }
+            (detail_area, detail)
+        });
 
         area.x += 1;
         area.width = area.width.saturating_sub(2); // Minus 2 to leave space for border if details are open
         area.height = line_height as u16;
-        let area = scroll_view.render_widget(line, area);
-        if is_clicked_left(&area, ctx.click) {
-            return Err(UiEvent::ToggleDetails(tree_data.path.clone()));
+        let vis_area = scroll_view.render_widget(line, area);
+        if ctx.selectable_row(scroll_view, area, vis_area)? {
+            return Err(match tree_data.selected {
+                Some(_) => UiEvent::SelectFuture(tree_data.path.clone()),
+                None => UiEvent::ToggleDetails(tree_data.path.clone()),
+            });
+        }
+
+        // Selected after the line of the future, in the order they are drawn.
+        if let Some((mut detail_area, detail)) = details {
+            for (paragraph, height, on_click, copy) in detail {
+                detail_area.height = height;
+                let area = scroll_view.render_widget(paragraph, detail_area);
+                let clicked = match copy {
+                    Some(copy) if is_clicked_middle(&area, ctx.click) => {
+                        return Err(UiEvent::CopyToClipboard(copy));
+                    }
+                    Some(copy) => {
+                        ctx.copyable_row(scroll_view, detail_area, area, &copy, UiEvent::SetScroll)?
+                    }
+                    None => is_clicked_left(&area, ctx.click),
+                };
+                if clicked {
+                    return Err(
+                        on_click.unwrap_or_else(|| UiEvent::ToggleDetails(tree_data.path.clone()))
+                    );
+                }
+                detail_area.y += height;
+            }
         }
 
         if tree_data.item_state.closed {
             return Ok(());
         }
 
+        let await_point_indent = indent + 2;
+        for line in Self::await_point_lines(tree_data, &ctx.theme) {
+            let line = Paragraph::new(line).wrap(Default::default());
+            let width = scroll_view
+                .frame_size()
+                .width
+                .saturating_sub(await_point_indent);
+            let height = line.line_count(width) as u16;
+
+            let mut area = scroll_view.next_area(height);
+            area.x += await_point_indent;
+            area.width = area.width.saturating_sub(await_point_indent);
+            scroll_view.render_widget(line, area);
+        }
+
         for (child_value, path_id) in children {
             let mut child_path = tree_data.path.clone();
             child_path.push(path_id);
 
             let item_state = match tree_data.item_state.children.get(&path_id) {
                 Some(item_state) => item_state,
-                None => &ItemState::default(),
+                None => &ItemState::new(tree_data.item_state.children_closed),
             };
 
             let child_tree_data = TreeData {
                 value: child_value,
+                pinned: tree_data
+                    .pinned
+                    .and_then(|pinned| Self::child_future(pinned, path_id)),
+                previous: tree_data
+                    .previous
+                    .and_then(|previous| Self::child_future(previous, path_id)),
                 path: child_path,
                 item_state,
+                task: tree_data.task,
+                selected: tree_data.selected,
+                show_await_points: tree_data.show_await_points,
+                show_sizes: tree_data.show_sizes,
+                show_addresses: tree_data.show_addresses,
+                highlighted: tree_data.highlighted,
+                scroll_to_highlighted: tree_data.scroll_to_highlighted,
             };
 
-            Self::add_future(&child_tree_data, scroll_view, ctx)?;
+            Self::add_future(&child_tree_data, scroll_view, ctx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the tree as shown on the page to `text`, with open details below their future.
+    fn tree_to_text(tree_data: &TreeData, ctx: &mut UiDrawCtx, text: &mut String) {
+        let (line, children) = Self::future_line(tree_data, ctx);
+        let indent = "  ".repeat(tree_data.path.len());
+        let marker = match tree_data.item_state.closed {
+            true => "-",
+            false => "+",
+        };
+        let _ = writeln!(text, "{indent}{marker} {line}");
+
+        if tree_data.item_state.details_open {
+            let details = Self::details(tree_data.value, tree_data.pinned, tree_data.previous, ctx);
+            for DetailLine { line, .. } in details {
+                let _ = writeln!(text, "{indent}  | {line}");
+            }
+        }
+
+        if tree_data.item_state.closed {
+            return;
+        }
+
+        for line in Self::await_point_lines(tree_data, &ctx.theme) {
+            let _ = writeln!(text, "{indent}  {line}");
+        }
+
+        for (child_value, path_id) in children {
+            let mut child_path = tree_data.path.clone();
+            child_path.push(path_id);
+
+            let item_state = match tree_data.item_state.children.get(&path_id) {
+                Some(item_state) => item_state,
+                None => &ItemState::new(tree_data.item_state.children_closed),
+            };
+
+            let child_tree_data = TreeData {
+                value: child_value,
+                pinned: tree_data
+                    .pinned
+                    .and_then(|pinned| Self::child_future(pinned, path_id)),
+                previous: tree_data
+                    .previous
+                    .and_then(|previous| Self::child_future(previous, path_id)),
+                path: child_path,
+                item_state,
+                task: tree_data.task,
+                selected: None,
+                show_await_points: tree_data.show_await_points,
+                show_sizes: tree_data.show_sizes,
+                show_addresses: tree_data.show_addresses,
+                highlighted: tree_data.highlighted,
+                scroll_to_highlighted: tree_data.scroll_to_highlighted,
+            };
+
+            Self::tree_to_text(&child_tree_data, ctx, text);
+        }
+    }
+
+    /// Lines with the details of a future, shown inline or in the details pane.
+    fn details<'a>(
+        value: &'a FutureValue,
+        pinned: Option<&'a FutureValue>,
+        previous: Option<&'a FutureValue>,
+        ctx: &mut UiDrawCtx,
+    ) -> Vec<DetailLine<'a>> {
+        match &value.kind {
+            FutureValueKind::AsyncFn(async_fn) => {
+                let state_member = &async_fn.ty.state_member;
+                let state_bytes = match &async_fn.state_value {
+                    // Discriminants are read as little endian, see `AsyncFnType::read_discriminant`.
+                    Ok(state) => {
+                        let size = (state_member.size as usize).min(8);
+                        state.discriminant.to_le_bytes()[..size].to_vec()
+                    }
+                    Err((_, bytes)) => member_bytes(bytes, state_member).to_vec(),
+                };
+                let mut lines = vec![DetailLine {
+                    line: state_member_line(&async_fn.ty, &ctx.theme),
+                    on_click: Some(UiEvent::EditMemory {
+                        address: value.address + state_member.offset,
+                        bytes: state_bytes,
+                    }),
+                    copy: None,
+                }];
+                if let Err((discriminant, bytes)) = &async_fn.state_value {
+                    let state_member = &async_fn.ty.state_member;
+                    let error = match discriminant {
+                        Some(discriminant) => format!("No state has discriminant {discriminant}"),
+                        None => String::from("The discriminant could not be read"),
+                    };
+                    lines.push(
+                        Line::from_iter([
+                            Span::raw(format!(
+                                "{error}, read from {}[{}] {} = ",
+                                state_member.offset, state_member.size, state_member.name
+                            )),
+                            Span::raw(format_raw_bytes(member_bytes(bytes, state_member)))
+                                .fg(ctx.theme.accent),
+                        ])
+                        .into(),
+                    );
+                    let known = async_fn
+                        .ty
+                        .states
+                        .iter()
+                        .map(|state| format!("{} ({})", state.discriminant_label(), state.name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    lines.push(Line::raw(format!("Known discriminants: {known}")).into());
+                    lines.push(format_bytes(bytes).into());
+                }
+                let as_async_fn = |value: Option<&'a FutureValue>| match value.map(|v| &v.kind) {
+                    Some(FutureValueKind::AsyncFn(value)) => Some(value),
+                    _ => None,
+                };
+                lines.extend(async_fn_to_text(
+                    &async_fn.ty,
+                    Some((async_fn, value.address)),
+                    as_async_fn(pinned),
+                    as_async_fn(previous),
+                    ctx,
+                ));
+                if let Ok(state) = &async_fn.state_value
+                    && let Some(source) = &state.state.source
+                {
+                    lines.extend(source_context(source, ctx));
+                }
+                lines
+            }
+            FutureValueKind::SelectValue(value) if value.done => vec![
+                Line::raw("Select polls ready the moment one of its childs is ready").into(),
+                Line::raw(
+                    "It already did, it is only in memory until the future holding it continues",
+                )
+                .into(),
+            ],
+            FutureValueKind::SelectValue(_) => {
+                vec![Line::raw("Select polls ready the moment one of its childs is ready").into()]
+            }
+            FutureValueKind::JoinValue(_) => vec![
+                Line::raw("Select polls ready once all of its children have polled ready once")
+                    .into(),
+            ],
+            FutureValueKind::OptionValue(value) if value.fused => vec![
+                Line::raw("A fused future, terminated once the future inside completed").into(),
+            ],
+            FutureValueKind::OptionValue(_) => vec![
+                Line::raw("An optional future, polls the future inside when there is one").into(),
+            ],
+            FutureValueKind::SharedValue(_) => vec![
+                Line::raw("A handle to a future shared by its clones, behind an Arc pointer")
+                    .into(),
+                Line::raw("The future is polled by whichever clone is polled").into(),
+            ],
+            FutureValueKind::TimerValue(_) => vec![
+                Line::raw("A timer, polls ready once the time driver reached its deadline").into(),
+                Line::raw("The deadline uses the tick rate of embassy-time, by default 1 MHz")
+                    .into(),
+            ],
+            FutureValueKind::EmbassySyncValue(value) => match value.kind {
+                SyncFutureKind::Receive => vec![
+                    Line::raw("Receiving from an embassy-sync channel, behind a reference").into(),
+                    Line::raw("Polls ready once a message is queued").into(),
+                ],
+                SyncFutureKind::Send => vec![
+                    Line::raw("Sending to an embassy-sync channel, behind a reference").into(),
+                    Line::raw("Polls ready once the queue has room for the message").into(),
+                ],
+                SyncFutureKind::Signal => vec![
+                    Line::raw("Waiting on an embassy-sync signal, behind a reference").into(),
+                    Line::raw("Polls ready once the signal is signaled, taking the value").into(),
+                ],
+            },
+            FutureValueKind::Output(bytes) | FutureValueKind::Unknown(bytes) => {
+                let mut lines =
+                    vec![(ctx.try_format_value)(bytes, &value.ty, ctx.max_elements).into()];
+                if let Some(pinned) = pinned
+                    && let FutureValueKind::Output(pinned_bytes)
+                    | FutureValueKind::Unknown(pinned_bytes) = &pinned.kind
+                    && pinned_bytes != bytes
+                {
+                    let mut line = Line::raw("pinned: ");
+                    line.extend((ctx.try_format_value)(
+                        pinned_bytes,
+                        &pinned.ty,
+                        ctx.max_elements,
+                    ));
+                    lines.push(line.fg(ctx.theme.dim).into());
+                }
+                lines
+            }
+        }
+    }
+
+    /// Split the area into the tree, the divider and the details pane if there is enough space.
+    fn split_areas(
+        &self,
+        ctx: &mut UiDrawCtx,
+        area: Rect,
+    ) -> Result<(Rect, Option<Rect>), UiEvent> {
+        let Some(detail_width) = self.detail_width else {
+            return Ok((area, None));
+        };
+        if area.width < MIN_SPLIT_WIDTH {
+            return Ok((area, None));
+        }
+
+        let max_detail_width = area.width - MIN_TREE_WIDTH;
+        let detail_width = detail_width.clamp(MIN_DETAIL_WIDTH, max_detail_width);
+        let [tree_area, divider_area, detail_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(detail_width),
+        ])
+        .areas(area);
+
+        if let Some(drag) = ctx.drag
+            && area.contains(drag)
+            && drag.x.abs_diff(divider_area.x) <= DIVIDER_GRAB_DISTANCE
+            && drag.x != divider_area.x
+        {
+            let width = (area.right() - drag.x - 1).clamp(MIN_DETAIL_WIDTH, max_detail_width);
+            return Err(UiEvent::SetDetailWidth(width));
+        }
+
+        ctx.frame
+            .render_widget(Block::new().borders(Borders::LEFT), divider_area);
+
+        Ok((tree_area, Some(detail_area)))
+    }
+
+    fn draw_detail_pane(
+        &self,
+        value: &FutureValue,
+        previous: Option<&FutureValue>,
+        ctx: &mut UiDrawCtx,
+        area: Rect,
+    ) -> Result<(), UiEvent> {
+        let block = Block::bordered().title(" Details ");
+        let inner = block.inner(area);
+        ctx.frame.render_widget(block, area);
+
+        if let Some((position, lines)) = ctx.scroll
+            && area.contains(position)
+        {
+            return Err(UiEvent::SetDetailScroll(
+                (self.detail_scroll + lines).max(0),
+            ));
+        }
+
+        let Some((value, pinned)) =
+            Self::find_future(value, self.pinned.as_deref(), &self.selected)
+        else {
+            ctx.frame.render_widget(
+                Paragraph::new("Select a future in the tree to see its details")
+                    .wrap(Default::default()),
+                inner,
+            );
+            return Ok(());
+        };
+
+        let previous = previous
+            .and_then(|previous| Self::find_future(previous, None, &self.selected))
+            .map(|(previous, _)| previous);
+
+        let mut scroll_view = ScrollView::new(inner, self.detail_scroll);
+        for DetailLine {
+            line,
+            on_click,
+            copy,
+        } in Self::details(value, pinned, previous, ctx)
+        {
+            let paragraph = Paragraph::new(line).wrap(Default::default());
+            let height = paragraph.line_count(inner.width) as u16;
+            let scroll_area = scroll_view.next_area(height);
+            let area = scroll_view.render_widget(paragraph, scroll_area);
+            let clicked = match copy {
+                Some(copy) if is_clicked_middle(&area, ctx.click) => {
+                    return Err(UiEvent::CopyToClipboard(copy));
+                }
+                Some(copy) => ctx.copyable_row(
+                    &mut scroll_view,
+                    scroll_area,
+                    area,
+                    &copy,
+                    UiEvent::SetDetailScroll,
+                )?,
+                None => is_clicked_left(&area, ctx.click),
+            };
+            if let Some(on_click) = on_click
+                && clicked
+            {
+                return Err(on_click);
+            }
+        }
+        if scroll_view.max_scroll() < self.detail_scroll {
+            return Err(UiEvent::SetDetailScroll(scroll_view.max_scroll()));
         }
+        ctx.frame.render_widget(scroll_view, inner);
 
         Ok(())
     }
@@ -401,49 +1895,227 @@ impl UiPage for Task {
     }
 
     fn apply_event(&mut self, event: UiEvent) {
+        let highlighted = self.highlighted.take();
+
         match event {
-            UiEvent::SetScroll(scroll) => self.scroll = scroll,
+            UiEvent::SetScroll(scroll) => {
+                self.scroll = scroll;
+                // Keep the highlight when this is the scroll to it.
+                if self.scroll_to_highlighted {
+                    self.scroll_to_highlighted = false;
+                    self.highlighted = highlighted;
+                }
+            }
             UiEvent::ToggleClosed(path) => {
                 self.item_state.toggle_closed(&path);
             }
+            UiEvent::ExpandAll | UiEvent::CollapseAll => {
+                let closed = matches!(event, UiEvent::CollapseAll);
+                self.item_state.set_all_closed(closed);
+                // The tree changed height, scroll back to the selected future or, when it got
+                // collapsed away, to the root.
+                if closed && !self.selected.is_empty() {
+                    self.selected.clear();
+                    self.detail_scroll = 0;
+                }
+                self.highlighted = Some(self.selected.clone());
+                self.scroll_to_highlighted = true;
+            }
             UiEvent::ToggleDetails(path) => {
                 self.item_state.toggle_details(&path);
+                // Also the focused future when the details are inline.
+                self.selected = path;
+                self.detail_scroll = 0;
+            }
+            UiEvent::SelectParent => {
+                if self.selected.pop().is_some() {
+                    self.detail_scroll = 0;
+                    self.highlighted = Some(self.selected.clone());
+                    self.scroll_to_highlighted = true;
+                }
+            }
+            UiEvent::SetPinned(pinned) => self.pinned = pinned,
+            UiEvent::ToggleSplit => {
+                self.detail_width = match self.detail_width {
+                    Some(_) => None,
+                    None => Some(DEFAULT_DETAIL_WIDTH),
+                }
             }
+            UiEvent::SelectFuture(path) => {
+                self.selected = path;
+                self.detail_scroll = 0;
+            }
+            UiEvent::SetDetailWidth(width) => self.detail_width = Some(width),
+            UiEvent::SetDetailScroll(scroll) => self.detail_scroll = scroll,
+            UiEvent::ToggleAwaitPoints => self.show_await_points = !self.show_await_points,
+            UiEvent::ToggleSizes => self.show_sizes = !self.show_sizes,
+            UiEvent::ToggleAddresses => self.show_addresses = !self.show_addresses,
             _ => {}
         }
     }
 
-    fn title(&self, values: &[TaskPoolValue]) -> String {
-        format!(
-            "Task: {}[{}]",
-            values[self.pool_idx].task_pool.path, self.task_idx
-        )
+    fn buttons(&self, ctx: &UiDrawCtx) -> Vec<(&'static str, UiEvent)> {
+        let mut buttons = vec![match self.detail_width {
+            Some(_) => ("Single view", UiEvent::ToggleSplit),
+            None => ("Split view", UiEvent::ToggleSplit),
+        }];
+
+        if self.pinned.is_some() {
+            buttons.push(("Unpin", UiEvent::SetPinned(None)));
+        } else if let Some(PageFuture {
+            value: Ok(value), ..
+        }) = self.page_future(ctx)
+        {
+            buttons.push((
+                "Pin values",
+                UiEvent::SetPinned(Some(Box::new(value.clone()))),
+            ));
+        }
+
+        if !self.selected.is_empty() {
+            buttons.push(("Parent", UiEvent::SelectParent));
+        }
+
+        buttons.push(match ctx.highlight_changes {
+            true => ("Changes [x]", UiEvent::ToggleChanges),
+            false => ("Changes [ ]", UiEvent::ToggleChanges),
+        });
+        buttons.push(("Expand all", UiEvent::ExpandAll));
+        buttons.push(("Collapse all", UiEvent::CollapseAll));
+
+        if ctx.holding {
+            buttons.push(("Continue", UiEvent::Continue));
+        } else if let Some(PageFuture {
+            task: Some((pool_path, task_idx)),
+            ..
+        }) = self.page_future(ctx)
+        {
+            buttons.push(match ctx.state_break == Some((pool_path, task_idx)) {
+                true => ("Stop waiting", UiEvent::RunToNextState(None)),
+                false => (
+                    "Run to next state",
+                    UiEvent::RunToNextState(Some((pool_path.to_owned(), task_idx))),
+                ),
+            });
+        }
+
+        buttons.push(match self.show_await_points {
+            true => ("Await points [x]", UiEvent::ToggleAwaitPoints),
+            false => ("Await points [ ]", UiEvent::ToggleAwaitPoints),
+        });
+        buttons.push(match self.show_sizes {
+            true => ("Sizes [x]", UiEvent::ToggleSizes),
+            false => ("Sizes [ ]", UiEvent::ToggleSizes),
+        });
+        buttons.push(match self.show_addresses {
+            true => ("Addresses [x]", UiEvent::ToggleAddresses),
+            false => ("Addresses [ ]", UiEvent::ToggleAddresses),
+        });
+
+        buttons
+    }
+
+    fn task(&self) -> Option<(usize, usize)> {
+        match self.source {
+            FutureSource::Task { pool_idx, task_idx } => Some((pool_idx, task_idx)),
+            FutureSource::Address(_) => None,
+        }
+    }
+
+    fn future_by_address(&self) -> Option<usize> {
+        match self.source {
+            FutureSource::Address(idx) => Some(idx),
+            FutureSource::Task { .. } => None,
+        }
+    }
+
+    fn item_state(&self) -> Option<&ItemState> {
+        Some(&self.item_state)
+    }
+
+    fn restore_item_state(&mut self, item_state: ItemState) {
+        self.item_state = item_state;
+    }
+
+    fn title(&self, ctx: &UiDrawCtx) -> String {
+        self.page_future(ctx)
+            .map(|future| future.title)
+            .unwrap_or_default()
+    }
+
+    fn to_text(&self, ctx: &mut UiDrawCtx) -> Option<String> {
+        let future = self.page_future(ctx)?;
+        let mut text = future.title + "\n";
+
+        match future.value {
+            Err(message) => {
+                text.push_str(message);
+                text.push('\n');
+            }
+            Ok(value) => {
+                if let FutureValueKind::AsyncFn(async_fn) = &value.kind {
+                    text.push_str(&state_member_line(&async_fn.ty, &ctx.theme).to_string());
+                    text.push('\n');
+                }
+                text.push_str("Await point backtrace:\n");
+                let tree_data = TreeData {
+                    value,
+                    pinned: self.pinned.as_deref(),
+                    previous: future.previous,
+                    path: Vec::new(),
+                    item_state: &self.item_state,
+                    task: future.task,
+                    selected: None,
+                    show_await_points: self.show_await_points,
+                    show_sizes: self.show_sizes,
+                    show_addresses: self.show_addresses,
+                    highlighted: self.highlighted.as_deref(),
+                    scroll_to_highlighted: self.scroll_to_highlighted,
+                };
+                Self::tree_to_text(&tree_data, ctx, &mut text);
+            }
+        }
+
+        Some(text)
     }
 
     fn draw(&self, ctx: &mut UiDrawCtx, area: Rect) -> Result<(), UiEvent> {
-        let Some(pool) = ctx.values.get(self.pool_idx) else {
-            return Err(UiEvent::Back);
-        };
-        let Some(task) = pool.task_values.get(self.task_idx) else {
+        let Some(future) = self.page_future(ctx) else {
             return Err(UiEvent::Back);
         };
 
-        let mut scroll_view = ScrollView::new(area, self.scroll);
+        let (area, detail_area) = self.split_areas(ctx, area)?;
+        let mut scroll_view = ScrollView::new(area, self.scroll).with_scrollbar(true);
 
-        match task {
-            TaskValue::Uninit => {
-                scroll_view.render_next_widget(Line::raw("Task is uninitialized"), 1);
+        match future.value {
+            Err(message) => {
+                scroll_view.render_next_widget(Line::raw(message), 1);
             }
-            TaskValue::Init(value) => {
+            Ok(value) => {
+                if let FutureValueKind::AsyncFn(async_fn) = &value.kind {
+                    scroll_view.render_next_widget(state_member_line(&async_fn.ty, &ctx.theme), 1);
+                }
                 scroll_view.render_next_widget(Line::raw("Await point backtrace:"), 1);
 
                 let tree_data = TreeData {
                     value,
+                    pinned: self.pinned.as_deref(),
+                    previous: future.previous,
                     path: Vec::new(),
                     item_state: &self.item_state,
+                    task: future.task,
+                    selected: detail_area.map(|_| self.selected.as_slice()),
+                    show_await_points: self.show_await_points,
+                    show_sizes: self.show_sizes,
+                    show_addresses: self.show_addresses,
+                    highlighted: self.highlighted.as_deref(),
+                    scroll_to_highlighted: self.scroll_to_highlighted,
                 };
 
                 Self::add_future(&tree_data, &mut scroll_view, ctx)?;
+                if let Some(detail_area) = detail_area {
+                    self.draw_detail_pane(value, future.previous, ctx, detail_area)?;
+                }
 
                 scroll_view.render_next_widget(Line::default(), 1);
                 scroll_view.render_next_widget(
@@ -452,6 +2124,20 @@ impl UiPage for Task {
                     ),
                     1,
                 );
+                if detail_area.is_some() {
+                    scroll_view.render_next_widget(
+                        Line::raw("Drag the divider to resize the details pane"),
+                        1,
+                    );
+                }
+                scroll_view.render_next_widget(
+                    Line::raw("Click on a member in the details to watch its memory for changes"),
+                    1,
+                );
+                scroll_view.render_next_widget(
+                    Line::raw("Pin the values to show them next to the values of later stops"),
+                    1,
+                );
             }
         }
 
@@ -465,16 +2151,334 @@ impl UiPage for Task {
     }
 }
 
+/// New bytes being typed for target memory, see [`UiEvent::EditMemory`].
+#[derive(Debug)]
+struct MemoryEdit {
+    address: u64,
+    /// Number of bytes to write, the same as were there.
+    size: usize,
+    input: String,
+    /// Why the last input could not be written.
+    error: Option<String>,
+}
+
+impl MemoryEdit {
+    /// The event writing the typed bytes, or why they can't be written.
+    fn write_event(&self) -> Result<UiEvent, String> {
+        let bytes = parse_raw_bytes(&self.input)?;
+        if bytes.len() != self.size {
+            return Err(format!("expected {} bytes, got {}", self.size, bytes.len()));
+        }
+        Ok(UiEvent::WriteMemory {
+            address: self.address,
+            bytes,
+        })
+    }
+}
+
+/// The address and type of a future being typed, see [`UiEvent::PromptFuture`].
+#[derive(Debug, Default)]
+struct FuturePrompt {
+    input: String,
+    /// Why the last input could not be parsed.
+    error: Option<String>,
+}
+
+impl FuturePrompt {
+    /// The event showing the typed future, or why the input is not an address and a type.
+    fn inspect_event(&self) -> Result<UiEvent, String> {
+        let (address, type_path) = parse_future_prompt(&self.input)?;
+        Ok(UiEvent::InspectFuture { address, type_path })
+    }
+}
+
+/// Parse an address, in hex with `0x` or in decimal, and the path of a type after it.
+fn parse_future_prompt(input: &str) -> Result<(u64, String), String> {
+    let (address, type_path) = input
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| String::from("expected an address and a type"))?;
+    let address = match address.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => address.parse(),
+    }
+    .map_err(|_| format!("`{address}` is not an address"))?;
+    Ok((address, type_path.trim().to_owned()))
+}
+
+/// The source code around the await point of the current state of an async fn, see
+/// [`Config::source_context_lines`](crate::Config::source_context_lines).
+fn source_context<'a>(source: &Source, ctx: &mut UiDrawCtx) -> Vec<DetailLine<'a>> {
+    let Some(context) = ctx.source_context_lines else {
+        return Vec::new();
+    };
+
+    let mut lines = vec![Line::default().into()];
+    let Some(code) = ctx.source_files.context(source, context) else {
+        lines.push(
+            Line::raw(format!("source unavailable: {source}"))
+                .fg(ctx.theme.dim)
+                .into(),
+        );
+        return lines;
+    };
+
+    lines.push(
+        Line::raw(format!("source at {source}:"))
+            .fg(ctx.theme.dim)
+            .into(),
+    );
+    let width = code
+        .last()
+        .map_or(0, |(number, _)| number.to_string().len());
+    for (number, code) in code {
+        let marker = match number == source.line {
+            true => ">",
+            false => " ",
+        };
+        let line = Line::from_iter([
+            Span::raw(format!("{marker} {number:>width$} │ ")).fg(ctx.theme.dim),
+            Span::raw(code.to_owned()),
+        ]);
+        lines.push(match number == source.line {
+            true => line.bg(ctx.theme.highlight).into(),
+            false => line.into(),
+        });
+    }
+    lines
+}
+
+/// Height of the log pane including its title.
+const LOG_PANE_HEIGHT: u16 = 8;
+
 #[derive(Debug)]
 pub(crate) struct UiState {
     page_stack: Vec<Box<dyn UiPage + Sync + Send>>,
+    /// The row selected with the keyboard on each page of the stack, kept for the pages below
+    /// the top so going back returns to the same row.
+    selected_rows: Vec<Option<usize>>,
+    /// Which futures were open on the last page of each task by pool and task index, see
+    /// [`UiPage::item_state`].
+    item_states: HashMap<(usize, usize), ItemState>,
+    /// Number of selectable rows on the top page when it was last drawn.
+    selectable_rows: usize,
+    /// See [`Selection::reveal`].
+    reveal_selected: bool,
+    /// See [`Selection::activate`].
+    activate_selected: bool,
+    /// See [`Selection::copy`].
+    copy_selected: bool,
+
+    /// Whether to show the executing task every time the target stops.
+    follow: bool,
+    /// Whether to show the legend of the state table of async fns.
+    show_legend: bool,
+    /// Whether to show the raw bytes of members next to their decoded values.
+    show_raw_bytes: bool,
+    /// Whether to highlight the members that changed since the previous stop.
+    highlight_changes: bool,
+    /// Whether to show the pane with the recent log records.
+    show_logs: bool,
+    /// The most verbose level shown in the log pane.
+    log_level: log::Level,
+    /// The bytes being typed to write to the target, shown instead of the title.
+    memory_edit: Option<MemoryEdit>,
+    /// The future being typed to show, shown instead of the title.
+    future_prompt: Option<FuturePrompt>,
+    /// See [`Config::max_width`](crate::Config::max_width).
+    max_width: Option<u16>,
 }
 
 impl UiState {
-    pub(crate) fn new() -> Self {
+    /// See [`Config::compact_main_menu`](crate::Config::compact_main_menu),
+    /// [`Config::hide_uninit_tasks`](crate::Config::hide_uninit_tasks) and
+    /// [`Config::max_width`](crate::Config::max_width).
+    pub(crate) fn new(
+        compact_main_menu: bool,
+        hide_uninit_tasks: bool,
+        max_width: Option<u16>,
+    ) -> Self {
         Self {
-            page_stack: vec![Box::new(MainMenu::new())],
+            page_stack: vec![Box::new(MainMenu::new(
+                compact_main_menu,
+                hide_uninit_tasks,
+            ))],
+            selected_rows: vec![None],
+            item_states: HashMap::new(),
+            selectable_rows: 0,
+            reveal_selected: false,
+            activate_selected: false,
+            copy_selected: false,
+            follow: false,
+            show_legend: false,
+            show_raw_bytes: false,
+            highlight_changes: true,
+            show_logs: false,
+            log_level: log::Level::Warn,
+            memory_edit: None,
+            future_prompt: None,
+            max_width,
+        }
+    }
+
+    pub(crate) fn follow(&self) -> bool {
+        self.follow
+    }
+
+    pub(crate) fn show_legend(&self) -> bool {
+        self.show_legend
+    }
+
+    pub(crate) fn show_raw_bytes(&self) -> bool {
+        self.show_raw_bytes
+    }
+
+    pub(crate) fn highlight_changes(&self) -> bool {
+        self.highlight_changes
+    }
+
+    /// The content of the current page as plain text, see [`UiPage::to_text`].
+    pub(crate) fn page_text(&self, ctx: &mut UiDrawCtx) -> Option<String> {
+        self.top().to_text(ctx)
+    }
+
+    /// Show the page of a future added by address, replacing the top page if it shows a different
+    /// one.
+    pub(crate) fn show_future_by_address(&mut self, idx: usize) {
+        match self.top().future_by_address() {
+            Some(shown) if shown == idx => return,
+            Some(_) => self.pop_page(),
+            None => {}
+        }
+        self.push_page(Box::new(Task::from_source(FutureSource::Address(idx))));
+    }
+
+    /// Show the page of the given task, replacing the top page if it shows a different task.
+    pub(crate) fn show_task(&mut self, pool_idx: usize, task_idx: usize) {
+        match self.top().task() {
+            Some(task) if task == (pool_idx, task_idx) => return,
+            Some(_) => self.pop_page(),
+            None => {}
+        }
+        self.push_page(Box::new(Task::new(pool_idx, task_idx)));
+    }
+
+    fn push_page(&mut self, mut page: Box<dyn UiPage + Sync + Send>) {
+        if let Some(task) = page.task()
+            && let Some(item_state) = self.item_states.get(&task)
+        {
+            page.restore_item_state(item_state.clone());
+        }
+        self.page_stack.push(page);
+        self.selected_rows.push(None);
+    }
+
+    fn pop_page(&mut self) {
+        if let Some(page) = self.page_stack.pop()
+            && let Some(task) = page.task()
+            && let Some(item_state) = page.item_state()
+        {
+            self.item_states.insert(task, item_state.clone());
+        }
+        self.selected_rows.pop();
+    }
+
+    /// The row of the top page selected with the keyboard.
+    pub(crate) fn selection(&self) -> Option<Selection> {
+        let row = (*self.selected_rows.last()?)?;
+        Some(Selection {
+            row,
+            reveal: self.reveal_selected,
+            activate: self.activate_selected,
+            copy: self.copy_selected,
+        })
+    }
+
+    /// Called when the selected row handled being activated or copied, so it is only done once.
+    pub(crate) fn selection_activated(&mut self) {
+        self.activate_selected = false;
+        self.copy_selected = false;
+    }
+
+    /// Called once the top page is drawn with the number of rows it can select, see
+    /// [`UiDrawCtx::selectable_row`].
+    pub(crate) fn set_selectable_rows(&mut self, rows: usize) {
+        self.selectable_rows = rows;
+        self.reveal_selected = false;
+        self.activate_selected = false;
+        self.copy_selected = false;
+        if let Some(Some(row)) = self.selected_rows.last_mut() {
+            *row = (*row).min(rows.saturating_sub(1));
+        }
+    }
+
+    /// Keys the top page does not handle itself, see [`UiPage::key_event`], move the selection with
+    /// the arrow keys, activate it with enter, copy it with `y` and go back with left or backspace.
+    ///
+    /// While editing memory the keys go to the typed bytes, returning the event to write them for
+    /// the inspector once enter is pressed.
+    pub(crate) fn apply_key(&mut self, key: KeyCode) -> Option<UiEvent> {
+        if let Some(edit) = &mut self.memory_edit {
+            match key {
+                KeyCode::Char(c) if c.is_ascii_hexdigit() || c == ' ' => edit.input.push(c),
+                KeyCode::Backspace => {
+                    edit.input.pop();
+                }
+                KeyCode::Esc => self.memory_edit = None,
+                KeyCode::Enter => match edit.write_event() {
+                    Ok(event) => {
+                        self.memory_edit = None;
+                        return Some(event);
+                    }
+                    Err(e) => edit.error = Some(e),
+                },
+                _ => {}
+            }
+            return None;
+        }
+
+        if let Some(prompt) = &mut self.future_prompt {
+            match key {
+                KeyCode::Char(c) => prompt.input.push(c),
+                KeyCode::Backspace => {
+                    prompt.input.pop();
+                }
+                KeyCode::Esc => self.future_prompt = None,
+                KeyCode::Enter => match prompt.inspect_event() {
+                    Ok(event) => {
+                        self.future_prompt = None;
+                        return Some(event);
+                    }
+                    Err(e) => prompt.error = Some(e),
+                },
+                _ => {}
+            }
+            return None;
+        }
+
+        if let Some(event) = self.top().key_event(key) {
+            self.apply_event(event);
+            return None;
+        }
+
+        let rows = self.selectable_rows;
+        let Some(selected) = self.selected_rows.last_mut() else {
+            return None;
+        };
+
+        match key {
+            KeyCode::Up | KeyCode::Down if rows == 0 => {}
+            KeyCode::Up => *selected = Some(selected.map_or(rows - 1, |row| row.saturating_sub(1))),
+            KeyCode::Down => *selected = Some(selected.map_or(0, |row| (row + 1).min(rows - 1))),
+            // The first enter only shows the selection, so it is clear what the next one does.
+            KeyCode::Enter if selected.is_none() && rows != 0 => *selected = Some(0),
+            KeyCode::Enter => self.activate_selected = true,
+            KeyCode::Char('y') => self.copy_selected = true,
+            KeyCode::Left | KeyCode::Backspace => self.apply_event(UiEvent::Back),
+            _ => {}
         }
+        self.reveal_selected = true;
+        None
     }
 
     fn top(&self) -> &dyn UiPage {
@@ -489,15 +2493,56 @@ impl UiState {
         self.top_mut().apply_scroll(scroll);
     }
 
+    /// Whether typed characters go to the UI, see [`UiPage::takes_text`].
+    pub(crate) fn takes_text(&self) -> bool {
+        self.memory_edit.is_some() || self.future_prompt.is_some() || self.top().takes_text()
+    }
+
     pub(crate) fn apply_event(&mut self, event: UiEvent) {
         match event {
             UiEvent::Back => {
                 if self.page_stack.len() != 1 {
-                    self.page_stack.pop();
+                    self.pop_page();
                 }
             }
             UiEvent::AddPage(page) => {
-                self.page_stack.push(page);
+                self.push_page(page);
+            }
+            UiEvent::ToggleFollow => self.follow = !self.follow,
+            UiEvent::ToggleLegend => self.show_legend = !self.show_legend,
+            UiEvent::ToggleRawBytes => self.show_raw_bytes = !self.show_raw_bytes,
+            UiEvent::ToggleChanges => self.highlight_changes = !self.highlight_changes,
+            UiEvent::ToggleLogs => self.show_logs = !self.show_logs,
+            UiEvent::CycleLogLevel => {
+                self.log_level = match self.log_level {
+                    log::Level::Error => log::Level::Warn,
+                    log::Level::Warn => log::Level::Info,
+                    log::Level::Info => log::Level::Debug,
+                    log::Level::Debug => log::Level::Trace,
+                    log::Level::Trace => log::Level::Error,
+                }
+            }
+            UiEvent::Scroll(lines) => self.apply_scroll(lines),
+            UiEvent::PromptFuture => self.future_prompt = Some(FuturePrompt::default()),
+            UiEvent::EditMemory { address, bytes } => {
+                let input = bytes
+                    .iter()
+                    .map(|b| format!("{b:0>2x}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.memory_edit = Some(MemoryEdit {
+                    address,
+                    size: bytes.len(),
+                    input,
+                    error: None,
+                });
+            }
+            UiEvent::SetFilter(filter) => {
+                // The selected row would be a different pool with the new filter.
+                if let Some(selected) = self.selected_rows.last_mut() {
+                    *selected = None;
+                }
+                self.top_mut().apply_event(UiEvent::SetFilter(filter));
             }
             other => self.top_mut().apply_event(other),
         }
@@ -515,18 +2560,79 @@ impl UiState {
 
             let back = Line::raw("Back")
                 .alignment(ratatui::layout::Alignment::Center)
-                .black()
-                .on_white();
+                .fg(ctx.theme.header_fg)
+                .bg(ctx.theme.header_bg);
 
             ctx.frame.render_widget(back, back_area);
         }
 
-        let title = self.top().title(ctx.values);
+        let follow_button = match self.follow {
+            true => ("Follow [x]", UiEvent::ToggleFollow),
+            false => ("Follow [ ]", UiEvent::ToggleFollow),
+        };
+        let logs_button = match self.show_logs {
+            true => ("Logs [x]", UiEvent::ToggleLogs),
+            false => ("Logs [ ]", UiEvent::ToggleLogs),
+        };
+        let buttons = [follow_button, ("Copy", UiEvent::CopyPage)]
+            .into_iter()
+            .chain(logger::is_installed().then_some(logs_button))
+            .chain(self.top().buttons(ctx));
+
+        for (label, event) in buttons {
+            let [rest_area, button_area] = Layout::horizontal([
+                Constraint::Fill(1),
+                Constraint::Length(label.len() as u16 + 2),
+            ])
+            .areas(area);
+            area = rest_area;
+
+            if is_clicked_left(&button_area, ctx.click) {
+                return Err(event);
+            }
+
+            let button = Line::raw(label)
+                .alignment(ratatui::layout::Alignment::Center)
+                .fg(ctx.theme.header_fg)
+                .bg(ctx.theme.header_bg);
+
+            ctx.frame.render_widget(button, button_area);
+        }
 
-        let title = Line::raw(title)
-            .alignment(ratatui::layout::Alignment::Center)
+        let title = if let Some(edit) = &self.memory_edit {
+            let mut title = Line::raw(format!(
+                "Write {} bytes at {:#x} (hex, enter to write, escape to cancel): {}_",
+                edit.size, edit.address, edit.input
+            ))
             .black()
-            .on_white();
+            .on_yellow();
+            if let Some(error) = &edit.error {
+                title.push_span(Span::raw(format!("  {error}")).red());
+            }
+            title
+        } else if let Some(prompt) = &self.future_prompt {
+            let mut title = Line::raw(format!(
+                "Future to show as <address> <type> (enter to show, escape to cancel): {}_",
+                prompt.input
+            ))
+            .black()
+            .on_yellow();
+            if let Some(error) = &prompt.error {
+                title.push_span(Span::raw(format!("  {error}")).red());
+            }
+            title
+        } else if ctx.disconnected {
+            Line::raw("Target disconnected, retrying. Showing the last read values")
+                .white()
+                .on_red()
+        } else if let Some(status) = ctx.status {
+            Line::raw(status.to_owned()).black().on_green()
+        } else {
+            Line::raw(self.top().title(ctx))
+                .fg(ctx.theme.header_fg)
+                .bg(ctx.theme.header_bg)
+        };
+        let title = title.alignment(ratatui::layout::Alignment::Center);
 
         ctx.frame.render_widget(title, area);
 
@@ -534,6 +2640,8 @@ impl UiState {
     }
 
     pub(crate) fn draw(&self, ctx: &mut UiDrawCtx) -> Result<(), UiEvent> {
+        ctx.selectable_rows = 0;
+
         if let Some(click) = ctx.click
             && click.button == ClickButton::Right
         {
@@ -541,24 +2649,189 @@ impl UiState {
         }
 
         let area = ctx.frame.area();
-        let [title_area, rest_area] =
-            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        let log_pane_height = match self.show_logs && logger::is_installed() {
+            true => LOG_PANE_HEIGHT,
+            false => 0,
+        };
+        let [title_area, rest_area, log_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(log_pane_height),
+        ])
+        .areas(area);
         self.draw_title_bar(ctx, title_area)?;
 
+        if log_pane_height != 0 {
+            self.draw_log_pane(ctx, log_area)?;
+        }
+
+        let rest_area = match self.max_width {
+            Some(max_width) if rest_area.width > max_width => {
+                let [centered] = Layout::horizontal([Constraint::Length(max_width)])
+                    .flex(Flex::Center)
+                    .areas(rest_area);
+                centered
+            }
+            _ => rest_area,
+        };
         self.top().draw(ctx, rest_area)?;
 
+        // Not taken by a pane of the page, like the details of a task.
+        if let Some((_, lines)) = ctx.scroll {
+            return Err(UiEvent::Scroll(lines));
+        }
+
+        Ok(())
+    }
+
+    /// The recent log records, clicking the title switches the level.
+    fn draw_log_pane(&self, ctx: &mut UiDrawCtx, area: Rect) -> Result<(), UiEvent> {
+        let title_area = Rect { height: 1, ..area };
+        if is_clicked_left(&title_area, ctx.click) {
+            return Err(UiEvent::CycleLogLevel);
+        }
+
+        let level = self.log_level.as_str().to_lowercase();
+        let block = Block::new()
+            .borders(Borders::TOP)
+            .title(format!(" Logs, up to {level} (click to change) "));
+        let inner = block.inner(area);
+        ctx.frame.render_widget(block, area);
+
+        let lines = logger::recent_records(self.log_level, inner.height as usize)
+            .into_iter()
+            .map(|record| {
+                let level = Span::raw(format!("{:5} ", record.level));
+                let level = match record.level {
+                    log::Level::Error => level.red(),
+                    log::Level::Warn => level.yellow(),
+                    log::Level::Info => level.green(),
+                    log::Level::Debug | log::Level::Trace => level.fg(ctx.theme.dim),
+                };
+                Line::from(vec![
+                    level,
+                    Span::raw(format!("{}: ", record.target)).fg(ctx.theme.dim),
+                    Span::raw(record.message),
+                ])
+            })
+            .collect::<Vec<_>>();
+        ctx.frame.render_widget(Text::from(lines), inner);
+
         Ok(())
     }
 }
 
-fn async_fn_to_text<'a, F>(
+/// The visible text of a rendered screen without the title bar, for pages without
+/// [`UiPage::to_text`].
+pub(crate) fn screen_to_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+
+    for y in area.top() + 1..area.bottom() {
+        let line = (area.left()..area.right())
+            .map(|x| buffer[(x, y)].symbol())
+            .collect::<String>();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Longest summary of the arms of a select or join on its line, in characters.
+const MAX_ARM_SUMMARY_WIDTH: usize = 60;
+
+/// Short list of the types of the arms of a select or join, like `[Timer, button_wait]`.
+fn arm_summary(awaitees: &[FutureValue]) -> String {
+    let names = awaitees
+        .iter()
+        .map(|awaitee| short_type_name(&awaitee.ty.to_string()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if names.chars().count() + 2 <= MAX_ARM_SUMMARY_WIDTH {
+        format!("[{names}]")
+    } else {
+        let names = names
+            .chars()
+            .take(MAX_ARM_SUMMARY_WIDTH - 3)
+            .collect::<String>();
+        format!("[{names}…]")
+    }
+}
+
+/// Where the discriminant of an async fn is read from, the first thing to check when states are
+/// read wrong.
+fn state_member_line(ty: &AsyncFnType, theme: &Theme) -> Line<'static> {
+    let member = &ty.state_member;
+    Line::raw(format!(
+        "state @ offset {}, size {}, type {}",
+        member.offset, member.size, member.ty
+    ))
+    .fg(theme.dim)
+}
+
+/// The bytes of `member` in the bytes of its async fn, empty if they are not all there.
+fn member_bytes<'b>(bytes: &'b [u8], member: &Member) -> &'b [u8] {
+    let start = member.offset as usize;
+    bytes
+        .get(start..start + member.size as usize)
+        .unwrap_or_default()
+}
+
+/// Explanation of the table made by [`async_fn_to_text`].
+const STATE_TABLE_LEGEND: &[&str] = &[
+    "Every row is a state of the async fn, `>` marks the state it is in.",
+    "The columns are the members as `offset[size] name`.",
+    "A `-` marks the members that are live in a state.",
+    "The `__state` column is the discriminant of each state, the last column the future it awaits.",
+];
+
+/// Whether the bytes of a member differ from before, only members that were also live before can
+/// be compared.
+fn member_changed(previous: Option<&AsyncFnValue>, member_value: &MemberValue) -> bool {
+    previous
+        .and_then(|previous| previous.state_value.as_ref().ok())
+        .and_then(|previous| {
+            previous
+                .members
+                .iter()
+                .find(|m| m.member == member_value.member)
+        })
+        .is_some_and(|previous| previous.bytes != member_value.bytes)
+}
+
+/// Add the formatted value of a member to its line, in yellow if it `changed`.
+fn push_member_value<'a>(
+    line: &mut Line<'a>,
+    formatted: Line<'a>,
+    changed: bool,
+    accessible: bool,
+) {
+    // Empty when unformattable values are hidden, see `UnformattableValues::Hidden`.
+    let hidden = formatted.spans.is_empty();
+    if !hidden {
+        line.push_span(" = ");
+        match changed {
+            true => line.extend(formatted.spans.into_iter().map(|span| span.yellow())),
+            false => line.extend(formatted),
+        }
+    }
+    // The color alone is not enough for screen readers or when there is no value.
+    if changed && (accessible || hidden) {
+        line.push_span(Span::raw(" (changed since the last stop)").yellow());
+    }
+}
+
+/// Lines describing the layout of an async fn, with the values of the members if a value and its
+/// address are given.
+fn async_fn_to_text<'a>(
     ty: &'a AsyncFnType,
-    value: Option<&AsyncFnValue>,
-    try_format_value: &mut F,
-) -> Text<'a>
-where
-    F: FnMut(&[u8], &Type) -> Line<'static>,
-{
+    value: Option<(&AsyncFnValue, u64)>,
+    pinned: Option<&AsyncFnValue>,
+    previous: Option<&AsyncFnValue>,
+    ctx: &mut UiDrawCtx,
+) -> Vec<DetailLine<'a>> {
     let seperator: Span<'static> = Span::raw(" | ");
 
     let mut member_positions = Vec::new();
@@ -577,7 +2850,22 @@ where
         (col, span_size)
     };
 
-    add_col(Span::raw("           "));
+    let state_names = ty
+        .states
+        .iter()
+        .map(|state| match ctx.show_discriminants {
+            true => format!("{} (disc={})", state.name, state.discriminant_label()),
+            false => state.name.clone(),
+        })
+        .collect::<Vec<_>>();
+    // Two extra for the "> " marking the current state.
+    let names_width = state_names
+        .iter()
+        .map(|name| name.len() + 2)
+        .max()
+        .unwrap_or(0)
+        .max(11);
+    add_col(Span::raw(" ".repeat(names_width)));
 
     let mut add_member = |member: &Member| {
         add_col(Span::raw(format!(
@@ -594,16 +2882,32 @@ where
 
     let awaitee_pos = add_col(Span::raw("awaitee"));
 
-    let mut text = Text::from_iter([members_line, Line::default()]);
+    let mut text: Vec<DetailLine<'a>> = match ctx.show_legend {
+        true => STATE_TABLE_LEGEND
+            .iter()
+            .map(|line| Line::raw(*line).fg(ctx.theme.dim).into())
+            .chain([DetailLine {
+                line: Line::raw("hide legend").fg(ctx.theme.dim),
+                on_click: Some(UiEvent::ToggleLegend),
+                copy: None,
+            }])
+            .collect(),
+        false => vec![DetailLine {
+            line: Line::raw("show legend").fg(ctx.theme.dim),
+            on_click: Some(UiEvent::ToggleLegend),
+            copy: None,
+        }],
+    };
+    text.extend([members_line.into(), Line::default().into()]);
 
-    for state in &ty.states {
-        let (name, highlight) = if let Some(value) = value
+    for (state, state_name) in ty.states.iter().zip(&state_names) {
+        let (name, highlight) = if let Some((value, _)) = value
             && let Ok(state_value) = &value.state_value
             && state_value.state.discriminant_value == state.discriminant_value
         {
-            (format!("> {}", state.name), true)
+            (format!("> {state_name}"), true)
         } else {
-            (format!("  {}", state.name), false)
+            (format!("  {state_name}"), false)
         };
 
         let mut current_col = name.len();
@@ -619,45 +2923,400 @@ where
         }
 
         line.push_span(Span::from(" ".repeat(state_pos.0 - current_col)));
-        let discriminant = state.discriminant_value.to_string();
+        let discriminant = state.discriminant_label();
         line.push_span(Span::from(discriminant.clone()));
         line.push_span(Span::from(" ".repeat(state_pos.1 - discriminant.len())));
         current_col = state_pos.0 + state_pos.1;
 
         if let Some(awaitee) = &state.awaitee {
             line.push_span(Span::from(" ".repeat(awaitee_pos.0 - current_col)));
-            line.push_span(Span::from(format!(
-                "{}[{}] {}",
-                awaitee.offset, awaitee.size, awaitee.ty
-            )));
+            line.push_span(Span::from(format!("{}[{}] ", awaitee.offset, awaitee.size)));
+            line.extend(style_type_name(&awaitee.ty.to_string(), &ctx.theme));
         }
 
         if highlight {
-            text.push_line(line.on_blue());
+            text.push(line.bg(ctx.theme.highlight).into());
         } else {
-            text.push_line(line);
+            text.push(line.into());
         }
     }
-    text.push_line(Line::default());
+    text.push(Line::default().into());
+
+    if value.is_some() {
+        let label = match ctx.show_raw_bytes {
+            true => "hide raw bytes",
+            false => "show raw bytes",
+        };
+        text.push(DetailLine {
+            line: Line::raw(label).fg(ctx.theme.dim),
+            on_click: Some(UiEvent::ToggleRawBytes),
+            copy: None,
+        });
+    }
 
     for member in &ty.members {
         let mut line = Line::raw(format!(
-            "{:>2}[{}] {:<15}: {}",
-            member.offset, member.size, member.name, member.ty
+            "{:>2}[{}] {:<15}: ",
+            member.offset, member.size, member.name
         ));
 
-        if let Some(value) = value
+        let mut copy = None;
+        let mut expand_line = None;
+        let mut watch_line = None;
+        let mut edit_line = None;
+        if let Some((value, address)) = value
             && let Ok(state) = &value.state_value
             && let Some(member_value) = state.members.iter().find(|m| &m.member == member)
         {
-            line.push_span(" = ");
-            line.extend(try_format_value(&member_value.bytes, &member.ty));
+            let address = address + member.offset;
+            let all_elements = ctx.all_elements.contains(&address);
+            let max_elements = match all_elements {
+                true => usize::MAX,
+                false => ctx.max_elements,
+            };
+
+            let changed = member_changed(previous, member_value);
+
+            line.extend(style_type_name(&member.ty.to_string(), &ctx.theme));
+            let formatted = (ctx.try_format_value)(&member_value.bytes, &member.ty, max_elements);
+            // Empty when unformattable values are hidden, see `UnformattableValues::Hidden`.
+            if !formatted.spans.is_empty() {
+                // Only the text, the styles are for the TUI.
+                copy = Some(formatted.to_string());
+            }
+            push_member_value(&mut line, formatted, changed, ctx.accessible);
+            if ctx.show_raw_bytes {
+                line.push_span(
+                    Span::raw(format!("  {}", format_raw_bytes(&member_value.bytes)))
+                        .fg(ctx.theme.dim),
+                );
+            }
+
+            if let Some(pinned) = pinned
+                && let Ok(pinned_state) = &pinned.state_value
+                && let Some(pinned_value) =
+                    pinned_state.members.iter().find(|m| &m.member == member)
+                && pinned_value.bytes != member_value.bytes
+            {
+                line.push_span(Span::raw(" (pinned: ").fg(ctx.theme.dim));
+                line.extend(
+                    (ctx.try_format_value)(&pinned_value.bytes, &member.ty, max_elements)
+                        .spans
+                        .into_iter()
+                        .map(|span| span.fg(ctx.theme.dim)),
+                );
+                line.push_span(Span::raw(")").fg(ctx.theme.dim));
+            }
+
+            // Any collection can be cut short, like arrays, heapless vecs or arrays in a struct.
+            let capped = (ctx.try_format_value)(&member_value.bytes, &member.ty, ctx.max_elements);
+            if capped != (ctx.try_format_value)(&member_value.bytes, &member.ty, usize::MAX) {
+                let label = match (all_elements, &member.ty) {
+                    (true, _) => format!("    show only the first {}", ctx.max_elements),
+                    (false, Type::Array { count, .. }) => format!("    show all {count} elements"),
+                    (false, _) => String::from("    show all elements"),
+                };
+                expand_line = Some(DetailLine {
+                    line: Line::raw(label).fg(ctx.theme.dim),
+                    on_click: Some(UiEvent::ToggleAllElements(address)),
+                    copy: None,
+                });
+            }
+
+            let watch = ctx.watches.iter().find(|w| w.matches(address, member.size));
+            if let Some(watch) = watch {
+                if watch.changed {
+                    if ctx.accessible {
+                        line.spans.insert(0, Span::raw("* "));
+                    }
+                    line.push_span(Span::raw(" (changed)").black().on_yellow());
+                } else {
+                    line.push_span(Span::raw(" (watched)").fg(ctx.theme.dim));
+                }
+                // Below the array and watch lines, which belong to the value.
+                edit_line = Some(DetailLine {
+                    line: Line::raw("    write new bytes").fg(ctx.theme.dim),
+                    on_click: Some(UiEvent::EditMemory {
+                        address,
+                        bytes: member_value.bytes.clone(),
+                    }),
+                    copy: None,
+                });
+            }
+            // A line of its own, clicking the member itself toggles the details like any other line.
+            let label = match watch {
+                Some(_) => "    stop watching",
+                None => "    watch for changes",
+            };
+            watch_line = Some(DetailLine {
+                line: Line::raw(label).fg(ctx.theme.dim),
+                on_click: Some(UiEvent::ToggleWatch {
+                    address,
+                    size: member.size,
+                }),
+                copy: None,
+            });
         } else {
-            line = line.gray();
+            line.push_span(member.ty.to_string());
+            // Without a valid state it is unknown which members are live, but their bytes can
+            // still help to see what went wrong.
+            if let Some((value, _)) = value
+                && let Err((_, bytes)) = &value.state_value
+            {
+                line.push_span(format!(
+                    "  {}",
+                    format_raw_bytes(member_bytes(bytes, member))
+                ));
+            }
+            line = line.fg(ctx.theme.missing);
         }
 
-        text.push_line(line);
+        text.push(DetailLine {
+            line,
+            on_click: None,
+            copy,
+        });
+        text.extend(expand_line);
+        text.extend(watch_line);
+        text.extend(edit_line);
     }
 
     text
 }
+
+#[cfg(test)]
+mod test {
+    use ratatui::style::Color;
+
+    use super::*;
+    use crate::model::async_fn::State;
+
+    #[test]
+    fn future_prompt() {
+        assert_eq!(
+            parse_future_prompt("0x20001000 app::manual"),
+            Ok((0x2000_1000, String::from("app::manual")))
+        );
+        assert_eq!(
+            parse_future_prompt(" 4096  app::Fut<u8, 2> "),
+            Ok((4096, String::from("app::Fut<u8, 2>")))
+        );
+        assert!(parse_future_prompt("0x20001000").is_err());
+        assert!(parse_future_prompt("manual app::manual").is_err());
+    }
+
+    #[test]
+    fn pool_filter() {
+        let mut menu = MainMenu::new(false, false);
+        assert!(menu.matches_filter("app::worker"));
+
+        menu.apply_event(UiEvent::SetFilter(String::from("WORK")));
+        assert!(menu.matches_filter("app::worker"));
+        assert!(!menu.matches_filter("app::collect"));
+    }
+
+    #[test]
+    fn filter_keys() {
+        let mut menu = MainMenu::new(false, false);
+        let press = |menu: &mut MainMenu, keys: &[KeyCode]| {
+            for key in keys {
+                if let Some(event) = menu.key_event(*key) {
+                    menu.apply_event(event);
+                }
+            }
+        };
+
+        // Characters are only for the filter after `/`.
+        press(&mut menu, &[KeyCode::Char('w')]);
+        assert_eq!(menu.filter, "");
+        assert!(!menu.takes_text());
+
+        press(
+            &mut menu,
+            &[
+                KeyCode::Char('/'),
+                KeyCode::Char('w'),
+                KeyCode::Char('x'),
+                KeyCode::Backspace,
+            ],
+        );
+        assert_eq!(menu.filter, "w");
+        assert!(menu.takes_text());
+
+        press(&mut menu, &[KeyCode::Enter]);
+        assert_eq!(menu.filter, "w");
+        assert!(!menu.takes_text());
+
+        // Escape clears the filter once not typing.
+        press(&mut menu, &[KeyCode::Esc]);
+        assert_eq!(menu.filter, "");
+
+        // Backspace on an empty filter stops typing.
+        press(&mut menu, &[KeyCode::Char('/'), KeyCode::Backspace]);
+        assert!(!menu.takes_text());
+    }
+
+    #[test]
+    fn filter_resets_selection() {
+        let mut state = UiState::new(false, false, None);
+        state.set_selectable_rows(5);
+        state.apply_key(KeyCode::Down);
+        state.apply_key(KeyCode::Down);
+        assert_eq!(state.selection().map(|s| s.row), Some(1));
+
+        state.apply_key(KeyCode::Char('/'));
+        state.apply_key(KeyCode::Char('a'));
+        assert_eq!(state.selection(), None);
+    }
+
+    #[test]
+    fn collapse_and_expand_all() {
+        let mut state = ItemState::default();
+        state.toggle_closed(&[1, 2]);
+        assert!(state.children[&1].children[&2].closed);
+
+        state.set_all_closed(true);
+        assert!(state.closed);
+        assert!(state.children[&1].closed);
+        assert!(state.children[&1].children[&2].closed);
+        // Futures not toggled before are closed once they are drawn.
+        assert!(state.child_mut(3).closed);
+        assert!(state.child_mut(1).child_mut(4).children_closed);
+
+        state.set_all_closed(false);
+        assert!(!state.closed);
+        assert!(!state.children[&1].children[&2].closed);
+        assert!(!state.children[&1].children[&4].closed);
+        assert!(!state.child_mut(5).closed);
+    }
+
+    #[test]
+    fn collapse_all_scrolls_to_root() {
+        let mut task = Task::new(0, 0);
+        task.apply_event(UiEvent::SelectFuture(vec![1, 2]));
+        task.apply_event(UiEvent::SetScroll(40));
+
+        task.apply_event(UiEvent::ExpandAll);
+        assert_eq!(task.selected, [1, 2]);
+        assert_eq!(task.highlighted.as_deref(), Some([1, 2].as_slice()));
+        assert!(task.scroll_to_highlighted);
+
+        task.apply_event(UiEvent::CollapseAll);
+        assert!(task.selected.is_empty());
+        assert_eq!(task.highlighted.as_deref(), Some([].as_slice()));
+        assert!(task.scroll_to_highlighted);
+
+        // The tree scrolls to the root in the next draw.
+        task.apply_event(UiEvent::SetScroll(0));
+        assert_eq!(task.scroll, 0);
+        assert!(!task.scroll_to_highlighted);
+    }
+
+    #[test]
+    fn changed_members() {
+        let member = |name: &str, offset| Member {
+            name: name.to_owned(),
+            ty: Type::Unknown,
+            offset,
+            size: 1,
+        };
+        let state = |name: &str, discriminant_value, active_members| State {
+            discriminant_value: Some(discriminant_value),
+            active_members,
+            awaitee: None,
+            name: name.to_owned(),
+            source: None,
+        };
+        let ty = AsyncFnType {
+            members: vec![member("a", 0), member("b", 1)],
+            state_member: member("__state", 2),
+            total_size: 3,
+            states: vec![
+                state("Unresumed", 0, vec![1]),
+                state("Suspend0", 3, vec![0, 1]),
+            ],
+        };
+        let value = |bytes: &[u8]| AsyncFnValue::new(&ty, 0x100, bytes, &HashMap::new());
+
+        let previous = value(&[1, 2, 3]);
+        let current = value(&[1, 5, 3]);
+        let members = &current.state_value.as_ref().unwrap().members;
+        assert!(!member_changed(Some(&previous), &members[0]));
+        assert!(member_changed(Some(&previous), &members[1]));
+        assert!(!member_changed(None, &members[1]));
+        // `a` was not live in the previous state.
+        assert!(!member_changed(Some(&value(&[9, 2, 0])), &members[0]));
+
+        let line = |formatted: &'static str, changed, accessible| {
+            let mut line = Line::raw("b: ");
+            // Hidden values are formatted without spans.
+            let formatted = match formatted {
+                "" => Line::default(),
+                formatted => Line::raw(formatted),
+            };
+            push_member_value(&mut line, formatted, changed, accessible);
+            line
+        };
+        let changed = line("5", true, false);
+        assert_eq!(changed.to_string(), "b:  = 5");
+        assert_eq!(changed.spans.last().unwrap().style.fg, Some(Color::Yellow));
+        let unchanged = line("5", false, true);
+        assert_eq!(unchanged.to_string(), "b:  = 5");
+        assert_eq!(unchanged.spans.last().unwrap().style.fg, None);
+
+        // Without the color, or without a value to color, the change is written out.
+        assert_eq!(
+            line("5", true, true).to_string(),
+            "b:  = 5 (changed since the last stop)"
+        );
+        assert_eq!(
+            line("", true, false).to_string(),
+            "b:  (changed since the last stop)"
+        );
+    }
+
+    #[test]
+    fn task_tree_restored() {
+        let mut state = UiState::new(false, false, None);
+        let closed = |state: &UiState| {
+            let item_state = state.top().item_state().unwrap();
+            item_state
+                .children
+                .get(&4)
+                .is_some_and(|child| child.closed)
+        };
+
+        state.show_task(0, 1);
+        state.apply_event(UiEvent::ToggleClosed(vec![4]));
+        assert!(closed(&state));
+
+        // Replacing the page of the task and going back to the main menu both keep its tree.
+        state.show_task(0, 2);
+        assert!(!closed(&state));
+        state.apply_event(UiEvent::Back);
+        assert!(state.top().item_state().is_none());
+
+        state.show_task(0, 1);
+        assert!(closed(&state));
+        state.apply_event(UiEvent::Back);
+        state.show_task(0, 2);
+        assert!(!closed(&state));
+    }
+
+    #[test]
+    fn copy_key() {
+        let mut state = UiState::new(false, false, None);
+        state.set_selectable_rows(3);
+        state.apply_key(KeyCode::Down);
+        assert!(!state.selection().unwrap().copy);
+
+        state.apply_key(KeyCode::Char('y'));
+        let selection = state.selection().unwrap();
+        assert!(selection.copy);
+        assert!(!selection.activate);
+
+        // Copied once, like enter activates the row once.
+        state.selection_activated();
+        assert!(!state.selection().unwrap().copy);
+    }
+}
@@ -0,0 +1,128 @@
+//! Page with information for checking whether the inspector itself works as expected.
+
+use ratatui::{
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+};
+
+use super::{UiDrawCtx, UiEvent, UiPage, scroll_view::ScrollView, style_type_name};
+
+#[derive(Debug, Clone)]
+pub(super) struct Diagnostics {
+    scroll: i32,
+}
+
+impl Diagnostics {
+    pub(super) fn new() -> Self {
+        Self { scroll: 0 }
+    }
+}
+
+impl UiPage for Diagnostics {
+    fn apply_scroll(&mut self, scroll: i32) {
+        self.scroll += scroll;
+        self.scroll = self.scroll.max(0);
+    }
+
+    fn apply_event(&mut self, event: UiEvent) {
+        match event {
+            UiEvent::SetScroll(scroll) => self.scroll = scroll,
+            _ => {}
+        }
+    }
+
+    fn title(&self, _ctx: &UiDrawCtx) -> String {
+        String::from("Diagnostics")
+    }
+
+    fn draw(&self, ctx: &mut UiDrawCtx, area: Rect) -> Result<(), UiEvent> {
+        let mut scroll_view = ScrollView::new(area, self.scroll).with_scrollbar(true);
+
+        let mut executors = Vec::new();
+        for breakpoint in ctx.poll_breakpoints {
+            if !executors.contains(&breakpoint.executor) {
+                executors.push(breakpoint.executor);
+            }
+        }
+        let mut header = Line::raw("Poll done breakpoints");
+        if !executors.is_empty() {
+            let labels = executors
+                .iter()
+                .map(|executor| executor.label())
+                .collect::<Vec<_>>();
+            header.push_span(Span::raw(" for "));
+            header.push_span(Span::raw(labels.join(" + ")).fg(ctx.theme.accent));
+        }
+        header.push_span(Span::raw(":"));
+        scroll_view.render_next_widget(header, 1);
+        if ctx.poll_breakpoints.is_empty() {
+            scroll_view.render_next_widget(
+                Line::raw("- none, no poll done addresses found in the debug data").red(),
+                1,
+            );
+        }
+        for breakpoint in ctx.poll_breakpoints {
+            let mut line = Line::from_iter([
+                Span::raw("- "),
+                Span::raw(format!("{:#010x}", breakpoint.address)).fg(ctx.theme.accent),
+                Span::raw(format!(" ({})", breakpoint.executor.label())).fg(ctx.theme.dim),
+                Span::raw(": "),
+            ]);
+            match &breakpoint.id {
+                Ok(id) => {
+                    line.push_span(Span::raw("breakpoint "));
+                    line.push_span(Span::raw(id.to_string()).fg(ctx.theme.accent));
+                    line.push_span(Span::raw(", hit "));
+                    line.push_span(Span::raw(breakpoint.hits.to_string()).fg(ctx.theme.accent));
+                    line.push_span(Span::raw(" times"));
+                }
+                Err(e) => {
+                    line.push_span(Span::raw(format!("failed to set breakpoint: {e}")).red());
+                }
+            }
+            scroll_view.render_next_widget(line, 1);
+        }
+
+        scroll_view.render_next_widget(Line::default(), 1);
+        scroll_view.render_next_widget(Line::raw("Task pools:"), 1);
+        if ctx.values.is_empty() {
+            scroll_view.render_next_widget(Line::raw("- none found in the debug data").red(), 1);
+        }
+        for pool in ctx.values {
+            let mut line = Line::raw("- ");
+            line.extend(style_type_name(&pool.task_pool.path, &ctx.theme));
+            line.push_span(Span::raw(": "));
+            line.push_span(Span::raw(pool.usage_label()).fg(ctx.theme.accent));
+            if pool.is_full() {
+                line.push_span(Span::raw(" FULL, spawning another task fails").red());
+            }
+            scroll_view.render_next_widget(line, 1);
+        }
+
+        if !ctx.overlapping_pools.is_empty() {
+            scroll_view.render_next_widget(Line::default(), 1);
+            scroll_view.render_next_widget(
+                Line::raw("Task pools sharing memory, their values can be wrong:").red(),
+                1,
+            );
+        }
+        for (a, b) in ctx.overlapping_pools {
+            let mut line = Line::raw("- ");
+            line.extend(style_type_name(a, &ctx.theme));
+            line.push_span(Span::raw(" and "));
+            line.extend(style_type_name(b, &ctx.theme));
+            scroll_view.render_next_widget(line, 1);
+        }
+        // The spawners are not shown, like spawn tokens that were taken but not spawned yet. That
+        // needs finding the structures of the executor in the debug data, see the README.
+
+        if scroll_view.max_scroll() < self.scroll {
+            return Err(UiEvent::SetScroll(scroll_view.max_scroll()));
+        }
+
+        ctx.frame.render_widget(scroll_view, area);
+
+        Ok(())
+    }
+}
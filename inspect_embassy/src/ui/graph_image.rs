@@ -0,0 +1,129 @@
+//! Drawing the [`TaskGraph`] as an image, for terminals that show images with the sixel, kitty or
+//! iTerm2 protocol. Only built with the `task-graph-image` feature.
+//!
+//! The graph is laid out by `layout-rs` as an SVG, which is rendered with `resvg` and sent to the
+//! terminal by `ratatui-image`. That only works when drawing with crossterm, backends drawing
+//! elsewhere show the graph as text.
+
+use anyhow::{Context, Result};
+use image::{DynamicImage, RgbaImage};
+use layout::{
+    backends::svg::SVGWriter,
+    core::{base::Orientation, geometry::Point, style::StyleAttr},
+    std_shapes::shapes::{Arrow, Element, ShapeKind},
+    topo::layout::VisualGraph,
+};
+use ratatui::{Frame, layout::Rect};
+use ratatui_image::{
+    StatefulImage,
+    picker::{Picker, ProtocolType},
+    protocol::StatefulProtocol,
+};
+use resvg::{tiny_skia, usvg};
+
+use super::task_graph::TaskGraph;
+
+/// The graph is rendered at this many pixels per point of the SVG, so the labels stay readable
+/// when the image is scaled down to fit the page.
+const SCALE: f32 = 2.0;
+/// Roughly the width of a character of the labels, `layout-rs` does not measure them.
+const CHAR_WIDTH: f64 = 9.0;
+
+/// The image of the graph drawn last, the graph is only laid out and rendered again when it
+/// changed.
+pub(crate) struct GraphImage {
+    picker: Picker,
+    /// With the system fonts loaded once, loading them takes a while.
+    svg_options: usvg::Options<'static>,
+    drawn: Option<(TaskGraph, Result<StatefulProtocol, String>)>,
+}
+
+impl std::fmt::Debug for GraphImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GraphImage").finish_non_exhaustive()
+    }
+}
+
+impl GraphImage {
+    /// `None` when the terminal does not support an image protocol. `ratatui-image` then falls
+    /// back to half blocks, which are too coarse to read the labels.
+    pub(crate) fn new(picker: Picker) -> Option<Self> {
+        if matches!(picker.protocol_type(), ProtocolType::Halfblocks) {
+            return None;
+        }
+
+        let mut svg_options = usvg::Options::default();
+        svg_options.fontdb_mut().load_system_fonts();
+        Some(Self {
+            picker,
+            svg_options,
+            drawn: None,
+        })
+    }
+
+    /// Draw the graph fitted into `area`, returning why it could not be rendered.
+    pub(crate) fn draw(&mut self, graph: &TaskGraph, frame: &mut Frame, area: Rect) -> Result<()> {
+        let protocol = match &mut self.drawn {
+            Some((drawn, protocol)) if drawn == graph => protocol,
+            slot => {
+                // Failures are kept as well, to not render the same graph again on every draw.
+                let protocol = render(graph, &self.svg_options)
+                    .map(|image| self.picker.new_resize_protocol(image))
+                    .map_err(|e| format!("{e:#}"));
+                &mut slot.insert((graph.clone(), protocol)).1
+            }
+        };
+
+        match protocol {
+            Ok(protocol) => {
+                frame.render_stateful_widget(StatefulImage::default(), area, protocol);
+                Ok(())
+            }
+            Err(e) => anyhow::bail!("{e}"),
+        }
+    }
+}
+
+/// Lay out the graph with the tasks at the top and what they wait on below them.
+fn render(graph: &TaskGraph, svg_options: &usvg::Options) -> Result<DynamicImage> {
+    let mut visual_graph = VisualGraph::new(Orientation::TopToBottom);
+    let handles = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let size = Point::new(node.label.chars().count() as f64 * CHAR_WIDTH + 20.0, 36.0);
+            visual_graph.add_node(Element::create(
+                ShapeKind::new_box(&node.label),
+                StyleAttr::simple(),
+                Orientation::LeftToRight,
+                size,
+            ))
+        })
+        .collect::<Vec<_>>();
+    for &(from, to) in &graph.edges {
+        visual_graph.add_edge(Arrow::simple(""), handles[from], handles[to]);
+    }
+
+    let mut svg = SVGWriter::new();
+    visual_graph.do_it(false, false, false, &mut svg);
+    let tree = usvg::Tree::from_str(&svg.finalize(), svg_options)?;
+
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(SCALE)
+        .context("the graph is too large to draw")?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .context("the graph is too large to draw")?;
+    // The SVG has no background, the lines and labels are black.
+    pixmap.fill(tiny_skia::Color::WHITE);
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(SCALE, SCALE),
+        &mut pixmap.as_mut(),
+    );
+
+    let image = RgbaImage::from_raw(size.width(), size.height(), pixmap.take())
+        .context("the rendered graph has the wrong size")?;
+    Ok(DynamicImage::ImageRgba8(image))
+}
@@ -7,6 +7,7 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Offset, Rect, Size},
+    style::{Style, Stylize},
     widgets::Widget,
 };
 
@@ -20,6 +21,9 @@ pub struct ScrollView {
     offset: Offset,
 
     next_y: u16,
+
+    /// Whether to draw a scrollbar on the right edge, see [`Self::with_scrollbar`].
+    scrollbar: bool,
 }
 
 impl ScrollView {
@@ -44,9 +48,18 @@ impl ScrollView {
             },
 
             next_y: 0,
+
+            scrollbar: false,
         }
     }
 
+    /// Draw a scrollbar over the rightmost column when the content does not fit, showing which
+    /// part of it is visible.
+    pub fn with_scrollbar(mut self, scrollbar: bool) -> Self {
+        self.scrollbar = scrollbar;
+        self
+    }
+
     /// The size that this [`ScrollView`] expects to be drawn in.
     pub fn frame_size(&self) -> Size {
         self.buf.area.as_size()
@@ -155,6 +168,35 @@ impl ScrollView {
         )
     }
 
+    /// Set the style of the part of `area` that is visible, `area` is given like for
+    /// [`Self::render_widget`].
+    pub fn set_style(&mut self, area: Rect, style: Style) {
+        let buff_area_y = area.y as i32 - self.scroll;
+        let visible = self.buf.area.intersection(Rect {
+            x: area.x,
+            y: buff_area_y.clamp(0, u16::MAX as i32) as u16,
+            width: area.width,
+            height: (area.height as i32 + buff_area_y.min(0)).max(0) as u16,
+        });
+        self.buf.set_style(visible, style);
+    }
+
+    /// Returns the scroll value to use so that `area` is fully visible, or as much of it as fits
+    /// starting at the top. `None` if it already is.
+    pub fn scroll_into_view(&self, area: Rect) -> Option<i32> {
+        let top = area.y as i32;
+        let bottom = top + area.height as i32;
+        let height = self.buf.area.height as i32;
+
+        if top < self.scroll {
+            Some(top)
+        } else if bottom > self.scroll + height {
+            Some((bottom - height).min(top))
+        } else {
+            None
+        }
+    }
+
     /// Returns the scroll value to use so that lowest rendered widget would just touch the bottom
     /// of the view.
     ///
@@ -162,6 +204,24 @@ impl ScrollView {
     pub fn max_scroll(&self) -> i32 {
         (self.next_y as i32 - self.buf.area.height as i32).max(0)
     }
+
+    /// The start and length of the thumb of the scrollbar in rows of the view, `None` if
+    /// everything fits.
+    ///
+    /// The thumb is as much of the view as the view is of the content, and reaches the bottom at
+    /// [`Self::max_scroll`].
+    fn scrollbar_thumb(&self) -> Option<(u16, u16)> {
+        let max_scroll = self.max_scroll();
+        if max_scroll == 0 {
+            return None;
+        }
+
+        let height = self.buf.area.height as i32;
+        let length = (height * height / self.next_y as i32).clamp(1, height);
+        let scroll = self.scroll.clamp(0, max_scroll);
+        let start = (scroll * (height - length) + max_scroll / 2) / max_scroll;
+        Some((start as u16, length as u16))
+    }
 }
 
 impl Widget for ScrollView {
@@ -179,12 +239,26 @@ impl Widget for ScrollView {
                 buf[dst_col] = self.buf[src_col].clone();
             }
         }
+
+        if let Some((start, length)) = self.scrollbar_thumb().filter(|_| self.scrollbar)
+            && !overlap.is_empty()
+        {
+            let x = overlap.right() - 1;
+            for (row, y) in (overlap.top()..overlap.bottom()).enumerate() {
+                let cell = &mut buf[(x, y)];
+                cell.reset();
+                match (start..start + length).contains(&(row as u16)) {
+                    true => cell.set_char('█'),
+                    false => cell.set_char('│').set_style(Style::new().dark_gray()),
+                };
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use ratatui::text::Text;
+    use ratatui::{style::Stylize, text::Text};
 
     use super::*;
 
@@ -312,6 +386,94 @@ mod test {
         );
     }
 
+    #[test]
+    fn scroll_into_view() {
+        let frame = Rect::new(0, 0, 6, 6);
+
+        let scroll_view = ScrollView::new(frame, 3);
+        assert_eq!(scroll_view.scroll_into_view(Rect::new(0, 4, 6, 2)), None);
+        assert_eq!(scroll_view.scroll_into_view(Rect::new(0, 1, 6, 1)), Some(1));
+        assert_eq!(
+            scroll_view.scroll_into_view(Rect::new(0, 10, 6, 1)),
+            Some(5)
+        );
+        // Taller than the view, the top is shown.
+        assert_eq!(
+            scroll_view.scroll_into_view(Rect::new(0, 10, 6, 8)),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn scrolled_style() {
+        let mut main_buf = Buffer::empty(Rect::new(0, 0, 6, 6));
+
+        let mut scroll_view = ScrollView::new(main_buf.area, 2);
+        draw_to_scroll_view(&mut scroll_view);
+        scroll_view.set_style(Rect::new(0, 1, 3, 2), Style::new().reversed());
+        scroll_view.set_style(Rect::new(0, 7, 6, 1), Style::new().reversed());
+        scroll_view.set_style(Rect::new(0, 20, 6, 1), Style::new().reversed());
+
+        scroll_view.render(main_buf.area, &mut main_buf);
+
+        #[rustfmt::skip]
+        let mut target = Buffer::with_lines(vec![
+            "DEF   ",
+            " 1:   ",
+            " ABC  ",
+            " DEF  ",
+            "  2:  ",
+            "  ABC ",
+        ]);
+        target.set_style(Rect::new(0, 0, 3, 1), Style::new().reversed());
+        target.set_style(Rect::new(0, 5, 6, 1), Style::new().reversed());
+
+        assert_eq!(main_buf, target);
+    }
+
+    #[test]
+    fn scrollbar_thumb() {
+        let frame = Rect::new(0, 0, 6, 6);
+        let thumb = |scroll| {
+            let mut scroll_view = ScrollView::new(frame, scroll);
+            draw_to_scroll_view(&mut scroll_view);
+            scroll_view.scrollbar_thumb()
+        };
+
+        // 6 of the 18 lines are visible.
+        assert_eq!(thumb(0), Some((0, 2)));
+        assert_eq!(thumb(6), Some((2, 2)));
+        assert_eq!(thumb(12), Some((4, 2)));
+        assert_eq!(thumb(20), Some((4, 2)));
+
+        let mut scroll_view = ScrollView::new(frame, 0);
+        scroll_view.render_widget(Text::raw("A\nB"), Rect::new(0, 0, 3, 2));
+        assert_eq!(scroll_view.scrollbar_thumb(), None);
+    }
+
+    #[test]
+    fn scrollbar() {
+        let mut main_buf = Buffer::empty(Rect::new(0, 0, 6, 6));
+
+        let mut scroll_view = ScrollView::new(main_buf.area, 12).with_scrollbar(true);
+        draw_to_scroll_view(&mut scroll_view);
+
+        scroll_view.render(main_buf.area, &mut main_buf);
+
+        #[rustfmt::skip]
+        let mut target = Buffer::with_lines(vec![
+            "    4│",
+            "    A│",
+            "    D│",
+            "     │",
+            "     █",
+            "     █",
+        ]);
+        target.set_style(Rect::new(5, 0, 1, 4), Style::new().dark_gray());
+
+        assert_eq!(main_buf, target);
+    }
+
     #[test]
     fn main_buffer_offset() {
         let mut main_buf = Buffer::empty(Rect::new(2, 2, 6, 6));
@@ -0,0 +1,464 @@
+//! Page with the graph of what the spawned tasks are waiting on.
+//!
+//! Unlike the tree of a single task this also shows what tasks share, a future behind a `Shared`
+//! handle or an embassy-sync channel that several tasks wait on is a single node. The graph is
+//! listed as text, with the `task-graph-image` feature it is drawn as an image on terminals that
+//! can show one, see [`GraphImage`](super::graph_image::GraphImage).
+
+use std::{collections::HashMap, fmt::Write as _};
+
+use ratatui::{
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+};
+
+use super::{
+    UiDrawCtx, UiEvent, UiPage, scroll_view::ScrollView, short_type_name, style_type_name,
+};
+use crate::model::{
+    embassy_sync::SyncFutureKind,
+    future::{FutureValue, FutureValueKind},
+    task_pool::{TaskPoolValue, TaskValue},
+    ty::Type,
+};
+
+/// What a node of the [`TaskGraph`] stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    /// A spawned task, with the future of its async fn.
+    Task,
+    /// A future awaited by a task or another future.
+    Future,
+    /// The channel or signal an embassy-sync future waits on.
+    Sync,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Node {
+    pub(crate) kind: NodeKind,
+    /// The pool path and index of a task, the short type name of a future or the address of a
+    /// channel or signal.
+    pub(crate) label: String,
+}
+
+/// The tasks and the futures they are waiting on, the same for the text and the image.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct TaskGraph {
+    pub(crate) nodes: Vec<Node>,
+    /// From the waiting node to the one it waits on, as indices into [`Self::nodes`].
+    pub(crate) edges: Vec<(usize, usize)>,
+}
+
+impl TaskGraph {
+    pub(crate) fn new(values: &[TaskPoolValue]) -> Self {
+        let mut tasks = Vec::new();
+        for pool in values {
+            for (task_idx, task) in pool.task_values.iter().enumerate() {
+                if let TaskValue::Init(value, _) = task {
+                    tasks.push((format!("{}[{task_idx}]", pool.task_pool.path), value));
+                }
+            }
+        }
+
+        Self::from_tasks(tasks)
+    }
+
+    /// The graph of the tasks with these labels and the futures of their async fns.
+    fn from_tasks<'a>(tasks: impl IntoIterator<Item = (String, &'a FutureValue)>) -> Self {
+        let mut graph = Self::default();
+        // Futures by address and type, channels and signals by address only.
+        let mut nodes_by_address = HashMap::new();
+
+        for (label, value) in tasks {
+            let task_node = graph.add_node(NodeKind::Task, label);
+            for (_, child) in value.children() {
+                graph.add_future(task_node, child, &mut nodes_by_address);
+            }
+        }
+
+        graph
+    }
+
+    fn add_node(&mut self, kind: NodeKind, label: String) -> usize {
+        self.nodes.push(Node { kind, label });
+        self.nodes.len() - 1
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize) {
+        if !self.edges.contains(&(from, to)) {
+            self.edges.push((from, to));
+        }
+    }
+
+    /// Add `value` as awaited by the node `waiting`, with what it awaits itself.
+    fn add_future(
+        &mut self,
+        waiting: usize,
+        value: &FutureValue,
+        nodes_by_address: &mut HashMap<(u64, Option<Type>), usize>,
+    ) {
+        // A finished join arm isn't waited on anymore.
+        if matches!(value.kind, FutureValueKind::Output(_)) {
+            return;
+        }
+
+        let key = (value.address, Some(value.ty.clone()));
+        if let Some(&node) = nodes_by_address.get(&key) {
+            // Already added with everything it awaits by another task.
+            self.add_edge(waiting, node);
+            return;
+        }
+        let node = self.add_node(NodeKind::Future, short_type_name(&value.ty.to_string()));
+        nodes_by_address.insert(key, node);
+        self.add_edge(waiting, node);
+
+        if let FutureValueKind::EmbassySyncValue(sync) = &value.kind
+            && let Some(pointer) = sync.pointer
+        {
+            let target = match sync.kind {
+                SyncFutureKind::Receive | SyncFutureKind::Send => "channel",
+                SyncFutureKind::Signal => "signal",
+            };
+            let sync_node = *nodes_by_address.entry((pointer, None)).or_insert_with(|| {
+                self.add_node(NodeKind::Sync, format!("{target} at {pointer:#x}"))
+            });
+            self.add_edge(node, sync_node);
+        }
+
+        for (_, child) in value.children() {
+            self.add_future(node, child, nodes_by_address);
+        }
+    }
+
+    /// The nodes every task and every node waiting on something waits on, in the order of
+    /// [`Self::nodes`].
+    pub(crate) fn adjacency_list(&self) -> Vec<(usize, Vec<usize>)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(node_idx, node)| {
+                let awaited = self
+                    .edges
+                    .iter()
+                    .filter(|(from, _)| *from == node_idx)
+                    .map(|(_, to)| *to)
+                    .collect::<Vec<_>>();
+                (node.kind == NodeKind::Task || !awaited.is_empty()).then_some((node_idx, awaited))
+            })
+            .collect()
+    }
+
+    /// A node as it is referred to in the text, the index tells apart the futures of the same type.
+    fn node_label(&self, node_idx: usize) -> String {
+        format!("#{node_idx} {}", self.nodes[node_idx].label)
+    }
+
+    fn node_spans(&self, node_idx: usize, ctx: &UiDrawCtx) -> Vec<Span<'static>> {
+        let node = &self.nodes[node_idx];
+        let mut spans = vec![Span::raw(format!("#{node_idx} ")).fg(ctx.theme.dim)];
+        match node.kind {
+            NodeKind::Task | NodeKind::Future => {
+                spans.extend(style_type_name(&node.label, &ctx.theme));
+            }
+            NodeKind::Sync => spans.push(Span::raw(node.label.clone()).fg(ctx.theme.accent)),
+        }
+        spans
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct TaskGraphPage {
+    scroll: i32,
+}
+
+impl TaskGraphPage {
+    pub(super) fn new() -> Self {
+        Self { scroll: 0 }
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut UiDrawCtx,
+        area: Rect,
+        graph: &TaskGraph,
+        image_error: Option<String>,
+    ) -> Result<(), UiEvent> {
+        let mut scroll_view = ScrollView::new(area, self.scroll).with_scrollbar(true);
+
+        if let Some(error) = image_error {
+            scroll_view.render_next_widget(
+                Line::raw(format!("Could not draw the graph as an image: {error}")).red(),
+                1,
+            );
+        }
+        if graph.nodes.is_empty() {
+            scroll_view.render_next_widget(Line::raw("No tasks are spawned").fg(ctx.theme.dim), 1);
+        }
+        for (node_idx, awaited) in graph.adjacency_list() {
+            let mut line = Line::raw("- ");
+            line.extend(graph.node_spans(node_idx, ctx));
+            line.push_span(Span::raw(" → "));
+            if awaited.is_empty() {
+                line.push_span(Span::raw("nothing").fg(ctx.theme.dim));
+            }
+            for (i, awaited_idx) in awaited.into_iter().enumerate() {
+                if i > 0 {
+                    line.push_span(Span::raw(", "));
+                }
+                line.extend(graph.node_spans(awaited_idx, ctx));
+            }
+            scroll_view.render_next_widget(line, 1);
+        }
+
+        if scroll_view.max_scroll() < self.scroll {
+            return Err(UiEvent::SetScroll(scroll_view.max_scroll()));
+        }
+
+        ctx.frame.render_widget(scroll_view, area);
+
+        Ok(())
+    }
+}
+
+impl UiPage for TaskGraphPage {
+    fn apply_scroll(&mut self, scroll: i32) {
+        self.scroll += scroll;
+        self.scroll = self.scroll.max(0);
+    }
+
+    fn apply_event(&mut self, event: UiEvent) {
+        match event {
+            UiEvent::SetScroll(scroll) => self.scroll = scroll,
+            _ => {}
+        }
+    }
+
+    fn title(&self, _ctx: &UiDrawCtx) -> String {
+        String::from("Task graph")
+    }
+
+    fn draw(&self, ctx: &mut UiDrawCtx, area: Rect) -> Result<(), UiEvent> {
+        let graph = TaskGraph::new(ctx.values);
+
+        #[cfg(feature = "task-graph-image")]
+        let image_error = match ctx.graph_image.as_deref_mut() {
+            Some(graph_image) if !graph.nodes.is_empty() => {
+                match graph_image.draw(&graph, ctx.frame, area) {
+                    Ok(()) => return Ok(()),
+                    Err(e) => Some(format!("{e:#}")),
+                }
+            }
+            _ => None,
+        };
+        #[cfg(not(feature = "task-graph-image"))]
+        let image_error = None;
+
+        self.draw_text(ctx, area, &graph, image_error)
+    }
+
+    fn to_text(&self, ctx: &mut UiDrawCtx) -> Option<String> {
+        let graph = TaskGraph::new(ctx.values);
+
+        let mut text = String::new();
+        for (node_idx, awaited) in graph.adjacency_list() {
+            let awaited = match awaited.is_empty() {
+                true => String::from("nothing"),
+                false => awaited
+                    .into_iter()
+                    .map(|awaited_idx| graph.node_label(awaited_idx))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            let _ = writeln!(text, "- {} → {awaited}", graph.node_label(node_idx));
+        }
+
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::model::{
+        embassy_sync::EmbassySyncValue,
+        future::{JoinValue, SharedValue},
+    };
+
+    fn future(name: &str, address: u64, kind: FutureValueKind) -> FutureValue {
+        FutureValue {
+            ty: Type::Base(name.to_owned()),
+            address,
+            kind,
+        }
+    }
+
+    fn unknown(name: &str, address: u64) -> FutureValue {
+        future(name, address, FutureValueKind::Unknown(Vec::new()))
+    }
+
+    /// The future of a task waiting on all of `awaitees`.
+    fn join(address: u64, awaitees: Vec<FutureValue>) -> FutureValue {
+        let join = JoinValue {
+            awaitees: awaitees.into_boxed_slice(),
+        };
+        future(
+            "embassy_futures::join::Join",
+            address,
+            FutureValueKind::JoinValue(join),
+        )
+    }
+
+    #[test]
+    fn nested_futures() {
+        let run = join(
+            0x100,
+            vec![
+                join(
+                    0x110,
+                    vec![
+                        unknown("app::blink::{async_fn_env#0}", 0x120),
+                        unknown("app::read::{async_fn_env#0}", 0x130),
+                    ],
+                ),
+                unknown("embassy_time::timer::Timer", 0x140),
+            ],
+        );
+        let idle = join(0x200, Vec::new());
+        let graph = TaskGraph::from_tasks([
+            (String::from("app::run[0]"), &run),
+            (String::from("app::run[1]"), &idle),
+        ]);
+
+        let labels = graph
+            .nodes
+            .iter()
+            .map(|node| node.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                "app::run[0]",
+                "Join",
+                "blink",
+                "read",
+                "Timer",
+                "app::run[1]"
+            ]
+        );
+        assert_eq!(graph.edges, [(0, 1), (1, 2), (1, 3), (0, 4)]);
+        // Futures that don't wait on anything are left out, tasks are always listed.
+        assert_eq!(
+            graph.adjacency_list(),
+            [(0, vec![1, 4]), (1, vec![2, 3]), (5, Vec::new())]
+        );
+    }
+
+    fn shared(address: u64) -> FutureValue {
+        let inner = unknown("app::wait::{async_fn_env#0}", 0x2000);
+        future(
+            "futures_util::future::Shared<app::wait::{async_fn_env#0}>",
+            address,
+            FutureValueKind::SharedValue(SharedValue {
+                pointer: Some(0x2000),
+                awaitee: Some(Box::new(inner)),
+                size: 4,
+            }),
+        )
+    }
+
+    fn receive(address: u64) -> FutureValue {
+        future(
+            "embassy_sync::channel::ReceiveFuture<u8>",
+            address,
+            FutureValueKind::EmbassySyncValue(EmbassySyncValue {
+                kind: SyncFutureKind::Receive,
+                pointer: Some(0x3000),
+                pointer_read: true,
+                state: None,
+                size: 4,
+            }),
+        )
+    }
+
+    #[test]
+    fn shared_nodes() {
+        let tasks = [
+            join(0x100, vec![shared(0x10)]),
+            join(0x200, vec![shared(0x20)]),
+            join(0x300, vec![receive(0x30)]),
+            join(0x400, vec![receive(0x40)]),
+        ];
+        let names = [
+            "app::run[0]",
+            "app::run[1]",
+            "app::receive[0]",
+            "app::receive[1]",
+        ];
+        let graph = TaskGraph::from_tasks(names.map(String::from).into_iter().zip(&tasks));
+
+        let labels = graph
+            .nodes
+            .iter()
+            .map(|node| node.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                "app::run[0]",
+                "Shared",
+                "wait",
+                "app::run[1]",
+                "Shared",
+                "app::receive[0]",
+                "ReceiveFuture",
+                "channel at 0x3000",
+                "app::receive[1]",
+                "ReceiveFuture",
+            ]
+        );
+        // Both handles lead to the same future, both receivers to the same channel.
+        assert_eq!(
+            graph.edges,
+            [
+                (0, 1),
+                (1, 2),
+                (3, 4),
+                (4, 2),
+                (5, 6),
+                (6, 7),
+                (8, 9),
+                (9, 7),
+            ]
+        );
+        assert_eq!(
+            graph.adjacency_list(),
+            [
+                (0, vec![1]),
+                (1, vec![2]),
+                (3, vec![4]),
+                (4, vec![2]),
+                (5, vec![6]),
+                (6, vec![7]),
+                (8, vec![9]),
+                (9, vec![7]),
+            ]
+        );
+    }
+
+    #[test]
+    fn finished_arms() {
+        let run = join(
+            0x100,
+            vec![
+                future("u8", 0x110, FutureValueKind::Output(vec![1])),
+                unknown("embassy_time::timer::Timer", 0x120),
+            ],
+        );
+        let graph = TaskGraph::from_tasks([(String::from("app::run[0]"), &run)]);
+
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.adjacency_list(), [(0, vec![1])]);
+    }
+}
@@ -0,0 +1,173 @@
+//! Light syntax highlighting for type names.
+//!
+//! Long generic type names like `embassy_nrf::gpio::Input<'_>` are hard to scan when they are
+//! rendered in a single color, so they get split into path segments, the final type name, generic
+//! arguments and lifetimes which all get their own style.
+
+use ratatui::{style::Stylize, text::Span};
+
+use crate::theme::Theme;
+
+/// Split a type name into styled spans.
+///
+/// - Path segments (anything followed by `::`) get the dim color of the theme.
+/// - The outermost type name gets the accent color of the theme.
+/// - Generic arguments get a distinct color.
+/// - Lifetimes are dimmed and italic.
+pub(crate) fn style_type_name(name: &str, theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut depth = 0usize;
+    let mut rest = name;
+
+    while let Some(first) = rest.chars().next() {
+        let segment_end = rest.find([':', '<', '>', ',', ' ']).unwrap_or(rest.len());
+
+        if segment_end == 0 {
+            let delimiter_len = if rest.starts_with("::") {
+                2
+            } else {
+                first.len_utf8()
+            };
+            let (delimiter, tail) = rest.split_at(delimiter_len);
+            match delimiter {
+                "<" => depth += 1,
+                ">" => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+
+            let span = Span::raw(delimiter.to_owned());
+            spans.push(match delimiter {
+                "::" => span.fg(theme.dim),
+                _ => span,
+            });
+            rest = tail;
+            continue;
+        }
+
+        let (segment, tail) = rest.split_at(segment_end);
+        let span = Span::raw(segment.to_owned());
+        spans.push(if segment.starts_with('\'') {
+            span.fg(theme.dim).italic()
+        } else if tail.starts_with("::") {
+            span.fg(theme.dim)
+        } else if depth == 0 {
+            span.fg(theme.accent)
+        } else {
+            span.cyan()
+        });
+        rest = tail;
+    }
+
+    spans
+}
+
+/// Shorten a type name to its last path segment without generics, like `Timer` for
+/// `embassy_time::timer::Timer`.
+///
+/// The generated types of async fns and blocks like `app::wait::{async_fn_env#0}` are named after
+/// the function they belong to, `wait`.
+pub(crate) fn short_type_name(name: &str) -> String {
+    let mut without_generics = String::new();
+    let mut depth = 0usize;
+    for c in name.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            c if depth == 0 => without_generics.push(c),
+            _ => {}
+        }
+    }
+
+    without_generics
+        .split("::")
+        .rfind(|segment| !segment.starts_with('{'))
+        .unwrap_or(name)
+        .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plain_name() {
+        assert_eq!(
+            style_type_name("u32", &Theme::dark()),
+            vec![Span::raw("u32").blue()]
+        );
+    }
+
+    #[test]
+    fn path_and_lifetime() {
+        assert_eq!(
+            style_type_name("embassy_nrf::gpio::Input<'_>", &Theme::dark()),
+            vec![
+                Span::raw("embassy_nrf").dark_gray(),
+                Span::raw("::").dark_gray(),
+                Span::raw("gpio").dark_gray(),
+                Span::raw("::").dark_gray(),
+                Span::raw("Input").blue(),
+                Span::raw("<"),
+                Span::raw("'_").dark_gray().italic(),
+                Span::raw(">"),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_generics() {
+        assert_eq!(
+            style_type_name(
+                "core::option::Option<alloc::vec::Vec<u8>, Foo<'static, u16>>",
+                &Theme::dark()
+            ),
+            vec![
+                Span::raw("core").dark_gray(),
+                Span::raw("::").dark_gray(),
+                Span::raw("option").dark_gray(),
+                Span::raw("::").dark_gray(),
+                Span::raw("Option").blue(),
+                Span::raw("<"),
+                Span::raw("alloc").dark_gray(),
+                Span::raw("::").dark_gray(),
+                Span::raw("vec").dark_gray(),
+                Span::raw("::").dark_gray(),
+                Span::raw("Vec").cyan(),
+                Span::raw("<"),
+                Span::raw("u8").cyan(),
+                Span::raw(">"),
+                Span::raw(","),
+                Span::raw(" "),
+                Span::raw("Foo").cyan(),
+                Span::raw("<"),
+                Span::raw("'static").dark_gray().italic(),
+                Span::raw(","),
+                Span::raw(" "),
+                Span::raw("u16").cyan(),
+                Span::raw(">"),
+                Span::raw(">"),
+            ]
+        );
+    }
+
+    #[test]
+    fn short_names() {
+        assert_eq!(short_type_name("embassy_time::timer::Timer"), "Timer");
+        assert_eq!(
+            short_type_name("app::button_wait::{async_fn_env#0}"),
+            "button_wait"
+        );
+        assert_eq!(
+            short_type_name("embassy_sync::channel::ReceiveFuture<'_, NoopRawMutex, u8, 4>"),
+            "ReceiveFuture"
+        );
+        assert_eq!(short_type_name("app::run<u8>::{async_block#1}"), "run");
+    }
+
+    #[test]
+    fn unbalanced_closing() {
+        // Function types contain a `->` which should not underflow the depth.
+        let spans = style_type_name("fn(u8) -> u8", &Theme::dark());
+        assert_eq!(spans.last(), Some(&Span::raw("u8").blue()));
+    }
+}
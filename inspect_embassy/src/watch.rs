@@ -0,0 +1,34 @@
+//! Software "watchpoints" that highlight when a range of target memory changed between stops.
+
+/// A range of memory on the target that gets re-read every time the target stops.
+#[derive(Debug, Clone)]
+pub(crate) struct Watch {
+    pub(crate) address: u64,
+    pub(crate) size: u64,
+
+    /// The bytes read during the last stop, `None` if they have not been read yet.
+    last_bytes: Option<Vec<u8>>,
+    /// Whether the bytes changed between the last two stops.
+    pub(crate) changed: bool,
+}
+
+impl Watch {
+    pub(crate) fn new(address: u64, size: u64) -> Self {
+        Self {
+            address,
+            size,
+            last_bytes: None,
+            changed: false,
+        }
+    }
+
+    /// Store the newly read bytes, marking the watch as changed if they differ from the last read.
+    pub(crate) fn update(&mut self, bytes: Vec<u8>) {
+        self.changed = self.last_bytes.as_ref().is_some_and(|last| *last != bytes);
+        self.last_bytes = Some(bytes);
+    }
+
+    pub(crate) fn matches(&self, address: u64, size: u64) -> bool {
+        self.address == address && self.size == size
+    }
+}
@@ -78,7 +78,7 @@ impl ProbeOptions {
         registry: &'r mut Registry,
         lister: &Lister,
     ) -> Result<(Session, LoadedProbeOptions<'r>), OperationError> {
-        let common_options = self.load(registry)?;
+        let mut common_options = self.load(registry)?;
 
         let target = common_options.get_target_selector()?;
         let probe = common_options.attach_probe(lister)?;
@@ -175,7 +175,12 @@ impl<'r> LoadedProbeOptions<'r> {
     /// If there is only one probe, it will be selected automatically.
     /// If there are multiple probes, the user will be prompted to select one unless
     /// started in non-interactive mode.
-    fn select_probe(lister: &Lister, non_interactive: bool) -> Result<Probe, OperationError> {
+    ///
+    /// Returns the opened probe together with a selector matching it.
+    fn select_probe(
+        lister: &Lister,
+        non_interactive: bool,
+    ) -> Result<(Probe, DebugProbeSelector), OperationError> {
         let list = lister.list_all();
         let selected = match list.len() {
             0 | 1 => list.first().ok_or(OperationError::NoProbesFound),
@@ -184,13 +189,19 @@ impl<'r> LoadedProbeOptions<'r> {
         };
 
         match selected {
-            Ok(probe_info) => Ok(lister.open(probe_info)?),
+            Ok(probe_info) => Ok((
+                lister.open(probe_info)?,
+                DebugProbeSelector::from(probe_info),
+            )),
             Err(error) => Err(error),
         }
     }
 
     /// Attaches to specified probe and configures it.
-    pub fn attach_probe(&self, lister: &Lister) -> Result<Probe, OperationError> {
+    ///
+    /// If no probe was specified the selector of the automatically or interactively selected probe
+    /// is stored in the options.
+    pub fn attach_probe(&mut self, lister: &Lister) -> Result<Probe, OperationError> {
         let mut probe = if self.0.dry_run {
             Probe::from_specific_probe(Box::new(FakeProbe::with_mocked_core()))
         } else {
@@ -198,7 +209,11 @@ impl<'r> LoadedProbeOptions<'r> {
             // matching the selector if possible.
             match &self.0.probe {
                 Some(selector) => lister.open(selector)?,
-                None => Self::select_probe(lister, self.0.non_interactive)?,
+                None => {
+                    let (probe, selector) = Self::select_probe(lister, self.0.non_interactive)?;
+                    self.0.probe = Some(selector);
+                    probe
+                }
             }
         };
 
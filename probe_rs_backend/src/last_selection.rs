@@ -0,0 +1,85 @@
+//! Persists the chip and probe of the last successful session, so they don't have to be given on
+//! every run.
+
+use std::{io::Write, path::PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The chip and probe used for the last successful attach.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct LastSelection {
+    pub(crate) chip: Option<String>,
+    /// Probe selector in the `VID:PID[:Serial]` format.
+    pub(crate) probe: Option<String>,
+}
+
+impl LastSelection {
+    /// Location of the file, `None` if no config directory could be found.
+    fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(
+            config_dir
+                .join("inspect-embassy")
+                .join("probe-rs-backend.toml"),
+        )
+    }
+
+    /// Read the last selection, returns `None` if none was stored or it could not be read.
+    pub(crate) fn load() -> Option<Self> {
+        let path = Self::path()?;
+        let content = std::fs::read_to_string(&path).ok()?;
+
+        match toml::from_str(&content) {
+            Ok(selection) => Some(selection),
+            Err(e) => {
+                log::warn!(
+                    "Ignoring invalid last selection file {}: {e}",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+
+    pub(crate) fn store(&self) -> Result<()> {
+        let path = Self::path().context("Could not determine the config directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Remove the stored selection, does nothing if none was stored.
+    pub(crate) fn forget() -> Result<()> {
+        if let Some(path) = Self::path()
+            && path.exists()
+        {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Ask the user whether to reuse this selection, always reuses it in non interactive mode.
+    pub(crate) fn offer_reuse(&self, non_interactive: bool) -> Result<bool> {
+        println!(
+            "Last used chip: {}, probe: {} (use --forget to clear)",
+            self.chip.as_deref().unwrap_or("auto"),
+            self.probe.as_deref().unwrap_or("auto"),
+        );
+        if non_interactive {
+            return Ok(true);
+        }
+
+        print!("Reuse it? [Y/n] ");
+        std::io::stdout().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        Ok(matches!(input.trim(), "" | "y" | "Y"))
+    }
+}
@@ -1,9 +1,15 @@
-use std::{path::PathBuf, time::Duration};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
+use base64::prelude::*;
 use clap::Parser;
 use probe_rs::{
-    Core, MemoryInterface,
+    Core, MemoryInterface, Session,
     architecture::{
         arm::ArmError, riscv::communication_interface::RiscvError,
         xtensa::communication_interface::XtensaError,
@@ -12,19 +18,26 @@ use probe_rs::{
     probe::{DebugProbeError, list::Lister},
 };
 
-use inspect_embassy::{Callback, Click, Event};
+use inspect_embassy::{
+    Assertion, BreakpointKind, Callback, ColorPalette, Config, Event, RecordingCallback,
+    SnapshotCallback, TargetDisconnected, Theme, UnformattableValues,
+};
 
-use common_options::ProbeOptions;
+use common_options::{LoadedProbeOptions, ProbeOptions};
+use last_selection::LastSelection;
 use ratatui::{
+    backend::TestBackend,
     crossterm::{
-        ExecutableCommand as _,
-        event::{self, MouseEventKind},
+        ExecutableCommand as _, event,
         terminal::{disable_raw_mode, enable_raw_mode},
     },
     prelude::CrosstermBackend,
 };
+use snapshot_server::SnapshotServer;
 
 mod common_options;
+mod last_selection;
+mod snapshot_server;
 
 #[derive(clap::Parser, Debug)]
 #[clap(
@@ -33,14 +46,167 @@ mod common_options;
 )]
 struct Cli {
     /// The path to the ELF file that has been flashed on the chip.
-    #[clap(index = 1)]
-    pub(crate) path: PathBuf,
+    #[clap(index = 1, required_unless_present = "snapshot")]
+    pub(crate) path: Option<PathBuf>,
+
+    /// Extra files to look for the debug data in when the ELF file has none, like a separate
+    /// debug file of a stripped binary. Can be given multiple times.
+    #[clap(long = "debug-file", value_name = "PATH")]
+    debug_files: Vec<PathBuf>,
+
+    /// Parse the debug data again instead of loading it from the cache written when the same ELF
+    /// file was used before.
+    #[clap(long)]
+    no_cache: bool,
 
     #[clap(flatten)]
     common: ProbeOptions,
 
     #[clap(long, default_value = "0")]
     core: usize,
+
+    /// Forget the chip and probe remembered from the last session.
+    #[clap(long)]
+    forget: bool,
+
+    /// Record all communication with the target to this file, to replay the session later.
+    #[clap(long)]
+    record: Option<PathBuf>,
+
+    /// Record every click, scroll and other event to this file, to replay the session together
+    /// with the file from `--record`.
+    #[clap(long, value_name = "PATH")]
+    record_events: Option<PathBuf>,
+
+    /// Serve the state of the tasks at the last stop as JSON on every `GET` to this address, like
+    /// `127.0.0.1:8080`, while the TUI runs.
+    #[clap(long, value_name = "ADDRESS")]
+    serve: Option<SocketAddr>,
+
+    /// Append a folded stack of what every task is waiting on to this file each time the target
+    /// stops, to create a flamegraph with `inferno-flamegraph` or FlameGraph.
+    #[clap(long, value_name = "PATH")]
+    folded_stacks: Option<PathBuf>,
+
+    /// Write the state of all tasks to this file as JSON when `e` is pressed.
+    #[clap(long, value_name = "PATH")]
+    export_json: Option<PathBuf>,
+
+    /// Show a file written with `--export-json` instead of a target, without a probe or the ELF
+    /// file.
+    #[clap(long, value_name = "PATH")]
+    snapshot: Option<PathBuf>,
+
+    /// What to show for values that can't be formatted: `bytes`, `placeholder` or `hidden`.
+    #[clap(long, default_value = "bytes")]
+    unformattable: UnformattableValues,
+
+    /// Show the discriminant values next to the state names of async fns.
+    #[clap(long)]
+    show_discriminants: bool,
+
+    /// Start with a main menu showing one line per task pool.
+    #[clap(long)]
+    compact: bool,
+
+    /// Show the uninitialized tasks of every pool in the main menu, instead of only the spawned
+    /// ones.
+    #[clap(long)]
+    show_uninit: bool,
+
+    /// List the pool of the task of `#[embassy_executor::main]` before the other pools.
+    #[clap(long)]
+    pin_main_task: bool,
+
+    /// Halt the target, print the TUI once and resume the target again, without starting the
+    /// interactive TUI.
+    #[clap(long)]
+    once: bool,
+
+    /// Halt the target, check the expression against the state of the tasks and exit with an
+    /// error if it does not hold, like `pool("app::worker")[0].state == "Suspend3"`. Can be given
+    /// multiple times.
+    #[clap(long = "assert", value_name = "EXPRESSION")]
+    assertions: Vec<Assertion>,
+
+    /// How many elements of arrays and collections to show.
+    #[clap(long, default_value = "64")]
+    max_elements: usize,
+
+    /// Only update the view on every this many polls, to keep up with busy executors.
+    #[clap(long, value_name = "N", default_value = "1")]
+    refresh_every: usize,
+
+    /// Mark tasks as stuck after they stayed in the same state for this many stops while other
+    /// tasks got polled, 0 to never mark them.
+    #[clap(long, value_name = "STOPS", default_value = "50")]
+    stuck_after: usize,
+
+    /// Draw the pages at most this many columns wide, centered in the terminal.
+    #[clap(long, value_name = "COLUMNS")]
+    max_width: Option<u16>,
+
+    /// Mark everything that is shown with a color with a symbol as well.
+    #[clap(long)]
+    accessible: bool,
+
+    /// The colors the terminal can show: `full` or `16` to replace the others by the nearest of
+    /// the 16 ANSI colors.
+    #[clap(long, default_value = "full")]
+    colors: ColorPalette,
+
+    /// The colors the pages are drawn with: `dark`, or `light` for terminals with a light
+    /// background.
+    #[clap(long, default_value = "dark")]
+    theme: Theme,
+
+    /// How many bytes before the end of the poll function to set the breakpoint, when the guess
+    /// from the architecture lands in the middle of an instruction.
+    #[clap(long)]
+    poll_breakpoint_offset: Option<u64>,
+
+    /// Replace the start of the source paths in the debug data, given as `<FROM>=<TO>`, when the
+    /// firmware was built somewhere else. Can be given multiple times.
+    #[clap(long = "substitute-path", value_name = "FROM=TO", value_parser = parse_substitute_path)]
+    source_path_remaps: Vec<(String, String)>,
+
+    /// How many lines of source code to show around the current await point of an async fn, 0 to
+    /// not show any source.
+    #[clap(long, value_name = "LINES", default_value = "2")]
+    source_context: usize,
+
+    /// Also show a future outside of the task pools, given as `<ADDRESS>=<TYPE>` where the type can
+    /// also be the path of the async fn. Can be given multiple times.
+    #[clap(long = "future", value_name = "ADDRESS=TYPE", value_parser = parse_future)]
+    futures: Vec<(u64, String)>,
+
+    /// The most verbose level of log messages to keep for the log pane of the TUI, one of `off`,
+    /// `error`, `warn`, `info`, `debug` or `trace`.
+    #[clap(long, default_value = "warn", value_parser = parse_log_level)]
+    log_level: log::LevelFilter,
+}
+
+fn parse_future(arg: &str) -> Result<(u64, String), String> {
+    let (address, ty) = arg
+        .split_once('=')
+        .ok_or_else(|| String::from("expected <ADDRESS>=<TYPE>"))?;
+    let address = match address.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => address.parse(),
+    }
+    .map_err(|e| format!("invalid address {address}: {e}"))?;
+    Ok((address, ty.to_owned()))
+}
+
+fn parse_log_level(arg: &str) -> Result<log::LevelFilter, String> {
+    arg.parse().map_err(|_| format!("invalid log level {arg}"))
+}
+
+fn parse_substitute_path(arg: &str) -> Result<(String, String), String> {
+    let (from, to) = arg
+        .split_once('=')
+        .ok_or_else(|| String::from("expected <FROM>=<TO>"))?;
+    Ok((from.to_owned(), to.to_owned()))
 }
 
 fn set_panic_hook() {
@@ -68,99 +234,383 @@ fn restore() -> Result<()> {
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> Result<ExitCode> {
+    let mut cli = Cli::parse();
+    let config = Config {
+        unformattable: cli.unformattable,
+        show_discriminants: cli.show_discriminants,
+        max_elements: cli.max_elements,
+        poll_breakpoint_offset: cli.poll_breakpoint_offset,
+        source_path_remaps: cli.source_path_remaps,
+        source_context_lines: (cli.source_context != 0).then_some(cli.source_context),
+        compact_main_menu: cli.compact,
+        hide_uninit_tasks: !cli.show_uninit,
+        pin_main_task: cli.pin_main_task,
+        folded_stacks: cli.folded_stacks,
+        refresh_every_polls: cli.refresh_every,
+        stuck_after_stops: (cli.stuck_after != 0).then_some(cli.stuck_after),
+        max_width: cli.max_width,
+        accessible: cli.accessible,
+        color_palette: cli.colors,
+        theme: cli.theme,
+        record_events: cli.record_events,
+        export_json: cli.export_json,
+        debug_data_cache: Config::default_debug_data_cache().filter(|_| !cli.no_cache),
+        ..Config::default()
+    };
+
+    if let Some(snapshot) = &cli.snapshot {
+        // Anything logged to the terminal would draw over the TUI.
+        if let Err(e) = inspect_embassy::init_tui_logger(cli.log_level) {
+            eprintln!("Could not install the logger: {e}");
+        }
+
+        set_panic_hook();
+        let backend = init()?;
+        let result = run_snapshot(backend, snapshot, config);
+        ratatui::restore();
+
+        return result.map(|()| ExitCode::SUCCESS);
+    }
+
+    if cli.forget {
+        LastSelection::forget()?;
+    } else if cli.common.chip.is_none()
+        && cli.common.probe.is_none()
+        && let Some(last) = LastSelection::load()
+        && last.offer_reuse(cli.common.non_interactive)?
+    {
+        cli.common.chip = last.chip;
+        cli.common.probe = last.probe.and_then(|probe| probe.parse().ok());
+    }
 
     let mut registry = Registry::from_builtin_families();
     let lister = Lister::new();
 
-    let (mut session, _options) = cli.common.simple_attach(&mut registry, &lister)?;
-    let core = session.core(cli.core)?;
+    let (session, options) = cli.common.simple_attach(&mut registry, &lister)?;
+
+    // Chips from a description file can't be found by name without that file, so only remember
+    // builtin chips.
+    if options.as_ref().chip_description_path.is_none() {
+        let selection = LastSelection {
+            chip: Some(session.target().name.clone()),
+            probe: options.as_ref().probe.as_ref().map(ToString::to_string),
+        };
+        if let Err(e) = selection.store() {
+            log::warn!("Could not remember the used chip and probe: {e}");
+        }
+    }
+
+    // TODO: Should not be a string, problem is that ddbug also takes a String
+    let elf_path = cli
+        .path
+        .as_ref()
+        .expect("clap requires the ELF file without --snapshot");
+    let object_files = std::iter::once(elf_path)
+        .chain(&cli.debug_files)
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let callback = ProbeRsCallback {
+        session: Some(session),
+        core: cli.core,
+        options,
+        lister: &lister,
+        object_files: &object_files,
+        at_breakpoint: false,
+        clipboard: None,
+    };
+
+    if !cli.assertions.is_empty() {
+        let all_hold = match cli.record {
+            Some(path) => RecordingCallback::new(callback, path).and_then(|callback| {
+                run_assertions(callback, config, &cli.futures, &cli.assertions)
+            }),
+            None => run_assertions(callback, config, &cli.futures, &cli.assertions),
+        }?;
+        return Ok(match all_hold {
+            true => ExitCode::SUCCESS,
+            false => ExitCode::FAILURE,
+        });
+    }
+
+    if cli.once {
+        match cli.record {
+            Some(path) => RecordingCallback::new(callback, path)
+                .and_then(|callback| run_once(callback, config, &cli.futures)),
+            None => run_once(callback, config, &cli.futures),
+        }?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Anything logged to the terminal would draw over the TUI.
+    if let Err(e) = inspect_embassy::init_tui_logger(cli.log_level) {
+        eprintln!("Could not install the logger: {e}");
+    }
+
+    let server = cli.serve.map(SnapshotServer::bind).transpose()?;
 
     set_panic_hook();
     let backend = init()?;
 
-    // TODO: Should not be a string, problem is that ddbug also takes a String
-    let result = run(backend, core, &[cli.path.to_string_lossy().into_owned()]);
+    let result = match cli.record {
+        Some(path) => RecordingCallback::new(callback, path)
+            .and_then(|callback| run(backend, callback, config, &cli.futures, server)),
+        None => run(backend, callback, config, &cli.futures, server),
+    };
 
     ratatui::restore();
 
+    result.map(|()| ExitCode::SUCCESS)
+}
+
+/// Halt the target and create an inspector reading its state a single time, for `--once` and
+/// `--assert`. The target is resumed afterwards if it was running.
+fn inspect_halted<
+    'a,
+    'r,
+    B: ratatui::backend::Backend,
+    C: Callback + AsProbeRsCallback<'a, 'r>,
+    T,
+>(
+    backend: B,
+    mut callback: C,
+    config: Config,
+    futures: &[(u64, String)],
+    inspect: impl FnOnce(&inspect_embassy::EmbassyInspector<B>, &mut C) -> T,
+) -> Result<T> {
+    let was_halted = {
+        let mut core = callback.probe_rs().attached_core()?;
+        let was_halted = core.core_halted().map_err(map_error)?;
+        if !was_halted {
+            core.halt(Duration::from_millis(100)).map_err(map_error)?;
+        }
+        was_halted
+    };
+
+    let result = inspect_then_shutdown(backend, &mut callback, config, futures, inspect);
+
+    // Also when inspecting failed, the error of that is returned first.
+    if !was_halted {
+        let resumed = callback
+            .probe_rs()
+            .attached_core()
+            .and_then(|mut core| core.run().map_err(map_error));
+        match (&result, resumed) {
+            (Err(_), Err(e)) => log::error!("Could not resume the target: {e:#}"),
+            (_, resumed) => resumed?,
+        }
+    }
+
     result
 }
 
-/// Return Err on an error and Ok(None) when there are no events
-fn poll_event() -> Result<Option<Event>> {
-    if !event::poll(Duration::default())? {
-        return Ok(None);
+/// The part of [`inspect_halted`] that can fail while the target is halted. The inspector is shut
+/// down afterwards also when inspecting failed, returning the first error.
+fn inspect_then_shutdown<
+    'a,
+    'r,
+    B: ratatui::backend::Backend,
+    C: Callback + AsProbeRsCallback<'a, 'r>,
+    T,
+>(
+    backend: B,
+    callback: &mut C,
+    config: Config,
+    futures: &[(u64, String)],
+    inspect: impl FnOnce(&inspect_embassy::EmbassyInspector<B>, &mut C) -> T,
+) -> Result<T> {
+    let mut inspector = inspect_embassy::EmbassyInspector::with_config(backend, callback, config)?;
+    let result = futures
+        .iter()
+        .try_for_each(|(address, ty)| inspector.inspect_future(*address, ty, callback))
+        .map(|()| inspect(&inspector, callback));
+    // The poll done breakpoints would keep stopping the target without the inspector resuming it.
+    // Only the ones of the inspector, breakpoints of another debug session stay.
+    let shutdown = inspector.shutdown(callback);
+    // Shows the cursor again.
+    drop(inspector);
+
+    match (result, shutdown) {
+        (Err(e), Err(shutdown_error)) => {
+            log::error!("Could not shut down the inspector: {shutdown_error:#}");
+            Err(e)
+        }
+        (result, shutdown) => shutdown.and(result),
     }
+}
 
-    let event = match event::read()? {
-        event::Event::Key(key_event) => {
-            if key_event.modifiers.contains(event::KeyModifiers::CONTROL)
-                && key_event.code == event::KeyCode::Char('c')
-            {
-                anyhow::bail!("Ctrl+C pressed");
-            }
-            return Ok(None);
-        }
-        event::Event::Mouse(mouse_event) => match mouse_event.kind {
-            MouseEventKind::Down(button) => {
-                let button = match button {
-                    event::MouseButton::Left => inspect_embassy::ClickButton::Left,
-                    event::MouseButton::Right => inspect_embassy::ClickButton::Right,
-                    event::MouseButton::Middle => inspect_embassy::ClickButton::Middle,
-                };
-                Event::Click(Click {
-                    pos: ratatui::layout::Position {
-                        x: mouse_event.column,
-                        y: mouse_event.row,
-                    },
-                    button,
-                })
+/// Render the TUI a single time on the normal screen of the terminal for `--once`.
+fn run_once<'a, 'r, C: Callback + AsProbeRsCallback<'a, 'r>>(
+    callback: C,
+    config: Config,
+    futures: &[(u64, String)],
+) -> Result<()> {
+    let backend = CrosstermBackend::new(std::io::stdout());
+    inspect_halted(backend, callback, config, futures, |_, _| ())?;
+
+    // Leave the shell prompt below the rendered screen.
+    let (_, rows) = ratatui::crossterm::terminal::size()?;
+    std::io::stdout().execute(ratatui::crossterm::cursor::MoveTo(0, rows))?;
+    println!();
+
+    Ok(())
+}
+
+/// Check the `--assert` expressions against the halted target and print the result of each.
+///
+/// Returns whether all of them hold.
+fn run_assertions<'a, 'r, C: Callback + AsProbeRsCallback<'a, 'r>>(
+    callback: C,
+    config: Config,
+    futures: &[(u64, String)],
+    assertions: &[Assertion],
+) -> Result<bool> {
+    // Nothing gets shown, the TUI is drawn into a buffer that is thrown away.
+    let backend = TestBackend::new(80, 24);
+    inspect_halted(backend, callback, config, futures, |inspector, callback| {
+        let mut all_hold = true;
+        for assertion in assertions {
+            match inspector.check_assertion(assertion, callback) {
+                Ok(()) => println!("ok: {assertion}"),
+                Err(reason) => {
+                    println!("FAILED: {assertion}: {reason}");
+                    all_hold = false;
+                }
             }
-            MouseEventKind::ScrollDown => Event::Scroll(-3),
-            MouseEventKind::ScrollUp => Event::Scroll(3),
-            _ => {
-                return Ok(None);
+        }
+        all_hold
+    })
+}
+
+/// Show the file from `--export-json` for `--snapshot` until the TUI is quit, there is no target
+/// to poll so this only waits for input.
+fn run_snapshot<B: ratatui::backend::Backend>(
+    backend: B,
+    path: &Path,
+    config: Config,
+) -> Result<()> {
+    let mut callback = SnapshotCallback;
+    let mut inspector =
+        inspect_embassy::EmbassyInspector::from_snapshot_with_config(backend, path, config)?;
+    #[cfg(feature = "task-graph-image")]
+    enable_graph_images(&mut inspector);
+
+    loop {
+        if let Some(event) = inspect_embassy::from_crossterm(event::read()?) {
+            inspector.handle_event(event, &mut callback)?;
+            if inspector.quit_requested() {
+                return Ok(());
             }
-        },
-        event::Event::Resize(_, _) => Event::Redraw,
-        _ => {
-            return Ok(None);
         }
-    };
+    }
+}
+
+/// Draw the task graph as an image when the terminal answers which image protocol it supports,
+/// it is shown as text otherwise. Needs the terminal in raw mode.
+#[cfg(feature = "task-graph-image")]
+fn enable_graph_images<B: ratatui::backend::Backend>(
+    inspector: &mut inspect_embassy::EmbassyInspector<B>,
+) {
+    match inspect_embassy::ratatui_image::picker::Picker::from_query_stdio() {
+        Ok(picker) => inspector.set_image_picker(picker),
+        Err(e) => log::info!("Showing the task graph as text, no image protocol found: {e}"),
+    }
+}
+
+/// Return Err on an error and Ok(None) when there are no events
+fn poll_event() -> Result<Option<Event>> {
+    if !event::poll(Duration::default())? {
+        return Ok(None);
+    }
 
-    Ok(Some(event))
+    Ok(inspect_embassy::from_crossterm(event::read()?))
 }
 
-fn run<B: ratatui::backend::Backend>(
+fn run<'a, 'r, B: ratatui::backend::Backend, C: Callback + AsProbeRsCallback<'a, 'r>>(
     backend: B,
-    mut core: Core,
-    object_files: &[String],
+    mut callback: C,
+    config: Config,
+    futures: &[(u64, String)],
+    server: Option<SnapshotServer>,
 ) -> Result<()> {
-    let mut callback = ProbeRsCallback {
-        core: &mut core,
-        object_files,
-    };
+    let mut inspect_embassyor =
+        inspect_embassy::EmbassyInspector::with_config(backend, &mut callback, config)?;
+    for (address, ty) in futures {
+        inspect_embassyor.inspect_future(*address, ty, &mut callback)?;
+    }
+    #[cfg(feature = "task-graph-image")]
+    enable_graph_images(&mut inspect_embassyor);
+
+    let result = event_loop(&mut inspect_embassyor, &mut callback, server);
+    // The poll done breakpoints would keep stopping the target in the next debug session.
+    if result.is_err()
+        && let Err(e) = inspect_embassyor.shutdown(&mut callback)
+    {
+        log::error!("Could not shut down the inspector: {e:#}");
+    }
+    result
+}
 
-    let mut inspect_embassyor = inspect_embassy::EmbassyInspector::new(backend, &mut callback)?;
+/// Poll the target and the terminal until the TUI is quit.
+fn event_loop<'a, 'r, B: ratatui::backend::Backend, C: Callback + AsProbeRsCallback<'a, 'r>>(
+    inspect_embassyor: &mut inspect_embassy::EmbassyInspector<B>,
+    callback: &mut C,
+    server: Option<SnapshotServer>,
+) -> Result<()> {
+    let mut last_reattach = Instant::now();
+    let mut last_tick = Instant::now();
 
     loop {
+        if let Some(server) = &server {
+            server.answer(|| inspect_embassyor.snapshot_json(callback));
+        }
+
         if let Some(event) = poll_event()? {
-            inspect_embassyor.handle_event(event, &mut callback)?;
+            handle_event(inspect_embassyor, event, callback)?;
+            if inspect_embassyor.quit_requested() {
+                return Ok(());
+            }
+            continue;
+        }
+
+        if last_tick.elapsed() >= TICK_INTERVAL {
+            last_tick = Instant::now();
+            handle_event(inspect_embassyor, Event::Tick, callback)?;
+        }
+
+        let Some(core) = callback.probe_rs().core() else {
+            if last_reattach.elapsed() >= REATTACH_INTERVAL {
+                last_reattach = Instant::now();
+                match callback.probe_rs().reattach() {
+                    Ok(()) => handle_event(inspect_embassyor, Event::Reconnected, callback)?,
+                    Err(e) => log::warn!("Reattaching failed: {e}"),
+                }
+            }
+            std::thread::sleep(Duration::from_millis(10));
+            continue;
+        };
+
+        // Stays halted until continued from the TUI, the core is still checked to notice a lost
+        // connection.
+        if inspect_embassyor.holding_target() {
+            if let Err(e) = core.and_then(|mut core| core.core_halted()) {
+                lost_connection(inspect_embassyor, callback, e)?;
+            }
+            std::thread::sleep(Duration::from_millis(10));
             continue;
         }
 
         // 10 ms was the highest value where I still felt it was responsive
-        match callback
-            .core
-            .wait_for_core_halted(Duration::from_millis(10))
-        {
-            Ok(()) => {
-                let addr = callback
-                    .core
-                    .read_core_reg(callback.core.program_counter())?;
-                inspect_embassyor.handle_event(Event::Breakpoint(addr), &mut callback)?;
+        let halted = core.and_then(|mut core| {
+            core.wait_for_core_halted(Duration::from_millis(10))?;
+            core.read_core_reg(core.program_counter())
+        });
+        match halted {
+            Ok(addr) => {
+                callback.probe_rs().at_breakpoint = true;
+                let handled = handle_event(inspect_embassyor, Event::Breakpoint(addr), callback);
+                callback.probe_rs().at_breakpoint = false;
+                handled?;
             }
             Err(
                 probe_rs::Error::Timeout
@@ -173,41 +623,233 @@ fn run<B: ratatui::backend::Backend>(
                     XtensaError::Timeout | XtensaError::DebugProbe(DebugProbeError::Timeout),
                 ),
             ) => {}
-            Err(other_err) => Err(other_err)?,
+            Err(other_err) => lost_connection(inspect_embassyor, callback, other_err)?,
+        }
+    }
+}
+
+/// Detach after an error talking to the core.
+fn lost_connection<
+    'a,
+    'r,
+    B: ratatui::backend::Backend,
+    C: Callback + AsProbeRsCallback<'a, 'r>,
+>(
+    inspector: &mut inspect_embassy::EmbassyInspector<B>,
+    callback: &mut C,
+    error: probe_rs::Error,
+) -> Result<()> {
+    // Resets and lost connections show up as all kinds of errors, so any of them is handled by
+    // attaching again.
+    log::error!("Lost connection to the target: {error}");
+    callback.probe_rs().detach();
+    handle_event(inspector, Event::Disconnected, callback)
+}
+
+/// How long to wait between attempts to attach again after the target got disconnected.
+const REATTACH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often to send [`Event::Tick`] while there are no other events.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Send the event to the inspector, detaching when the target turns out to be disconnected.
+fn handle_event<'a, 'r, B: ratatui::backend::Backend, C: Callback + AsProbeRsCallback<'a, 'r>>(
+    inspector: &mut inspect_embassy::EmbassyInspector<B>,
+    event: Event,
+    callback: &mut C,
+) -> Result<()> {
+    match inspector.handle_event(event, callback) {
+        Err(e) if TargetDisconnected::is_cause_of(&e) => {
+            log::error!("{e:#}");
+            callback.probe_rs().detach();
+            inspector.handle_event(Event::Disconnected, callback)
         }
+        result => result,
     }
 }
 
-struct ProbeRsCallback<'a, 'probe> {
-    core: &'a mut Core<'probe>,
+/// Whether the error means the connection to the probe was lost.
+fn is_disconnect(error: &probe_rs::Error) -> bool {
+    match error {
+        probe_rs::Error::Probe(probe_error)
+        | probe_rs::Error::Arm(ArmError::Probe(probe_error))
+        | probe_rs::Error::Riscv(RiscvError::DebugProbe(probe_error))
+        | probe_rs::Error::Xtensa(XtensaError::DebugProbe(probe_error)) => {
+            !matches!(probe_error, DebugProbeError::Timeout)
+        }
+        _ => false,
+    }
+}
+
+/// Convert the error, marking it as [`TargetDisconnected`] if it is caused by a lost connection.
+fn map_error(error: probe_rs::Error) -> anyhow::Error {
+    if is_disconnect(&error) {
+        anyhow::Error::new(TargetDisconnected).context(error.to_string())
+    } else {
+        error.into()
+    }
+}
+
+struct ProbeRsCallback<'a, 'r> {
+    /// `None` while the target is disconnected.
+    session: Option<Session>,
+    core: usize,
+    /// The options used to attach, also used to attach again after a disconnect.
+    options: LoadedProbeOptions<'r>,
+    lister: &'a Lister,
+
     object_files: &'a [String],
+    /// Set by the event loop while handling a poll hit, so resuming doesn't have to check whether
+    /// the core is halted.
+    at_breakpoint: bool,
+    /// Opened on the first copy and kept open, on X11 the copied text is gone once it is dropped.
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl ProbeRsCallback<'_, '_> {
+    /// Get the core, `None` while detached.
+    fn core(&mut self) -> Option<Result<Core<'_>, probe_rs::Error>> {
+        self.session.as_mut().map(|session| session.core(self.core))
+    }
+
+    /// Get the core, returning [`TargetDisconnected`] while detached.
+    fn attached_core(&mut self) -> Result<Core<'_>> {
+        match self.core() {
+            Some(core) => core.map_err(map_error),
+            None => Err(TargetDisconnected.into()),
+        }
+    }
+
+    /// Drop the session of a lost connection, so the probe can be opened again.
+    fn detach(&mut self) {
+        self.session = None;
+    }
+
+    fn reattach(&mut self) -> Result<()> {
+        self.detach();
+
+        // The probe selector was stored in the options during the first attach, so this opens the
+        // same probe without asking.
+        let target = self.options.get_target_selector()?;
+        let probe = self.options.attach_probe(self.lister)?;
+        self.session = Some(self.options.attach_session(probe, target)?);
+        Ok(())
+    }
 }
 
-impl<'a, 'b> Callback for ProbeRsCallback<'a, 'b> {
+/// Access to the [`ProbeRsCallback`] for the event loop, also when it is wrapped.
+trait AsProbeRsCallback<'a, 'r> {
+    fn probe_rs(&mut self) -> &mut ProbeRsCallback<'a, 'r>;
+}
+
+impl<'a, 'r> AsProbeRsCallback<'a, 'r> for ProbeRsCallback<'a, 'r> {
+    fn probe_rs(&mut self) -> &mut ProbeRsCallback<'a, 'r> {
+        self
+    }
+}
+
+impl<'a, 'r, C: Callback + AsProbeRsCallback<'a, 'r>> AsProbeRsCallback<'a, 'r>
+    for RecordingCallback<C>
+{
+    fn probe_rs(&mut self) -> &mut ProbeRsCallback<'a, 'r> {
+        self.inner_mut().probe_rs()
+    }
+}
+
+impl<'a, 'r> Callback for ProbeRsCallback<'a, 'r> {
     fn get_objectfiles(&mut self) -> Result<impl Iterator<Item = String>> {
         Ok(self.object_files.into_iter().cloned())
     }
 
-    fn set_breakpoint(&mut self, addr: u64) -> Result<u64> {
-        self.core.set_hw_breakpoint(addr)?;
+    fn set_breakpoint(&mut self, addr: u64, kind: BreakpointKind) -> Result<u64> {
+        // probe-rs only sets breakpoints with the breakpoint unit of the core.
+        if kind == BreakpointKind::Software {
+            anyhow::bail!("software breakpoints are unsupported by probe-rs, use hardware ones");
+        }
+        self.attached_core()?
+            .set_hw_breakpoint(addr)
+            .map_err(map_error)?;
         Ok(addr)
     }
 
+    fn clear_breakpoint(&mut self, id: u64) -> Result<()> {
+        // The id is the address, see `set_breakpoint`.
+        self.attached_core()?
+            .clear_hw_breakpoint(id)
+            .map_err(map_error)?;
+        Ok(())
+    }
+
     fn resume(&mut self) -> Result<()> {
-        self.core.run()?;
+        let at_breakpoint = self.at_breakpoint;
+        let mut core = self.attached_core()?;
+        // Do nothing when already running, see `Callback::resume`. That can only happen when
+        // continuing from the TUI, after a poll hit the core was just seen halted.
+        if at_breakpoint || core.core_halted().map_err(map_error)? {
+            core.run().map_err(map_error)?;
+        }
         Ok(())
     }
 
-    fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
-        let mut buf = vec![0; len.next_multiple_of(4) as usize];
-        self.core.read_mem_32bit(addr, &mut buf)?;
+    fn halt(&mut self) -> Result<()> {
+        let mut core = self.attached_core()?;
+        if !core.core_halted().map_err(map_error)? {
+            core.halt(Duration::from_millis(100)).map_err(map_error)?;
+        }
+        Ok(())
+    }
 
-        buf.truncate(len as usize);
+    fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
+        // probe-rs picks the widest accesses the core supports for the aligned part of the range,
+        // which also works on cores that aren't 32 bit.
+        let mut buf = vec![0; len as usize];
+        self.attached_core()?
+            .read(addr, &mut buf)
+            .map_err(map_error)?;
 
         Ok(buf)
     }
 
+    fn write_memory(&mut self, addr: u64, bytes: &[u8]) -> Result<()> {
+        self.attached_core()?
+            .write_8(addr, bytes)
+            .map_err(map_error)?;
+        Ok(())
+    }
+
     fn try_format_value(&mut self, _bytes: &[u8], _ty: &inspect_embassy::Type) -> Option<String> {
         None
     }
+
+    fn read_pc(&mut self) -> Result<Option<u64>> {
+        let mut core = self.attached_core()?;
+        let pc = core
+            .read_core_reg(core.program_counter())
+            .map_err(map_error)?;
+        Ok(Some(pc))
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str) -> Result<String> {
+        use std::io::Write;
+
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => Ok(clipboard),
+            None => arboard::Clipboard::new(),
+        };
+        match clipboard.and_then(|mut clipboard| {
+            clipboard.set_text(text)?;
+            Ok(clipboard)
+        }) {
+            Ok(clipboard) => {
+                self.clipboard = Some(clipboard);
+                return Ok(String::from("Copied to the clipboard"));
+            }
+            Err(e) => log::debug!("Could not use the clipboard, falling back to OSC 52: {e}"),
+        }
+
+        // OSC 52, supported by most terminals, also over SSH. There is no way to know if it worked.
+        let mut stdout = std::io::stdout();
+        write!(stdout, "\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text))?;
+        stdout.flush()?;
+        Ok(String::from("Copied to the clipboard"))
+    }
 }
@@ -0,0 +1,163 @@
+//! A tiny HTTP server for `--serve`, answering every `GET` with the state of the tasks at the last
+//! stop as JSON, for dashboards and other tools monitoring the target.
+//!
+//! Connections are accepted and read on a thread of their own, so a slow client never holds up the
+//! TUI. Only the snapshot itself is made by the event loop in between polling the target, so the
+//! inspector does not have to be shared.
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, Sender},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+/// Largest request head that is read, the rest of a request is ignored anyway.
+const MAX_REQUEST_SIZE: usize = 4096;
+/// How long a client gets to send its request before it is dropped, requests are read one by one.
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long a request waits for the event loop to make a snapshot.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) struct SnapshotServer {
+    /// Requests for a snapshot from the server thread, with where to send it.
+    requests: Receiver<Sender<String>>,
+}
+
+impl SnapshotServer {
+    pub(crate) fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+        let listener = TcpListener::bind(addr).context("Could not start the snapshot server")?;
+        if let Ok(addr) = listener.local_addr() {
+            log::info!("Serving snapshots on http://{addr}");
+        }
+
+        let (sender, requests) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(String::from("snapshot server"))
+            .spawn(move || serve(listener, sender))
+            .context("Could not start the snapshot server")?;
+        Ok(Self { requests })
+    }
+
+    /// Answer the requests that are waiting, `snapshot` is only called when there is one and at
+    /// most once for all of them.
+    pub(crate) fn answer(&self, snapshot: impl FnOnce() -> String) {
+        let mut snapshot = Some(snapshot);
+        let mut json = None;
+
+        while let Ok(reply) = self.requests.try_recv() {
+            let body = json.get_or_insert_with(|| snapshot.take().map(|f| f()).unwrap_or_default());
+            // The request timed out in the meantime.
+            let _ = reply.send(body.clone());
+        }
+    }
+}
+
+/// Accept connections for as long as the backend runs.
+fn serve(listener: TcpListener, requests: Sender<Sender<String>>) {
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| answer_request(stream, &requests));
+        if let Err(e) = result {
+            log::warn!("Could not answer a snapshot request: {e}");
+        }
+    }
+}
+
+fn answer_request(mut stream: TcpStream, requests: &Sender<Sender<String>>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut request = Vec::new();
+    let mut buf = [0; 512];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_SIZE {
+        match stream.read(&mut buf)? {
+            0 => break,
+            read => request.extend_from_slice(&buf[..read]),
+        }
+    }
+
+    let response = match route(&String::from_utf8_lossy(&request)) {
+        Route::Snapshot => match request_snapshot(requests) {
+            Some(snapshot) => response("200 OK", &snapshot),
+            None => response(
+                "503 Service Unavailable",
+                r#"{"error":"the inspector did not answer"}"#,
+            ),
+        },
+        Route::NotFound => response("404 Not Found", r#"{"error":"not found"}"#),
+        Route::MethodNotAllowed => response(
+            "405 Method Not Allowed",
+            r#"{"error":"only GET is supported"}"#,
+        ),
+    };
+    stream.write_all(response.as_bytes())
+}
+
+/// Wait for the event loop to make a snapshot, `None` if it does not in time or is gone.
+fn request_snapshot(requests: &Sender<Sender<String>>) -> Option<String> {
+    let (reply, snapshot) = mpsc::channel();
+    requests.send(reply).ok()?;
+    snapshot.recv_timeout(SNAPSHOT_TIMEOUT).ok()
+}
+
+/// What a request asks for.
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    Snapshot,
+    NotFound,
+    MethodNotAllowed,
+}
+
+/// Route a request by its request line, the headers and body are ignored.
+fn route(request: &str) -> Route {
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/" | "/snapshot")) => Route::Snapshot,
+        (Some("GET"), _) => Route::NotFound,
+        _ => Route::MethodNotAllowed,
+    }
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn routes() {
+        assert_eq!(
+            route("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            Route::Snapshot
+        );
+        assert_eq!(route("GET /snapshot HTTP/1.1\r\n\r\n"), Route::Snapshot);
+        assert_eq!(route("GET /tasks HTTP/1.1\r\n\r\n"), Route::NotFound);
+        assert_eq!(route("GET"), Route::NotFound);
+        assert_eq!(route("POST / HTTP/1.1\r\n\r\n"), Route::MethodNotAllowed);
+        assert_eq!(route(""), Route::MethodNotAllowed);
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(
+            response("200 OK", "{}"),
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: 2\r\n\
+             Connection: close\r\n\
+             \r\n\
+             {}"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use embassy_executor::Spawner;
+use embassy_nrf::{
+    Peri,
+    gpio::{AnyPin, Input, Pull},
+};
+use {defmt_rtt as _, panic_probe as _};
+
+/// Keeps an `arrayvec::ArrayVec` and a `tinyvec::ArrayVec` alive across await points, with only
+/// part of their capacity used.
+#[embassy_executor::task(pool_size = 1)]
+async fn count_presses(button: Peri<'static, AnyPin>) {
+    let mut button = Input::new(button, Pull::Up);
+
+    let mut presses = arrayvec::ArrayVec::<u32, 8>::new();
+    let mut releases = tinyvec::ArrayVec::<[u16; 8]>::new();
+
+    for press in 0.. {
+        button.wait_for_low().await;
+
+        if presses.is_full() {
+            presses.clear();
+        }
+        presses.push(press);
+        info!("pressed {} times", press + 1);
+
+        button.wait_for_high().await;
+
+        if releases.len() == releases.capacity() {
+            releases.clear();
+        }
+        releases.push(press as u16);
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_nrf::init(Default::default());
+
+    spawner.spawn(count_presses(p.P0_11.into())).unwrap();
+
+    info!("All threads spawned");
+}
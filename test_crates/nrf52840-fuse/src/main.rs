@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use embassy_executor::Spawner;
+use embassy_futures::select::{Either, select};
+use embassy_nrf::{
+    Peri,
+    gpio::{AnyPin, Input, Level, Output, OutputDrive, Pull},
+};
+use embassy_time::Timer;
+use futures_util::FutureExt;
+use {defmt_rtt as _, panic_probe as _};
+
+async fn blink(led: &mut Output<'_>) {
+    led.set_low();
+    Timer::after_millis(200).await;
+    led.set_high();
+}
+
+/// Awaits a fused async fn directly and selects over fused futures.
+#[embassy_executor::task(pool_size = 1)]
+async fn fused(led: Peri<'static, AnyPin>, button: Peri<'static, AnyPin>) {
+    let mut led = Output::new(led, Level::High, OutputDrive::Standard);
+    let mut button = Input::new(button, Pull::Up);
+
+    loop {
+        blink(&mut led).fuse().await;
+
+        match select(
+            Timer::after_secs(5).fuse(),
+            button.wait_for_low().fuse(),
+        )
+        .await
+        {
+            Either::First(()) => info!("Timed out"),
+            Either::Second(()) => info!("Pressed"),
+        }
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_nrf::init(Default::default());
+
+    spawner
+        .spawn(fused(p.P0_13.into(), p.P0_11.into()))
+        .unwrap();
+
+    info!("All threads spawned");
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+
+use defmt::info;
+use embassy_executor::Spawner;
+use embassy_nrf::{
+    Peri,
+    gpio::{AnyPin, Input, Pull},
+};
+use {defmt_rtt as _, panic_probe as _};
+
+/// Keeps a `heapless::Vec` and `heapless::String` alive across await points, with only part of
+/// their capacity used.
+#[embassy_executor::task(pool_size = 1)]
+async fn count_presses(button: Peri<'static, AnyPin>) {
+    let mut button = Input::new(button, Pull::Up);
+
+    let mut presses = heapless::Vec::<u32, 8>::new();
+    let mut message = heapless::String::<32>::new();
+
+    for press in 0.. {
+        button.wait_for_low().await;
+
+        if presses.is_full() {
+            presses.clear();
+        }
+        presses.push(press).unwrap();
+
+        message.clear();
+        write!(message, "pressed {} times", press + 1).unwrap();
+        info!("{}", message.as_str());
+
+        button.wait_for_high().await;
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_nrf::init(Default::default());
+
+    spawner.spawn(count_presses(p.P0_11.into())).unwrap();
+
+    info!("All threads spawned");
+}
@@ -3,7 +3,10 @@
 
 use defmt::info;
 use embassy_executor::Spawner;
-use embassy_futures::join::join4;
+use embassy_futures::{
+    join::{join_array, join4},
+    select::select_array,
+};
 use embassy_nrf::{
     Peri,
     gpio::{AnyPin, Input, Level, Output, OutputDrive, Pull},
@@ -43,6 +46,20 @@ async fn wait_on_all(
     }
 }
 
+/// Waits on zero length select and join arrays, which are legal but easy to get wrong.
+#[embassy_executor::task(pool_size = 1)]
+async fn wait_on_nothing(button: Peri<'static, AnyPin>) {
+    let mut button = Input::new(button, Pull::Up);
+
+    let nothing: [core::future::Pending<()>; 0] = [];
+    join_array(nothing).await;
+
+    button.wait_for_low().await;
+
+    let nothing: [core::future::Pending<()>; 0] = [];
+    select_array(nothing).await;
+}
+
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
     let p = embassy_nrf::init(Default::default());
@@ -56,6 +73,7 @@ async fn main(spawner: Spawner) {
     );
 
     spawner.spawn(b1).unwrap();
+    spawner.spawn(wait_on_nothing(p.P0_26.into())).unwrap();
 
     info!("All threads spawned");
 }
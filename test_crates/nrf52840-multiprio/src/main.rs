@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+use cortex_m_rt::entry;
+use defmt::info;
+use embassy_executor::{Executor, InterruptExecutor};
+use embassy_nrf::interrupt;
+use embassy_nrf::interrupt::{InterruptExt, Priority};
+use embassy_time::Timer;
+use static_cell::StaticCell;
+use {defmt_rtt as _, panic_probe as _};
+
+/// Runs on the interrupt executor, preempting the thread mode task.
+#[embassy_executor::task]
+async fn fast_ticker() {
+    loop {
+        info!("fast tick");
+        Timer::after_millis(100).await;
+    }
+}
+
+/// Runs on the thread mode executor.
+#[embassy_executor::task]
+async fn slow_ticker() {
+    loop {
+        info!("slow tick");
+        Timer::after_secs(1).await;
+    }
+}
+
+static EXECUTOR_HIGH: InterruptExecutor = InterruptExecutor::new();
+static EXECUTOR_LOW: StaticCell<Executor> = StaticCell::new();
+
+#[interrupt]
+unsafe fn EGU1_SWI1() {
+    unsafe { EXECUTOR_HIGH.on_interrupt() }
+}
+
+/// Polls tasks from both a thread mode and an interrupt executor, so both kinds of poll done
+/// breakpoints are set.
+#[entry]
+fn main() -> ! {
+    let _p = embassy_nrf::init(Default::default());
+
+    interrupt::EGU1_SWI1.set_priority(Priority::P6);
+    let spawner = EXECUTOR_HIGH.start(interrupt::EGU1_SWI1);
+    spawner.spawn(fast_ticker()).unwrap();
+
+    let executor = EXECUTOR_LOW.init(Executor::new());
+    executor.run(|spawner| {
+        spawner.spawn(slow_ticker()).unwrap();
+    })
+}
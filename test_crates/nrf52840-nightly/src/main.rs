@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+#![feature(type_alias_impl_trait)]
+
+use defmt::info;
+use embassy_executor::Spawner;
+use embassy_nrf::{
+    Peri,
+    gpio::{AnyPin, Input, Level, Output, OutputDrive, Pull},
+};
+use embassy_time::Timer;
+use {defmt_rtt as _, panic_probe as _};
+
+#[embassy_executor::task(pool_size = 2)]
+async fn on_button(led: Peri<'static, AnyPin>, button: Peri<'static, AnyPin>) {
+    let mut led = Output::new(led, Level::Low, OutputDrive::Standard);
+    let mut button = Input::new(button, Pull::Up);
+
+    loop {
+        button.wait_for_low().await;
+        led.set_low();
+
+        button.wait_for_high().await;
+        led.set_high();
+    }
+}
+
+#[embassy_executor::task]
+async fn blink(led: Peri<'static, AnyPin>) {
+    let mut led = Output::new(led, Level::Low, OutputDrive::Standard);
+
+    loop {
+        led.toggle();
+        Timer::after_millis(500).await;
+    }
+}
+
+// Main is itself an async task as well.
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    // Initialize the embassy-nrf HAL.
+    let p = embassy_nrf::init(Default::default());
+
+    spawner
+        .spawn(on_button(p.P0_13.into(), p.P0_11.into()))
+        .unwrap();
+    spawner
+        .spawn(on_button(p.P0_14.into(), p.P0_12.into()))
+        .unwrap();
+    spawner.spawn(blink(p.P0_15.into())).unwrap();
+
+    info!("All tasks spawned");
+}